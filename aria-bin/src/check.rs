@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: Apache-2.0
+use aria_compiler::{
+    CompilationOptions,
+    checker::{CheckError, check_module},
+    compile_from_ast,
+};
+use aria_parser::ast::{ParsedModule, SourceBuffer, source_to_ast};
+
+use crate::error_reporting::{
+    print_report_from_checker_error, print_report_from_compiler_error,
+    print_report_from_parser_error,
+};
+
+// the checker's warnings point at code that runs as written but is likely
+// wrong, e.g. a match that misses a case, so every compile reports them;
+// its errors are left to aria check
+pub(crate) fn compile_warnings(ast: &ParsedModule) -> Vec<CheckError> {
+    check_module(ast)
+        .into_iter()
+        .filter(|e| e.reason.is_warning())
+        .collect()
+}
+
+#[derive(Default)]
+struct Findings {
+    errors: usize,
+    warnings: usize,
+}
+
+// what was found in a source buffer, which has already been reported to
+// stderr
+fn check_buffer(src: &SourceBuffer) -> Findings {
+    let ast = match source_to_ast(src) {
+        Ok(ast) => ast,
+        Err(err) => {
+            print_report_from_parser_error(&err);
+            return Findings {
+                errors: 1,
+                warnings: 0,
+            };
+        }
+    };
+
+    if let Err(errs) = compile_from_ast(&ast, &CompilationOptions::default()) {
+        errs.iter().for_each(print_report_from_compiler_error);
+        return Findings {
+            errors: errs.len(),
+            warnings: 0,
+        };
+    }
+
+    let errs = check_module(&ast);
+    errs.iter().for_each(print_report_from_checker_error);
+    let warnings = errs.iter().filter(|e| e.reason.is_warning()).count();
+    Findings {
+        errors: errs.len() - warnings,
+        warnings,
+    }
+}
+
+fn plural(n: usize, what: &str) -> String {
+    format!("{n} {what}{}", if n == 1 { "" } else { "s" })
+}
+
+// warnings are reported, but only errors make the check fail
+pub(crate) fn check(paths: &[String]) -> i32 {
+    let mut found = Findings::default();
+    for path in paths {
+        match SourceBuffer::file(path) {
+            Ok(src) => {
+                let in_file = check_buffer(&src);
+                found.errors += in_file.errors;
+                found.warnings += in_file.warnings;
+            }
+            Err(err) => {
+                eprintln!("error reading source file {path}: {err}");
+                found.errors += 1;
+            }
+        }
+    }
+
+    match (found.errors, found.warnings) {
+        (0, 0) => {}
+        (0, w) => eprintln!("found {}", plural(w, "warning")),
+        (e, 0) => eprintln!("found {}", plural(e, "problem")),
+        (e, w) => eprintln!(
+            "found {} and {}",
+            plural(e, "problem"),
+            plural(w, "warning")
+        ),
+    }
+    if found.errors == 0 { 0 } else { 1 }
+}
@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::path::{Path, PathBuf};
+
+use haxby_vm::{
+    coverage::{Coverage, CoverageOptions},
+    vm::VirtualMachine,
+};
+
+#[derive(clap::Args, Debug, Default)]
+pub(crate) struct CoverageArgs {
+    /// Record which lines run and write them to this file as an lcov tracefile
+    #[arg(long("coverage-lcov"), value_name = "PATH")]
+    pub lcov: Option<PathBuf>,
+    /// Record which lines run and print how much of each file ran
+    #[arg(long("coverage-summary"))]
+    pub summary: bool,
+    /// Also record which way each condition went, as branch coverage
+    #[arg(long("coverage-branches"))]
+    pub branches: bool,
+    /// Also report on the files of the Aria library, and not only on the program
+    #[arg(long("coverage-include-lib"))]
+    pub include_lib: bool,
+}
+
+impl CoverageArgs {
+    // how to record coverage, or None when it is not recorded
+    pub(crate) fn options(&self) -> Option<CoverageOptions> {
+        if self.lcov.is_none() && !self.summary {
+            return None;
+        }
+        Some(CoverageOptions {
+            branches: self.branches,
+        })
+    }
+
+    pub(crate) fn check(&self) -> Option<String> {
+        if (self.branches || self.include_lib) && self.options().is_none() {
+            Some(
+                "--coverage-branches and --coverage-include-lib have no effect without --coverage-lcov or --coverage-summary"
+                    .to_owned(),
+            )
+        } else {
+            None
+        }
+    }
+
+    // writes the lcov file, and returns the summary to print if one was asked for
+    pub(crate) fn report(&self, mut coverage: Coverage) -> Result<Option<String>, String> {
+        // leaves out code that has no file, such as the drivers of test functions
+        let lib_paths = VirtualMachine::get_aria_library_paths()
+            .iter()
+            .filter_map(|path| path.canonicalize().ok())
+            .collect::<Vec<_>>();
+        coverage.retain_files(|file| {
+            let Ok(path) = Path::new(file).canonicalize() else {
+                return false;
+            };
+            self.include_lib || !lib_paths.iter().any(|lib| path.starts_with(lib))
+        });
+
+        if let Some(dest) = &self.lcov {
+            std::fs::write(dest, coverage.lcov())
+                .map_err(|e| format!("could not write {}: {e}", dest.display()))?;
+        }
+        Ok(self.summary.then(|| coverage.summary()))
+    }
+}
@@ -0,0 +1,579 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    io::{BufRead, Write},
+    path::Path,
+    rc::Rc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, Sender, channel},
+    },
+};
+
+use aria_compiler::{CompilationOptions, compile_from_ast};
+use aria_parser::ast::{SourceBuffer, source_to_ast};
+use haxby_vm::{
+    console::Console,
+    debugger::{DebugHandler, PausedVm, ResumeMode, StopReason},
+    runtime_module::RuntimeModule,
+    runtime_value::RuntimeValue,
+    vm::{RunloopExit, VirtualMachine, VmOptions},
+};
+use serde_json::{Value, json};
+
+use crate::{
+    Args,
+    error_reporting::{
+        PrintableReport, build_report_from_compiler_error, build_report_from_parser_error,
+        build_report_from_vm_error, build_report_from_vm_exception,
+    },
+};
+
+// the debug adapter only ever runs one thread of Aria code
+const THREAD_ID: u64 = 1;
+
+pub(crate) type Output = Arc<Mutex<Box<dyn Write + Send>>>;
+
+// writes protocol messages, each framed by a Content-Length header
+struct Outbox {
+    out: Output,
+    seq: Mutex<u64>,
+}
+
+impl Outbox {
+    fn send(&self, mut message: Value) {
+        {
+            let mut seq = self.seq.lock().unwrap();
+            *seq += 1;
+            message["seq"] = json!(*seq);
+        }
+        let text = message.to_string();
+        let mut out = self.out.lock().unwrap();
+        let _ = write!(out, "Content-Length: {}\r\n\r\n{text}", text.len());
+        let _ = out.flush();
+    }
+
+    fn event(&self, event: &str, body: Value) {
+        self.send(json!({"type": "event", "event": event, "body": body}));
+    }
+
+    fn respond(&self, request: &Value, body: Value) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    fn fail(&self, request: &Value, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+}
+
+pub(crate) fn read_message(input: &mut impl BufRead) -> Option<Value> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+
+    let mut body = vec![0; length?];
+    input.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+// state that the protocol reader updates while the program runs
+#[derive(Default)]
+struct Shared {
+    breakpoints: Mutex<HashMap<String, HashSet<usize>>>,
+    pause: AtomicBool,
+}
+
+fn canonical_path(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .ok()
+        .and_then(|p| p.to_str().map(str::to_owned))
+        .unwrap_or_else(|| path.to_owned())
+}
+
+// answers the requests that do not need the VM, and queues the rest for the
+// thread running the program
+fn read_requests(
+    mut input: impl BufRead,
+    outbox: Arc<Outbox>,
+    shared: Arc<Shared>,
+    to_vm: Sender<Value>,
+    exit_on_disconnect: bool,
+) {
+    while let Some(request) = read_message(&mut input) {
+        if request["type"] != "request" {
+            continue;
+        }
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                outbox.respond(
+                    &request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsEvaluateForHovers": true,
+                    }),
+                );
+                outbox.event("initialized", json!({}));
+            }
+            "setBreakpoints" => {
+                let path = canonical_path(
+                    request["arguments"]["source"]["path"]
+                        .as_str()
+                        .unwrap_or_default(),
+                );
+                let lines = request["arguments"]["breakpoints"]
+                    .as_array()
+                    .map(|bps| {
+                        bps.iter()
+                            .filter_map(|bp| bp["line"].as_u64())
+                            .map(|l| l as usize)
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                let verified = lines
+                    .iter()
+                    .map(|line| json!({"verified": true, "line": line}))
+                    .collect::<Vec<_>>();
+                shared
+                    .breakpoints
+                    .lock()
+                    .unwrap()
+                    .insert(path, lines.into_iter().collect());
+                outbox.respond(&request, json!({"breakpoints": verified}));
+            }
+            "setExceptionBreakpoints" => outbox.respond(&request, json!({})),
+            "threads" => outbox.respond(
+                &request,
+                json!({"threads": [{"id": THREAD_ID, "name": "main"}]}),
+            ),
+            "pause" => {
+                shared.pause.store(true, Ordering::SeqCst);
+                outbox.respond(&request, json!({}));
+            }
+            "disconnect" | "terminate" => {
+                outbox.respond(&request, json!({}));
+                if exit_on_disconnect {
+                    std::process::exit(0);
+                }
+                let _ = to_vm.send(request);
+                return;
+            }
+            _ => {
+                if to_vm.send(request).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// program output goes to the client, since stdout carries the protocol
+struct DapConsole {
+    outbox: Arc<Outbox>,
+}
+
+impl DapConsole {
+    fn output(&self, category: &str, text: &str) {
+        self.outbox
+            .event("output", json!({"category": category, "output": text}));
+    }
+}
+
+impl Console for DapConsole {
+    fn print(&mut self, s: &str) -> std::io::Result<()> {
+        self.output("stdout", s);
+        Ok(())
+    }
+
+    fn println(&mut self, s: &str) -> std::io::Result<()> {
+        self.output("stdout", &format!("{s}\n"));
+        Ok(())
+    }
+
+    fn eprintln(&mut self, s: &str) -> std::io::Result<()> {
+        self.output("stderr", &format!("{s}\n"));
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+enum Handle {
+    Locals(usize),
+    Stack(usize),
+    Value(RuntimeValue),
+}
+
+struct DapHandler {
+    outbox: Arc<Outbox>,
+    shared: Arc<Shared>,
+    requests: Rc<Receiver<Value>>,
+    // variablesReference n refers to handles[n - 1]; they are only valid
+    // until execution resumes
+    handles: Vec<Handle>,
+}
+
+impl DapHandler {
+    fn new_handle(&mut self, handle: Handle) -> usize {
+        self.handles.push(handle);
+        self.handles.len()
+    }
+
+    fn value_handle(&mut self, val: &RuntimeValue) -> usize {
+        if val.as_list().is_some() || val.as_map().is_some() || val.as_object().is_some() {
+            self.new_handle(Handle::Value(val.clone()))
+        } else {
+            0
+        }
+    }
+
+    fn variable(&mut self, vm: &mut PausedVm<'_>, name: String, val: &RuntimeValue) -> Value {
+        json!({
+            "name": name,
+            "value": vm.describe(val),
+            "variablesReference": self.value_handle(val),
+        })
+    }
+
+    fn stack_trace(&self, vm: &mut PausedVm<'_>, request: &Value) {
+        let frames = vm
+            .stack_frames()
+            .into_iter()
+            .enumerate()
+            .map(|(depth, frame)| {
+                let mut dap_frame = json!({
+                    "id": depth,
+                    "name": frame.name,
+                    "line": 0,
+                    "column": 0,
+                });
+                if let Some(loc) = frame.location {
+                    let name = Path::new(&loc.file)
+                        .file_name()
+                        .map_or(loc.file.clone(), |n| n.to_string_lossy().into_owned());
+                    dap_frame["source"] = json!({"name": name, "path": loc.file});
+                    dap_frame["line"] = json!(loc.line);
+                    dap_frame["column"] = json!(1);
+                }
+                dap_frame
+            })
+            .collect::<Vec<_>>();
+        self.outbox.respond(
+            request,
+            json!({"totalFrames": frames.len(), "stackFrames": frames}),
+        );
+    }
+
+    fn scopes(&mut self, request: &Value) {
+        let depth = request["arguments"]["frameId"].as_u64().unwrap_or(0) as usize;
+        let locals = self.new_handle(Handle::Locals(depth));
+        let stack = self.new_handle(Handle::Stack(depth));
+        self.outbox.respond(
+            request,
+            json!({"scopes": [
+                {"name": "Locals", "variablesReference": locals, "expensive": false},
+                {"name": "Stack", "variablesReference": stack, "expensive": false},
+            ]}),
+        );
+    }
+
+    fn variables(&mut self, vm: &mut PausedVm<'_>, request: &Value) {
+        let reference = request["arguments"]["variablesReference"]
+            .as_u64()
+            .unwrap_or(0) as usize;
+        let children = match reference.checked_sub(1).and_then(|i| self.handles.get(i)) {
+            Some(Handle::Locals(depth)) => vm.locals(*depth),
+            Some(Handle::Stack(depth)) => vm
+                .operand_stack(*depth)
+                .into_iter()
+                .enumerate()
+                .map(|(idx, val)| (format!("[{idx}]"), val))
+                .collect(),
+            Some(Handle::Value(val)) => {
+                let val = val.clone();
+                vm.children(&val)
+            }
+            None => return self.outbox.fail(request, "unknown variables reference"),
+        };
+        let variables = children
+            .into_iter()
+            .map(|(name, val)| self.variable(vm, name, &val))
+            .collect::<Vec<_>>();
+        self.outbox
+            .respond(request, json!({"variables": variables}));
+    }
+
+    fn evaluate(&mut self, vm: &mut PausedVm<'_>, request: &Value) {
+        let args = &request["arguments"];
+        let depth = args["frameId"].as_u64().unwrap_or(0) as usize;
+        let expr = args["expression"].as_str().unwrap_or_default();
+        match vm.evaluate(depth, expr) {
+            Ok(val) => {
+                let result = vm.describe(&val);
+                let reference = self.value_handle(&val);
+                self.outbox.respond(
+                    request,
+                    json!({"result": result, "variablesReference": reference}),
+                );
+            }
+            Err(err) => self.outbox.fail(request, &err),
+        }
+    }
+}
+
+impl DebugHandler for DapHandler {
+    fn has_breakpoint(&self, file: &str, line: usize) -> bool {
+        self.shared
+            .breakpoints
+            .lock()
+            .unwrap()
+            .get(file)
+            .is_some_and(|lines| lines.contains(&line))
+    }
+
+    fn pause_requested(&self) -> bool {
+        self.shared.pause.swap(false, Ordering::SeqCst)
+    }
+
+    fn stopped(&mut self, reason: StopReason, vm: &mut PausedVm<'_>) -> ResumeMode {
+        let reason = match reason {
+            StopReason::Entry => "entry",
+            StopReason::Breakpoint => "breakpoint",
+            StopReason::Step => "step",
+            StopReason::Pause => "pause",
+        };
+        self.outbox.event(
+            "stopped",
+            json!({"reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true}),
+        );
+
+        self.handles.clear();
+        let requests = self.requests.clone();
+        while let Ok(request) = requests.recv() {
+            let resume = match request["command"].as_str().unwrap_or_default() {
+                "stackTrace" => {
+                    self.stack_trace(vm, &request);
+                    None
+                }
+                "scopes" => {
+                    self.scopes(&request);
+                    None
+                }
+                "variables" => {
+                    self.variables(vm, &request);
+                    None
+                }
+                "evaluate" => {
+                    self.evaluate(vm, &request);
+                    None
+                }
+                "continue" => Some(ResumeMode::Continue),
+                "next" => Some(ResumeMode::StepOver),
+                "stepIn" => Some(ResumeMode::StepIn),
+                "stepOut" => Some(ResumeMode::StepOut),
+                "disconnect" | "terminate" => return ResumeMode::Continue,
+                command => {
+                    self.outbox
+                        .fail(&request, &format!("unsupported request {command}"));
+                    None
+                }
+            };
+            if let Some(resume) = resume {
+                self.outbox
+                    .respond(&request, json!({"allThreadsContinued": true}));
+                return resume;
+            }
+        }
+        ResumeMode::Continue
+    }
+}
+
+fn write_report(vm: &VirtualMachine, report: PrintableReport<'_>) {
+    let console = vm.console().clone();
+    let mut console = console.borrow_mut();
+    let _ = report.0.write(report.1, &mut *console);
+}
+
+fn run_program(vm: &mut VirtualMachine, path: &str, args: &Args) -> i32 {
+    let sb = match SourceBuffer::file(path) {
+        Ok(sb) => sb,
+        Err(err) => {
+            let _ = vm
+                .console()
+                .borrow_mut()
+                .eprintln(&format!("error reading source file: {err}"));
+            return 1;
+        }
+    };
+    let ast = match source_to_ast(&sb) {
+        Ok(ast) => ast,
+        Err(err) => {
+            write_report(vm, build_report_from_parser_error(&err));
+            return 1;
+        }
+    };
+    let c_module = match compile_from_ast(&ast, &CompilationOptions::from(args)) {
+        Ok(module) => module,
+        Err(errs) => {
+            errs.iter()
+                .for_each(|e| write_report(vm, build_report_from_compiler_error(e)));
+            return 1;
+        }
+    };
+    let r_module = match RuntimeModule::new(vm, c_module) {
+        Ok(m) => m,
+        Err(err) => {
+            write_report(vm, build_report_from_vm_error(&err.into()));
+            return 1;
+        }
+    };
+
+    let result = match vm.load_into_module("", r_module) {
+        Ok(RunloopExit::Ok(m)) => vm.execute_module(&m.module),
+        other => other.map(|_| RunloopExit::Ok(())),
+    };
+    match result {
+        Ok(RunloopExit::Ok(_)) => 0,
+        Ok(RunloopExit::Exception(exc)) => {
+            let report = build_report_from_vm_exception(vm, &exc);
+            write_report(vm, report);
+            1
+        }
+        Err(err) => {
+            write_report(vm, build_report_from_vm_error(&err));
+            1
+        }
+    }
+}
+
+pub(crate) fn run_session(
+    input: impl BufRead + Send + 'static,
+    output: Output,
+    args: &Args,
+    exit_on_disconnect: bool,
+) -> i32 {
+    let outbox = Arc::new(Outbox {
+        out: output,
+        seq: Mutex::new(0),
+    });
+    let shared = Arc::new(Shared::default());
+    let (to_vm, requests) = channel();
+    {
+        let outbox = outbox.clone();
+        let shared = shared.clone();
+        std::thread::spawn(move || read_requests(input, outbox, shared, to_vm, exit_on_disconnect));
+    }
+
+    // the program starts once it is known and the client has sent its
+    // breakpoints
+    let mut launch = None;
+    let mut configured = false;
+    while launch.is_none() || !configured {
+        let Ok(request) = requests.recv() else {
+            return 0;
+        };
+        match request["command"].as_str().unwrap_or_default() {
+            "launch" => {
+                if request["arguments"]["program"].is_string() {
+                    outbox.respond(&request, json!({}));
+                    launch = Some(request["arguments"].clone());
+                } else {
+                    outbox.fail(&request, "launch needs a program to run");
+                }
+            }
+            "configurationDone" => {
+                outbox.respond(&request, json!({}));
+                configured = true;
+            }
+            "disconnect" | "terminate" => return 0,
+            command => outbox.fail(&request, &format!("unsupported request {command}")),
+        }
+    }
+    let launch = launch.unwrap();
+
+    if let Some(cwd) = launch["cwd"].as_str()
+        && let Err(err) = std::env::set_current_dir(cwd)
+    {
+        outbox.event(
+            "output",
+            json!({"category": "stderr", "output": format!("cannot change to {cwd}: {err}\n")}),
+        );
+    }
+
+    let mut vm_options = VmOptions::from(args);
+    vm_options.console = Rc::new(RefCell::new(DapConsole {
+        outbox: outbox.clone(),
+    }));
+    vm_options.vm_args = launch["args"]
+        .as_array()
+        .map(|args| {
+            args.iter()
+                .filter_map(|a| a.as_str().map(str::to_owned))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut vm = VirtualMachine::with_options(vm_options);
+
+    let requests = Rc::new(requests);
+    vm.attach_debugger(
+        Box::new(DapHandler {
+            outbox: outbox.clone(),
+            shared,
+            requests: requests.clone(),
+            handles: vec![],
+        }),
+        launch["stopOnEntry"].as_bool().unwrap_or(false),
+    );
+
+    let program = launch["program"].as_str().unwrap_or_default();
+    let exit_code = run_program(&mut vm, program, args);
+    vm.detach_debugger();
+
+    outbox.event("exited", json!({"exitCode": exit_code}));
+    outbox.event("terminated", json!({}));
+
+    // nothing is stopped any more, but the client may still ask
+    while let Ok(request) = requests.recv() {
+        match request["command"].as_str().unwrap_or_default() {
+            "disconnect" | "terminate" => break,
+            _ => outbox.fail(&request, "the program has exited"),
+        }
+    }
+    exit_code
+}
+
+pub(crate) fn dap(args: &Args) -> i32 {
+    let input = std::io::BufReader::new(std::io::stdin());
+    run_session(
+        input,
+        Arc::new(Mutex::new(Box::new(std::io::stdout()))),
+        args,
+        true,
+    )
+}
@@ -1,11 +1,11 @@
 // SPDX-License-Identifier: Apache-2.0
 use std::{collections::HashMap, vec};
 
-use aria_compiler::do_compile::CompilationError;
+use aria_compiler::{checker::CheckError, do_compile::CompilationError};
 use aria_parser::ast::{ParserError, SourcePointer};
 use ariadne::{Color, Label, Report, ReportKind, Source};
 use haxby_vm::{
-    error::{exception::VmException, vm_error::VmError},
+    error::{backtrace::Backtrace, exception::VmException, vm_error::VmError},
     vm::VirtualMachine,
 };
 
@@ -38,21 +38,86 @@ pub(crate) type PrintableReport<'a> = (
 fn build_report_from_msg_and_location<'a>(
     msg: &str,
     locations: Vec<SourcePointer>,
+) -> PrintableReport<'a> {
+    build_report_of_kind(ReportKind::Error, msg, here_labels(locations), vec![])
+}
+
+fn here_labels(locations: Vec<SourcePointer>) -> Vec<(SourcePointer, String)> {
+    locations
+        .into_iter()
+        .map(|loc| (loc, "here".to_owned()))
+        .collect()
+}
+
+// a label naming the function of each frame, and a note that lists the frames
+// the way a traceback does, outermost first
+// runaway recursion leaves a backtrace with a great many entries; only the
+// calls at both ends of it are shown
+const MAX_TRACEBACK_ENTRIES: usize = 20;
+
+fn build_traceback(backtrace: &Backtrace) -> (Vec<(SourcePointer, String)>, Option<String>) {
+    let labels = backtrace
+        .entries_iter()
+        .take(MAX_TRACEBACK_ENTRIES)
+        .map(|entry| {
+            let msg = match &entry.function {
+                Some(function) => format!("in {function}"),
+                None => "here".to_owned(),
+            };
+            (entry.loc.clone(), msg)
+        })
+        .collect();
+    if backtrace.len() < 2 {
+        return (labels, None);
+    }
+    let entries = backtrace.entries_iter().rev().collect::<Vec<_>>();
+    let hidden = entries.len().saturating_sub(MAX_TRACEBACK_ENTRIES);
+    let mut traceback = "traceback (most recent call last):".to_owned();
+    for (i, entry) in entries.iter().enumerate() {
+        let shown = MAX_TRACEBACK_ENTRIES / 2;
+        if hidden > 0 && i >= shown && i < shown + hidden {
+            if i == shown {
+                traceback.push_str(&format!("\n  ... {hidden} more calls"));
+            }
+            continue;
+        }
+        // past the most recent ones, the frames that tail calls replaced
+        // are gone and only their number is known
+        match entry.tail_calls {
+            0 => {}
+            1 => traceback.push_str("\n  ... 1 tail call"),
+            n => traceback.push_str(&format!("\n  ... {n} tail calls")),
+        }
+        traceback.push_str(&format!(
+            "\n  {entry}\n      {}",
+            entry.source_line().trim()
+        ));
+    }
+    (labels, Some(traceback))
+}
+
+fn build_report_of_kind<'a>(
+    kind: ReportKind<'a>,
+    msg: &str,
+    labels: Vec<(SourcePointer, String)>,
+    notes: Vec<String>,
 ) -> PrintableReport<'a> {
     let config = ariadne::Config::default().with_index_type(ariadne::IndexType::Byte);
     let magenta = Color::Magenta;
-    let mut report = Report::build(ReportKind::Error, ("unknown".to_owned(), 0..0))
+    let mut report = Report::build(kind, ("unknown".to_owned(), 0..0))
         .with_message(msg)
         .with_config(config);
+    for note in notes {
+        report = report.with_note(note);
+    }
     let mut cache = StringCache::default();
-    for (idx, loc) in locations.iter().enumerate() {
-        let loc = loc.clone();
+    for (idx, (loc, label)) in labels.into_iter().enumerate() {
         report = report.with_label(
             Label::new((
                 loc.buffer.name.clone(),
                 loc.location.start..loc.location.stop,
             ))
-            .with_message("here")
+            .with_message(label)
             .with_order(idx as i32)
             .with_color(magenta),
         );
@@ -76,6 +141,11 @@ pub(crate) fn print_report_from_compiler_error(err: &CompilationError) {
     report.eprint(cache).unwrap();
 }
 
+pub(crate) fn print_report_from_checker_error(err: &CheckError) {
+    let (report, cache) = build_report_from_checker_error(err);
+    report.eprint(cache).unwrap();
+}
+
 pub(crate) fn print_report_from_parser_error(err: &ParserError) {
     let (report, cache) = build_report_from_parser_error(err);
     report.eprint(cache).unwrap();
@@ -95,8 +165,13 @@ pub(crate) fn build_report_from_vm_error<'a>(err: &VmError) -> PrintableReport<'
             build_report_from_msg_and_location(&msg, vec![])
         }
     } else {
-        let backtraces: Vec<_> = err.backtrace.entries_iter().cloned().collect();
-        build_report_from_msg_and_location(&msg, backtraces)
+        let (labels, traceback) = build_traceback(&err.backtrace);
+        build_report_of_kind(
+            ReportKind::Error,
+            &msg,
+            labels,
+            traceback.into_iter().collect(),
+        )
     }
 }
 
@@ -106,8 +181,16 @@ pub(crate) fn build_report_from_vm_exception<'a>(
 ) -> PrintableReport<'a> {
     let mut cur_frame = Default::default();
     let msg = exc.value.prettyprint(&mut cur_frame, vm);
-    let backtraces: Vec<_> = exc.backtrace.entries_iter().cloned().collect();
-    build_report_from_msg_and_location(&msg, backtraces)
+    let (labels, traceback) = build_traceback(&exc.backtrace);
+    let notes = traceback
+        .into_iter()
+        .chain(
+            exc.causes(vm)
+                .iter()
+                .map(|cause| format!("caused by: {}", cause.prettyprint(&mut cur_frame, vm))),
+        )
+        .collect();
+    build_report_of_kind(ReportKind::Error, &msg, labels, notes)
 }
 
 pub(crate) fn build_report_from_compiler_error<'a>(
@@ -118,6 +201,16 @@ pub(crate) fn build_report_from_compiler_error<'a>(
     build_report_from_msg_and_location(&msg, vec![loc.clone()])
 }
 
+pub(crate) fn build_report_from_checker_error<'a>(err: &'a CheckError) -> PrintableReport<'a> {
+    let msg = err.reason.to_string();
+    let kind = if err.reason.is_warning() {
+        ReportKind::Warning
+    } else {
+        ReportKind::Error
+    };
+    build_report_of_kind(kind, &msg, here_labels(vec![err.loc.clone()]), vec![])
+}
+
 pub(crate) fn build_report_from_parser_error<'a>(err: &'a ParserError) -> PrintableReport<'a> {
     let msg = &err.msg;
     let loc = &err.loc;
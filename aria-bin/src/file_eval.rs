@@ -1,5 +1,10 @@
 // SPDX-License-Identifier: Apache-2.0
-use aria_compiler::{CompilationOptions, compile_from_ast};
+use std::path::{Path, PathBuf};
+
+use aria_compiler::{
+    CompilationOptions, compile_from_ast,
+    widget::{MANIFEST_FILE_NAME, Manifest, find_widget_root},
+};
 use aria_parser::ast::{
     SourceBuffer,
     prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
@@ -13,8 +18,8 @@ use haxby_vm::{
 use crate::{
     Args,
     error_reporting::{
-        print_report_from_compiler_error, print_report_from_parser_error,
-        print_report_from_vm_error, print_report_from_vm_exception,
+        print_report_from_checker_error, print_report_from_compiler_error,
+        print_report_from_parser_error, print_report_from_vm_error, print_report_from_vm_exception,
     },
 };
 
@@ -49,7 +54,7 @@ fn eval_buffer(
 
     let comp_opts = CompilationOptions::from(args);
 
-    let c_module = match compile_from_ast(&ast, &comp_opts) {
+    let mut c_module = match compile_from_ast(&ast, &comp_opts) {
         Ok(module) => module,
         Err(err) => {
             err.iter().for_each(print_report_from_compiler_error);
@@ -57,6 +62,13 @@ fn eval_buffer(
         }
     };
 
+    crate::check::compile_warnings(&ast)
+        .iter()
+        .for_each(print_report_from_checker_error);
+
+    // lets the program import from its widget and the widget's dependencies
+    c_module.widget_root_path = find_widget_root(Path::new(&sb.name));
+
     if args.dump_mod {
         let mod_buffer = PrintoutAccumulator::default();
         let output = c_module.prettyprint(mod_buffer).value();
@@ -95,6 +107,17 @@ fn eval_buffer(
     }
 }
 
+// a widget directory runs its entry point, main.aria unless the manifest
+// says otherwise
+fn entry_point(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    if !path.join(MANIFEST_FILE_NAME).is_file() {
+        return Ok(path);
+    }
+    let manifest = Manifest::load(&path).map_err(|e| e.to_string())?;
+    Ok(path.join(manifest.entry.as_deref().unwrap_or("main.aria")))
+}
+
 pub(crate) fn file_eval(path: &str, args: &Args) -> i32 {
     use pprof::protos::Message;
     use std::io::Write;
@@ -117,7 +140,21 @@ pub(crate) fn file_eval(path: &str, args: &Args) -> i32 {
         None
     };
 
-    let buffer = SourceBuffer::file(path);
+    let path = match entry_point(path) {
+        Ok(path) => path,
+        Err(err) => {
+            eprintln!("{err}");
+            return 1;
+        }
+    };
+    if let Some(options) = args.profiler_options() {
+        vm.start_profiling(options);
+    }
+    if let Some(options) = args.coverage.options() {
+        vm.start_coverage(options);
+    }
+
+    let buffer = SourceBuffer::file(&path.to_string_lossy());
     let exit = match buffer {
         Ok(src) => match eval_buffer(src, &mut vm, args) {
             Ok(_) => 0,
@@ -129,6 +166,23 @@ pub(crate) fn file_eval(path: &str, args: &Args) -> i32 {
         }
     };
 
+    if let Some(profile) = vm.stop_profiling()
+        && let Err(err) = crate::profile_report::write_profile(&profile, args)
+    {
+        eprintln!("{err}");
+        return 1;
+    }
+
+    if let Some(coverage) = vm.stop_coverage() {
+        match args.coverage.report(coverage) {
+            Ok(summary) => eprint!("{}", summary.unwrap_or_default()),
+            Err(err) => {
+                eprintln!("{err}");
+                return 1;
+            }
+        }
+    }
+
     if let Some(guard) = guard
         && let Ok(report) = guard.report().build()
     {
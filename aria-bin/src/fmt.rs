@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: Apache-2.0
+use aria_parser::{ast::SourceBuffer, format::format_source};
+
+use crate::{error_reporting::print_report_from_parser_error, sources::collect_sources};
+
+pub(crate) fn fmt(paths: &[String], check_only: bool) -> i32 {
+    let files = match collect_sources(paths) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("{err}");
+            return 1;
+        }
+    };
+
+    let mut failed = false;
+    for file in files {
+        let src = match SourceBuffer::from_path(&file) {
+            Ok(src) => src,
+            Err(err) => {
+                eprintln!("error reading source file {}: {err}", file.display());
+                failed = true;
+                continue;
+            }
+        };
+        let formatted = match format_source(&src) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                print_report_from_parser_error(&err);
+                failed = true;
+                continue;
+            }
+        };
+        if formatted == *src.content {
+            continue;
+        }
+
+        if check_only {
+            println!("{}", file.display());
+            failed = true;
+        } else if let Err(err) = std::fs::write(&file, formatted) {
+            eprintln!("error writing {}: {err}", file.display());
+            failed = true;
+        }
+    }
+
+    if failed { 1 } else { 0 }
+}
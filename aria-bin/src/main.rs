@@ -1,22 +1,57 @@
 // SPDX-License-Identifier: Apache-2.0
+mod check;
+mod coverage_report;
+mod dap;
 mod error_reporting;
 mod file_eval;
+mod fmt;
+mod profile_report;
 mod repl_eval;
+mod sources;
+mod test_report;
+mod test_runner;
+mod widget;
 
 #[cfg(test)]
 mod test;
 
-use clap::Parser;
-use haxby_vm::vm::{VirtualMachine, VmOptions};
+use std::{path::PathBuf, time::Duration};
+
+use clap::{Parser, Subcommand};
+use haxby_vm::{
+    profiler::ProfilerOptions,
+    sandbox::Permissions,
+    vm::{VirtualMachine, VmOptions},
+};
 
 #[derive(Default, Parser, Debug)]
-#[command(author, name = "aria", version = env!("CARGO_PKG_VERSION"), about, trailing_var_arg = true)]
+#[command(author, name = "aria", version = env!("CARGO_PKG_VERSION"), about, trailing_var_arg = true, args_conflicts_with_subcommands = true)]
 struct Args {
-    /// The name of the program file to run
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// The name of the program file to run, or a widget directory to run the entry point of
     path: Option<String>,
     /// The destination for the VM performance trace
     #[arg(long("perf-trace-dest"))]
     perf_trace_dest: Option<String>,
+    /// Sample the Aria call stack and write it as folded stacks, for flame graph tools
+    #[arg(long("profile-folded"), value_name = "PATH")]
+    profile_folded: Option<PathBuf>,
+    /// Sample the Aria call stack and write it as a pprof protobuf profile
+    #[arg(long("profile-pprof"), value_name = "PATH")]
+    profile_pprof: Option<PathBuf>,
+    /// Sample the Aria call stack and count opcodes, then print the time spent in each function and how often each opcode ran
+    #[arg(long("profile-summary"))]
+    profile_summary: bool,
+    /// How often to sample the Aria call stack, in microseconds
+    #[arg(
+        long("profile-interval"),
+        value_name = "MICROSECONDS",
+        default_value_t = 1000
+    )]
+    profile_interval: u64,
+    #[command(flatten)]
+    coverage: coverage_report::CoverageArgs,
     /// Should the VM trace instruction execution
     #[arg(long("trace-exec"))]
     #[cfg(debug_assertions)]
@@ -37,6 +72,12 @@ struct Args {
     /// Turn off compile-time optimizations
     #[arg(long("disable-optimizer"))]
     disable_optimizer: bool,
+    /// Always compile imported modules from source, ignoring the bytecode cache
+    #[arg(long("no-bytecode-cache"))]
+    no_bytecode_cache: bool,
+    /// How deep Aria function calls may nest before a CallDepthExceeded error is thrown; 0 means no limit
+    #[arg(long("max-call-depth"), value_name = "DEPTH")]
+    max_call_depth: Option<usize>,
     #[arg(trailing_var_arg = true)]
     extra_args: Vec<String>,
     #[arg(long("print-lib-path"))]
@@ -44,6 +85,63 @@ struct Args {
     /// Turn off REPL preamble
     #[arg(long("no-repl-preamble"))]
     no_repl_preamble: bool,
+    /// Run as a Debug Adapter Protocol server over stdin/stdout
+    #[arg(long("dap"))]
+    dap: bool,
+    /// Run in a sandbox that only allows what the --allow-* flags grant; any of them implies it
+    #[arg(long("sandbox"))]
+    sandbox: bool,
+    /// Allow reading these files and directories, or any of them if none is given
+    #[arg(long("allow-read"), value_name = "PATH", num_args = 0.., require_equals = true, value_delimiter = ',')]
+    allow_read: Option<Vec<PathBuf>>,
+    /// Allow writing these files and directories, or any of them if none is given
+    #[arg(long("allow-write"), value_name = "PATH", num_args = 0.., require_equals = true, value_delimiter = ',')]
+    allow_write: Option<Vec<PathBuf>>,
+    /// Allow connecting to these hosts (host, host:port or [ipv6]:port), or any of them if none is given
+    #[arg(long("allow-net"), value_name = "HOST", num_args = 0.., require_equals = true, value_delimiter = ',')]
+    allow_net: Option<Vec<String>>,
+    /// Allow loading these dylibs besides the ones that ship with Aria, or any of them if none is given
+    #[arg(long("allow-dylib"), value_name = "NAME", num_args = 0.., require_equals = true, value_delimiter = ',')]
+    allow_dylib: Option<Vec<String>>,
+    /// Allow running subprocesses
+    #[arg(long("allow-run"))]
+    allow_run: bool,
+    /// Allow reading and changing environment variables
+    #[arg(long("allow-env"))]
+    allow_env: bool,
+    /// Allow exiting the process with exit()
+    #[arg(long("allow-exit"))]
+    allow_exit: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Look for type errors and other mistakes without running the program
+    Check {
+        /// The program files to check
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Rewrite program files in the canonical layout
+    Fmt {
+        /// Only list the files that are not formatted, and fail if there are any
+        #[arg(long("check"))]
+        check: bool,
+        /// The program files, or directories of them, to format
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Find and run tests: test_* functions, TestCase structs and test files
+    Test(test_runner::TestArgs),
+    /// Resolve the dependencies of a widget and write its widget.lock
+    Widget {
+        /// Only verify that widget.lock is up to date, and fail if it is not
+        #[arg(long("check"))]
+        check: bool,
+        /// The directory of the widget
+        #[arg(default_value = ".")]
+        dir: String,
+    },
 }
 
 impl From<&Args> for VmOptions {
@@ -60,22 +158,94 @@ impl From<&Args> for VmOptions {
 
         options.vm_args = value.extra_args.clone();
 
+        if value.no_bytecode_cache {
+            options.bytecode_cache_dir = None;
+        }
+
+        if let Some(permissions) = value.permissions() {
+            options.permissions = permissions;
+        }
+
+        if let Some(depth) = value.max_call_depth {
+            options.limits.max_call_depth = (depth > 0).then_some(depth);
+        }
+
         options
     }
 }
 
 impl Args {
+    // what a sandboxed program may do, or None when it is not sandboxed
+    fn permissions(&self) -> Option<Permissions> {
+        let sandboxed = self.sandbox
+            || self.allow_read.is_some()
+            || self.allow_write.is_some()
+            || self.allow_net.is_some()
+            || self.allow_dylib.is_some()
+            || self.allow_run
+            || self.allow_env
+            || self.allow_exit;
+        if !sandboxed {
+            return None;
+        }
+
+        let mut permissions = Permissions::none();
+        if let Some(paths) = &self.allow_read {
+            permissions.read.add(paths.clone());
+        }
+        if let Some(paths) = &self.allow_write {
+            permissions.write.add(paths.clone());
+        }
+        if let Some(hosts) = &self.allow_net {
+            permissions.net.add(hosts.clone());
+        }
+        if let Some(names) = &self.allow_dylib {
+            permissions.dylibs.add(names.clone());
+        }
+        permissions.run = self.allow_run;
+        permissions.env = self.allow_env;
+        permissions.exit = self.allow_exit;
+        Some(permissions)
+    }
+
+    // how to profile the program, or None when it is not profiled
+    fn profiler_options(&self) -> Option<ProfilerOptions> {
+        if self.profile_folded.is_none() && self.profile_pprof.is_none() && !self.profile_summary {
+            return None;
+        }
+        Some(ProfilerOptions {
+            interval: Duration::from_micros(self.profile_interval),
+            count_opcodes: self.profile_summary,
+        })
+    }
+
     fn check(&self) -> Vec<String> {
         let mut ret = vec![];
 
         if self.path.is_some() && self.no_repl_preamble {
             ret.push("--no-repl-preamble has no effect when a file path is provided".to_string());
         }
+        if self.dap && self.path.is_some() {
+            ret.push(
+                "--dap takes the program to run from the launch request, not the command line"
+                    .to_string(),
+            );
+        }
         if self.path.is_none() && self.perf_trace_dest.is_some() {
             ret.push(
                 "--perf-trace-dest has no effect when a file path is not provided".to_string(),
             );
         }
+        if self.path.is_none() && self.profiler_options().is_some() {
+            ret.push("--profile-* has no effect when a file path is not provided".to_string());
+        }
+        if self.profile_interval == 0 {
+            ret.push("--profile-interval must be at least 1 microsecond".to_string());
+        }
+        if self.path.is_none() && self.coverage.options().is_some() {
+            ret.push("--coverage-* has no effect when a file path is not provided".to_string());
+        }
+        ret.extend(self.coverage.check());
 
         ret
     }
@@ -104,7 +274,16 @@ fn main_loop() -> i32 {
         return 1;
     }
 
-    if let Some(path) = &args.path {
+    if let Some(command) = &args.command {
+        match command {
+            Command::Check { paths } => check::check(paths),
+            Command::Fmt { check, paths } => fmt::fmt(paths, *check),
+            Command::Test(test_args) => test_runner::run_tests(test_args),
+            Command::Widget { check, dir } => widget::widget(dir, *check),
+        }
+    } else if args.dap {
+        dap::dap(&args)
+    } else if let Some(path) = &args.path {
         file_eval::file_eval(path, &args)
     } else {
         repl_eval::repl_eval(&args)
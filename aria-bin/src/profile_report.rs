@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::collections::HashMap;
+
+use haxby_vm::profiler::Profile;
+use pprof::protos::{self, Message};
+
+use crate::Args;
+
+#[derive(Default)]
+struct StringTable {
+    strings: Vec<String>,
+    index: HashMap<String, i64>,
+}
+
+impl StringTable {
+    fn new() -> Self {
+        // pprof requires the first string to be empty
+        let mut table = Self::default();
+        table.intern("");
+        table
+    }
+
+    fn intern(&mut self, s: &str) -> i64 {
+        if let Some(idx) = self.index.get(s) {
+            return *idx;
+        }
+        let idx = self.strings.len() as i64;
+        self.strings.push(s.to_owned());
+        self.index.insert(s.to_owned(), idx);
+        idx
+    }
+}
+
+// a pprof profile with one location per Aria function and line, so that
+// `go tool pprof` and similar tools show Aria code
+pub(crate) fn to_pprof(profile: &Profile) -> protos::Profile {
+    let mut strings = StringTable::new();
+    let mut functions: HashMap<(String, Option<String>), u64> = HashMap::new();
+    let mut locations: HashMap<(u64, usize), u64> = HashMap::new();
+    let mut out = protos::Profile::default();
+    let interval = profile.interval.as_nanos() as i64;
+
+    for (stack, count) in &profile.stacks {
+        let mut location_ids = vec![];
+        // pprof lists the innermost frame first
+        for frame in stack.iter().rev() {
+            let file = frame.location.as_ref().map(|l| l.file.clone());
+            let line = frame.location.as_ref().map_or(0, |l| l.line);
+            let next_id = functions.len() as u64 + 1;
+            let function_id = *functions
+                .entry((frame.function.clone(), file.clone()))
+                .or_insert_with(|| {
+                    out.function.push(protos::Function {
+                        id: next_id,
+                        name: strings.intern(&frame.function),
+                        system_name: strings.intern(&frame.function),
+                        filename: strings.intern(file.as_deref().unwrap_or_default()),
+                        start_line: 0,
+                    });
+                    next_id
+                });
+            let next_id = locations.len() as u64 + 1;
+            let location_id = *locations.entry((function_id, line)).or_insert_with(|| {
+                out.location.push(protos::Location {
+                    id: next_id,
+                    line: vec![protos::Line {
+                        function_id,
+                        line: line as i64,
+                    }],
+                    ..Default::default()
+                });
+                next_id
+            });
+            location_ids.push(location_id);
+        }
+        out.sample.push(protos::Sample {
+            location_id: location_ids,
+            value: vec![*count as i64, *count as i64 * interval],
+            label: vec![],
+        });
+    }
+
+    let samples = strings.intern("samples");
+    let count = strings.intern("count");
+    let wall = strings.intern("wall");
+    let nanoseconds = strings.intern("nanoseconds");
+    out.sample_type = vec![
+        protos::ValueType {
+            ty: samples,
+            unit: count,
+        },
+        protos::ValueType {
+            ty: wall,
+            unit: nanoseconds,
+        },
+    ];
+    out.period_type = Some(protos::ValueType {
+        ty: wall,
+        unit: nanoseconds,
+    });
+    out.period = interval;
+    out.duration_nanos = profile.duration.as_nanos() as i64;
+    out.string_table = strings.strings;
+    out
+}
+
+pub(crate) fn write_profile(profile: &Profile, args: &Args) -> Result<(), String> {
+    if let Some(dest) = &args.profile_folded {
+        std::fs::write(dest, profile.folded())
+            .map_err(|e| format!("could not write {}: {e}", dest.display()))?;
+    }
+    if let Some(dest) = &args.profile_pprof {
+        let mut content = Vec::new();
+        to_pprof(profile)
+            .encode(&mut content)
+            .map_err(|e| format!("could not encode the pprof profile: {e}"))?;
+        std::fs::write(dest, content)
+            .map_err(|e| format!("could not write {}: {e}", dest.display()))?;
+    }
+    if args.profile_summary {
+        eprint!("{}", profile.summary());
+    }
+    Ok(())
+}
@@ -16,8 +16,8 @@ use reedline::{DefaultPrompt, Reedline, Validator};
 use crate::{
     Args,
     error_reporting::{
-        build_report_from_compiler_error, build_report_from_parser_error,
-        build_report_from_vm_error, build_report_from_vm_exception,
+        build_report_from_checker_error, build_report_from_compiler_error,
+        build_report_from_parser_error, build_report_from_vm_error, build_report_from_vm_exception,
         print_report_from_compiler_error, print_report_from_parser_error,
         print_report_from_vm_error, print_report_from_vm_exception,
     },
@@ -272,6 +272,10 @@ impl<'a> Repl<'a> {
             }
         };
 
+        crate::check::compile_warnings(&ast)
+            .iter()
+            .for_each(|w| self.print_error_report(build_report_from_checker_error(w)));
+
         if self.args.dump_mod {
             let mod_buffer = PrintoutAccumulator::default();
             let output = c_module.prettyprint(mod_buffer).value();
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::path::{Path, PathBuf};
+
+fn collect(path: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        out.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let mut entries = std::fs::read_dir(path)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "aria") {
+            collect(&entry, out)?;
+        }
+    }
+    Ok(())
+}
+
+// the program files named on the command line; directories stand for every
+// .aria file below them
+pub(crate) fn collect_sources(paths: &[String]) -> Result<Vec<PathBuf>, String> {
+    let mut files = vec![];
+    for path in paths {
+        collect(Path::new(path), &mut files)
+            .map_err(|err| format!("error reading {path}: {err}"))?;
+    }
+    Ok(files)
+}
@@ -253,6 +253,34 @@ fn repl_op_count_error() {
     );
 }
 
+#[test]
+fn repl_exception_report_shows_traceback() {
+    let cmdline_options = Args {
+        no_repl_preamble: true,
+        ..Default::default()
+    };
+    let mut repl = build_test_repl(&cmdline_options);
+
+    run_passing_repl_line(
+        &mut repl,
+        "struct Foo { type func fail() { throw \"bad\"; } }",
+        &[],
+    );
+    run_passing_repl_line(&mut repl, "func helper() { Foo.fail(); }", &[]);
+    run_check_repl_line(
+        &mut repl,
+        "helper();",
+        false,
+        &[
+            "in Foo.fail",
+            "in helper",
+            "traceback (most recent call last):",
+            "Foo.fail in repl at",
+        ],
+        &[],
+    );
+}
+
 #[test]
 fn repl_test_printf() {
     let cmdline_options = Args::default();
@@ -267,15 +295,42 @@ fn repl_test_printf() {
 }
 
 #[test]
-fn repl_test_invalid_literal() {
+fn repl_test_big_hex_literal() {
     let cmdline_options = Args::default();
     let mut repl = build_test_repl(&cmdline_options);
 
     run_check_repl_line(
         &mut repl,
         "0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
-        false,
-        &["0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF is not a valid literal"],
+        true,
+        &["6901746346790563787434755862277025452451108972170386555162524223799295"],
+        &[],
+    );
+}
+
+#[test]
+fn repl_warns_about_match_rules() {
+    let cmdline_options = Args::default();
+    let mut repl = build_test_repl(&cmdline_options);
+
+    run_check_repl_line(
+        &mut repl,
+        r#"enum Color { case Red, case Green, case Blue }
+func name(c: Color) {
+    match c {
+        case Red => { return "red"; },
+        case Green => { return "green"; },
+        case Red => { return "also red"; },
+    }
+    return "?";
+}
+name(Color::Green);"#,
+        true,
+        &[
+            "match rule can never be reached, as the rule at",
+            "match over Color has no else and no rule for Blue",
+            "green",
+        ],
         &[],
     );
 }
@@ -352,3 +407,861 @@ for i in 0.to(5) { x += i; }
         &["i = 10"],
     );
 }
+
+#[derive(Clone, Default)]
+struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn dap_session_stops_at_breakpoint() {
+    use serde_json::{Value, json};
+
+    let program = std::env::temp_dir().join(format!("aria-dap-{}.aria", std::process::id()));
+    std::fs::write(
+        &program,
+        "func main() {\n    val x = 40;\n    val y = x + 2;\n    println(y);\n}\n",
+    )
+    .unwrap();
+    let path = program.to_str().unwrap();
+
+    let requests = [
+        json!({"command": "initialize", "arguments": {"adapterID": "aria"}}),
+        json!({"command": "launch", "arguments": {"program": path}}),
+        json!({"command": "setBreakpoints", "arguments": {
+            "source": {"path": path}, "breakpoints": [{"line": 3}]}}),
+        json!({"command": "configurationDone"}),
+        json!({"command": "stackTrace", "arguments": {"threadId": 1}}),
+        json!({"command": "scopes", "arguments": {"frameId": 0}}),
+        json!({"command": "variables", "arguments": {"variablesReference": 1}}),
+        json!({"command": "evaluate", "arguments": {"expression": "x * 2", "frameId": 0}}),
+        json!({"command": "next", "arguments": {"threadId": 1}}),
+        json!({"command": "continue", "arguments": {"threadId": 1}}),
+        json!({"command": "disconnect"}),
+    ];
+    let mut input = vec![];
+    for (seq, mut request) in requests.into_iter().enumerate() {
+        request["seq"] = json!(seq + 1);
+        request["type"] = json!("request");
+        let text = request.to_string();
+        input.extend(format!("Content-Length: {}\r\n\r\n{text}", text.len()).bytes());
+    }
+
+    let output = SharedBuffer::default();
+    let exit = crate::dap::run_session(
+        std::io::Cursor::new(input),
+        std::sync::Arc::new(std::sync::Mutex::new(Box::new(output.clone()))),
+        &Args::default(),
+        false,
+    );
+    let _ = std::fs::remove_file(&program);
+    assert_eq!(exit, 0);
+
+    let data = output.0.lock().unwrap().clone();
+    let mut reader = std::io::Cursor::new(data);
+    let mut messages = vec![];
+    while let Some(message) = crate::dap::read_message(&mut reader) {
+        messages.push(message);
+    }
+    let response = |command: &str| -> Value {
+        messages
+            .iter()
+            .find(|m| m["type"] == "response" && m["command"] == command)
+            .unwrap_or_else(|| panic!("no response to {command}"))
+            .clone()
+    };
+    let stops = messages
+        .iter()
+        .filter(|m| m["event"] == "stopped")
+        .map(|m| m["body"]["reason"].as_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(stops, ["breakpoint", "step"]);
+
+    let frames = &response("stackTrace")["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "main");
+    assert_eq!(frames[0]["line"], 3);
+
+    let variables = &response("variables")["body"]["variables"];
+    assert_eq!(variables[0]["name"], "x");
+    assert_eq!(variables[0]["value"], "40");
+
+    let evaluate = response("evaluate");
+    assert_eq!(evaluate["success"], true);
+    assert_eq!(evaluate["body"]["result"], "80");
+
+    assert!(
+        messages
+            .iter()
+            .any(|m| m["event"] == "output" && m["body"]["output"] == "42\n")
+    );
+    assert!(
+        messages
+            .iter()
+            .any(|m| m["event"] == "exited" && m["body"]["exitCode"] == 0)
+    );
+}
+
+fn check_source(source: &str) -> Vec<String> {
+    let src = aria_parser::ast::SourceBuffer::stdin(source);
+    let ast = aria_parser::ast::source_to_ast(&src).unwrap();
+    aria_compiler::checker::check_module(&ast)
+        .iter()
+        .map(|e| e.reason.to_string())
+        .collect()
+}
+
+#[test]
+fn check_flags_calls_with_wrong_arguments() {
+    let errors = check_source(
+        r#"
+struct Point {
+    type func new(x: Int, y: Int) {
+        return alloc(This) { .x = x, .y = y };
+    }
+    func scale(k: Int|Float) {
+        return Point.new(this.x * k, this.y * k);
+    }
+}
+func norm(p: Point, squared = false) {
+    return p.x * p.x + p.y * p.y;
+}
+func main() {
+    val p = Point.new(1, 2, 3);
+    val q = Point.new("1", 2);
+    q.scale(2.5);
+    q.scale("2");
+    norm(q, true);
+    norm(3);
+    val r: Float = 1;
+}
+"#,
+    );
+    assert_eq!(
+        errors,
+        [
+            "'new' accepts 2 arguments, but 3 were provided",
+            "argument 'x' of 'new' expects Int, but a value of type String was provided",
+            "argument 'k' of 'scale' expects Int|Float, but a value of type String was provided",
+            "argument 'p' of 'norm' expects Point, but a value of type Int was provided",
+            "'r' is declared as Float, but a value of type Int is stored in it",
+        ]
+    );
+}
+
+#[test]
+fn check_flags_unknown_attributes_and_cases() {
+    let errors = check_source(
+        r#"
+enum Shape {
+    case Circle(Float),
+    case Square(Float),
+    func describe() { return "shape"; }
+}
+struct Counter {
+    type func new() = alloc(This) { .count = 0 };
+    func bump() { this.count += 1; this.last = this.count; }
+}
+func main() {
+    val c = Counter.new();
+    c.bump();
+    println(c.count, c.last, c.total);
+    val s = Shape::Circle(1.0);
+    println(s.describe(), s.is_Circle(), s.unwrap_Square(), s.area());
+    println(Shape::Triangle);
+    println(Counter.default);
+}
+"#,
+    );
+    assert_eq!(
+        errors,
+        [
+            "Counter has no attribute 'total'",
+            "Shape has no attribute 'area'",
+            "enum Shape has no case 'Triangle'",
+            "type Counter has no attribute 'default'",
+        ]
+    );
+}
+
+#[test]
+fn check_knows_the_cause_of_thrown_values() {
+    let errors = check_source(
+        r#"
+struct ConfigError {
+    type func new(msg) = alloc(This) { .msg };
+}
+func main() {
+    try {
+        try {
+            throw "file missing";
+        } catch e {
+            throw ConfigError.new("cannot load config") from e;
+        }
+    } catch e: ConfigError {
+        println(e.msg, e.cause, e.reason);
+    }
+}
+"#,
+    );
+    assert_eq!(errors, ["ConfigError has no attribute 'reason'"]);
+}
+
+#[test]
+fn check_flags_unreachable_match_rules() {
+    let errors = check_source(
+        r#"
+enum Token {
+    case Number(Int),
+    case Word(String),
+}
+func size(n) {
+    match n {
+        < 10 => { return "small"; },
+        < 5 => { return "tiny"; },
+        == 7 => { return "seven"; },
+        >= 10 and < 100 => { return "medium"; },
+        >= 20 => { return "large"; },
+    }
+}
+func kind(t: Token) {
+    match t {
+        case Number(n) => { return n; },
+        case Number(n: Int) => { return n; },
+        case Word(w: String) => { return w; },
+        case Word(w) => { return w; },
+        case Symbol => { return 0; },
+    }
+}
+"#,
+    );
+    assert_eq!(
+        errors,
+        [
+            "match rule can never be reached, as the rule at <stdin>:8 matches first",
+            "match rule can never be reached, as the rule at <stdin>:8 matches first",
+            "match rule can never be reached, as the rule at <stdin>:17 matches first",
+            "enum Token has no case 'Symbol'",
+        ]
+    );
+}
+
+#[test]
+fn check_flags_unreachable_destructuring_rules() {
+    let errors = check_source(
+        r#"
+func shape(v) {
+    match v {
+        [a, b] if a == b => { return "same"; },
+        [a, b] => { return "pair"; },
+        [0, _] => { return "zero pair"; },
+        [_, ...rest] => { return "some"; },
+        [x, y, z] => { return "triple"; },
+        case Some(Ok(x)) => { return x; },
+        case Some(_) => { return 0; },
+        case Some(Err(e)) => { return e; },
+    }
+}
+"#,
+    );
+    assert_eq!(
+        errors,
+        [
+            "match rule can never be reached, as the rule at <stdin>:5 matches first",
+            "match rule can never be reached, as the rule at <stdin>:7 matches first",
+            "match rule can never be reached, as the rule at <stdin>:10 matches first",
+        ]
+    );
+}
+
+#[test]
+fn check_flags_non_exhaustive_matches() {
+    let errors = check_source(
+        r#"
+enum Light {
+    case Red,
+    case Amber,
+    case Green,
+}
+func go(l: Light) {
+    match l {
+        case Red => { return false; },
+        case Amber => { return false; },
+    }
+}
+func wait(l: Light) {
+    match l {
+        case Green => { return false; },
+        case Red if l == Light::Red => { return true; },
+    } else {
+        return true;
+    }
+}
+func all(l: Light) {
+    match l {
+        case Red => { return 1; },
+        case Amber => { return 1; },
+        case Green => { return 1; },
+        isa Light => { return 2; },
+    } else {
+        return 3;
+    }
+}
+func unwrap(m) {
+    match m {
+        case Some(Ok(x)) => { return x; },
+        case None => { return 0; },
+    }
+}
+"#,
+    );
+    assert_eq!(
+        errors,
+        [
+            "match over Light has no else and no rule for Green",
+            "match rule can never be reached, as the rules before it handle every case of Light",
+            "else can never run, as the rules handle every case of Light",
+            "match over Maybe has no else and no rule for Some(Err)",
+        ]
+    );
+}
+
+fn format_source(source: &str) -> String {
+    let src = aria_parser::ast::SourceBuffer::stdin(source);
+    aria_parser::format::format_source(&src).unwrap()
+}
+
+#[test]
+fn fmt_normalizes_indentation_and_spacing() {
+    let formatted = format_source(
+        r#"func add(a,b){
+return a+b;
+}
+struct Pair{
+  type func new(x : Int|Float,y) = alloc(This){ .x=x, .y=y };
+  operator ==(rhs) { return this.x==rhs.x&&this.y==-rhs.y; }
+}
+val f = | x , y | =>x*y;
+"#,
+    );
+    assert_eq!(
+        formatted,
+        r#"func add(a, b) {
+    return a + b;
+}
+struct Pair {
+    type func new(x: Int|Float, y) = alloc(This) { .x = x, .y = y };
+    operator ==(rhs) { return this.x == rhs.x && this.y == -rhs.y; }
+}
+val f = |x, y| => x * y;
+"#
+    );
+}
+
+#[test]
+fn fmt_keeps_comments_and_blank_lines() {
+    let formatted = format_source(
+        r#"# a header comment
+
+
+val x = 1;   # trailing comment
+func foo() {
+
+    # inside
+    val y = x
+        + 2;
+
+
+    return y;
+
+}
+"#,
+    );
+    assert_eq!(
+        formatted,
+        r#"# a header comment
+
+val x = 1; # trailing comment
+func foo() {
+    # inside
+    val y = x
+        + 2;
+
+    return y;
+}
+"#
+    );
+}
+
+#[test]
+fn fmt_normalizes_trailing_commas() {
+    let formatted = format_source(
+        r#"val l = [1, 2, 3,];
+val m = foo(
+    1,
+    2
+);
+enum E { case A, case B, }
+match x {
+    case A => { println("a"); }
+    case B => { println("b"); }
+}
+"#,
+    );
+    assert_eq!(
+        formatted,
+        r#"val l = [1, 2, 3];
+val m = foo(
+    1,
+    2,
+);
+enum E { case A, case B }
+match x {
+    case A => { println("a"); },
+    case B => { println("b"); },
+}
+"#
+    );
+}
+
+#[test]
+fn fmt_keeps_interpolated_strings_whole() {
+    let formatted = format_source(
+        r#"val s = f"it's {x+1:>4} and {"a"+'b'}"+f'{y}';
+"#,
+    );
+    assert_eq!(
+        formatted,
+        r#"val s = f"it's {x+1:>4} and {"a"+'b'}" + f'{y}';
+"#
+    );
+}
+
+#[test]
+fn fmt_keeps_multiline_raw_and_byte_strings_whole() {
+    let formatted = format_source(
+        r#"val s = """
+    a+b "c"
+    """+r"\d+"+b"\"x\""+"\"";
+"#,
+    );
+    assert_eq!(
+        formatted,
+        r#"val s = """
+    a+b "c"
+    """ + r"\d+" + b"\"x\"" + "\"";
+"#
+    );
+}
+
+#[test]
+fn malformed_strings_are_reported_where_they_go_wrong() {
+    let parse_error = |source: &str| {
+        let src = aria_parser::ast::SourceBuffer::stdin(source);
+        let err = aria_parser::ast::source_to_ast(&src).unwrap_err();
+        (err.loc.location.start, err.msg)
+    };
+
+    // \" escapes the quote, so this string runs on past the end of its line
+    let (at, msg) = parse_error("func main() {\n    val p = \"C:\\\";\n    println(p);\n}\n");
+    assert_eq!(at, 29);
+    assert!(msg.starts_with("unterminated string literal: an escaped quote"));
+    assert!(msg.contains("r\"...\""));
+
+    let (at, msg) = parse_error("val x = \"ab\\x\";\n");
+    assert_eq!(at, 11);
+    assert!(msg.contains("\\x takes exactly two hex digits"));
+    let (at, msg) = parse_error("val x = f'{1} \\x4g';\n");
+    assert_eq!(at, 14);
+    assert!(msg.contains("\\x takes exactly two hex digits"));
+    let (at, msg) = parse_error("val x = b\"\\u{}\";\n");
+    assert_eq!(at, 10);
+    assert!(msg.contains("\\u takes one to six hex digits"));
+    for not_a_char in ["110000", "D800", "dfff", "00DABC", "7FFFFF"] {
+        let (at, msg) = parse_error(&format!("val x = \"a\\u{{{not_a_char}}}\";\n"));
+        assert_eq!(at, 10, "{not_a_char}");
+        assert!(
+            msg.contains("takes a code point up to 10FFFF"),
+            "{not_a_char}"
+        );
+    }
+    let src = aria_parser::ast::SourceBuffer::stdin(
+        "val x = \"\\u{10FFFF}\\u{D7FF}\\u{0E000}\\u{0}\";\n",
+    );
+    assert!(aria_parser::ast::source_to_ast(&src).is_ok());
+
+    let (at, msg) = parse_error("val x = 1;\nval y = \"abc;\n");
+    assert_eq!((at, msg.as_str()), (19, "unterminated string literal"));
+
+    // errors that have nothing to do with strings are left alone
+    let (at, msg) = parse_error("val x = [1;\nval y = \"a\\\\\";\n");
+    assert_eq!(at, 10);
+    assert!(!msg.contains("string"));
+}
+
+#[test]
+fn fmt_is_idempotent_on_the_standard_library() {
+    let lib = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../lib/aria/core");
+    for entry in std::fs::read_dir(lib).unwrap() {
+        let path = entry.unwrap().path();
+        let src = aria_parser::ast::SourceBuffer::from_path(&path).unwrap();
+        let once = aria_parser::format::format_source(&src).unwrap();
+        assert_eq!(format_source(&once), once, "{}", path.display());
+    }
+}
+
+#[test]
+fn test_runner_parses_tags() {
+    let tags = aria_parser::tags::parse_tags(
+        "### TAGS: xfail, Flaky\nval x = 1;\n  ###tags:slow\n## TAGS: no\n",
+    );
+    let mut tags = tags.into_iter().collect::<Vec<_>>();
+    tags.sort();
+    assert_eq!(tags, ["FLAKY", "SLOW", "XFAIL"]);
+}
+
+#[test]
+fn test_runner_discovers_and_reports_tests() {
+    use clap::Parser;
+
+    let dir = std::env::temp_dir().join(format!("aria-test-runner-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("units.aria"),
+        r#"import TestCase from aria.test.test;
+struct Passing {
+    func test() { this.assert_equal(1, 1); }
+    include TestCase
+}
+struct Inherited : TestCase {
+    func test() { this.assert_equal(2, 2); }
+}
+func test_passes() { assert 1 + 1 == 2; }
+func test_fails() { println("about to fail"); assert 1 + 1 == 3; }
+func helper() { return 3; }
+"#,
+    )
+    .unwrap();
+    std::fs::write(dir.join("script.aria"), "func main() { assert true; }\n").unwrap();
+    std::fs::write(
+        dir.join("expected.aria"),
+        "### TAGS: XFAIL\nfunc test_broken() { assert false; }\n",
+    )
+    .unwrap();
+
+    let report = dir.join("report.json");
+    let args = crate::Args::try_parse_from([
+        "aria",
+        "test",
+        dir.to_str().unwrap(),
+        "--json",
+        report.to_str().unwrap(),
+    ])
+    .unwrap();
+    let Some(crate::Command::Test(test_args)) = &args.command else {
+        panic!("expected a test command");
+    };
+    assert_eq!(crate::test_runner::run_tests(test_args), 1);
+
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    let outcomes = report["tests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| {
+            (
+                t["name"]
+                    .as_str()
+                    .unwrap()
+                    .rsplit('/')
+                    .next()
+                    .unwrap()
+                    .to_owned(),
+                t["outcome"].as_str().unwrap().to_owned(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let outcome_of = |name: &str| {
+        outcomes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, o)| o.as_str())
+    };
+    assert_eq!(outcomes.len(), 6);
+    assert_eq!(outcome_of("test_broken"), Some("xfail"));
+    assert_eq!(outcome_of("script.aria"), Some("pass"));
+    assert_eq!(outcome_of("Passing"), Some("pass"));
+    assert_eq!(outcome_of("Inherited"), Some("pass"));
+    assert_eq!(outcome_of("test_passes"), Some("pass"));
+    assert_eq!(outcome_of("test_fails"), Some("fail"));
+    assert_eq!(report["failed"], 1);
+
+    let failed = &report["tests"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["outcome"] == "fail")
+        .unwrap();
+    assert_eq!(failed["output"], "about to fail\n");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn widget_manifest_validation() {
+    use aria_compiler::widget::{Manifest, version_matches};
+    use std::path::Path;
+
+    assert_eq!(version_matches(">=0.9, <1.0", "0.9.20251222"), Ok(true));
+    assert_eq!(version_matches(">=0.9, <1.0", "1.0.0"), Ok(false));
+    assert_eq!(version_matches("0.9", "0.9.0"), Ok(true));
+    assert_eq!(version_matches(">0.9", "0.9"), Ok(false));
+    assert!(version_matches(">=banana", "1.0").is_err());
+
+    let path = Path::new("widget.json");
+    let manifest = Manifest::parse(
+        r#"{"name": "app", "entry": "run.aria", "dependencies": {"geo": {"path": "../geo"}}}"#,
+        path,
+    )
+    .unwrap();
+    assert_eq!(manifest.entry.as_deref(), Some("run.aria"));
+    assert_eq!(manifest.dependencies.len(), 1);
+    assert!(Manifest::parse("{}", path).is_ok());
+    assert!(Manifest::parse(r#"{"dependencies": {"aria": {"path": "x"}}}"#, path).is_err());
+    assert!(Manifest::parse(r#"{"dependencies": {"geo": {}}}"#, path).is_err());
+    assert!(Manifest::parse(r#"{"aria": "soon"}"#, path).is_err());
+}
+
+#[test]
+fn widget_dependencies_resolve_through_the_lockfile() {
+    use clap::Parser;
+
+    let dir = std::env::temp_dir().join(format!("aria-widget-{}", std::process::id()));
+    let app = dir.join("app");
+    let geo = dir.join("geo");
+    std::fs::create_dir_all(&app).unwrap();
+    std::fs::create_dir_all(&geo).unwrap();
+
+    std::fs::write(
+        app.join("widget.json"),
+        r#"{"name": "app", "version": "1.0.0",
+            "dependencies": {"geo": {"path": "../geo"}, "colors": {"archive": "colors.tar"}}}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        app.join("uses_deps.aria"),
+        "import area from geo.shapes;\nimport red from colors.palette;\n\
+         func test_imports() { assert area(3) == 9; assert red() == \"red\"; }\n",
+    )
+    .unwrap();
+    // geo needs colors too, and gets it through the lockfile of app
+    std::fs::write(
+        geo.join("widget.json"),
+        r#"{"name": "geo", "version": "0.2.0",
+            "dependencies": {"colors": {"archive": "../app/colors.tar"}}}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        geo.join("shapes.aria"),
+        "import red from colors.palette;\nfunc area(r) { return r * r; }\n",
+    )
+    .unwrap();
+
+    let mut archive = tar::Builder::new(vec![]);
+    for (name, content) in [
+        (
+            "colors/widget.json",
+            r#"{"name": "colors", "version": "0.3.0"}"#,
+        ),
+        ("colors/palette.aria", "func red() { return \"red\"; }\n"),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, name, content.as_bytes())
+            .unwrap();
+    }
+    std::fs::write(app.join("colors.tar"), archive.into_inner().unwrap()).unwrap();
+
+    let app_dir = app.to_str().unwrap();
+    assert_eq!(crate::widget::widget(app_dir, true), 1);
+    assert_eq!(crate::widget::widget(app_dir, false), 0);
+    assert_eq!(crate::widget::widget(app_dir, true), 0);
+
+    let lockfile = aria_compiler::widget::Lockfile::load(&app)
+        .unwrap()
+        .unwrap();
+    assert_eq!(lockfile.widgets["geo"].root.to_str(), Some("../geo"));
+    assert_eq!(lockfile.widgets["geo"].dependencies, ["colors"]);
+    assert_eq!(
+        lockfile.widgets["colors"].root.to_str(),
+        Some(".widgets/colors/colors")
+    );
+    assert!(lockfile.widgets["colors"].checksum.is_some());
+
+    let args =
+        crate::Args::try_parse_from(["aria", "test", app.join("uses_deps.aria").to_str().unwrap()])
+            .unwrap();
+    let Some(crate::Command::Test(test_args)) = &args.command else {
+        panic!("expected a test command");
+    };
+    assert_eq!(crate::test_runner::run_tests(test_args), 0);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn widgets_import_their_own_modules_with_the_same_name() {
+    use clap::Parser;
+
+    let dir = std::env::temp_dir().join(format!("aria-widget-names-{}", std::process::id()));
+    let app = dir.join("app");
+    let geo = dir.join("geo");
+    std::fs::create_dir_all(&app).unwrap();
+    std::fs::create_dir_all(&geo).unwrap();
+
+    std::fs::write(
+        app.join("widget.json"),
+        r#"{"name": "app", "dependencies": {"geo": {"path": "../geo"}}}"#,
+    )
+    .unwrap();
+    std::fs::write(app.join("util.aria"), "func owner() { return \"app\"; }\n").unwrap();
+    // app imports its widget.util first, then geo imports its own
+    std::fs::write(
+        app.join("uses_geo.aria"),
+        "import owner from widget.util;\nimport geo_owner from geo.shapes;\n\
+         func test_owners() { assert owner() == \"app\"; assert geo_owner() == \"geo\"; }\n",
+    )
+    .unwrap();
+    std::fs::write(geo.join("widget.json"), r#"{"name": "geo"}"#).unwrap();
+    std::fs::write(geo.join("util.aria"), "func owner() { return \"geo\"; }\n").unwrap();
+    std::fs::write(
+        geo.join("shapes.aria"),
+        "import owner from widget.util;\nfunc geo_owner() { return owner(); }\n",
+    )
+    .unwrap();
+
+    assert_eq!(crate::widget::widget(app.to_str().unwrap(), false), 0);
+
+    let args =
+        crate::Args::try_parse_from(["aria", "test", app.join("uses_geo.aria").to_str().unwrap()])
+            .unwrap();
+    let Some(crate::Command::Test(test_args)) = &args.command else {
+        panic!("expected a test command");
+    };
+    assert_eq!(crate::test_runner::run_tests(test_args), 0);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn allow_flags_sandbox_the_vm() {
+    use clap::Parser;
+    use haxby_vm::sandbox::Grant;
+
+    let args = Args::try_parse_from(["aria", "program.aria"]).unwrap();
+    assert!(args.permissions().is_none());
+
+    let args = Args::try_parse_from([
+        "aria",
+        "--allow-read=data,config",
+        "--allow-net",
+        "--allow-env",
+        "program.aria",
+    ])
+    .unwrap();
+    let permissions = args.permissions().unwrap();
+    assert_eq!(
+        permissions.read,
+        Grant::Only(vec!["data".into(), "config".into()])
+    );
+    assert_eq!(permissions.write, Grant::none());
+    assert_eq!(permissions.net, Grant::All);
+    assert!(permissions.env && !permissions.run && !permissions.exit);
+    assert_eq!(args.path.as_deref(), Some("program.aria"));
+
+    let args = Args::try_parse_from(["aria", "--sandbox", "program.aria"]).unwrap();
+    assert_eq!(
+        args.permissions(),
+        Some(haxby_vm::sandbox::Permissions::none())
+    );
+}
+
+#[test]
+fn max_call_depth_flag_sets_the_limit() {
+    use clap::Parser;
+    use haxby_vm::{limits::DEFAULT_MAX_CALL_DEPTH, vm::VmOptions};
+
+    let depth_of = |argv: &[&str]| {
+        let args = Args::try_parse_from(argv).unwrap();
+        VmOptions::from(&args).limits.max_call_depth
+    };
+    assert_eq!(
+        depth_of(&["aria", "program.aria"]),
+        Some(DEFAULT_MAX_CALL_DEPTH)
+    );
+    assert_eq!(
+        depth_of(&["aria", "--max-call-depth", "500", "program.aria"]),
+        Some(500)
+    );
+    assert_eq!(
+        depth_of(&["aria", "--max-call-depth", "0", "program.aria"]),
+        None
+    );
+}
+
+#[test]
+fn pprof_profile_lists_innermost_frames_first() {
+    use haxby_vm::{
+        debugger::SourceLine,
+        profiler::{Profile, ProfileFrame},
+    };
+
+    let frame = |function: &str, line| ProfileFrame {
+        function: function.to_owned(),
+        location: Some(SourceLine {
+            file: "main.aria".to_owned(),
+            line,
+        }),
+    };
+    let profile = Profile {
+        interval: std::time::Duration::from_millis(1),
+        stacks: vec![
+            (vec![frame("main", 3), frame("fib", 10)], 5),
+            (vec![frame("main", 4)], 2),
+        ],
+        ..Default::default()
+    };
+
+    let pprof = crate::profile_report::to_pprof(&profile);
+    assert_eq!(pprof.string_table[0], "");
+    assert_eq!(pprof.function.len(), 2);
+    assert_eq!(pprof.location.len(), 3);
+    assert_eq!(pprof.sample[0].value, vec![5, 5_000_000]);
+
+    let name_of_location = |id: u64| {
+        let loc = pprof.location.iter().find(|l| l.id == id).unwrap();
+        let func = pprof
+            .function
+            .iter()
+            .find(|f| f.id == loc.line[0].function_id)
+            .unwrap();
+        (
+            pprof.string_table[func.name as usize].clone(),
+            loc.line[0].line,
+        )
+    };
+    let leaf = name_of_location(pprof.sample[0].location_id[0]);
+    assert_eq!(leaf, ("fib".to_owned(), 10));
+}
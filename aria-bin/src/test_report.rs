@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{fmt::Display, time::Duration};
+
+use serde_json::json;
+
+use crate::test_runner::{Outcome, TestResult};
+
+pub(crate) struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub xfailed: usize,
+    pub duration: Duration,
+}
+
+impl Summary {
+    pub(crate) fn new(results: &[TestResult], duration: Duration) -> Self {
+        let count = |f: fn(&Outcome) -> bool| results.iter().filter(|r| f(&r.outcome)).count();
+        Self {
+            passed: count(|o| matches!(o, Outcome::Pass)),
+            failed: count(|o| matches!(o, Outcome::Fail(_))),
+            xfailed: count(|o| matches!(o, Outcome::XFail(_))),
+            duration,
+        }
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} tests total - {} passed, {} failed, {} xfailed - in {}.{:03} seconds",
+            self.passed + self.failed + self.xfailed,
+            self.passed,
+            self.failed,
+            self.xfailed,
+            self.duration.as_secs(),
+            self.duration.subsec_millis(),
+        )
+    }
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // not allowed in XML 1.0 at all
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// one testsuite per file, in the order the files were run
+pub(crate) fn junit(results: &[TestResult], duration: Duration) -> String {
+    let summary = Summary::new(results, duration);
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<testsuites name=\"aria\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        results.len(),
+        summary.failed,
+        summary.xfailed,
+        duration.as_secs_f64()
+    ));
+
+    let mut files: Vec<&str> = vec![];
+    for result in results {
+        if !files.contains(&result.file.as_str()) {
+            files.push(&result.file);
+        }
+    }
+    for file in files {
+        let suite = results
+            .iter()
+            .filter(|r| r.file == file)
+            .collect::<Vec<_>>();
+        let failures = suite.iter().filter(|r| r.outcome.is_fail()).count();
+        let skipped = suite
+            .iter()
+            .filter(|r| matches!(r.outcome, Outcome::XFail(_)))
+            .count();
+        let time: Duration = suite.iter().map(|r| r.duration).sum();
+        out.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{failures}\" skipped=\"{skipped}\" time=\"{:.3}\">\n",
+            escape_xml(file),
+            suite.len(),
+            time.as_secs_f64()
+        ));
+        for result in suite {
+            out.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape_xml(&result.name),
+                escape_xml(file),
+                result.duration.as_secs_f64()
+            ));
+            let detail = match &result.outcome {
+                Outcome::Pass => None,
+                Outcome::Fail(reason) => Some(format!(
+                    "      <failure message=\"{}\"/>\n",
+                    escape_xml(reason)
+                )),
+                Outcome::XFail(reason) => Some(format!(
+                    "      <skipped message=\"expected failure: {}\"/>\n",
+                    escape_xml(reason)
+                )),
+            };
+            if detail.is_none() && result.output.is_empty() {
+                out.push_str("/>\n");
+                continue;
+            }
+            out.push_str(">\n");
+            out.push_str(&detail.unwrap_or_default());
+            if !result.output.is_empty() {
+                out.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    escape_xml(&result.output)
+                ));
+            }
+            out.push_str("    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n");
+    }
+
+    out.push_str("</testsuites>\n");
+    out
+}
+
+pub(crate) fn json(results: &[TestResult], duration: Duration) -> String {
+    let summary = Summary::new(results, duration);
+    let tests = results
+        .iter()
+        .map(|r| {
+            let (outcome, message) = match &r.outcome {
+                Outcome::Pass => ("pass", None),
+                Outcome::Fail(reason) => ("fail", Some(reason)),
+                Outcome::XFail(reason) => ("xfail", Some(reason)),
+            };
+            json!({
+                "file": r.file,
+                "name": r.name,
+                "outcome": outcome,
+                "message": message,
+                "duration": r.duration.as_secs_f64(),
+                "output": r.output,
+            })
+        })
+        .collect::<Vec<_>>();
+    let report = json!({
+        "passed": summary.passed,
+        "failed": summary.failed,
+        "xfailed": summary.xfailed,
+        "duration": duration.as_secs_f64(),
+        "tests": tests,
+    });
+    serde_json::to_string_pretty(&report).unwrap_or_default()
+}
@@ -0,0 +1,446 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use aria_compiler::{compile_from_source, module::CompiledModule};
+use aria_parser::{
+    ast::{
+        Expression, SourceBuffer, StructDecl, StructEntry, TopLevelEntry,
+        prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+        source_to_ast,
+    },
+    tags::parse_tags,
+};
+use haxby_vm::{
+    console::TestConsole,
+    coverage::{Coverage, CoverageOptions},
+    frame::Frame,
+    runtime_module::RuntimeModule,
+    vm::{RunloopExit, VirtualMachine, VmOptions},
+};
+
+use crate::{coverage_report::CoverageArgs, sources::collect_sources, test_report};
+
+// the name under which the module being tested can be imported by the code
+// that calls into it
+const SUBJECT_MODULE: &str = "aria_test_subject";
+
+#[derive(clap::Args, Debug)]
+pub(crate) struct TestArgs {
+    /// Test files, or directories to look for them in
+    #[arg(default_value = ".")]
+    paths: Vec<String>,
+    /// Only run tests whose name contains one of these strings. May repeat.
+    #[arg(long("filter"))]
+    filter: Vec<String>,
+    /// Only run tests from files with one of these tags. May repeat.
+    #[arg(long("tag"))]
+    tag: Vec<String>,
+    /// Skip tests from files with any of these tags. May repeat.
+    #[arg(long("skip-tag"))]
+    skip_tag: Vec<String>,
+    /// Stop starting new tests once one has failed
+    #[arg(long("fail-fast"))]
+    fail_fast: bool,
+    /// How many tests to run at once; defaults to the number of CPUs
+    #[arg(long("jobs"), short('j'))]
+    jobs: Option<usize>,
+    /// Print every result, not only the failures
+    #[arg(long("verbose"))]
+    verbose: bool,
+    /// Write a JUnit XML report to this file
+    #[arg(long("junit"))]
+    junit: Option<PathBuf>,
+    /// Write a JSON report to this file
+    #[arg(long("json"))]
+    json: Option<PathBuf>,
+    #[command(flatten)]
+    coverage: CoverageArgs,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TestKind {
+    // a file that runs as a whole, and passes if it does not throw
+    File,
+    // a function named test_*, called with no arguments
+    Function(String),
+    // a struct that includes TestCase, run through TestCase.run()
+    Case(String),
+}
+
+#[derive(Debug)]
+struct Test {
+    file: PathBuf,
+    kind: TestKind,
+    tags: HashSet<String>,
+}
+
+impl Test {
+    fn file_name(&self) -> String {
+        self.file.display().to_string()
+    }
+
+    fn short_name(&self) -> String {
+        match &self.kind {
+            TestKind::File => self.file_name(),
+            TestKind::Function(name) | TestKind::Case(name) => name.clone(),
+        }
+    }
+
+    fn name(&self) -> String {
+        match &self.kind {
+            TestKind::File => self.file_name(),
+            TestKind::Function(name) | TestKind::Case(name) => {
+                format!("{}::{name}", self.file_name())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum Outcome {
+    Pass,
+    Fail(String),
+    XFail(String),
+}
+
+impl Outcome {
+    pub(crate) fn is_fail(&self) -> bool {
+        matches!(self, Outcome::Fail(_))
+    }
+}
+
+pub(crate) struct TestResult {
+    pub file: String,
+    pub name: String,
+    pub duration: Duration,
+    pub outcome: Outcome,
+    // whatever the test printed while it ran
+    pub output: String,
+    pub coverage: Option<Coverage>,
+}
+
+impl TestResult {
+    pub(crate) fn full_name(&self) -> String {
+        if self.name == self.file {
+            self.file.clone()
+        } else {
+            format!("{}::{}", self.file, self.name)
+        }
+    }
+}
+
+fn is_test_case(what: &Expression) -> bool {
+    let what = what.prettyprint(PrintoutAccumulator::default()).value();
+    what == "TestCase" || what.ends_with(".TestCase")
+}
+
+// a struct is a test case whether it says "include TestCase" in its body or
+// "struct Name : TestCase", which is shorthand for the same thing
+fn includes_test_case(decl: &StructDecl) -> bool {
+    decl.inherits.iter().any(is_test_case)
+        || decl.body.iter().any(|entry| match entry {
+            StructEntry::MixinInclude(m) => is_test_case(&m.what),
+            _ => false,
+        })
+}
+
+// the tests in a file: its test_* functions and TestCase structs, unless it
+// has a main function or neither of those, in which case it is one test
+fn discover(file: &Path) -> Vec<Test> {
+    let text = std::fs::read_to_string(file).unwrap_or_default();
+    let tags = parse_tags(&text);
+    let whole_file = || {
+        vec![Test {
+            file: file.to_path_buf(),
+            kind: TestKind::File,
+            tags: tags.clone(),
+        }]
+    };
+
+    let Ok(ast) = source_to_ast(&SourceBuffer::stdin_with_name(
+        &text,
+        &file.to_string_lossy(),
+    )) else {
+        return whole_file();
+    };
+
+    let mut kinds = vec![];
+    for entry in &ast.entries {
+        match entry {
+            TopLevelEntry::FunctionDecl(f) if f.name.value == "main" => return whole_file(),
+            TopLevelEntry::FunctionDecl(f)
+                if f.name.value.starts_with("test_")
+                    && f.args.names.iter().all(|a| a.deft.is_some()) =>
+            {
+                kinds.push(TestKind::Function(f.name.value.clone()));
+            }
+            TopLevelEntry::StructDecl(s) if includes_test_case(s) => {
+                kinds.push(TestKind::Case(s.name.value.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    if kinds.is_empty() {
+        return whole_file();
+    }
+    kinds
+        .into_iter()
+        .map(|kind| Test {
+            file: file.to_path_buf(),
+            kind,
+            tags: tags.clone(),
+        })
+        .collect()
+}
+
+fn describe_exception(
+    vm: &mut VirtualMachine,
+    exc: haxby_vm::error::exception::VmException,
+) -> String {
+    let mut frame = Frame::default();
+    let mut text = exc.value.prettyprint(&mut frame, vm);
+    for cause in exc.causes(vm) {
+        text += &format!(" (caused by: {})", cause.prettyprint(&mut frame, vm));
+    }
+    text
+}
+
+fn compile(buffer: &SourceBuffer) -> Result<CompiledModule, String> {
+    compile_from_source(buffer, &Default::default()).map_err(|errs| {
+        let msg = errs
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("compilation error: {msg}")
+    })
+}
+
+fn load(vm: &mut VirtualMachine, module: CompiledModule) -> Result<RuntimeModule, String> {
+    match vm.load_module("", module) {
+        Ok(RunloopExit::Ok(m)) => Ok(m.module),
+        Ok(RunloopExit::Exception(exc)) => Err(describe_exception(vm, exc)),
+        Err(err) => Err(err.prettyprint(None)),
+    }
+}
+
+fn run_in_vm(vm: &mut VirtualMachine, test: &Test) -> Result<(), String> {
+    let path = test.file_name();
+    let buffer = SourceBuffer::file(&path).map_err(|err| format!("I/O error: {err}"))?;
+    let module = load(vm, compile(&buffer)?)?;
+
+    let driver = match &test.kind {
+        TestKind::File => {
+            return match vm.execute_module(&module) {
+                Ok(RunloopExit::Ok(_)) => Ok(()),
+                Ok(RunloopExit::Exception(exc)) => Err(describe_exception(vm, exc)),
+                Err(err) => Err(err.prettyprint(Some(module))),
+            };
+        }
+        TestKind::Function(name) => {
+            format!("import {name} from {SUBJECT_MODULE};\n{name}();\n")
+        }
+        TestKind::Case(name) => format!(
+            "import {name} from {SUBJECT_MODULE};\n\
+             val result = {name}.new().run();\n\
+             if result.is_Fail() {{ throw result.unwrap_Fail(); }}\n"
+        ),
+    };
+
+    vm.inject_imported_module(SUBJECT_MODULE, module);
+    let buffer = SourceBuffer::stdin_with_name(&driver, &test.name());
+    load(vm, compile(&buffer)?).map(|_| ())
+}
+
+fn run_once(
+    test: &Test,
+    coverage: Option<&CoverageOptions>,
+) -> (Result<(), String>, String, Option<Coverage>) {
+    let console = Rc::new(RefCell::new(TestConsole::default()));
+    let mut vm = VirtualMachine::with_options(VmOptions {
+        console: console.clone(),
+        ..Default::default()
+    });
+    if let Some(options) = coverage {
+        vm.start_coverage(options.clone());
+    }
+    let result = run_in_vm(&mut vm, test);
+    let console = console.borrow();
+    (
+        result,
+        format!("{}{}", console.stdout, console.stderr),
+        vm.stop_coverage(),
+    )
+}
+
+// runs a test in a VM of its own, and interprets the XFAIL and FLAKY tags
+fn run_test(test: &Test, coverage: Option<&CoverageOptions>) -> TestResult {
+    let start = Instant::now();
+    let (mut result, mut output, mut recorded) = run_once(test, coverage);
+    if result.is_err() && (test.tags.contains("FLAKY") || test.tags.contains("FLAKEY")) {
+        (result, output, recorded) = run_once(test, coverage);
+    }
+
+    let outcome = match (result, test.tags.contains("XFAIL")) {
+        (Ok(()), false) => Outcome::Pass,
+        (Err(reason), false) => Outcome::Fail(reason),
+        (Ok(()), true) => Outcome::Fail("unexpected pass (XFAIL)".to_owned()),
+        (Err(reason), true) => Outcome::XFail(reason),
+    };
+    TestResult {
+        file: test.file_name(),
+        name: test.short_name(),
+        duration: start.elapsed(),
+        outcome,
+        output,
+        coverage: recorded,
+    }
+}
+
+fn run_all(
+    tests: &[Test],
+    jobs: usize,
+    fail_fast: bool,
+    coverage: Option<&CoverageOptions>,
+) -> Vec<TestResult> {
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let results = Mutex::new(vec![]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, tests.len().max(1)) {
+            scope.spawn(|| {
+                while !stop.load(Ordering::SeqCst) {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(test) = tests.get(idx) else {
+                        break;
+                    };
+                    let result = run_test(test, coverage);
+                    if fail_fast && result.outcome.is_fail() {
+                        stop.store(true, Ordering::SeqCst);
+                    }
+                    results.lock().unwrap().push((idx, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn print_result(result: &TestResult, with_output: bool) {
+    let (emoji, reason) = match &result.outcome {
+        Outcome::Pass => ("✅", String::new()),
+        Outcome::Fail(reason) => ("❌", format!(" [{reason}]")),
+        Outcome::XFail(_) => ("⚠️ ", String::new()),
+    };
+    println!(
+        "{emoji} {}{reason} [in {}.{:03} seconds]",
+        result.full_name(),
+        result.duration.as_secs(),
+        result.duration.subsec_millis()
+    );
+    if with_output && !result.output.is_empty() {
+        for line in result.output.lines() {
+            println!("    {line}");
+        }
+    }
+}
+
+pub(crate) fn run_tests(args: &TestArgs) -> i32 {
+    if let Some(err) = args.coverage.check() {
+        eprintln!("Error: {err}");
+        return 1;
+    }
+
+    let files = match collect_sources(&args.paths) {
+        Ok(files) => files,
+        Err(err) => {
+            eprintln!("{err}");
+            return 1;
+        }
+    };
+
+    let wanted_tags = args
+        .tag
+        .iter()
+        .map(|t| t.to_ascii_uppercase())
+        .collect::<HashSet<_>>();
+    let skipped_tags = args
+        .skip_tag
+        .iter()
+        .map(|t| t.to_ascii_uppercase())
+        .collect::<HashSet<_>>();
+    let tests = files
+        .iter()
+        .flat_map(|file| discover(file))
+        .filter(|test| {
+            let name = test.name();
+            (args.filter.is_empty() || args.filter.iter().any(|f| name.contains(f.as_str())))
+                && (wanted_tags.is_empty() || !test.tags.is_disjoint(&wanted_tags))
+                && test.tags.is_disjoint(&skipped_tags)
+        })
+        .collect::<Vec<_>>();
+
+    let jobs = args
+        .jobs
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let start = Instant::now();
+    let coverage_options = args.coverage.options();
+    let results = run_all(&tests, jobs, args.fail_fast, coverage_options.as_ref());
+    let duration = start.elapsed();
+
+    for result in &results {
+        if args.verbose || result.outcome.is_fail() {
+            print_result(result, result.outcome.is_fail());
+        }
+    }
+    let summary = test_report::Summary::new(&results, duration);
+    println!("{summary}");
+
+    let reports = [
+        (
+            &args.junit,
+            test_report::junit as fn(&[TestResult], Duration) -> String,
+        ),
+        (&args.json, test_report::json),
+    ];
+    for (path, report) in reports {
+        if let Some(path) = path
+            && let Err(err) = std::fs::write(path, report(&results, duration))
+        {
+            eprintln!("error writing {}: {err}", path.display());
+            return 1;
+        }
+    }
+
+    if coverage_options.is_some() {
+        // the coverage of the whole suite, across every test and file
+        let mut coverage = Coverage::default();
+        for recorded in results.iter().filter_map(|r| r.coverage.as_ref()) {
+            coverage.merge(recorded);
+        }
+        match args.coverage.report(coverage) {
+            Ok(report) => print!("{}", report.unwrap_or_default()),
+            Err(err) => {
+                eprintln!("{err}");
+                return 1;
+            }
+        }
+    }
+
+    if summary.failed > 0 { 1 } else { 0 }
+}
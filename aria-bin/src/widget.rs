@@ -0,0 +1,256 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::{Component, Path, PathBuf},
+};
+
+use aria_compiler::{
+    module_cache::COMPILER_VERSION,
+    widget::{
+        DependencySource, LOCK_FILE_NAME, LockedWidget, Lockfile, MANIFEST_FILE_NAME, Manifest,
+        version_matches,
+    },
+};
+use sha2::{Digest, Sha256};
+
+// archives are unpacked here, inside the root widget
+const VENDOR_DIR: &str = ".widgets";
+
+// the path that leads from base to path; both must be absolute
+fn relative_path(base: &Path, path: &Path) -> PathBuf {
+    let base = base.components().collect::<Vec<_>>();
+    let path = path.components().collect::<Vec<_>>();
+    let common = base.iter().zip(&path).take_while(|(a, b)| a == b).count();
+
+    let mut rel = PathBuf::new();
+    for _ in common..base.len() {
+        rel.push(Component::ParentDir);
+    }
+    for component in &path[common..] {
+        rel.push(component);
+    }
+    if rel.as_os_str().is_empty() {
+        rel.push(Component::CurDir);
+    }
+    rel
+}
+
+fn check_aria_version(name: &str, manifest: &Manifest) -> Result<(), String> {
+    if let Some(req) = &manifest.aria
+        && !version_matches(req, COMPILER_VERSION)?
+    {
+        return Err(format!(
+            "widget {name} needs Aria {req}, but this is Aria {COMPILER_VERSION}"
+        ));
+    }
+    Ok(())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+// the directory inside an unpacked archive that holds the widget: either
+// the top level, or the only directory in it
+fn unpacked_widget_root(dest: &Path) -> Option<PathBuf> {
+    if dest.join(MANIFEST_FILE_NAME).is_file() {
+        return Some(dest.to_path_buf());
+    }
+    let entries = std::fs::read_dir(dest)
+        .ok()?
+        .filter_map(Result::ok)
+        .collect::<Vec<_>>();
+    match entries.as_slice() {
+        [only] if only.path().join(MANIFEST_FILE_NAME).is_file() => Some(only.path()),
+        _ => None,
+    }
+}
+
+// unpacks an archive into dest, replacing whatever was there; when only
+// checking, the archive must already be unpacked
+fn unpack_archive(archive: &[u8], dest: &Path, check_only: bool) -> Result<PathBuf, String> {
+    if !check_only {
+        if dest.exists() {
+            std::fs::remove_dir_all(dest)
+                .map_err(|e| format!("cannot clear {}: {e}", dest.display()))?;
+        }
+        std::fs::create_dir_all(dest)
+            .map_err(|e| format!("cannot create {}: {e}", dest.display()))?;
+        tar::Archive::new(archive)
+            .unpack(dest)
+            .map_err(|e| format!("cannot unpack into {}: {e}", dest.display()))?;
+    }
+    unpacked_widget_root(dest).ok_or_else(|| {
+        if check_only {
+            format!("{} has not been unpacked", dest.display())
+        } else {
+            format!(
+                "the archive unpacked into {} has no {MANIFEST_FILE_NAME}",
+                dest.display()
+            )
+        }
+    })
+}
+
+struct Pending {
+    name: String,
+    source: DependencySource,
+    // the directory of the widget that depends on this one
+    from: PathBuf,
+}
+
+// walks the dependency graph of the widget rooted at root, which must be an
+// absolute path, and returns the lockfile that describes it
+pub(crate) fn resolve(root: &Path, check_only: bool) -> Result<Lockfile, String> {
+    let manifest = Manifest::load(root).map_err(|e| e.to_string())?;
+    let root_name = manifest.name.clone().unwrap_or_else(|| "<root>".to_owned());
+    check_aria_version(&root_name, &manifest)?;
+
+    let mut pending = manifest
+        .dependencies
+        .iter()
+        .map(|(name, source)| Pending {
+            name: name.clone(),
+            source: source.clone(),
+            from: root.to_path_buf(),
+        })
+        .collect::<VecDeque<_>>();
+
+    let mut widgets: BTreeMap<String, LockedWidget> = BTreeMap::new();
+    // what makes each locked widget the widget it is: its directory, or
+    // the checksum of its archive
+    let mut identities: BTreeMap<String, String> = BTreeMap::new();
+
+    while let Some(dep) = pending.pop_front() {
+        if dep.name == root_name {
+            return Err(format!("widget {} depends on itself", dep.name));
+        }
+
+        let (identity, archive) = match &dep.source {
+            DependencySource::Path(path) => {
+                let dir =
+                    dep.from.join(path).canonicalize().map_err(|e| {
+                        format!("dependency {} at {}: {e}", dep.name, path.display())
+                    })?;
+                (dir.display().to_string(), None)
+            }
+            DependencySource::Archive(path) => {
+                let bytes = std::fs::read(dep.from.join(path))
+                    .map_err(|e| format!("dependency {} at {}: {e}", dep.name, path.display()))?;
+                (sha256_hex(&bytes), Some(bytes))
+            }
+        };
+        match identities.get(&dep.name) {
+            Some(existing) if *existing == identity => continue,
+            Some(_) => {
+                return Err(format!(
+                    "two different widgets are named {}, one of them from {}",
+                    dep.name, dep.source
+                ));
+            }
+            None => {}
+        }
+
+        let (dir, checksum) = match archive {
+            None => (PathBuf::from(&identity), None),
+            Some(bytes) => {
+                let dest = root.join(VENDOR_DIR).join(&dep.name);
+                (
+                    unpack_archive(&bytes, &dest, check_only)?,
+                    Some(identity.clone()),
+                )
+            }
+        };
+        identities.insert(dep.name.clone(), identity);
+
+        let manifest = Manifest::load(&dir).map_err(|e| e.to_string())?;
+        check_aria_version(&dep.name, &manifest)?;
+        for (name, source) in &manifest.dependencies {
+            pending.push_back(Pending {
+                name: name.clone(),
+                source: source.clone(),
+                from: dir.clone(),
+            });
+        }
+
+        widgets.insert(
+            dep.name.clone(),
+            LockedWidget {
+                version: manifest.version.clone(),
+                source: dep.source.to_string(),
+                root: relative_path(root, &dir),
+                checksum,
+                dependencies: manifest.dependencies.keys().cloned().collect(),
+            },
+        );
+    }
+
+    Ok(Lockfile { widgets })
+}
+
+fn print_graph(name: &str, lockfile: &Lockfile, depth: usize, seen: &mut Vec<String>) {
+    let Some(widget) = lockfile.widgets.get(name) else {
+        return;
+    };
+    let version = widget.version.as_deref().unwrap_or("(no version)");
+    let repeat = seen.iter().any(|s| s == name);
+    println!(
+        "{}{name} {version} [{}]{}",
+        "  ".repeat(depth),
+        widget.source,
+        if repeat { " (see above)" } else { "" }
+    );
+    if repeat {
+        return;
+    }
+    seen.push(name.to_owned());
+    for dep in &widget.dependencies {
+        print_graph(dep, lockfile, depth + 1, seen);
+    }
+}
+
+pub(crate) fn widget(dir: &str, check_only: bool) -> i32 {
+    let root = match Path::new(dir).canonicalize() {
+        Ok(root) if root.join(MANIFEST_FILE_NAME).is_file() => root,
+        _ => {
+            eprintln!("{dir} is not a widget: it has no {MANIFEST_FILE_NAME}");
+            return 1;
+        }
+    };
+
+    let lockfile = match resolve(&root, check_only) {
+        Ok(lockfile) => lockfile,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return 1;
+        }
+    };
+
+    let manifest = Manifest::load(&root).unwrap_or_default();
+    println!(
+        "{} {}",
+        manifest.name.as_deref().unwrap_or("<root>"),
+        manifest.version.as_deref().unwrap_or("(no version)")
+    );
+    let mut seen = vec![];
+    for dep in manifest.dependencies.keys() {
+        print_graph(dep, &lockfile, 1, &mut seen);
+    }
+
+    let lock_path = root.join(LOCK_FILE_NAME);
+    let text = lockfile.to_json();
+    if check_only {
+        if std::fs::read_to_string(&lock_path).ok().as_deref() != Some(text.as_str()) {
+            eprintln!("{} is out of date", lock_path.display());
+            return 1;
+        }
+    } else if let Err(err) = std::fs::write(&lock_path, text) {
+        eprintln!("error writing {}: {err}", lock_path.display());
+        return 1;
+    }
+
+    0
+}
@@ -0,0 +1,45 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::path::{Path, PathBuf};
+
+// the sources that decide what bytecode a module compiles to; a change to
+// any of them gives the compiler a new build id, which invalidates modules
+// cached by an earlier build without anyone having to bump a version
+const SOURCE_DIRS: &[&str] = &["src", "../opcodes-lib/src", "../parser-lib/src"];
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+// FNV-1a, as in module_cache.rs, so the id does not depend on the toolchain
+fn stable_hash(hash: u64, data: &[u8]) -> u64 {
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    data.iter()
+        .fold(hash, |hash, b| (hash ^ (*b as u64)).wrapping_mul(FNV_PRIME))
+}
+
+fn main() {
+    let mut files = vec![];
+    for dir in SOURCE_DIRS {
+        println!("cargo:rerun-if-changed={dir}");
+        collect_files(Path::new(dir), &mut files);
+    }
+    files.sort();
+
+    let mut hash = 0xcbf29ce484222325;
+    for file in &files {
+        hash = stable_hash(hash, file.to_string_lossy().as_bytes());
+        hash = stable_hash(hash, &std::fs::read(file).unwrap_or_default());
+    }
+    println!("cargo:rustc-env=ARIA_COMPILER_BUILD_ID={hash:016x}");
+}
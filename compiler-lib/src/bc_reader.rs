@@ -266,8 +266,56 @@ impl BytecodeReader {
             haxby_opcodes::OPCODE_CALL => self
                 .read_u8()
                 .map_or(Err(DecodeError::InsufficientData), |b| Ok(Opcode::Call(b))),
+            haxby_opcodes::OPCODE_TAIL_CALL => self
+                .read_u8()
+                .map_or(Err(DecodeError::InsufficientData), |b| {
+                    Ok(Opcode::TailCall(b))
+                }),
+            haxby_opcodes::OPCODE_CALL_LOCAL_METHOD => {
+                let b0 = match self.read_u8() {
+                    Ok(b) => b,
+                    Err(_) => {
+                        return Err(DecodeError::InsufficientData);
+                    }
+                };
+                let w1 = match self.read_u16() {
+                    Ok(w) => w,
+                    Err(_) => {
+                        return Err(DecodeError::InsufficientData);
+                    }
+                };
+                let b2 = match self.read_u8() {
+                    Ok(b) => b,
+                    Err(_) => {
+                        return Err(DecodeError::InsufficientData);
+                    }
+                };
+                Ok(Opcode::CallLocalMethod(b0, w1, b2))
+            }
+            haxby_opcodes::OPCODE_CALL_LOCAL_METHOD_SYMBOL => {
+                let b0 = match self.read_u8() {
+                    Ok(b) => b,
+                    Err(_) => {
+                        return Err(DecodeError::InsufficientData);
+                    }
+                };
+                let w1 = match self.read_u32() {
+                    Ok(w) => w,
+                    Err(_) => {
+                        return Err(DecodeError::InsufficientData);
+                    }
+                };
+                let b2 = match self.read_u8() {
+                    Ok(b) => b,
+                    Err(_) => {
+                        return Err(DecodeError::InsufficientData);
+                    }
+                };
+                Ok(Opcode::CallLocalMethodSymbol(b0, w1, b2))
+            }
             haxby_opcodes::OPCODE_RETURN => Ok(Opcode::Return),
             haxby_opcodes::OPCODE_RETURN_UNIT => Ok(Opcode::ReturnUnit),
+            haxby_opcodes::OPCODE_YIELD => Ok(Opcode::Yield),
             haxby_opcodes::OPCODE_TRY_ENTER => self
                 .read_u16()
                 .map_or(Err(DecodeError::InsufficientData), |b| {
@@ -275,11 +323,17 @@ impl BytecodeReader {
                 }),
             haxby_opcodes::OPCODE_TRY_EXIT => Ok(Opcode::TryExit),
             haxby_opcodes::OPCODE_THROW => Ok(Opcode::Throw),
+            haxby_opcodes::OPCODE_THROW_FROM => Ok(Opcode::ThrowFrom),
             haxby_opcodes::OPCODE_BUILD_LIST => self
                 .read_u32()
                 .map_or(Err(DecodeError::InsufficientData), |b| {
                     Ok(Opcode::BuildList(b))
                 }),
+            haxby_opcodes::OPCODE_BUILD_MAP => self
+                .read_u32()
+                .map_or(Err(DecodeError::InsufficientData), |b| {
+                    Ok(Opcode::BuildMap(b))
+                }),
             haxby_opcodes::OPCODE_BUILD_FUNCTION => Ok(Opcode::BuildFunction),
             haxby_opcodes::OPCODE_STORE_UPLEVEL => self
                 .read_u8()
@@ -366,6 +420,16 @@ impl BytecodeReader {
                 .map_or(Err(DecodeError::InsufficientData), |b| {
                     Ok(Opcode::TryUnwrapProtocol(b))
                 }),
+            haxby_opcodes::OPCODE_MATCH_FAILED => self
+                .read_u16()
+                .map_or(Err(DecodeError::InsufficientData), |b| {
+                    Ok(Opcode::MatchFailed(b))
+                }),
+            haxby_opcodes::OPCODE_FORMAT_VALUE => self
+                .read_u16()
+                .map_or(Err(DecodeError::InsufficientData), |b| {
+                    Ok(Opcode::FormatValue(b))
+                }),
             haxby_opcodes::OPCODE_ISA => Ok(Opcode::Isa),
             haxby_opcodes::OPCODE_IMPORT => self
                 .read_u16()
@@ -111,14 +111,28 @@ impl BytecodeWriter {
                 .write_u8(*n)
                 .write_u16(*d),
             Opcode::Call(n) => self.write_u8(haxby_opcodes::OPCODE_CALL).write_u8(*n),
+            Opcode::TailCall(n) => self.write_u8(haxby_opcodes::OPCODE_TAIL_CALL).write_u8(*n),
+            Opcode::CallLocalMethod(x, n, argc) => self
+                .write_u8(haxby_opcodes::OPCODE_CALL_LOCAL_METHOD)
+                .write_u8(*x)
+                .write_u16(*n)
+                .write_u8(*argc),
+            Opcode::CallLocalMethodSymbol(x, n, argc) => self
+                .write_u8(haxby_opcodes::OPCODE_CALL_LOCAL_METHOD_SYMBOL)
+                .write_u8(*x)
+                .write_u32(*n)
+                .write_u8(*argc),
             Opcode::Return => self.write_u8(haxby_opcodes::OPCODE_RETURN),
             Opcode::ReturnUnit => self.write_u8(haxby_opcodes::OPCODE_RETURN_UNIT),
+            Opcode::Yield => self.write_u8(haxby_opcodes::OPCODE_YIELD),
             Opcode::TryEnter(n) => self.write_u8(haxby_opcodes::OPCODE_TRY_ENTER).write_u16(*n),
             Opcode::TryExit => self.write_u8(haxby_opcodes::OPCODE_TRY_EXIT),
             Opcode::Throw => self.write_u8(haxby_opcodes::OPCODE_THROW),
+            Opcode::ThrowFrom => self.write_u8(haxby_opcodes::OPCODE_THROW_FROM),
             Opcode::BuildList(n) => self
                 .write_u8(haxby_opcodes::OPCODE_BUILD_LIST)
                 .write_u32(*n),
+            Opcode::BuildMap(n) => self.write_u8(haxby_opcodes::OPCODE_BUILD_MAP).write_u32(*n),
             Opcode::BuildFunction => self.write_u8(haxby_opcodes::OPCODE_BUILD_FUNCTION),
             Opcode::StoreUplevel(n) => self
                 .write_u8(haxby_opcodes::OPCODE_STORE_UPLEVEL)
@@ -155,6 +169,12 @@ impl BytecodeWriter {
             Opcode::TryUnwrapProtocol(n) => self
                 .write_u8(haxby_opcodes::OPCODE_TRY_UNWRAP_PROTOCOL)
                 .write_u8(*n),
+            Opcode::MatchFailed(n) => self
+                .write_u8(haxby_opcodes::OPCODE_MATCH_FAILED)
+                .write_u16(*n),
+            Opcode::FormatValue(n) => self
+                .write_u8(haxby_opcodes::OPCODE_FORMAT_VALUE)
+                .write_u16(*n),
             Opcode::Isa => self.write_u8(haxby_opcodes::OPCODE_ISA),
             Opcode::Import(n) => self.write_u8(haxby_opcodes::OPCODE_IMPORT).write_u16(*n),
             Opcode::LiftModule => self.write_u8(haxby_opcodes::OPCODE_LIFT_MODULE),
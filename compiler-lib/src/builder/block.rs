@@ -6,7 +6,11 @@ use aria_parser::ast::SourcePointer;
 
 use crate::{
     bc_writer::BytecodeWriter,
-    builder::{compiler_opcodes::CompilerOpcode, func::FunctionBuilder},
+    builder::{
+        compiler_opcodes::CompilerOpcode,
+        fold::{Literal, fold_binary, fold_unary},
+        func::FunctionBuilder,
+    },
     constant_value::ConstantValues,
     line_table::LineTable,
 };
@@ -110,33 +114,82 @@ impl BasicBlock {
         false
     }
 
+    // turns a call that is the last thing in the block into a tail call
+    pub(crate) fn make_tail_call(&self) {
+        let mut br = self.imp.writer.borrow_mut();
+        if let Some(entry) = br.last_mut()
+            && let CompilerOpcode::Call(argc) = entry.op
+        {
+            entry.op = CompilerOpcode::TailCall(argc);
+        }
+    }
+
     #[allow(dead_code)]
     pub(crate) fn remove_op_at_idx(&self, idx: usize) {
         self.imp.writer.borrow_mut().remove(idx);
     }
 
-    fn replace_double_jump(&self) -> bool {
-        let mut any = false;
+    // points each jump at the end of the chain of jumps it leads to; the
+    // chain is followed only until it comes back around, since jumps can
+    // form a cycle (e.g. the blocks of `while true {}`)
+    fn thread_destination(&self, dest: &BasicBlock) -> BasicBlock {
+        let mut final_dest = dest.clone();
+        let mut seen = HashSet::from([self.imp.id]);
+        while seen.insert(final_dest.imp.id) {
+            let next = match final_dest.imp.writer.borrow().first() {
+                Some(BasicBlockEntry {
+                    op: CompilerOpcode::Jump(next),
+                    ..
+                }) => next.clone(),
+                _ => break,
+            };
+            final_dest = next;
+        }
+        final_dest
+    }
 
+    // a jump to a block that does nothing but return is replaced by the
+    // return itself
+    fn thread_jumps(&self) {
         let mut br = self.imp.writer.borrow_mut();
-        for i in 0..br.len() {
-            if let CompilerOpcode::Jump(dest) = &br[i].op {
-                let dest = dest.clone();
-                if dest.imp.id == self.imp.id {
-                    continue;
+        for entry in br.iter_mut() {
+            entry.op = match &entry.op {
+                CompilerOpcode::Jump(dest) => {
+                    let dest = self.thread_destination(dest);
+                    if dest.imp.id == self.imp.id {
+                        continue;
+                    }
+                    let returns = match dest.imp.writer.borrow().as_slice() {
+                        [only]
+                            if matches!(
+                                only.op,
+                                CompilerOpcode::Return | CompilerOpcode::ReturnUnit
+                            ) =>
+                        {
+                            Some(only.op.clone())
+                        }
+                        _ => None,
+                    };
+                    returns.unwrap_or(CompilerOpcode::Jump(dest))
                 }
-                if dest.is_empty() {
-                    continue;
+                CompilerOpcode::JumpTrue(dest) => {
+                    CompilerOpcode::JumpTrue(self.thread_destination(dest))
                 }
-                let dest_br = dest.imp.writer.borrow();
-                if let CompilerOpcode::Jump(final_dest) = &dest_br[0].op {
-                    br[i].op = CompilerOpcode::Jump(final_dest.clone());
-                    any = true;
+                CompilerOpcode::JumpFalse(dest) => {
+                    CompilerOpcode::JumpFalse(self.thread_destination(dest))
                 }
-            }
+                CompilerOpcode::JumpConditionally(if_true, if_false) => {
+                    CompilerOpcode::JumpConditionally(
+                        self.thread_destination(if_true),
+                        self.thread_destination(if_false),
+                    )
+                }
+                CompilerOpcode::JumpIfArgSupplied(arg, dest) => {
+                    CompilerOpcode::JumpIfArgSupplied(*arg, self.thread_destination(dest))
+                }
+                _ => continue,
+            };
         }
-
-        any
     }
 
     fn optimize_true_false(&self, cv: &ConstantValues) {
@@ -165,6 +218,12 @@ impl BasicBlock {
                     br.remove(i + 1);
                     continue;
                 }
+                (CompilerOpcode::PushTrue, CompilerOpcode::JumpConditionally(target, _))
+                | (CompilerOpcode::PushFalse, CompilerOpcode::JumpConditionally(_, target)) => {
+                    br[i].op = CompilerOpcode::Jump(target.clone());
+                    br.remove(i + 1);
+                    continue;
+                }
                 (CompilerOpcode::PushTrue, CompilerOpcode::JumpFalse(_))
                 | (CompilerOpcode::PushFalse, CompilerOpcode::JumpTrue(_)) => {
                     br[i].op = CompilerOpcode::Nop;
@@ -178,7 +237,43 @@ impl BasicBlock {
         }
     }
 
-    fn remove_instructions_after_terminal(&self) {
+    // evaluates arithmetic and comparisons on literals, e.g. 60 * 60 * 24
+    fn fold_constants(&self, cv: &mut ConstantValues) {
+        let mut br = self.imp.writer.borrow_mut();
+        let mut i = 1;
+        while i < br.len() {
+            let unary = Literal::of(&br[i - 1].op, cv)
+                .and_then(|val| fold_unary(&br[i].op, &val))
+                .and_then(|val| val.to_opcode(cv));
+            if let Some(op) = unary {
+                br[i - 1].op = op;
+                br.remove(i);
+                continue;
+            }
+
+            let binary = if i >= 2 {
+                Literal::of(&br[i - 2].op, cv)
+                    .zip(Literal::of(&br[i - 1].op, cv))
+                    .and_then(|(lhs, rhs)| fold_binary(&br[i].op, &lhs, &rhs))
+                    .and_then(|val| val.to_opcode(cv))
+            } else {
+                None
+            };
+            if let Some(op) = binary {
+                // the result is attributed to the operator, like it would be
+                // if it were computed at runtime
+                br[i - 2].op = op;
+                br[i - 2].src = br[i].src.clone();
+                br.drain(i - 1..=i);
+                i -= 1;
+                continue;
+            }
+
+            i += 1;
+        }
+    }
+
+    pub(crate) fn remove_instructions_after_terminal(&self) {
         let mut br = self.imp.writer.borrow_mut();
         for i in 0..br.len() {
             if br[i].op.is_terminal() {
@@ -258,8 +353,11 @@ impl BasicBlock {
                 && let CompilerOpcode::ReadLocal(y) = br[i + 1].op
                 && x == y
             {
+                // the store stays attributed to its own line, so that a
+                // debugger stepping to the next line sees the value stored
                 br[i].op = CompilerOpcode::Dup;
                 br[i + 1].op = CompilerOpcode::WriteLocal(x);
+                br[i + 1].src = br[i].src.clone();
             }
         }
     }
@@ -277,16 +375,8 @@ impl BasicBlock {
         let mut br = self.imp.writer.borrow_mut();
 
         for i in 0..br.len() - 1 {
-            if let (
-                CompilerOpcode::Push0
-                | CompilerOpcode::Push1
-                | CompilerOpcode::PushFalse
-                | CompilerOpcode::PushTrue
-                | CompilerOpcode::Push(_)
-                | CompilerOpcode::PushBuiltinTy(_)
-                | CompilerOpcode::Dup,
-                CompilerOpcode::Pop,
-            ) = (&br[i].op, &br[i + 1].op)
+            if (br[i].op.is_pure_push() || matches!(br[i].op, CompilerOpcode::Dup))
+                && matches!(br[i + 1].op, CompilerOpcode::Pop)
             {
                 br[i].op = CompilerOpcode::Nop;
                 br[i + 1].op = CompilerOpcode::Nop;
@@ -294,8 +384,59 @@ impl BasicBlock {
         }
     }
 
-    pub(crate) fn run_optimize_passes(&self, cv: &ConstantValues) {
+    // swaps that cancel out or have nothing to do, and pairs of values that
+    // are pushed only to be swapped right away
+    fn remove_redundant_swaps(&self) {
+        let mut br = self.imp.writer.borrow_mut();
+        let mut i = 1;
+        while i < br.len() {
+            if matches!(br[i].op, CompilerOpcode::Swap) {
+                match br[i - 1].op {
+                    CompilerOpcode::Swap => {
+                        br.drain(i - 1..=i);
+                        i = i.saturating_sub(1).max(1);
+                        continue;
+                    }
+                    CompilerOpcode::Dup => {
+                        br.remove(i);
+                        continue;
+                    }
+                    _ if i >= 2 && br[i - 2].op.is_pure_push() && br[i - 1].op.is_pure_push() => {
+                        br.swap(i - 2, i - 1);
+                        br.remove(i);
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            i += 1;
+        }
+    }
+
+    // x.method(...) on a local x is common enough to get an instruction of
+    // its own, which saves two trips around the run loop per call
+    fn fuse_local_method_calls(&self) {
+        let mut br = self.imp.writer.borrow_mut();
+        let mut i = 2;
+        while i < br.len() {
+            if let (
+                CompilerOpcode::ReadLocal(local),
+                CompilerOpcode::ReadAttribute(name),
+                CompilerOpcode::Call(argc),
+            ) = (&br[i - 2].op, &br[i - 1].op, &br[i].op)
+            {
+                br[i - 2].op = CompilerOpcode::CallLocalMethod(*local, *name, *argc);
+                br[i - 2].src = br[i].src.clone();
+                br.drain(i - 1..=i);
+                continue;
+            }
+            i += 1;
+        }
+    }
+
+    pub(crate) fn run_optimize_passes(&self, cv: &mut ConstantValues) {
         self.optimize_true_false(cv);
+        self.fold_constants(cv);
         self.optimize_redundant_conditional_jumps();
         self.remove_redundant_local_reads();
         self.remove_redundant_named_reads();
@@ -304,7 +445,9 @@ impl BasicBlock {
         self.remove_nop_instructions();
         self.remove_push_pop_pairs();
         self.remove_nop_instructions();
-        while self.replace_double_jump() {}
+        self.remove_redundant_swaps();
+        self.fuse_local_method_calls();
+        self.thread_jumps();
     }
 
     pub(crate) fn drop_unused_locals(&self, values: &HashSet<u8>) {
@@ -312,18 +455,13 @@ impl BasicBlock {
 
         for i in 0..br.len() {
             match br[i].op {
-                CompilerOpcode::ReadLocal(x) => {
+                CompilerOpcode::ReadLocal(x) | CompilerOpcode::CallLocalMethod(x, ..) => {
                     assert!(!values.contains(&x));
                 }
-                CompilerOpcode::TypedefLocal(x) => {
-                    if values.contains(&x) {
-                        br[i].op = CompilerOpcode::Pop;
-                    }
-                }
-                CompilerOpcode::WriteLocal(x) => {
-                    if values.contains(&x) {
-                        br[i].op = CompilerOpcode::Pop;
-                    }
+                CompilerOpcode::TypedefLocal(x) | CompilerOpcode::WriteLocal(x)
+                    if values.contains(&x) =>
+                {
+                    br[i].op = CompilerOpcode::Pop;
                 }
                 _ => {}
             }
@@ -334,7 +472,9 @@ impl BasicBlock {
         let br = self.imp.writer.borrow();
         for i in 0..br.len() {
             match br[i].op {
-                CompilerOpcode::ReadLocal(x) | CompilerOpcode::StoreUplevel(x) => {
+                CompilerOpcode::ReadLocal(x)
+                | CompilerOpcode::StoreUplevel(x)
+                | CompilerOpcode::CallLocalMethod(x, ..) => {
                     dest.reads.insert(x);
                 }
                 CompilerOpcode::WriteLocal(x) => {
@@ -369,13 +509,11 @@ impl BasicBlock {
     }
 
     pub(crate) fn write_line_table(&self, offset: u16, line_table: &LineTable) {
-        let mut cur_offset = offset;
         let br = self.imp.writer.borrow();
-        for src_op in br.as_slice() {
+        for (cur_offset, src_op) in (offset..).zip(br.as_slice()) {
             if let Some(src) = &src_op.src {
                 line_table.insert(cur_offset, src.clone());
             }
-            cur_offset += 1;
         }
     }
 }
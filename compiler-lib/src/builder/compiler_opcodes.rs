@@ -56,12 +56,19 @@ pub enum CompilerOpcode {
     JumpConditionally(BasicBlock, BasicBlock),
     JumpIfArgSupplied(u8, BasicBlock),
     Call(u8),
+    // a call whose result is returned right away; always followed by Return
+    TailCall(u8),
+    // ReadLocal(x); ReadAttribute(name); Call(argc)
+    CallLocalMethod(u8, u16, u8),
     Return,
     ReturnUnit,
+    Yield,
     TryEnter(BasicBlock),
     TryExit,
     Throw,
+    ThrowFrom,
     BuildList(u32),
+    BuildMap(u32),
     BuildFunction,
     StoreUplevel(u8),
     BuildStruct,
@@ -73,6 +80,8 @@ pub enum CompilerOpcode {
     EnumCheckIsCase(u16),
     EnumTryExtractPayload,
     TryUnwrapProtocol(u8),
+    MatchFailed(u16),
+    FormatValue(u16),
     Isa,
     Import(u16),
     LiftModule,
@@ -132,12 +141,17 @@ impl CompilerOpcode {
             Self::JumpConditionally(..) => true,
             Self::JumpIfArgSupplied(..) => false,
             Self::Call(_) => false,
+            Self::TailCall(_) => false,
+            Self::CallLocalMethod(..) => false,
             Self::Return => true,
             Self::ReturnUnit => true,
+            Self::Yield => false,
             Self::TryEnter(_) => false,
             Self::TryExit => false,
             Self::Throw => true,
+            Self::ThrowFrom => true,
             Self::BuildList(_) => false,
+            Self::BuildMap(_) => false,
             Self::BuildFunction => false,
             Self::StoreUplevel(_) => false,
             Self::BuildStruct => false,
@@ -149,6 +163,8 @@ impl CompilerOpcode {
             Self::EnumCheckIsCase(_) => false,
             Self::EnumTryExtractPayload => false,
             Self::TryUnwrapProtocol(_) => false,
+            Self::MatchFailed(_) => true,
+            Self::FormatValue(_) => false,
             Self::Isa => false,
             Self::Import(_) => false,
             Self::LiftModule => false,
@@ -158,6 +174,20 @@ impl CompilerOpcode {
         }
     }
 
+    // pushes a value without side effects, so it can be dropped or reordered
+    pub fn is_pure_push(&self) -> bool {
+        matches!(
+            self,
+            Self::Push(_)
+                | Self::Push0
+                | Self::Push1
+                | Self::PushTrue
+                | Self::PushFalse
+                | Self::PushBuiltinTy(_)
+                | Self::ReadLocal(_)
+        )
+    }
+
     pub fn is_jump_instruction(&self) -> Vec<BasicBlock> {
         match self {
             Self::TryEnter(dst)
@@ -248,8 +278,11 @@ impl CompilerOpcode {
                 VmOpcode::JumpIfArgSupplied(*arg, offset)
             }
             Self::Call(n) => VmOpcode::Call(*n),
+            Self::TailCall(n) => VmOpcode::TailCall(*n),
+            Self::CallLocalMethod(x, name, argc) => VmOpcode::CallLocalMethod(*x, *name, *argc),
             Self::Return => VmOpcode::Return,
             Self::ReturnUnit => VmOpcode::ReturnUnit,
+            Self::Yield => VmOpcode::Yield,
             Self::TryEnter(dst) => {
                 let offset = parent
                     .position_of_block_instructions(dst)
@@ -258,7 +291,9 @@ impl CompilerOpcode {
             }
             Self::TryExit => VmOpcode::TryExit,
             Self::Throw => VmOpcode::Throw,
+            Self::ThrowFrom => VmOpcode::ThrowFrom,
             Self::BuildList(v) => VmOpcode::BuildList(*v),
+            Self::BuildMap(v) => VmOpcode::BuildMap(*v),
             Self::BuildFunction => VmOpcode::BuildFunction,
             Self::StoreUplevel(a) => VmOpcode::StoreUplevel(*a),
             Self::BuildStruct => VmOpcode::BuildStruct,
@@ -272,6 +307,8 @@ impl CompilerOpcode {
             Self::EnumCheckIsCase(v) => VmOpcode::EnumCheckIsCase(*v),
             Self::EnumTryExtractPayload => VmOpcode::EnumTryExtractPayload,
             Self::TryUnwrapProtocol(v) => VmOpcode::TryUnwrapProtocol(*v),
+            Self::MatchFailed(v) => VmOpcode::MatchFailed(*v),
+            Self::FormatValue(v) => VmOpcode::FormatValue(*v),
             Self::Isa => VmOpcode::Isa,
             Self::Import(v) => VmOpcode::Import(*v),
             Self::LiftModule => VmOpcode::LiftModule,
@@ -338,12 +375,19 @@ impl std::fmt::Display for CompilerOpcode {
                 write!(f, "JumpConditionally({}, {})", tr.name(), fa.name())
             }
             Call(n) => write!(f, "Call({})", n),
+            TailCall(n) => write!(f, "TailCall({})", n),
+            CallLocalMethod(x, name, argc) => {
+                write!(f, "CallLocalMethod({}, {}, {})", x, name, argc)
+            }
             Return => write!(f, "Return"),
             ReturnUnit => write!(f, "ReturnUnit"),
+            Yield => write!(f, "Yield"),
             TryEnter(dst) => write!(f, "TryEnter({})", dst.name()),
             TryExit => write!(f, "TryExit"),
             Throw => write!(f, "Throw"),
+            ThrowFrom => write!(f, "ThrowFrom"),
             BuildList(v) => write!(f, "BuildList({})", v),
+            BuildMap(v) => write!(f, "BuildMap({})", v),
             BuildFunction => write!(f, "BuildFunction"),
             StoreUplevel(a) => write!(f, "StoreUplevel({})", a),
             BuildStruct => write!(f, "BuildStruct"),
@@ -355,6 +399,8 @@ impl std::fmt::Display for CompilerOpcode {
             EnumCheckIsCase(v) => write!(f, "EnumCheckIsCase({})", v),
             EnumTryExtractPayload => write!(f, "EnumTryExtractPayload"),
             TryUnwrapProtocol(v) => write!(f, "TryUnwrapProtocol({})", v),
+            MatchFailed(v) => write!(f, "MatchFailed({})", v),
+            FormatValue(v) => write!(f, "FormatValue({})", v),
             Isa => write!(f, "Isa"),
             Import(v) => write!(f, "Import({})", v),
             LiftModule => write!(f, "LiftModule"),
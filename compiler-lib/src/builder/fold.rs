@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// Constant folding only covers the operations that the VM evaluates natively
+// on these operand types, so that the result is what running the code would
+// produce; anything that could fail at runtime (e.g. division by zero, or an
+// Int overflowing into a big integer) is left for the VM to do.
+
+use crate::{
+    builder::compiler_opcodes::CompilerOpcode,
+    constant_value::{ConstantValue, ConstantValues},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Literal {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+}
+
+impl Literal {
+    // the value an instruction pushes, if it is a literal
+    pub(crate) fn of(op: &CompilerOpcode, cv: &ConstantValues) -> Option<Self> {
+        match op {
+            CompilerOpcode::Push0 => Some(Self::Int(0)),
+            CompilerOpcode::Push1 => Some(Self::Int(1)),
+            CompilerOpcode::PushTrue => Some(Self::Bool(true)),
+            CompilerOpcode::PushFalse => Some(Self::Bool(false)),
+            CompilerOpcode::Push(idx) => match cv.get(*idx as usize)? {
+                ConstantValue::Integer(n) => Some(Self::Int(n)),
+                ConstantValue::Float(f) => Some(Self::Float(f.raw_value())),
+                ConstantValue::String(s) => Some(Self::String(s)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // the instruction that pushes this value, or None if the constant table
+    // is full
+    pub(crate) fn to_opcode(&self, cv: &mut ConstantValues) -> Option<CompilerOpcode> {
+        let constant = match self {
+            Self::Int(0) => return Some(CompilerOpcode::Push0),
+            Self::Int(1) => return Some(CompilerOpcode::Push1),
+            Self::Bool(true) => return Some(CompilerOpcode::PushTrue),
+            Self::Bool(false) => return Some(CompilerOpcode::PushFalse),
+            Self::Int(n) => ConstantValue::Integer(*n),
+            Self::Float(f) => ConstantValue::Float((*f).into()),
+            Self::String(s) => ConstantValue::String(s.clone()),
+        };
+        cv.insert(constant).ok().map(CompilerOpcode::Push)
+    }
+}
+
+// lhs is the operand that was pushed first
+pub(crate) fn fold_binary(op: &CompilerOpcode, lhs: &Literal, rhs: &Literal) -> Option<Literal> {
+    use Literal::*;
+
+    match (op, lhs, rhs) {
+        (CompilerOpcode::Add, Int(a), Int(b)) => a.checked_add(*b).map(Int),
+        (CompilerOpcode::Sub, Int(a), Int(b)) => a.checked_sub(*b).map(Int),
+        (CompilerOpcode::Mul, Int(a), Int(b)) => a.checked_mul(*b).map(Int),
+        (CompilerOpcode::Div, Int(a), Int(b)) => a.checked_div(*b).map(Int),
+        (CompilerOpcode::Rem, Int(a), Int(b)) => a.checked_rem(*b).map(Int),
+        (CompilerOpcode::Add, Float(a), Float(b)) => Some(Float(a + b)),
+        (CompilerOpcode::Sub, Float(a), Float(b)) => Some(Float(a - b)),
+        (CompilerOpcode::Mul, Float(a), Float(b)) => Some(Float(a * b)),
+        (CompilerOpcode::Div, Float(a), Float(b)) if *b != 0.0 => Some(Float(a / b)),
+        (CompilerOpcode::Rem, Float(a), Float(b)) if *b != 0.0 => Some(Float(a % b)),
+        (CompilerOpcode::Add, String(a), String(b)) => Some(String(format!("{a}{b}"))),
+        (CompilerOpcode::LessThan, Int(a), Int(b)) => Some(Bool(a < b)),
+        (CompilerOpcode::LessThanEqual, Int(a), Int(b)) => Some(Bool(a <= b)),
+        (CompilerOpcode::GreaterThan, Int(a), Int(b)) => Some(Bool(a > b)),
+        (CompilerOpcode::GreaterThanEqual, Int(a), Int(b)) => Some(Bool(a >= b)),
+        (CompilerOpcode::LessThan, Float(a), Float(b)) => Some(Bool(a < b)),
+        (CompilerOpcode::LessThanEqual, Float(a), Float(b)) => Some(Bool(a <= b)),
+        (CompilerOpcode::GreaterThan, Float(a), Float(b)) => Some(Bool(a > b)),
+        (CompilerOpcode::GreaterThanEqual, Float(a), Float(b)) => Some(Bool(a >= b)),
+        _ => None,
+    }
+}
+
+pub(crate) fn fold_unary(op: &CompilerOpcode, val: &Literal) -> Option<Literal> {
+    match (op, val) {
+        (CompilerOpcode::Neg, Literal::Int(n)) => n.checked_neg().map(Literal::Int),
+        (CompilerOpcode::Neg, Literal::Float(f)) => Some(Literal::Float(-f)),
+        (CompilerOpcode::Not, Literal::Bool(b)) => Some(Literal::Bool(!b)),
+        _ => None,
+    }
+}
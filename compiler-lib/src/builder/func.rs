@@ -15,6 +15,7 @@ pub struct FunctionBuilder {
     current: BasicBlock,
     bb_id: usize,
     line_table: LineTable,
+    is_generator: bool,
 }
 
 impl Default for FunctionBuilder {
@@ -25,6 +26,7 @@ impl Default for FunctionBuilder {
             current: BasicBlock::new("entry", 0),
             bb_id: 1,
             line_table: Default::default(),
+            is_generator: false,
         };
         this.blocks.push(this.current.clone());
         this.names.insert(this.current.name().to_owned());
@@ -103,6 +105,15 @@ impl FunctionBuilder {
         self.current = blk;
     }
 
+    // a function that contains a yield anywhere in its body is a generator
+    pub fn mark_as_generator(&mut self) {
+        self.is_generator = true;
+    }
+
+    pub fn is_generator(&self) -> bool {
+        self.is_generator
+    }
+
     pub fn get_current_block(&self) -> BasicBlock {
         self.current.clone()
     }
@@ -119,51 +130,52 @@ impl FunctionBuilder {
         None
     }
 
-    fn has_entrypoints(&self, blk: &BasicBlock) -> bool {
-        for src_blk in &self.blocks {
-            let br = src_blk.imp.writer.borrow();
-            for src_op in br.as_slice() {
-                for dst in src_op.op.is_jump_instruction() {
-                    if dst.id() == blk.id() {
-                        return true;
-                    }
-                }
+    // blocks that can be reached from the entry block, either through a jump
+    // or by falling through an empty block into the one after it
+    fn find_reachable_blocks(&self) -> HashSet<usize> {
+        let mut reachable = HashSet::from([0]);
+        let mut pending = vec![0];
+
+        while let Some(id) = pending.pop() {
+            let Some(pos) = self.blocks.iter().position(|blk| blk.id() == id) else {
+                continue;
+            };
+            let blk = &self.blocks[pos];
+            let mut successors = vec![];
+            if blk.is_empty()
+                && let Some(next) = self.blocks.get(pos + 1)
+            {
+                successors.push(next.id());
             }
-        }
-        false
-    }
-
-    fn find_orphaned_blocks(&self) -> HashSet<usize> {
-        let mut orphans = HashSet::<usize>::default();
-
-        for blk in &self.blocks {
-            if blk.id() != 0 {
-                let has_entrypoints = self.has_entrypoints(blk);
-                if !has_entrypoints {
-                    orphans.insert(blk.id());
+            for entry in blk.imp.writer.borrow().as_slice() {
+                successors.extend(entry.op.is_jump_instruction().iter().map(|dst| dst.id()));
+            }
+            for succ in successors {
+                if reachable.insert(succ) {
+                    pending.push(succ);
                 }
             }
         }
 
-        orphans
+        reachable
     }
 
-    fn remove_block_with_id(&mut self, id: usize) -> bool {
-        for i in 0..self.blocks.len() {
-            if self.blocks[i].id() == id {
-                self.blocks.remove(i);
-                return true;
-            }
-        }
+    fn remove_unreachable_blocks(&mut self) {
+        let reachable = self.find_reachable_blocks();
+        self.blocks.retain(|blk| reachable.contains(&blk.id()));
+    }
 
-        false
+    fn instruction_count(&self) -> usize {
+        self.blocks.iter().map(|blk| blk.len()).sum()
     }
 
-    fn run_optimize_passes(&mut self, cv: &ConstantValues) {
-        let orphans = self.find_orphaned_blocks();
-        for orphan_id in &orphans {
-            assert!(self.remove_block_with_id(*orphan_id));
+    fn run_optimize_passes(&mut self, cv: &mut ConstantValues) {
+        // code after a return, throw or break can never run, and neither can
+        // anything that only it jumps to
+        for blk in &self.blocks {
+            blk.remove_instructions_after_terminal();
         }
+        self.remove_unreachable_blocks();
 
         let locals_access = self.calculate_locals_access();
         let unused_locals = locals_access.calculate_unused_locals();
@@ -174,6 +186,9 @@ impl FunctionBuilder {
             }
             blk.run_optimize_passes(cv);
         }
+
+        // jumps that were folded or threaded may have left more blocks behind
+        self.remove_unreachable_blocks();
     }
 
     fn calculate_locals_access(&self) -> LocalValuesAccess {
@@ -188,16 +203,25 @@ impl FunctionBuilder {
 
     pub fn write(
         &mut self,
-        cv: &ConstantValues,
+        name: &str,
+        cv: &mut ConstantValues,
         options: &CompilationOptions,
     ) -> Result<Vec<u8>, crate::do_compile::CompilationErrorReason> {
         if options.dump_builder {
-            println!("(unopt) Intermediate Representation Dump:\n{}", self);
+            println!(
+                "(unopt) Intermediate Representation Dump of {name} ({} instructions):\n{}",
+                self.instruction_count(),
+                self
+            );
         }
         if options.optimize {
             self.run_optimize_passes(cv);
             if options.dump_builder {
-                println!("(opt) Intermediate Representation Dump:\n{}", self);
+                println!(
+                    "(opt) Intermediate Representation Dump of {name} ({} instructions):\n{}",
+                    self.instruction_count(),
+                    self
+                );
             }
         }
 
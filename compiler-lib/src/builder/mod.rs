@@ -1,4 +1,5 @@
 // SPDX-License-Identifier: Apache-2.0
 pub mod block;
 pub mod compiler_opcodes;
+pub(crate) mod fold;
 pub mod func;
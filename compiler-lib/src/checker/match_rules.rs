@@ -0,0 +1,409 @@
+// SPDX-License-Identifier: Apache-2.0
+use aria_parser::ast::{
+    CompSymbol, Expression, IntLiteralBase, MatchPattern, MatchPatternField, MatchPatternList,
+    MatchPatternStruct, MatchRule, PostfixTerm, Primary, RelSymbol, UnarySymbol,
+    prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+};
+
+use crate::checker::types::as_postfix;
+
+fn text_of(expr: &Expression) -> String {
+    expr.prettyprint(PrintoutAccumulator::default()).value()
+}
+
+// an expression that evaluates to the same value every time a match runs
+// through its rules: a literal, or a name optionally followed by attributes
+fn is_stable(expr: &Expression) -> bool {
+    let Some(postfix) = as_postfix(expr) else {
+        return numeric_value(expr).is_some();
+    };
+    let base_ok = !matches!(
+        postfix.base,
+        Primary::ListLiteral(_) | Primary::MapLiteral(_) | Primary::ParenExpression(_)
+    );
+    base_ok
+        && postfix.terms.iter().all(|t| match t {
+            PostfixTerm::PostfixTermAttribute(_) => true,
+            PostfixTerm::PostfixTermEnumCase(c) => c.payload.is_none(),
+            _ => false,
+        })
+}
+
+fn numeric_value(expr: &Expression) -> Option<f64> {
+    let Expression::LogOperation(log) = expr else {
+        return None;
+    };
+    let unary = &log.left.left.left.left.left.left;
+    if !log.right.is_empty()
+        || log.left.right.is_some()
+        || log.left.left.right.is_some()
+        || log.left.left.left.right.is_some()
+        || !log.left.left.left.left.right.is_empty()
+        || !log.left.left.left.left.left.right.is_empty()
+        || !unary.postfix.expr.terms.is_empty()
+    {
+        return None;
+    }
+    let value = match &unary.postfix.expr.base {
+        Primary::IntLiteral(i) => {
+            let (radix, digits) = match i.base {
+                IntLiteralBase::Binary => (2, i.val.trim_start_matches("0b")),
+                IntLiteralBase::Octal => (8, i.val.trim_start_matches("0o")),
+                IntLiteralBase::Decimal => (10, i.val.as_str()),
+                IntLiteralBase::Hexadecimal => (16, i.val.trim_start_matches("0x")),
+            };
+            i64::from_str_radix(&digits.replace('_', ""), radix).ok()? as f64
+        }
+        Primary::FloatLiteral(f) => f.val.replace('_', "").parse().ok()?,
+        Primary::ParenExpression(p) => numeric_value(&p.value)?,
+        _ => return None,
+    };
+    match unary.operand {
+        None => Some(value),
+        Some(UnarySymbol::Minus) => Some(-value),
+        Some(UnarySymbol::Exclamation) => None,
+    }
+}
+
+// a constraint on numbers, as described by a comparison pattern
+#[derive(Clone, Copy)]
+enum Bound {
+    Eq(f64),
+    Less(f64),
+    LessEq(f64),
+    Greater(f64),
+    GreaterEq(f64),
+}
+
+fn bound_of(pattern: &MatchPattern) -> Option<Bound> {
+    match pattern {
+        MatchPattern::MatchPatternComp(c) if c.op == CompSymbol::Equal => {
+            numeric_value(&c.expr).map(Bound::Eq)
+        }
+        MatchPattern::MatchPatternRel(r) => {
+            let n = numeric_value(&r.expr)?;
+            Some(match r.op {
+                RelSymbol::Less => Bound::Less(n),
+                RelSymbol::LessEqual => Bound::LessEq(n),
+                RelSymbol::Greater => Bound::Greater(n),
+                RelSymbol::GreaterEqual => Bound::GreaterEq(n),
+            })
+        }
+        _ => None,
+    }
+}
+
+// true if every number that satisfies narrow also satisfies wide
+fn bound_implies(narrow: Bound, wide: Bound) -> bool {
+    use Bound::*;
+    match (narrow, wide) {
+        (Eq(a), Eq(b)) => a == b,
+        (Eq(a), Less(b)) => a < b,
+        (Eq(a), LessEq(b)) | (Less(a), Less(b)) | (Less(a), LessEq(b)) | (LessEq(a), LessEq(b)) => {
+            a <= b
+        }
+        (LessEq(a), Less(b)) => a < b,
+        (Eq(a), Greater(b)) => a > b,
+        (Eq(a), GreaterEq(b))
+        | (Greater(a), Greater(b))
+        | (Greater(a), GreaterEq(b))
+        | (GreaterEq(a), GreaterEq(b)) => a >= b,
+        (GreaterEq(a), Greater(b)) => a > b,
+        _ => false,
+    }
+}
+
+// the element of a list pattern at index idx from the front, or from the
+// back when from_back is set, if the pattern fixes one there
+fn list_element(list: &MatchPatternList, idx: usize, from_back: bool) -> Option<&MatchPattern> {
+    // without a rest, all the patterns are in before
+    let (near, far) = if from_back {
+        (&list.after, &list.before)
+    } else {
+        (&list.before, &list.after)
+    };
+    let (side, idx) = if idx < near.len() {
+        (near, idx)
+    } else if list.rest.is_none() {
+        (far, idx - near.len())
+    } else {
+        return None;
+    };
+    if from_back {
+        side.get(side.len().checked_sub(idx + 1)?)
+    } else {
+        side.get(idx)
+    }
+}
+
+fn list_implies(narrow: &MatchPatternList, wide: &MatchPatternList) -> bool {
+    let narrow_len = narrow.before.len() + narrow.after.len();
+    let wide_len = wide.before.len() + wide.after.len();
+    let lengths_ok = match (&narrow.rest, &wide.rest) {
+        (None, None) => narrow_len == wide_len,
+        (_, Some(_)) => narrow_len >= wide_len,
+        (Some(_), None) => false,
+    };
+    lengths_ok
+        && wide
+            .before
+            .iter()
+            .enumerate()
+            .all(|(i, w)| list_element(narrow, i, false).is_some_and(|n| pattern_implies(n, w)))
+        && wide
+            .after
+            .iter()
+            .rev()
+            .enumerate()
+            .all(|(i, w)| list_element(narrow, i, true).is_some_and(|n| pattern_implies(n, w)))
+}
+
+fn struct_implies(narrow: &MatchPatternStruct, wide: &MatchPatternStruct) -> bool {
+    let type_ok = match (&narrow.ty, &wide.ty) {
+        (_, None) => true,
+        (Some(n), Some(w)) => is_stable(w) && text_of(n) == text_of(w),
+        (None, Some(_)) => false,
+    };
+    type_ok
+        && wide.fields.iter().all(|w| {
+            narrow
+                .fields
+                .iter()
+                .any(|n| n.name.value == w.name.value && field_implies(n, w))
+        })
+}
+
+fn field_implies(narrow: &MatchPatternField, wide: &MatchPatternField) -> bool {
+    match (&narrow.pattern, &wide.pattern) {
+        (_, None) => true,
+        (Some(n), Some(w)) => pattern_implies(n, w),
+        (None, Some(w)) => matches_anything(w),
+    }
+}
+
+// wildcards and names without a type match every value
+fn matches_anything(pattern: &MatchPattern) -> bool {
+    match pattern {
+        MatchPattern::MatchPatternWildcard(_) => true,
+        MatchPattern::MatchPatternBinding(b) => b.id.ty.is_none(),
+        _ => false,
+    }
+}
+
+// true if every value that matches narrow also matches wide
+fn pattern_implies(narrow: &MatchPattern, wide: &MatchPattern) -> bool {
+    if let (Some(n), Some(w)) = (bound_of(narrow), bound_of(wide)) {
+        return bound_implies(n, w);
+    }
+    if matches_anything(wide) {
+        return true;
+    }
+
+    match (narrow, wide) {
+        (MatchPattern::MatchPatternComp(n), MatchPattern::MatchPatternComp(w)) => {
+            n.op == w.op && is_stable(&w.expr) && text_of(&n.expr) == text_of(&w.expr)
+        }
+        (MatchPattern::MatchPatternRel(n), MatchPattern::MatchPatternRel(w)) => {
+            n.op == w.op && is_stable(&w.expr) && text_of(&n.expr) == text_of(&w.expr)
+        }
+        (MatchPattern::MatchPatternEnumCase(n), MatchPattern::MatchPatternEnumCase(w)) => {
+            if n.case.value != w.case.value {
+                return false;
+            }
+            match (&n.payload, &w.payload) {
+                (_, None) => true,
+                (None, Some(_)) => false,
+                (Some(n), Some(w)) => pattern_implies(n, w),
+            }
+        }
+        (MatchPattern::MatchPatternBinding(n), MatchPattern::MatchPatternBinding(w)) => {
+            match (&n.id.ty, &w.id.ty) {
+                (Some(nt), Some(wt)) => is_stable(wt) && text_of(nt) == text_of(wt),
+                _ => false,
+            }
+        }
+        (MatchPattern::MatchPatternList(n), MatchPattern::MatchPatternList(w)) => {
+            list_implies(n, w)
+        }
+        (MatchPattern::MatchPatternStruct(n), MatchPattern::MatchPatternStruct(w)) => {
+            struct_implies(n, w)
+        }
+        _ => false,
+    }
+}
+
+// true if rule wide matches every value that rule narrow matches, in which
+// case narrow can never run if it comes after wide
+pub(crate) fn rule_covers(wide: &MatchRule, narrow: &MatchRule) -> bool {
+    // a guard can reject any value
+    wide.guard.is_none()
+        && wide
+            .patterns
+            .iter()
+            .all(|w| narrow.patterns.iter().any(|n| pattern_implies(n, w)))
+}
+
+// an enum the checker knows every case of, in the order they are declared
+pub(crate) struct KnownEnum {
+    pub(crate) name: String,
+    pub(crate) cases: Vec<String>,
+    // the cases that carry a payload; a pattern with a payload never
+    // matches a case that has none
+    pub(crate) with_payload: Vec<String>,
+}
+
+// the one enum that has a case by each of these names, if there is one
+fn enum_with_cases<'a>(enums: &'a [KnownEnum], names: &[&str]) -> Option<&'a KnownEnum> {
+    if names.is_empty() {
+        return None;
+    }
+    let mut candidates = enums
+        .iter()
+        .filter(|e| names.iter().all(|n| e.cases.iter().any(|c| c == n)));
+    let found = candidates.next()?;
+    candidates.next().is_none().then_some(found)
+}
+
+fn case_names<'a>(patterns: &[&'a MatchPattern]) -> Vec<&'a str> {
+    patterns
+        .iter()
+        .filter_map(|p| match p {
+            MatchPattern::MatchPatternEnumCase(c) => Some(c.case.value.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+// what a rule accepts, for the rules whose patterns the checker can follow
+enum Handles<'a> {
+    Everything,
+    Case(&'a MatchPattern),
+}
+
+// a rule with a guard, or with patterns other than one case, those that
+// match anything and `isa` of the enum, can reject values of any case
+fn rule_handles<'a>(rule: &'a MatchRule, enum_name: &str) -> Option<Handles<'a>> {
+    if rule.guard.is_some() {
+        return None;
+    }
+    let mut case = None;
+    for pattern in &rule.patterns {
+        match pattern {
+            MatchPattern::MatchPatternEnumCase(_) if case.is_none() => case = Some(pattern),
+            MatchPattern::MatchPatternComp(c)
+                if c.op == CompSymbol::Isa
+                    && as_postfix(&c.expr).is_some_and(|p| {
+                        p.terms.is_empty()
+                            && matches!(&p.base, Primary::Identifier(id) if id.value == enum_name)
+                    }) => {}
+            p if matches_anything(p) => {}
+            _ => return None,
+        }
+    }
+    Some(case.map_or(Handles::Everything, Handles::Case))
+}
+
+// the values of the enum that none of the patterns match, written the way
+// a pattern for them would be; a payload the checker cannot follow, e.g. a
+// literal or a typed binding, counts as matching everything unless strict
+fn unmatched_values(
+    known: &KnownEnum,
+    patterns: &[&MatchPattern],
+    enums: &[KnownEnum],
+    strict: bool,
+) -> Vec<String> {
+    let mut unmatched = vec![];
+    for case in &known.cases {
+        let payloads = patterns
+            .iter()
+            .filter_map(|p| match p {
+                MatchPattern::MatchPatternEnumCase(c) if c.case.value == *case => {
+                    Some(c.payload.as_deref())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if !known.with_payload.contains(case) {
+            if !payloads.iter().any(|p| p.is_none()) {
+                unmatched.push(case.clone());
+            }
+        } else if payloads.is_empty() {
+            unmatched.push(case.clone());
+        } else if payloads
+            .iter()
+            .all(|p| p.is_some_and(|p| !matches_anything(p)))
+        {
+            let inner = payloads.into_iter().flatten().collect::<Vec<_>>();
+            if let Some(payload_enum) = enum_with_cases(enums, &case_names(&inner)) {
+                for value in unmatched_values(payload_enum, &inner, enums, strict) {
+                    unmatched.push(format!("{case}({value})"));
+                }
+            } else if strict {
+                unmatched.push(format!("{case}(..)"));
+            }
+        }
+    }
+    unmatched
+}
+
+fn unhandled(
+    rules: &[MatchRule],
+    known: &KnownEnum,
+    enums: &[KnownEnum],
+    strict: bool,
+) -> Vec<String> {
+    let mut patterns = vec![];
+    for rule in rules {
+        match rule_handles(rule, &known.name) {
+            Some(Handles::Everything) => return vec![],
+            Some(Handles::Case(p)) => patterns.push(p),
+            None => {}
+        }
+    }
+    unmatched_values(known, &patterns, enums, strict)
+}
+
+// whether every case the rule looks for is a case of the enum; a rule for a
+// case it does not have is reported as such instead
+pub(crate) fn names_only_cases_of(rule: &MatchRule, known: &KnownEnum) -> bool {
+    rule.patterns.iter().all(|p| match p {
+        MatchPattern::MatchPatternEnumCase(c) => known.cases.contains(&c.case.value),
+        _ => true,
+    })
+}
+
+// the values of the enum that the rules of a match may leave unhandled
+pub(crate) fn unhandled_values(
+    rules: &[MatchRule],
+    known: &KnownEnum,
+    enums: &[KnownEnum],
+) -> Vec<String> {
+    unhandled(rules, known, enums, false)
+}
+
+// whether the rules of a match surely handle every value of the enum
+pub(crate) fn handles_every_value(
+    rules: &[MatchRule],
+    known: &KnownEnum,
+    enums: &[KnownEnum],
+) -> bool {
+    unhandled(rules, known, enums, true).is_empty()
+}
+
+// the enum whose cases the rules of a match look for, when every rule looks
+// for a case and only one enum has all of those cases
+pub(crate) fn enum_of_rules<'a>(
+    rules: &[MatchRule],
+    enums: &'a [KnownEnum],
+) -> Option<&'a KnownEnum> {
+    let mut names = vec![];
+    for rule in rules {
+        let patterns = rule.patterns.iter().collect::<Vec<_>>();
+        let cases = case_names(&patterns);
+        if cases.is_empty() {
+            return None;
+        }
+        names.extend(cases);
+    }
+    enum_with_cases(enums, &names)
+}
@@ -0,0 +1,1236 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// an ahead of time checker, which looks for mistakes that would otherwise
+// only surface when the faulty code runs; it only knows about the types of
+// literals, of the functions, structs and enums declared in the module, and
+// of type annotations, and stays silent about anything else
+use std::collections::{HashMap, HashSet};
+
+use aria_parser::ast::{
+    AddOperation, AddSymbol, ArgumentList, AssignStatement, CodeBlock, CompOperation, ElsePiece,
+    EnumDecl, EnumDeclEntry, Expression, ExtensionDecl, FunctionBody, Identifier, IfStatement,
+    ImportTarget, InterpolatedStringPart, LambdaBody, LogOperation, LogSymbol, MatchPattern,
+    MatchStatement, MethodAccess, MixinDecl, ModuleFlag, MulOperation, ParsedModule,
+    PostfixExpression, PostfixTerm, PostfixTermWrite, Primary, RelOperation, ShiftOperation,
+    SourcePointer, Statement, StructDecl, StructEntry, TopLevelEntry, TryBlock, UnaryOperation,
+    UnarySymbol, ValDeclStatement, WriteOpEqStatement,
+};
+use thiserror::Error;
+
+use crate::do_compile::operator_method_name;
+
+mod match_rules;
+mod types;
+
+use match_rules::KnownEnum;
+
+pub use types::Arity;
+use types::{Allowed, Signature, Ty, accepts, as_identifier, as_postfix, describe_allowed};
+
+#[derive(Debug, Error)]
+pub enum CheckErrorReason {
+    #[error("'{0}' accepts {1} arguments, but {2} were provided")]
+    ArityMismatch(String, Arity, usize),
+    #[error("argument '{1}' of '{0}' expects {2}, but a value of type {3} was provided")]
+    ArgumentTypeMismatch(String, String, String, String),
+    #[error("'{0}' is declared as {1}, but a value of type {2} is stored in it")]
+    DeclarationTypeMismatch(String, String, String),
+    #[error("{0} has no attribute '{1}'")]
+    UnknownAttribute(String, String),
+    #[error("enum {0} has no case '{1}'")]
+    UnknownEnumCase(String, String),
+    #[error("match rule can never be reached, as the rule at {0} matches first")]
+    UnreachableMatchRule(SourcePointer),
+    #[error("match rule can never be reached, as the rules before it handle every case of {0}")]
+    MatchRuleAfterAllCases(String),
+    #[error("match over {0} has no else and no rule for {1}")]
+    NonExhaustiveMatch(String, String),
+    #[error("else can never run, as the rules handle every case of {0}")]
+    UnreachableMatchElse(String),
+}
+
+impl CheckErrorReason {
+    // warnings point at code that is likely wrong, but runs as written
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            Self::UnreachableMatchRule(_)
+                | Self::MatchRuleAfterAllCases(_)
+                | Self::NonExhaustiveMatch(..)
+                | Self::UnreachableMatchElse(_)
+        )
+    }
+}
+
+pub struct CheckError {
+    pub loc: SourcePointer,
+    pub reason: CheckErrorReason,
+}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at {}, check failed: {}", self.loc, self.reason)
+    }
+}
+
+impl std::fmt::Debug for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at {}, check failed: {}", self.loc, self.reason)
+    }
+}
+
+// enums of the VM that a match can be over without the module declaring them,
+// with whether each case carries a payload
+const BUILTIN_ENUMS: &[(&str, &[(&str, bool)])] = &[
+    ("Maybe", &[("Some", true), ("None", false)]),
+    ("Result", &[("Ok", true), ("Err", true)]),
+];
+
+#[derive(Clone, Copy)]
+enum Member {
+    InstanceMethod(usize),
+    TypeMethod(usize),
+    Other,
+}
+
+// a struct or enum declared at the top level of the module
+#[derive(Default)]
+struct TypeInfo {
+    members: HashMap<String, Member>,
+    // in the order they are declared
+    cases: Option<Vec<String>>,
+    // the cases that carry a payload
+    with_payload: Vec<String>,
+    // members can come from somewhere the checker cannot see, e.g. a mixin
+    open: bool,
+}
+
+#[derive(Clone)]
+struct Binding {
+    ty: Ty,
+    declared: Allowed,
+}
+
+// what the module does anywhere in its code, learned before checking it
+#[derive(Default)]
+struct ModuleFacts {
+    // names that are the target of an assignment
+    reassigned: HashSet<String>,
+    // attribute names that are written to, or accessed by name
+    attributes: HashSet<String>,
+}
+
+struct Checker {
+    errors: Vec<CheckError>,
+    signatures: Vec<Signature>,
+    types: HashMap<String, TypeInfo>,
+    scopes: Vec<HashMap<String, Binding>>,
+    facts: ModuleFacts,
+}
+
+pub fn check_module(module: &ParsedModule) -> Vec<CheckError> {
+    // the first walk only learns which names and attributes are assigned to,
+    // which the second one needs to know before it reaches the assignment
+    let mut learner = Checker::new(module, ModuleFacts::default());
+    learner.module(module);
+
+    let mut checker = Checker::new(module, learner.facts);
+    checker.module(module);
+    checker.errors
+}
+
+fn is_alloc_this(expr: &Expression) -> bool {
+    let Some(postfix) = as_postfix(expr) else {
+        return false;
+    };
+    let Primary::Identifier(base) = &postfix.base else {
+        return false;
+    };
+    let Some((PostfixTerm::PostfixTermCall(call), rest)) = postfix.terms.split_first() else {
+        return false;
+    };
+    base.value == "alloc"
+        && call.args.expressions.len() == 1
+        && as_identifier(&call.args.expressions[0]).is_some_and(|id| id.value == "This")
+        && rest
+            .iter()
+            .all(|t| matches!(t, PostfixTerm::PostfixTermObjectWrite(_)))
+}
+
+// the values returned by a function body, not counting nested functions
+fn collect_returns<'a>(block: &'a CodeBlock, dest: &mut Vec<Option<&'a Expression>>) {
+    for stmt in &block.entries {
+        match stmt {
+            Statement::ReturnStatement(r) => dest.push(r.val.as_ref()),
+            Statement::CodeBlock(b) => collect_returns(b, dest),
+            Statement::IfStatement(i) => {
+                collect_returns(&i.iff.content.then, dest);
+                for e in &i.elsif {
+                    collect_returns(&e.content.then, dest);
+                }
+                if let Some(e) = &i.els {
+                    collect_returns(&e.then, dest);
+                }
+            }
+            Statement::MatchStatement(m) => {
+                for r in &m.rules {
+                    collect_returns(&r.then, dest);
+                }
+                if let Some(e) = &m.els {
+                    collect_returns(&e.then, dest);
+                }
+            }
+            Statement::WhileStatement(w) => collect_returns(&w.then, dest),
+            Statement::ForStatement(f) => collect_returns(&f.then, dest),
+            Statement::TryBlock(t) => {
+                collect_returns(&t.body, dest);
+                for c in &t.catches {
+                    collect_returns(&c.body, dest);
+                }
+                if let Some(f) = &t.finally {
+                    collect_returns(f, dest);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// a type method that only ever returns alloc(This) is a constructor
+fn is_constructor(body: &FunctionBody) -> bool {
+    let mut returns = vec![];
+    collect_returns(&body.code, &mut returns);
+    !returns.is_empty() && returns.iter().all(|r| r.is_some_and(is_alloc_this))
+}
+
+fn string_literal(expr: &Expression) -> Option<&str> {
+    match as_postfix(expr) {
+        Some(PostfixExpression {
+            base: Primary::StringLiteral(s),
+            terms,
+            ..
+        }) if terms.is_empty() => Some(&s.value),
+        _ => None,
+    }
+}
+
+fn numeric_result(left: &Ty, right: &Ty) -> Ty {
+    match (left, right) {
+        (Ty::Int, Ty::Int) => Ty::Int,
+        (l, r) if l.is_number() && r.is_number() => Ty::Float,
+        _ => Ty::Unknown,
+    }
+}
+
+impl Checker {
+    fn new(module: &ParsedModule, facts: ModuleFacts) -> Self {
+        let mut this = Self {
+            errors: vec![],
+            signatures: vec![],
+            types: HashMap::new(),
+            scopes: vec![HashMap::new()],
+            facts,
+        };
+        this.declare_module(module);
+        this
+    }
+
+    fn error(&mut self, loc: &SourcePointer, reason: CheckErrorReason) {
+        self.errors.push(CheckError {
+            loc: loc.clone(),
+            reason,
+        });
+    }
+
+    fn bind(&mut self, name: &str, ty: Ty, declared: Allowed) {
+        self.scopes
+            .last_mut()
+            .expect("no scope to bind into")
+            .insert(name.to_owned(), Binding { ty, declared });
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|s| s.get(name))
+    }
+
+    fn with_scope<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push(HashMap::new());
+        let ret = f(self);
+        self.scopes.pop();
+        ret
+    }
+
+    // the types a type annotation allows
+    fn annotation(&self, expr: &Expression) -> Allowed {
+        let mut allowed = vec![];
+        for member in types::union_members(expr) {
+            let name = as_identifier(&member)?;
+            if self
+                .lookup(&name.value)
+                .is_some_and(|b| !matches!(b.ty, Ty::Type(_)))
+            {
+                return None;
+            }
+            allowed.push(match name.value.as_str() {
+                "Int" => Ty::Int,
+                "Float" => Ty::Float,
+                "String" => Ty::String,
+                "Bool" => Ty::Bool,
+                "List" => Ty::List,
+                "Map" => Ty::Map,
+                other if self.types.contains_key(other) => Ty::Instance(other.to_owned()),
+                _ => return None,
+            });
+        }
+        Some(allowed)
+    }
+
+    fn add_signature(&mut self, name: &str, args: &ArgumentList, ret: Ty) -> usize {
+        let mut sig = Signature::new(name, args, |e| self.annotation(e));
+        sig.ret = ret;
+        self.signatures.push(sig);
+        self.signatures.len() - 1
+    }
+
+    fn declare_type(&mut self, name: &Identifier) {
+        if self.types.contains_key(&name.value) {
+            // two declarations of the same name, give up on both
+            self.types.get_mut(&name.value).unwrap().open = true;
+        } else {
+            self.types.insert(name.value.clone(), TypeInfo::default());
+        }
+        self.bind(&name.value, Ty::Type(name.value.clone()), None);
+    }
+
+    fn declare_members(&mut self, owner: &str, body: &[StructEntry]) {
+        for entry in body {
+            let (name, member) = match entry {
+                StructEntry::Method(m) => {
+                    let member = match m.access {
+                        MethodAccess::Instance => Member::InstanceMethod(self.add_signature(
+                            &m.name.value,
+                            &m.args,
+                            Ty::Unknown,
+                        )),
+                        MethodAccess::Type => {
+                            let ret = if is_constructor(&m.body) {
+                                Ty::Instance(owner.to_owned())
+                            } else {
+                                Ty::Unknown
+                            };
+                            Member::TypeMethod(self.add_signature(&m.name.value, &m.args, ret))
+                        }
+                    };
+                    (m.name.value.clone(), member)
+                }
+                StructEntry::Variable(v) => {
+                    for d in &v.decls {
+                        self.add_member(owner, &d.id.name.value, Member::Other);
+                    }
+                    continue;
+                }
+                StructEntry::Struct(s) => (s.name.value.clone(), Member::Other),
+                StructEntry::Enum(e) => (e.name.value.clone(), Member::Other),
+                StructEntry::MixinInclude(_) => {
+                    self.types.get_mut(owner).unwrap().open = true;
+                    continue;
+                }
+                StructEntry::Operator(o) => match operator_method_name(o) {
+                    Some(name) => (name, Member::Other),
+                    None => continue,
+                },
+            };
+            self.add_member(owner, &name, member);
+        }
+    }
+
+    fn add_member(&mut self, owner: &str, name: &str, member: Member) {
+        self.types
+            .get_mut(owner)
+            .unwrap()
+            .members
+            .insert(name.to_owned(), member);
+    }
+
+    fn declare_enum(&mut self, e: &EnumDecl) {
+        let mut cases = vec![];
+        let mut with_payload = vec![];
+        let mut entries = vec![];
+        for entry in &e.body {
+            match entry {
+                EnumDeclEntry::EnumCaseDecl(c) => {
+                    cases.push(c.name.value.clone());
+                    if c.payload.is_some() {
+                        with_payload.push(c.name.value.clone());
+                    }
+                }
+                EnumDeclEntry::StructEntry(s) => entries.push(s.clone()),
+            }
+        }
+        self.declare_members(&e.name.value, &entries);
+        let info = self.types.get_mut(&e.name.value).unwrap();
+        info.cases = Some(cases);
+        info.with_payload = with_payload;
+    }
+
+    fn declare_module(&mut self, module: &ParsedModule) {
+        for entry in &module.entries {
+            match entry {
+                TopLevelEntry::FunctionDecl(f) => {
+                    let idx = self.add_signature(&f.name.value, &f.args, Ty::Unknown);
+                    self.bind(&f.name.value, Ty::Func(idx), None);
+                }
+                TopLevelEntry::StructDecl(s) => {
+                    self.declare_type(&s.name);
+                    if !s.inherits.is_empty() {
+                        self.types.get_mut(&s.name.value).unwrap().open = true;
+                    }
+                }
+                TopLevelEntry::EnumDecl(e) => self.declare_type(&e.name),
+                TopLevelEntry::MixinDecl(m) => self.bind(&m.name.value, Ty::Unknown, None),
+                TopLevelEntry::ImportStatement(i) => {
+                    if let Some(first) = i.what.entries.first() {
+                        self.bind(&first.value, Ty::Unknown, None);
+                    }
+                }
+                TopLevelEntry::ImportFromStatement(i) => {
+                    if let ImportTarget::IdentifierList(ids) = &i.what {
+                        for id in &ids.identifiers {
+                            self.bind(&id.value, Ty::Unknown, None);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // native code can add members to any type
+        if module
+            .flags
+            .flags
+            .iter()
+            .any(|f| matches!(f, ModuleFlag::UsesDylib(_)))
+        {
+            self.types.values_mut().for_each(|info| info.open = true);
+        }
+
+        // members are declared once all type names are known, so that
+        // annotations can refer to types declared further down
+        for entry in &module.entries {
+            match entry {
+                TopLevelEntry::StructDecl(s) => self.declare_members(&s.name.value, &s.body),
+                TopLevelEntry::EnumDecl(e) => self.declare_enum(e),
+                TopLevelEntry::ExtensionDecl(e) => {
+                    let target = as_identifier(&e.target)
+                        .map(|id| id.value.clone())
+                        .filter(|name| self.types.contains_key(name));
+                    if let Some(target) = target {
+                        self.declare_members(&target, &e.body);
+                        if !e.inherits.is_empty() {
+                            self.types.get_mut(&target).unwrap().open = true;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn module(&mut self, module: &ParsedModule) {
+        for entry in &module.entries {
+            match entry {
+                TopLevelEntry::ExpressionStatement(e) => {
+                    if let Some(v) = &e.val {
+                        self.expression(v);
+                    }
+                }
+                TopLevelEntry::ValDeclStatement(v) => self.val_decl(v),
+                TopLevelEntry::WriteOpEqStatement(w) => self.write_op_eq(w),
+                TopLevelEntry::AssignStatement(a) => self.assign(a),
+                TopLevelEntry::FunctionDecl(f) => self.function(&f.args, &f.body, None),
+                TopLevelEntry::StructDecl(s) => self.struct_decl(s, true),
+                TopLevelEntry::MixinDecl(m) => self.mixin_decl(m),
+                TopLevelEntry::EnumDecl(e) => self.enum_decl(e, true),
+                TopLevelEntry::ExtensionDecl(e) => self.extension_decl(e),
+                TopLevelEntry::AssertStatement(a) => {
+                    self.expression(&a.val);
+                }
+                TopLevelEntry::ImportStatement(_) | TopLevelEntry::ImportFromStatement(_) => {}
+                TopLevelEntry::IfStatement(i) => self.if_statement(i),
+                TopLevelEntry::MatchStatement(m) => self.match_statement(m),
+                TopLevelEntry::WhileStatement(w) => {
+                    self.expression(&w.cond);
+                    self.block(&w.then);
+                    self.else_piece(&w.els);
+                }
+                TopLevelEntry::ForStatement(f) => self.for_statement(f),
+                TopLevelEntry::CodeBlock(b) => self.block(b),
+                TopLevelEntry::TryBlock(t) => self.try_block(t),
+            }
+        }
+    }
+
+    fn function(&mut self, args: &ArgumentList, body: &FunctionBody, receiver: Option<(&str, Ty)>) {
+        self.with_scope(|this| {
+            if let Some((name, ty)) = receiver {
+                this.bind(name, ty, None);
+            }
+            this.arguments(args);
+            this.statements(&body.code.entries);
+        });
+    }
+
+    fn arguments(&mut self, args: &ArgumentList) {
+        for arg in &args.names {
+            let declared = arg.type_info().and_then(|t| self.annotation(t));
+            if let Some(deft) = &arg.deft {
+                let ty = self.expression(deft);
+                self.check_stored(&arg.id.name, &declared, &ty);
+            }
+            let ty = match &declared {
+                Some(tys) if tys.len() == 1 => tys[0].clone(),
+                _ => Ty::Unknown,
+            };
+            self.bind(arg.name(), ty, declared);
+        }
+        if args.vararg {
+            self.bind("varargs", Ty::List, None);
+        }
+    }
+
+    fn members(&mut self, owner: Option<&str>, body: &[StructEntry]) {
+        let (instance, typ) = match owner {
+            Some(name) => (Ty::Instance(name.to_owned()), Ty::Type(name.to_owned())),
+            None => (Ty::Unknown, Ty::Unknown),
+        };
+        for entry in body {
+            match entry {
+                StructEntry::Method(m) => {
+                    let receiver = match m.access {
+                        MethodAccess::Instance => ("this", instance.clone()),
+                        MethodAccess::Type => ("This", typ.clone()),
+                    };
+                    self.function(&m.args, &m.body, Some(receiver));
+                }
+                StructEntry::Operator(o) => {
+                    self.function(&o.args, &o.body, Some(("this", instance.clone())))
+                }
+                StructEntry::Variable(v) => {
+                    for d in &v.decls {
+                        self.expression(&d.val);
+                    }
+                }
+                StructEntry::Struct(s) => self.struct_decl(s, false),
+                StructEntry::Enum(e) => self.enum_decl(e, false),
+                StructEntry::MixinInclude(m) => {
+                    self.expression(&m.what);
+                }
+            }
+        }
+    }
+
+    // only types at the top level of the module are known by name, nested
+    // ones are checked without knowing what this is
+    fn struct_decl(&mut self, s: &StructDecl, top_level: bool) {
+        for i in &s.inherits {
+            self.expression(i);
+        }
+        let owner = top_level.then_some(s.name.value.as_str());
+        self.members(owner, &s.body);
+    }
+
+    fn enum_decl(&mut self, e: &EnumDecl, top_level: bool) {
+        let owner = top_level.then_some(e.name.value.as_str());
+        let entries = e
+            .body
+            .iter()
+            .filter_map(|entry| match entry {
+                EnumDeclEntry::StructEntry(s) => Some(s.clone()),
+                EnumDeclEntry::EnumCaseDecl(_) => None,
+            })
+            .collect::<Vec<_>>();
+        self.members(owner, &entries);
+    }
+
+    fn mixin_decl(&mut self, m: &MixinDecl) {
+        self.members(None, &m.body);
+    }
+
+    fn extension_decl(&mut self, e: &ExtensionDecl) {
+        let target = as_identifier(&e.target)
+            .map(|id| id.value.clone())
+            .filter(|name| self.types.contains_key(name));
+        self.members(target.as_deref(), &e.body);
+    }
+
+    fn block(&mut self, block: &CodeBlock) {
+        self.with_scope(|this| this.statements(&block.entries));
+    }
+
+    fn else_piece(&mut self, els: &Option<ElsePiece>) {
+        if let Some(e) = els {
+            self.block(&e.then);
+        }
+    }
+
+    fn statements(&mut self, stmts: &[Statement]) {
+        for stmt in stmts {
+            self.statement(stmt);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::ValDeclStatement(v) => self.val_decl(v),
+            Statement::AssignStatement(a) => self.assign(a),
+            Statement::WriteOpEqStatement(w) => self.write_op_eq(w),
+            Statement::IfStatement(i) => self.if_statement(i),
+            Statement::MatchStatement(m) => self.match_statement(m),
+            Statement::WhileStatement(w) => {
+                self.expression(&w.cond);
+                self.block(&w.then);
+                self.else_piece(&w.els);
+            }
+            Statement::ForStatement(f) => self.for_statement(f),
+            Statement::CodeBlock(b) => self.block(b),
+            Statement::ReturnStatement(r) => {
+                if let Some(v) = &r.val {
+                    self.expression(v);
+                }
+            }
+            Statement::ThrowStatement(t) => {
+                self.expression(&t.val);
+                // throw ... from writes the cause onto the thrown value
+                if let Some(cause) = &t.cause {
+                    self.facts.attributes.insert("cause".to_owned());
+                    self.expression(cause);
+                }
+            }
+            Statement::YieldStatement(y) => {
+                self.expression(&y.val);
+            }
+            Statement::TryBlock(t) => self.try_block(t),
+            Statement::AssertStatement(a) => {
+                self.expression(&a.val);
+            }
+            Statement::ExpressionStatement(e) => {
+                if let Some(v) = &e.val {
+                    self.expression(v);
+                }
+            }
+            Statement::BreakStatement(_) | Statement::ContinueStatement(_) => {}
+            Statement::StructDecl(s) => {
+                self.bind(&s.name.value, Ty::Unknown, None);
+                self.struct_decl(s, false);
+            }
+            Statement::EnumDecl(e) => {
+                self.bind(&e.name.value, Ty::Unknown, None);
+                self.enum_decl(e, false);
+            }
+            Statement::FunctionDecl(f) => {
+                let idx = self.add_signature(&f.name.value, &f.args, Ty::Unknown);
+                self.bind(&f.name.value, Ty::Func(idx), None);
+                self.function(&f.args, &f.body, None);
+            }
+        }
+    }
+
+    fn check_stored(&mut self, name: &Identifier, declared: &Allowed, ty: &Ty) {
+        if !accepts(declared, ty) {
+            let expected = describe_allowed(declared.as_deref().unwrap_or_default());
+            self.error(
+                &name.loc,
+                CheckErrorReason::DeclarationTypeMismatch(
+                    name.value.clone(),
+                    expected,
+                    ty.to_string(),
+                ),
+            );
+        }
+    }
+
+    fn val_decl(&mut self, v: &ValDeclStatement) {
+        for decl in &v.decls {
+            let ty = self.expression(&decl.val);
+            if let Some(pattern) = &decl.pattern {
+                self.pattern(pattern, None);
+                continue;
+            }
+            let declared = decl.id.ty.as_ref().and_then(|t| self.annotation(t));
+            self.check_stored(&decl.id.name, &declared, &ty);
+            // a value that may be replaced later is only known by its annotation
+            let ty = if !self.facts.reassigned.contains(&decl.id.name.value) {
+                ty
+            } else {
+                match &declared {
+                    Some(tys) if tys.len() == 1 => tys[0].clone(),
+                    _ => Ty::Unknown,
+                }
+            };
+            self.bind(&decl.id.name.value, ty, declared);
+        }
+    }
+
+    // the target of an assignment; returns the variable being assigned, if
+    // the target is a plain name
+    fn assign_target<'a>(&mut self, target: &'a PostfixExpression) -> Option<&'a Identifier> {
+        if let (Primary::Identifier(id), true) = (&target.base, target.terms.is_empty()) {
+            self.facts.reassigned.insert(id.value.clone());
+            return Some(id);
+        }
+        self.postfix(target, true);
+        None
+    }
+
+    fn assign(&mut self, a: &AssignStatement) {
+        for (target, val) in a.id.iter().zip(&a.val) {
+            let ty = self.expression(val);
+            if let Some(id) = self.assign_target(target)
+                && let Some(declared) = self.lookup(&id.value).map(|b| b.declared.clone())
+            {
+                self.check_stored(id, &declared, &ty);
+            }
+        }
+    }
+
+    fn write_op_eq(&mut self, w: &WriteOpEqStatement) {
+        self.expression(&w.val);
+        self.assign_target(&w.id);
+    }
+
+    fn if_statement(&mut self, i: &IfStatement) {
+        self.expression(&i.iff.content.expression);
+        self.block(&i.iff.content.then);
+        for e in &i.elsif {
+            self.expression(&e.content.expression);
+            self.block(&e.content.then);
+        }
+        self.else_piece(&i.els);
+    }
+
+    fn for_statement(&mut self, f: &aria_parser::ast::ForStatement) {
+        self.expression(&f.expr);
+        self.with_scope(|this| {
+            match &f.pattern {
+                Some(pattern) => this.pattern(pattern, None),
+                None => this.bind(&f.id.value, Ty::Unknown, None),
+            }
+            this.statements(&f.then.entries);
+        });
+        self.else_piece(&f.els);
+    }
+
+    fn try_block(&mut self, t: &TryBlock) {
+        self.block(&t.body);
+        for c in &t.catches {
+            self.with_scope(|this| {
+                let declared = c.id.ty.as_ref().and_then(|t| this.annotation(t));
+                let ty = match &declared {
+                    Some(tys) if tys.len() == 1 => tys[0].clone(),
+                    _ => Ty::Unknown,
+                };
+                this.bind(&c.id.name.value, ty, declared);
+                this.statements(&c.body.entries);
+            });
+        }
+        if let Some(f) = &t.finally {
+            self.block(f);
+        }
+    }
+
+    // the enums whose cases are all known: those declared in the module,
+    // and the builtin ones the module does not shadow
+    fn known_enums(&self) -> Vec<KnownEnum> {
+        let declared =
+            self.types
+                .iter()
+                .filter(|(_, info)| !info.open)
+                .filter_map(|(name, info)| {
+                    Some(KnownEnum {
+                        name: name.clone(),
+                        cases: info.cases.clone()?,
+                        with_payload: info.with_payload.clone(),
+                    })
+                });
+        let builtin = BUILTIN_ENUMS
+            .iter()
+            .filter(|(name, _)| {
+                !self.types.contains_key(*name) && !self.scopes[0].contains_key(*name)
+            })
+            .map(|(name, cases)| KnownEnum {
+                name: name.to_string(),
+                cases: cases.iter().map(|(c, _)| c.to_string()).collect(),
+                with_payload: cases
+                    .iter()
+                    .filter(|(_, payload)| *payload)
+                    .map(|(c, _)| c.to_string())
+                    .collect(),
+            });
+        declared.chain(builtin).collect()
+    }
+
+    fn match_statement(&mut self, m: &MatchStatement) {
+        let control = self.expression(&m.expr);
+        let enums = self.known_enums();
+        // the enum the match is over, and whether that is proven by the type
+        // of the value or only guessed from the cases the rules look for
+        let typed = match &control {
+            Ty::Instance(name) => enums.iter().find(|e| e.name == *name),
+            _ => None,
+        };
+        let over = typed.or_else(|| match_rules::enum_of_rules(&m.rules, &enums));
+
+        for (idx, rule) in m.rules.iter().enumerate() {
+            if let Some(earlier) = m.rules[..idx]
+                .iter()
+                .find(|r| match_rules::rule_covers(r, rule))
+            {
+                self.error(
+                    &rule.loc,
+                    CheckErrorReason::UnreachableMatchRule(earlier.loc.clone()),
+                );
+            } else if let Some(known) = typed
+                && idx > 0
+                && match_rules::names_only_cases_of(rule, known)
+                && match_rules::handles_every_value(&m.rules[..idx], known, &enums)
+            {
+                self.error(
+                    &rule.loc,
+                    CheckErrorReason::MatchRuleAfterAllCases(known.name.clone()),
+                );
+            }
+
+            self.with_scope(|this| {
+                for pattern in &rule.patterns {
+                    this.pattern(pattern, typed);
+                }
+                if let Some(guard) = &rule.guard {
+                    this.expression(guard);
+                }
+                this.statements(&rule.then.entries);
+            });
+        }
+
+        if let Some(known) = over {
+            match &m.els {
+                None => {
+                    let unhandled = match_rules::unhandled_values(&m.rules, known, &enums);
+                    if !unhandled.is_empty() {
+                        self.error(
+                            m.expr.loc(),
+                            CheckErrorReason::NonExhaustiveMatch(
+                                known.name.clone(),
+                                unhandled.join(", "),
+                            ),
+                        );
+                    }
+                }
+                // a value of some other type would still reach the else,
+                // unless the type of the value is known
+                Some(els)
+                    if typed.is_some()
+                        && match_rules::handles_every_value(&m.rules, known, &enums) =>
+                {
+                    self.error(
+                        &els.loc,
+                        CheckErrorReason::UnreachableMatchElse(known.name.clone()),
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        self.else_piece(&m.els);
+    }
+
+    // checks the expressions in a pattern and binds the names it declares;
+    // enum_cases are the cases of the value being matched, when known
+    fn pattern(&mut self, pattern: &MatchPattern, enum_cases: Option<&KnownEnum>) {
+        match pattern {
+            MatchPattern::MatchPatternComp(c) => {
+                self.expression(&c.expr);
+            }
+            MatchPattern::MatchPatternRel(r) => {
+                self.expression(&r.expr);
+            }
+            MatchPattern::MatchPatternEnumCase(c) => {
+                if let Some(known) = enum_cases
+                    && !known.cases.contains(&c.case.value)
+                {
+                    self.error(
+                        &c.case.loc,
+                        CheckErrorReason::UnknownEnumCase(known.name.clone(), c.case.value.clone()),
+                    );
+                }
+                if let Some(payload) = &c.payload {
+                    self.pattern(payload, None);
+                }
+            }
+            MatchPattern::MatchPatternWildcard(_) => {}
+            MatchPattern::MatchPatternBinding(b) => {
+                let declared = b.id.ty.as_ref().and_then(|t| self.annotation(t));
+                let ty = match &declared {
+                    Some(tys) if tys.len() == 1 => tys[0].clone(),
+                    _ => Ty::Unknown,
+                };
+                self.bind(&b.id.name.value, ty, declared);
+            }
+            MatchPattern::MatchPatternList(l) => {
+                for p in &l.before {
+                    self.pattern(p, None);
+                }
+                if let Some(name) = l.rest.as_ref().and_then(|r| r.name.as_ref()) {
+                    self.bind(&name.value, Ty::List, None);
+                }
+                for p in &l.after {
+                    self.pattern(p, None);
+                }
+            }
+            MatchPattern::MatchPatternStruct(st) => {
+                if let Some(ty) = &st.ty {
+                    self.expression(ty);
+                }
+                for field in &st.fields {
+                    // the field is looked up by name, as with hasattr
+                    self.facts.attributes.insert(field.name.value.clone());
+                    match &field.pattern {
+                        Some(p) => self.pattern(p, None),
+                        None => self.bind(&field.name.value, Ty::Unknown, None),
+                    }
+                }
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression) -> Ty {
+        match expr {
+            Expression::LambdaFunction(l) => {
+                self.with_scope(|this| {
+                    this.arguments(&l.args);
+                    match l.body.as_ref() {
+                        LambdaBody::Expression(e) => {
+                            this.expression(e);
+                        }
+                        LambdaBody::CodeBlock(b) => this.statements(&b.entries),
+                    }
+                });
+                Ty::Unknown
+            }
+            Expression::LogOperation(l) => self.log_operation(l),
+            Expression::TernaryExpression(t) => {
+                self.log_operation(&t.condition);
+                let a = self.expression(&t.true_expression);
+                let b = self.expression(&t.false_expression);
+                a.join(&b)
+            }
+            Expression::TryUnwrapExpression(t) => {
+                self.log_operation(&t.left);
+                self.expression(&t.right);
+                Ty::Unknown
+            }
+        }
+    }
+
+    fn log_operation(&mut self, l: &LogOperation) -> Ty {
+        let mut ty = self.comp_operation(&l.left);
+        for (sym, right) in &l.right {
+            let rt = self.comp_operation(right);
+            ty = match sym {
+                LogSymbol::DoubleAmpersand | LogSymbol::DoublePipe => Ty::Bool,
+                _ => match (&ty, &rt) {
+                    (Ty::Int, Ty::Int) => Ty::Int,
+                    (Ty::Bool, Ty::Bool) => Ty::Bool,
+                    _ => Ty::Unknown,
+                },
+            };
+        }
+        ty
+    }
+
+    fn comp_operation(&mut self, c: &CompOperation) -> Ty {
+        let left = self.rel_operation(&c.left);
+        match &c.right {
+            None => left,
+            Some((_, r)) => {
+                let right = self.rel_operation(r);
+                // user types may define == to return anything
+                if matches!(left, Ty::Instance(_)) || matches!(right, Ty::Instance(_)) {
+                    Ty::Unknown
+                } else {
+                    Ty::Bool
+                }
+            }
+        }
+    }
+
+    fn rel_operation(&mut self, r: &RelOperation) -> Ty {
+        let left = self.shift_operation(&r.left);
+        match &r.right {
+            None => left,
+            Some((_, s)) => {
+                let right = self.shift_operation(s);
+                if left.is_number() && right.is_number() {
+                    Ty::Bool
+                } else {
+                    Ty::Unknown
+                }
+            }
+        }
+    }
+
+    fn shift_operation(&mut self, s: &ShiftOperation) -> Ty {
+        let left = self.add_operation(&s.left);
+        match &s.right {
+            None => left,
+            Some((_, a)) => {
+                let right = self.add_operation(a);
+                if left == Ty::Int && right == Ty::Int {
+                    Ty::Int
+                } else {
+                    Ty::Unknown
+                }
+            }
+        }
+    }
+
+    fn add_operation(&mut self, a: &AddOperation) -> Ty {
+        let mut ty = self.mul_operation(&a.left);
+        for (sym, right) in &a.right {
+            let rt = self.mul_operation(right);
+            ty = match (sym, &ty, &rt) {
+                (AddSymbol::Plus, Ty::String, Ty::String) => Ty::String,
+                _ => numeric_result(&ty, &rt),
+            };
+        }
+        ty
+    }
+
+    fn mul_operation(&mut self, m: &MulOperation) -> Ty {
+        let mut ty = self.unary_operation(&m.left);
+        for (_, right) in &m.right {
+            let rt = self.unary_operation(right);
+            ty = numeric_result(&ty, &rt);
+        }
+        ty
+    }
+
+    fn unary_operation(&mut self, u: &UnaryOperation) -> Ty {
+        let ty = self.postfix(&u.postfix.expr, false);
+        match u.operand {
+            None => ty,
+            Some(UnarySymbol::Minus) if ty.is_number() => ty,
+            Some(UnarySymbol::Exclamation) if ty == Ty::Bool => ty,
+            Some(_) => Ty::Unknown,
+        }
+    }
+
+    fn primary(&mut self, p: &Primary) -> Ty {
+        match p {
+            Primary::IntLiteral(_) => Ty::Int,
+            Primary::FloatLiteral(_) => Ty::Float,
+            Primary::StringLiteral(_) => Ty::String,
+            Primary::InterpolatedString(s) => {
+                for part in &s.parts {
+                    if let InterpolatedStringPart::Value(v) = part {
+                        self.expression(&v.value);
+                    }
+                }
+                Ty::String
+            }
+            Primary::BytesLiteral(_) => Ty::List,
+            Primary::ListLiteral(l) => {
+                for item in &l.items.expressions {
+                    self.expression(item);
+                }
+                Ty::List
+            }
+            Primary::MapLiteral(m) => {
+                for entry in &m.entries {
+                    self.expression(&entry.key);
+                    self.expression(&entry.value);
+                }
+                Ty::Map
+            }
+            Primary::ParenExpression(p) => self.expression(&p.value),
+            Primary::Identifier(id) => match self.lookup(&id.value) {
+                Some(b) => b.ty.clone(),
+                None if id.value == "true" || id.value == "false" => Ty::Bool,
+                None => Ty::Unknown,
+            },
+        }
+    }
+
+    // evaluates a postfix expression; if it is the target of a write, its
+    // last attribute is being written rather than read
+    fn postfix(&mut self, e: &PostfixExpression, write_target: bool) -> Ty {
+        let mut ty = self.primary(&e.base);
+        // a builtin function called by name, as opposed to a local value
+        let builtin = match &e.base {
+            Primary::Identifier(id) if self.lookup(&id.value).is_none() => Some(id.value.as_str()),
+            _ => None,
+        };
+
+        for (idx, term) in e.terms.iter().enumerate() {
+            let last = idx + 1 == e.terms.len();
+            ty = match term {
+                PostfixTerm::PostfixTermAttribute(a) => {
+                    if write_target && last {
+                        self.facts.attributes.insert(a.id.value.clone());
+                        Ty::Unknown
+                    } else {
+                        self.read_attribute(&ty, &a.id)
+                    }
+                }
+                PostfixTerm::PostfixTermIndex(i) => {
+                    for e in &i.index.expressions {
+                        self.expression(e);
+                    }
+                    Ty::Unknown
+                }
+                PostfixTerm::PostfixTermCall(c) => {
+                    let args = c
+                        .args
+                        .expressions
+                        .iter()
+                        .map(|a| self.expression(a))
+                        .collect::<Vec<_>>();
+                    let builtin = if idx == 0 { builtin } else { None };
+                    self.call(&ty, builtin, &c.args.expressions, &args, &c.loc)
+                }
+                PostfixTerm::PostfixTermObjectWrite(w) => {
+                    for t in &w.terms.terms {
+                        match t {
+                            PostfixTermWrite::PostfixTermFieldWrite(f) => {
+                                self.facts.attributes.insert(f.id.value.clone());
+                                if let Some(v) = &f.val {
+                                    self.expression(v);
+                                }
+                            }
+                            PostfixTermWrite::PostfixTermIndexWrite(i) => {
+                                for e in &i.idx.expressions {
+                                    self.expression(e);
+                                }
+                                self.expression(&i.val);
+                            }
+                        }
+                    }
+                    ty
+                }
+                PostfixTerm::PostfixTermEnumCase(c) => {
+                    if let Some(p) = &c.payload {
+                        self.expression(p);
+                    }
+                    self.enum_case(&ty, &c.id)
+                }
+                PostfixTerm::PostfixTermTryProtocol(_) => Ty::Unknown,
+            };
+        }
+        ty
+    }
+
+    fn read_attribute(&mut self, ty: &Ty, name: &Identifier) -> Ty {
+        let (owner, on_type) = match ty {
+            Ty::Instance(owner) => (owner, false),
+            Ty::Type(owner) => (owner, true),
+            _ => return Ty::Unknown,
+        };
+        let Some(info) = self.types.get(owner).filter(|info| !info.open) else {
+            return Ty::Unknown;
+        };
+
+        match info.members.get(&name.value) {
+            Some(Member::InstanceMethod(idx)) if !on_type => return Ty::Func(*idx),
+            Some(Member::TypeMethod(idx)) if on_type => return Ty::Func(*idx),
+            Some(_) => return Ty::Unknown,
+            None => {}
+        }
+        let enum_helper = !on_type
+            && info.cases.as_ref().is_some_and(|cases| {
+                ["is_", "unwrap_"].iter().any(|prefix| {
+                    name.value
+                        .strip_prefix(prefix)
+                        .is_some_and(|case| cases.iter().any(|c| c == case))
+                })
+            });
+        if !enum_helper && !self.facts.attributes.contains(&name.value) {
+            self.error(
+                &name.loc,
+                CheckErrorReason::UnknownAttribute(ty.to_string(), name.value.clone()),
+            );
+        }
+        Ty::Unknown
+    }
+
+    fn enum_case(&mut self, ty: &Ty, case: &Identifier) -> Ty {
+        let Ty::Type(owner) = ty else {
+            return Ty::Unknown;
+        };
+        let Some(cases) = self
+            .types
+            .get(owner)
+            .filter(|info| !info.open)
+            .and_then(|info| info.cases.as_ref())
+        else {
+            return Ty::Unknown;
+        };
+        if !cases.contains(&case.value) {
+            self.error(
+                &case.loc,
+                CheckErrorReason::UnknownEnumCase(owner.clone(), case.value.clone()),
+            );
+        }
+        Ty::Instance(owner.clone())
+    }
+
+    fn call(
+        &mut self,
+        callee: &Ty,
+        builtin: Option<&str>,
+        arg_exprs: &[Expression],
+        args: &[Ty],
+        loc: &SourcePointer,
+    ) -> Ty {
+        match (callee, builtin) {
+            (Ty::Func(idx), _) => {
+                let sig = &self.signatures[*idx];
+                let mut errors = vec![];
+                if !sig.arity.accepts(args.len()) {
+                    errors.push((
+                        loc.clone(),
+                        CheckErrorReason::ArityMismatch(sig.name.clone(), sig.arity, args.len()),
+                    ));
+                }
+                for ((param, allowed), (arg, expr)) in
+                    sig.params.iter().zip(args.iter().zip(arg_exprs))
+                {
+                    if !accepts(allowed, arg) {
+                        errors.push((
+                            expr.loc().clone(),
+                            CheckErrorReason::ArgumentTypeMismatch(
+                                sig.name.clone(),
+                                param.clone(),
+                                describe_allowed(allowed.as_deref().unwrap_or_default()),
+                                arg.to_string(),
+                            ),
+                        ));
+                    }
+                }
+                let ret = sig.ret.clone();
+                for (loc, reason) in errors {
+                    self.error(&loc, reason);
+                }
+                ret
+            }
+            (_, Some("alloc")) => match args {
+                [Ty::Type(name)] => Ty::Instance(name.clone()),
+                _ => Ty::Unknown,
+            },
+            (_, Some("hasattr" | "readattr" | "writeattr")) => {
+                for name in arg_exprs.iter().filter_map(string_literal) {
+                    self.facts.attributes.insert(name.to_owned());
+                }
+                Ty::Unknown
+            }
+            _ => Ty::Unknown,
+        }
+    }
+}
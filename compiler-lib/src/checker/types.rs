@@ -0,0 +1,189 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::fmt::Display;
+
+use aria_parser::ast::{
+    ArgumentList, Expression, Identifier, LogOperation, LogSymbol, PostfixExpression, Primary,
+};
+
+// what the checker knows about the type of a value; Unknown is anything the
+// checker cannot prove, and is compatible with every annotation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Ty {
+    Unknown,
+    Int,
+    Float,
+    String,
+    Bool,
+    List,
+    Map,
+    // a function declared in the module, as an index into the signatures
+    Func(usize),
+    // a struct or enum declared in the module, as a value
+    Type(String),
+    // an instance of a struct, or a case of an enum, declared in the module
+    Instance(String),
+}
+
+impl Ty {
+    pub(crate) fn join(&self, other: &Ty) -> Ty {
+        if self == other {
+            self.clone()
+        } else {
+            Ty::Unknown
+        }
+    }
+
+    pub(crate) fn is_number(&self) -> bool {
+        matches!(self, Ty::Int | Ty::Float)
+    }
+}
+
+impl Display for Ty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ty::Unknown => write!(f, "Any"),
+            Ty::Int => write!(f, "Int"),
+            Ty::Float => write!(f, "Float"),
+            Ty::String => write!(f, "String"),
+            Ty::Bool => write!(f, "Bool"),
+            Ty::List => write!(f, "List"),
+            Ty::Map => write!(f, "Map"),
+            Ty::Func(_) => write!(f, "Function"),
+            Ty::Type(name) => write!(f, "type {name}"),
+            Ty::Instance(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+// the types a type annotation allows; None when the annotation allows
+// anything, or names something the checker does not know about
+pub(crate) type Allowed = Option<Vec<Ty>>;
+
+pub(crate) fn accepts(allowed: &Allowed, ty: &Ty) -> bool {
+    match (allowed, ty) {
+        (None, _) | (_, Ty::Unknown) => true,
+        (Some(tys), ty) => tys.contains(ty),
+    }
+}
+
+pub(crate) fn describe_allowed(tys: &[Ty]) -> String {
+    tys.iter()
+        .map(|t| t.to_string())
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+// the number of arguments a function accepts, not counting this
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub(crate) fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max}"),
+            Some(max) => write!(f, "{} to {max}", self.min),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+pub(crate) struct Signature {
+    pub name: String,
+    pub arity: Arity,
+    pub params: Vec<(String, Allowed)>,
+    pub ret: Ty,
+}
+
+impl Signature {
+    pub(crate) fn new(
+        name: &str,
+        args: &ArgumentList,
+        annotation: impl Fn(&Expression) -> Allowed,
+    ) -> Self {
+        let min = args.names.iter().filter(|a| a.deft.is_none()).count();
+        Self {
+            name: name.to_owned(),
+            arity: Arity {
+                min,
+                max: if args.vararg {
+                    None
+                } else {
+                    Some(args.names.len())
+                },
+            },
+            params: args
+                .names
+                .iter()
+                .map(|a| (a.name().clone(), a.type_info().and_then(&annotation)))
+                .collect(),
+            ret: Ty::Unknown,
+        }
+    }
+}
+
+// the postfix expression an expression consists of, if it has no operators
+pub(crate) fn as_postfix(expr: &Expression) -> Option<&PostfixExpression> {
+    let Expression::LogOperation(log) = expr else {
+        return None;
+    };
+    if !log.right.is_empty() {
+        return None;
+    }
+    let comp = &log.left;
+    if comp.right.is_some() {
+        return None;
+    }
+    let rel = &comp.left;
+    if rel.right.is_some() {
+        return None;
+    }
+    let shift = &rel.left;
+    if shift.right.is_some() {
+        return None;
+    }
+    let add = &shift.left;
+    if !add.right.is_empty() {
+        return None;
+    }
+    let mul = &add.left;
+    if !mul.right.is_empty() || mul.left.operand.is_some() {
+        return None;
+    }
+    Some(&mul.left.postfix.expr)
+}
+
+pub(crate) fn as_identifier(expr: &Expression) -> Option<&Identifier> {
+    match as_postfix(expr) {
+        Some(PostfixExpression {
+            base: Primary::Identifier(id),
+            terms,
+            ..
+        }) if terms.is_empty() => Some(id),
+        _ => None,
+    }
+}
+
+// the alternatives of a union annotation such as Int|String
+pub(crate) fn union_members(expr: &Expression) -> Vec<Expression> {
+    match expr {
+        Expression::LogOperation(log)
+            if !log.right.is_empty()
+                && log.right.iter().all(|(sym, _)| *sym == LogSymbol::Pipe) =>
+        {
+            std::iter::once(&log.left)
+                .chain(log.right.iter().map(|(_, c)| c))
+                .map(|c| Expression::from(&LogOperation::from(c)))
+                .collect()
+        }
+        _ => vec![expr.clone()],
+    }
+}
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 
 use aria_parser::ast::SourcePointer;
 use enum_as_inner::EnumAsInner;
+use num_bigint::BigInt;
 
 use crate::line_table::LineTable;
 
@@ -16,6 +17,7 @@ pub struct CompiledCodeObject {
     pub loc: SourcePointer,
     pub line_table: LineTable,
     pub frame_size: u8,
+    pub local_names: Vec<String>, // indexed by local slot, used by the debugger
 }
 
 #[derive(Clone, Copy)]
@@ -56,6 +58,7 @@ impl std::hash::Hash for CompiledCodeObject {
 #[derive(EnumAsInner, Clone, PartialEq, Eq, Hash)]
 pub enum ConstantValue {
     Integer(i64),
+    BigInteger(BigInt), // only for literals that do not fit in an i64
     String(String),
     Float(FpConst),
     CompiledCodeObject(CompiledCodeObject),
@@ -65,6 +68,7 @@ impl std::fmt::Display for ConstantValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Integer(arg0) => write!(f, "int:{}", *arg0),
+            Self::BigInteger(arg0) => write!(f, "bigint:{arg0}"),
             Self::Float(arg0) => write!(f, "fp:{}", arg0.0),
             Self::String(arg0) => write!(f, "str:\"{}\"", *arg0),
             Self::CompiledCodeObject(_) => write!(f, "compiled-code-object"),
@@ -83,6 +87,17 @@ pub enum ConstantValuesError {
 }
 
 impl ConstantValues {
+    // values are taken as-is, without deduplication, so that indices stored
+    // in previously generated bytecode remain valid
+    pub(crate) fn from_values(values: Vec<ConstantValue>) -> Self {
+        let mut ret = Self::default();
+        for (idx, v) in values.iter().enumerate() {
+            ret.uniq.entry(v.clone()).or_insert(idx);
+        }
+        ret.values = values;
+        ret
+    }
+
     pub fn insert(&mut self, v: ConstantValue) -> Result<u16, ConstantValuesError> {
         if let Some(idx) = self.uniq.get(&v) {
             Ok(*idx as u16)
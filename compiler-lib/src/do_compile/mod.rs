@@ -1,5 +1,5 @@
 // SPDX-License-Identifier: Apache-2.0
-use std::{collections::HashSet, fmt::Display, path::PathBuf};
+use std::{collections::HashSet, fmt::Display, path::PathBuf, rc::Rc};
 
 use aria_parser::ast::{
     ArgumentDecl, ArgumentList, AssertStatement, CodeBlock, DeclarationId, ElsePiece, EnumCaseDecl,
@@ -56,6 +56,8 @@ pub enum CompilationErrorReason {
     DuplicateArgumentName(String),
     #[error("struct members do not support type hints")]
     NoTypeHintOnStructMember,
+    #[error("struct members cannot be declared with a pattern")]
+    NoPatternOnStructMember,
     #[error("nested closures are not supported")]
     NestedClosureDisallowed,
     #[error("attempted to write to {0} values, but {1} were provided")]
@@ -110,10 +112,73 @@ impl std::fmt::Debug for CompilationError {
 
 pub type CompilationResult<T = (), E = CompilationError> = Result<T, E>;
 
-#[derive(Default)]
+// a finally block, with the scope and targets of its try statement, so that
+// it can be compiled again on every way out of the try
+struct FinallyBlock {
+    body: CodeBlock,
+    scope: CompilationScope,
+    cflow: ControlFlowTargets,
+}
+
+impl FinallyBlock {
+    fn compile(&self, params: &mut CompileParams) -> CompilationResult {
+        let mut f_params = CompileParams {
+            module: params.module,
+            scope: &self.scope,
+            writer: params.writer,
+            cflow: &self.cflow,
+            options: params.options,
+        };
+        self.body.do_compile(&mut f_params)
+    }
+}
+
+#[derive(Default, Clone)]
 struct ControlFlowTargets {
     break_dest: Option<BasicBlock>,
     continue_dest: Option<BasicBlock>,
+    // the try handlers that are active here, innermost last, each with the
+    // finally block to run when control leaves it
+    handlers: Vec<Option<Rc<FinallyBlock>>>,
+    // how many of the handlers were already active when the innermost loop began
+    loop_handlers: usize,
+}
+
+impl ControlFlowTargets {
+    fn for_loop(&self, break_dest: BasicBlock, continue_dest: BasicBlock) -> Self {
+        Self {
+            break_dest: Some(break_dest),
+            continue_dest: Some(continue_dest),
+            handlers: self.handlers.clone(),
+            loop_handlers: self.handlers.len(),
+        }
+    }
+
+    fn with_handler(&self, finally: Option<Rc<FinallyBlock>>) -> Self {
+        let mut this = self.clone();
+        this.handlers.push(finally);
+        this
+    }
+}
+
+// a return, break or continue exits the try handlers it leaves, from the
+// innermost out to the given depth, and runs their finally blocks on the way
+fn emit_leave_handlers(
+    params: &mut CompileParams,
+    depth: usize,
+    loc: &SourcePointer,
+) -> CompilationResult {
+    let handlers = params.cflow.handlers[depth..].to_vec();
+    for finally in handlers.iter().rev() {
+        params
+            .writer
+            .get_current_block()
+            .write_opcode_and_source_info(CompilerOpcode::TryExit, loc.clone());
+        if let Some(finally) = finally {
+            finally.compile(params)?;
+        }
+    }
+    Ok(())
 }
 
 struct CompileParams<'a> {
@@ -156,6 +221,19 @@ trait CompileNode<'a, T = (), E = CompilationError> {
     }
 }
 
+// a pattern checks the value stored in the variable named subject; when the
+// value does not match, execution jumps to miss, otherwise it falls through
+// with the names that the pattern binds defined in the current scope. either
+// way the stack is left as it was
+trait CompilePattern {
+    fn compile_check(
+        &self,
+        subject: &str,
+        miss: &BasicBlock,
+        params: &mut CompileParams,
+    ) -> CompilationResult;
+}
+
 mod nodes;
 mod postfix;
 
@@ -326,9 +404,21 @@ fn emit_type_mixin_include_decl_compile(
     Ok(())
 }
 
-// assume your parent struct is on the stack
-fn emit_method_decl_compile(md: &MethodDecl, params: &mut CompileParams) -> CompilationResult {
-    md.do_compile(params)?;
+// assume your parent struct is on the stack; the function is named after the
+// type it belongs to, so that backtraces and profiles say Type.method
+fn emit_method_decl_compile(
+    md: &MethodDecl,
+    owner: &str,
+    params: &mut CompileParams,
+) -> CompilationResult {
+    let qualified = MethodDecl {
+        name: Identifier {
+            loc: md.name.loc.clone(),
+            value: format!("{owner}.{}", md.name.value),
+        },
+        ..md.clone()
+    };
+    qualified.do_compile(params)?;
 
     let name_idx = md.insert_const_or_fail(
         params,
@@ -382,6 +472,34 @@ struct OperatorInfo {
     reverse_name: &'static str,
 }
 
+impl OperatorInfo {
+    fn method_name(&self, reverse: bool) -> String {
+        format!(
+            "_op_impl_{}",
+            if reverse {
+                self.reverse_name
+            } else {
+                self.direct_name
+            }
+        )
+    }
+}
+
+fn operator_symbol(op: &OperatorDecl) -> String {
+    op.symbol
+        .prettyprint(
+            aria_parser::ast::prettyprint::printout_accumulator::PrintoutAccumulator::default(),
+        )
+        .value()
+}
+
+// the name of the method an operator declaration defines, if it is valid
+pub(crate) fn operator_method_name(op: &OperatorDecl) -> Option<String> {
+    OPERATOR_INFO
+        .get(operator_symbol(op).as_str())
+        .map(|info| info.method_name(op.reverse))
+}
+
 lazy_static::lazy_static! {
     static ref OPERATOR_INFO: std::collections::HashMap<&'static str, OperatorInfo> = {
         let mut map = std::collections::HashMap::new();
@@ -550,13 +668,12 @@ lazy_static::lazy_static! {
 }
 
 // assume your parent struct is on the stack
-fn emit_operator_decl_compile(op: &OperatorDecl, params: &mut CompileParams) -> CompilationResult {
-    let op_symbol = op
-        .symbol
-        .prettyprint(
-            aria_parser::ast::prettyprint::printout_accumulator::PrintoutAccumulator::default(),
-        )
-        .value();
+fn emit_operator_decl_compile(
+    op: &OperatorDecl,
+    owner: &str,
+    params: &mut CompileParams,
+) -> CompilationResult {
+    let op_symbol = operator_symbol(op);
 
     let op_info = match OPERATOR_INFO.get(op_symbol.as_str()) {
         Some(info) => info,
@@ -586,14 +703,7 @@ fn emit_operator_decl_compile(op: &OperatorDecl, params: &mut CompileParams) ->
         });
     }
 
-    let op_fn_name = format!(
-        "_op_impl_{}",
-        if op.reverse {
-            op_info.reverse_name
-        } else {
-            op_info.direct_name
-        }
-    );
+    let op_fn_name = op_info.method_name(op.reverse);
 
     let md = MethodDecl {
         loc: op.loc.clone(),
@@ -606,7 +716,7 @@ fn emit_operator_decl_compile(op: &OperatorDecl, params: &mut CompileParams) ->
         body: op.body.clone(),
     };
 
-    emit_method_decl_compile(&md, params)
+    emit_method_decl_compile(&md, owner, params)
 }
 
 // assume your parent struct is on the stack
@@ -621,6 +731,12 @@ fn emit_type_val_decl_compile(
                 reason: CompilationErrorReason::NoTypeHintOnStructMember,
             });
         }
+        if decl.pattern.is_some() {
+            return Err(CompilationError {
+                loc: vd.loc.clone(),
+                reason: CompilationErrorReason::NoPatternOnStructMember,
+            });
+        }
         decl.val.do_compile(params)?;
         let name_idx = vd.insert_const_or_fail(
             params,
@@ -638,6 +754,7 @@ fn emit_type_val_decl_compile(
 // assume your parent struct is on the stack
 fn emit_type_members_compile(
     entries: &[StructEntry],
+    owner: &str,
     params: &mut CompileParams,
     drop_at_end: bool,
 ) -> CompilationResult {
@@ -648,11 +765,15 @@ fn emit_type_members_compile(
             .write_opcode_and_source_info(CompilerOpcode::Dup, se.loc().clone());
 
         match se {
-            aria_parser::ast::StructEntry::Method(md) => emit_method_decl_compile(md, params)?,
-            aria_parser::ast::StructEntry::Operator(od) => emit_operator_decl_compile(od, params)?,
+            aria_parser::ast::StructEntry::Method(md) => {
+                emit_method_decl_compile(md, owner, params)?
+            }
+            aria_parser::ast::StructEntry::Operator(od) => {
+                emit_operator_decl_compile(od, owner, params)?
+            }
             aria_parser::ast::StructEntry::Variable(vd) => emit_type_val_decl_compile(vd, params)?,
             aria_parser::ast::StructEntry::Struct(sd) => {
-                do_struct_compile(sd, params)?;
+                do_struct_compile(sd, &format!("{owner}.{}", sd.name.value), params)?;
 
                 let name_idx = sd.insert_const_or_fail(
                     params,
@@ -668,31 +789,36 @@ fn emit_type_members_compile(
                     );
             }
             aria_parser::ast::StructEntry::Enum(ed) => {
-                do_enum_compile(ed, params, |name, params| {
-                    params
-                        .writer
-                        .get_current_block()
-                        .write_opcode_and_source_info(CompilerOpcode::Swap, ed.loc.clone());
-                    params
-                        .writer
-                        .get_current_block()
-                        .write_opcode_and_source_info(CompilerOpcode::Copy(1), ed.loc.clone());
-
-                    let name_idx = ed.insert_const_or_fail(
-                        params,
-                        ConstantValue::String(name.to_owned()),
-                        &ed.loc,
-                    )?;
-
-                    params
-                        .writer
-                        .get_current_block()
-                        .write_opcode_and_source_info(
-                            CompilerOpcode::WriteAttribute(name_idx),
-                            ed.loc.clone(),
-                        );
-                    Ok(())
-                })?;
+                do_enum_compile(
+                    ed,
+                    &format!("{owner}.{}", ed.name.value),
+                    params,
+                    |name, params| {
+                        params
+                            .writer
+                            .get_current_block()
+                            .write_opcode_and_source_info(CompilerOpcode::Swap, ed.loc.clone());
+                        params
+                            .writer
+                            .get_current_block()
+                            .write_opcode_and_source_info(CompilerOpcode::Copy(1), ed.loc.clone());
+
+                        let name_idx = ed.insert_const_or_fail(
+                            params,
+                            ConstantValue::String(name.to_owned()),
+                            &ed.loc,
+                        )?;
+
+                        params
+                            .writer
+                            .get_current_block()
+                            .write_opcode_and_source_info(
+                                CompilerOpcode::WriteAttribute(name_idx),
+                                ed.loc.clone(),
+                            );
+                        Ok(())
+                    },
+                )?;
             }
             aria_parser::ast::StructEntry::MixinInclude(mi) => {
                 emit_type_mixin_include_decl_compile(mi, params)?
@@ -712,7 +838,12 @@ fn emit_type_members_compile(
     Ok(())
 }
 
-fn do_struct_compile(sd: &StructDecl, params: &mut CompileParams) -> CompilationResult {
+// qualname is the name of the struct as seen from the module, e.g. Outer.Inner
+fn do_struct_compile(
+    sd: &StructDecl,
+    qualname: &str,
+    params: &mut CompileParams,
+) -> CompilationResult {
     let self_name = StringLiteral {
         loc: sd.loc.clone(),
         value: sd.name.value.clone(),
@@ -738,11 +869,12 @@ fn do_struct_compile(sd: &StructDecl, params: &mut CompileParams) -> Compilation
         sd.body.clone()
     };
 
-    emit_type_members_compile(&body, params, false)
+    emit_type_members_compile(&body, qualname, params, false)
 }
 
 fn do_enum_compile<T>(
     ed: &EnumDecl,
+    qualname: &str,
     params: &mut CompileParams,
     name_writer: T,
 ) -> CompilationResult
@@ -772,9 +904,9 @@ where
     }
 
     let enum_helper_methods = generate_case_helpers_extension_for_enum(&cases);
-    emit_type_members_compile(&enum_helper_methods, params, false)?;
+    emit_type_members_compile(&enum_helper_methods, qualname, params, false)?;
 
-    emit_type_members_compile(&entries, params, false)?;
+    emit_type_members_compile(&entries, qualname, params, false)?;
 
     emit_enum_cases(&cases, params)?;
 
@@ -806,6 +938,7 @@ fn generate_is_case_helper_for_enum(case: &EnumCaseDecl) -> MethodDecl {
     let match_rule = MatchRule {
         loc: case.loc.clone(),
         patterns: vec![MatchPattern::MatchPatternEnumCase(match_case_pattern)],
+        guard: None,
         then: CodeBlock::from(&Statement::ReturnStatement(return_true_stmt)),
     };
 
@@ -856,19 +989,20 @@ fn generate_unwap_case_helper_for_enum(case: &EnumCaseDecl) -> MethodDecl {
     let match_case_pattern = MatchPatternEnumCase {
         loc: case.loc.clone(),
         case: case.name.clone(),
-        payload: Some(DeclarationId {
+        payload: Some(Box::new(MatchPattern::from(&DeclarationId {
             loc: case.loc.clone(),
             name: Identifier {
                 loc: case.loc.clone(),
                 value: "__case_payload".to_owned(),
             },
             ty: None,
-        }),
+        }))),
     };
 
     let match_rule = MatchRule {
         loc: case.loc.clone(),
         patterns: vec![MatchPattern::MatchPatternEnumCase(match_case_pattern)],
+        guard: None,
         then: CodeBlock::from(&Statement::ReturnStatement(return_true_stmt)),
     };
 
@@ -970,20 +1104,7 @@ pub(crate) fn compile_from_source(
         module.widget_root_path = if src_path.exists() {
             // Ensure the source buffer name is a file path
             debug_assert!(src_path.is_file());
-
-            let mut ancestors = src_path.ancestors();
-            loop {
-                if let Some(widget_path) = ancestors.next() {
-                    let widget_json = widget_path.join("widget.json");
-                    if !widget_json.exists() {
-                        continue;
-                    }
-
-                    break Some(widget_path.to_path_buf());
-                }
-
-                break None;
-            }
+            crate::widget::find_widget_root(&src_path)
         } else {
             None
         };
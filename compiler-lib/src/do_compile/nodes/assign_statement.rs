@@ -38,6 +38,7 @@ impl<'a> CompileNode<'a> for aria_parser::ast::AssignStatement {
                 decls: vec![ValDeclEntry {
                     loc: self.loc.clone(),
                     id: DeclarationId::from(&temp_buffer_store),
+                    pattern: None,
                     val: zero_val,
                 }],
             })
@@ -3,19 +3,18 @@ use crate::{
     builder::compiler_opcodes::CompilerOpcode,
     do_compile::{
         CompilationError, CompilationErrorReason, CompilationResult, CompileNode, CompileParams,
+        emit_leave_handlers,
     },
 };
 
 impl<'a> CompileNode<'a> for aria_parser::ast::BreakStatement {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
-        if let Some(break_target) = &params.cflow.break_dest {
+        if let Some(break_target) = params.cflow.break_dest.clone() {
+            emit_leave_handlers(params, params.cflow.loop_handlers, &self.loc)?;
             params
                 .writer
                 .get_current_block()
-                .write_opcode_and_source_info(
-                    CompilerOpcode::Jump(break_target.clone()),
-                    self.loc.clone(),
-                );
+                .write_opcode_and_source_info(CompilerOpcode::Jump(break_target), self.loc.clone());
             Ok(())
         } else {
             Err(CompilationError {
@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    builder::compiler_opcodes::CompilerOpcode,
+    constant_value::ConstantValue,
+    do_compile::{
+        CompilationError, CompilationErrorReason, CompilationResult, CompileNode, CompileParams,
+    },
+};
+
+impl<'a> CompileNode<'a> for aria_parser::ast::BytesLiteral {
+    fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
+        if self.value.len() > u32::MAX as usize {
+            return Err(CompilationError {
+                loc: self.loc.clone(),
+                reason: CompilationErrorReason::ListTooLarge,
+            });
+        }
+
+        for byte in &self.value {
+            let const_idx =
+                self.insert_const_or_fail(params, ConstantValue::Integer(*byte as i64), &self.loc)?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(CompilerOpcode::Push(const_idx), self.loc.clone());
+        }
+        params
+            .writer
+            .get_current_block()
+            .write_opcode_and_source_info(
+                CompilerOpcode::BuildList(self.value.len() as u32),
+                self.loc.clone(),
+            );
+        Ok(())
+    }
+}
@@ -3,17 +3,19 @@ use crate::{
     builder::compiler_opcodes::CompilerOpcode,
     do_compile::{
         CompilationError, CompilationErrorReason, CompilationResult, CompileNode, CompileParams,
+        emit_leave_handlers,
     },
 };
 
 impl<'a> CompileNode<'a> for aria_parser::ast::ContinueStatement {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
-        if let Some(continue_target) = &params.cflow.continue_dest {
+        if let Some(continue_target) = params.cflow.continue_dest.clone() {
+            emit_leave_handlers(params, params.cflow.loop_handlers, &self.loc)?;
             params
                 .writer
                 .get_current_block()
                 .write_opcode_and_source_info(
-                    CompilerOpcode::Jump(continue_target.clone()),
+                    CompilerOpcode::Jump(continue_target),
                     self.loc.clone(),
                 );
             Ok(())
@@ -6,7 +6,7 @@ use crate::{
 
 impl<'a> CompileNode<'a> for aria_parser::ast::EnumDecl {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
-        do_enum_compile(self, params, |name, params| {
+        do_enum_compile(self, &self.name.value, params, |name, params| {
             params
                 .writer
                 .get_current_block()
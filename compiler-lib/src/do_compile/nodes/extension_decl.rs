@@ -1,4 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
+use aria_parser::ast::prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator};
+
 use crate::do_compile::{
     CompilationResult, CompileNode, CompileParams, MixinIncludeDecl, StructEntry,
     emit_type_members_compile,
@@ -7,6 +9,10 @@ use crate::do_compile::{
 impl<'a> CompileNode<'a> for aria_parser::ast::ExtensionDecl {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
         self.target.do_compile(params)?;
+        let owner = self
+            .target
+            .prettyprint(PrintoutAccumulator::default())
+            .value();
 
         // Inject mixin includes for each item in the inherits list
         if !self.inherits.is_empty() {
@@ -19,9 +25,9 @@ impl<'a> CompileNode<'a> for aria_parser::ast::ExtensionDecl {
             }
             new_body.extend_from_slice(&self.body);
 
-            emit_type_members_compile(&new_body, params, true)
+            emit_type_members_compile(&new_body, &owner, params, true)
         } else {
-            emit_type_members_compile(&self.body, params, true)
+            emit_type_members_compile(&self.body, &owner, params, true)
         }
     }
 }
@@ -18,6 +18,7 @@ macro_rules! val_decl_statement {
                     loc: $loc,
                     value: $id.clone(),
                 }),
+                pattern: None,
                 val: $val,
             }],
         })
@@ -145,6 +146,22 @@ impl<'a> CompileNode<'a> for aria_parser::ast::ForStatement {
             val: vec![true_cond.clone()],
         });
 
+        // val <pattern> = x; for a loop that takes each value apart
+        let destructure = self.pattern.as_ref().map(|pattern| {
+            Statement::ValDeclStatement(ValDeclStatement {
+                loc: self.loc.clone(),
+                decls: vec![ValDeclEntry {
+                    loc: self.loc.clone(),
+                    id: DeclarationId::from(&Identifier {
+                        loc: pattern.loc().clone(),
+                        value: format!("__val_pattern_{}", pattern.loc().location.start),
+                    }),
+                    pattern: Some(pattern.clone()),
+                    val: Expression::from(&self.id),
+                }],
+            })
+        });
+
         // case Some(x)
         let case_some_blk = MatchRule::enum_and_case(
             self.loc.clone(),
@@ -153,7 +170,10 @@ impl<'a> CompileNode<'a> for aria_parser::ast::ForStatement {
             Some(self.id.clone()),
             CodeBlock {
                 loc: self.loc.clone(),
-                entries: vec![assign_to_any_hit, Statement::CodeBlock(self.then.clone())],
+                entries: std::iter::once(assign_to_any_hit)
+                    .chain(destructure)
+                    .chain(std::iter::once(Statement::CodeBlock(self.then.clone())))
+                    .collect(),
             },
         );
 
@@ -195,6 +215,7 @@ impl<'a> CompileNode<'a> for aria_parser::ast::ForStatement {
         let throw_ut = Statement::ThrowStatement(ThrowStatement {
             loc: self.loc.clone(),
             val: unexpected_type,
+            cause: None,
         });
 
         // read __for__next
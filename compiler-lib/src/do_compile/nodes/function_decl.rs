@@ -1,5 +1,5 @@
 // SPDX-License-Identifier: Apache-2.0
-use haxby_opcodes::function_attribs::FUNC_ACCEPTS_VARARG;
+use haxby_opcodes::function_attribs::{FUNC_ACCEPTS_VARARG, FUNC_IS_GENERATOR};
 
 use crate::{
     builder::{compiler_opcodes::CompilerOpcode, func::FunctionBuilder},
@@ -27,7 +27,11 @@ impl<'a> CompileNode<'a> for aria_parser::ast::FunctionDecl {
         self.body.do_compile(&mut c_params)?;
         self.return_unit_value(&mut c_params, &self.loc)?;
 
-        let co = match writer.write(&params.module.constants, params.options) {
+        let co = match writer.write(
+            &self.name.value,
+            &mut params.module.constants,
+            params.options,
+        ) {
             Ok(c) => c,
             Err(er) => {
                 return Err(CompilationError {
@@ -37,11 +41,16 @@ impl<'a> CompileNode<'a> for aria_parser::ast::FunctionDecl {
             }
         };
         let frame_size = params.scope.as_function_root().unwrap().num_locals();
+        let local_names = params.scope.as_function_root().unwrap().local_names();
         let line_table = writer.write_line_table().clone();
         let a = if self.args.vararg {
             FUNC_ACCEPTS_VARARG
         } else {
             0_u8
+        } | if writer.is_generator() {
+            FUNC_IS_GENERATOR
+        } else {
+            0_u8
         };
         let cco = CompiledCodeObject {
             name: self.name.value.clone(),
@@ -52,6 +61,7 @@ impl<'a> CompileNode<'a> for aria_parser::ast::FunctionDecl {
             loc: self.loc.clone(),
             line_table,
             frame_size,
+            local_names,
         };
         let cco_idx =
             self.insert_const_or_fail(params, ConstantValue::CompiledCodeObject(cco), &self.loc)?;
@@ -1,4 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
+use num_bigint::BigInt;
+
 use crate::{
     builder::compiler_opcodes::CompilerOpcode,
     constant_value::ConstantValue,
@@ -11,30 +13,42 @@ impl<'a> CompileNode<'a> for aria_parser::ast::IntLiteral {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
         let inp_str = &self.val.replace('_', "");
 
-        // I don't particularly like this code, but I don't know an easy way to write
-        // if X { Result<A,E> } else if XX { Result<B,E> } ... . map_err(...)? as B;
-        // basically, some paths want to generate a u64 and some want to generate an i64,
-        // but all need to converge on i64 in the end.
-        let val = if let Some(hex_str) = inp_str.strip_prefix("0x") {
-            u64::from_str_radix(hex_str, 16).map_err(|_| CompilationError {
-                loc: self.loc.clone(),
-                reason: CompilationErrorReason::InvalidLiteral(inp_str.to_owned()),
-            })? as i64
+        // hex and binary literals that fit in 64 bits keep their bit pattern, so
+        // 0xFFFFFFFFFFFFFFFF is -1; anything too large for that, in any base, is
+        // kept as a big integer
+        let (digits, radix) = if let Some(hex_str) = inp_str.strip_prefix("0x") {
+            (hex_str, 16)
         } else if let Some(bin_str) = inp_str.strip_prefix("0b") {
-            u64::from_str_radix(bin_str, 2).map_err(|_| CompilationError {
-                loc: self.loc.clone(),
-                reason: CompilationErrorReason::InvalidLiteral(inp_str.to_owned()),
-            })? as i64
+            (bin_str, 2)
         } else if let Some(oct_str) = inp_str.strip_prefix("0o") {
-            i64::from_str_radix(oct_str, 8).map_err(|_| CompilationError {
-                loc: self.loc.clone(),
-                reason: CompilationErrorReason::InvalidLiteral(inp_str.to_owned()),
-            })?
+            (oct_str, 8)
         } else {
-            inp_str.parse::<i64>().map_err(|_| CompilationError {
-                loc: self.loc.clone(),
-                reason: CompilationErrorReason::InvalidLiteral(inp_str.to_owned()),
-            })?
+            (inp_str.as_str(), 10)
+        };
+        let val = match radix {
+            16 | 2 => u64::from_str_radix(digits, radix).map(|val| val as i64),
+            _ => i64::from_str_radix(digits, radix),
+        };
+        let val = match val {
+            Ok(val) => val,
+            Err(_) => {
+                let val = BigInt::parse_bytes(digits.as_bytes(), radix).ok_or_else(|| {
+                    CompilationError {
+                        loc: self.loc.clone(),
+                        reason: CompilationErrorReason::InvalidLiteral(inp_str.to_owned()),
+                    }
+                })?;
+                let const_idx =
+                    self.insert_const_or_fail(params, ConstantValue::BigInteger(val), &self.loc)?;
+                params
+                    .writer
+                    .get_current_block()
+                    .write_opcode_and_source_info(
+                        CompilerOpcode::Push(const_idx),
+                        self.loc.clone(),
+                    );
+                return Ok(());
+            }
         };
 
         if val == 0 {
@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: Apache-2.0
+use aria_parser::ast::InterpolatedStringPart;
+
+use crate::{
+    builder::compiler_opcodes::CompilerOpcode,
+    constant_value::ConstantValue,
+    do_compile::{CompilationResult, CompileNode, CompileParams},
+};
+
+impl<'a> CompileNode<'a> for aria_parser::ast::InterpolatedString {
+    fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
+        // each part is turned into a string and added to the ones before it
+        for (idx, part) in self.parts.iter().enumerate() {
+            match part {
+                InterpolatedStringPart::Text(text) => {
+                    let const_idx = self.insert_const_or_fail(
+                        params,
+                        ConstantValue::String(text.clone()),
+                        &self.loc,
+                    )?;
+                    params
+                        .writer
+                        .get_current_block()
+                        .write_opcode_and_source_info(
+                            CompilerOpcode::Push(const_idx),
+                            self.loc.clone(),
+                        );
+                }
+                InterpolatedStringPart::Value(value) => {
+                    value.value.do_compile(params)?;
+                    let spec = value.spec.clone().unwrap_or_default();
+                    let const_idx =
+                        self.insert_const_or_fail(params, ConstantValue::String(spec), &value.loc)?;
+                    params
+                        .writer
+                        .get_current_block()
+                        .write_opcode_and_source_info(
+                            CompilerOpcode::FormatValue(const_idx),
+                            value.loc.clone(),
+                        );
+                }
+            }
+            if idx > 0 {
+                params
+                    .writer
+                    .get_current_block()
+                    .write_opcode_and_source_info(CompilerOpcode::Add, self.loc.clone());
+            }
+        }
+
+        if self.parts.is_empty() {
+            let const_idx =
+                self.insert_const_or_fail(params, ConstantValue::String(String::new()), &self.loc)?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(CompilerOpcode::Push(const_idx), self.loc.clone());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    builder::compiler_opcodes::CompilerOpcode,
+    do_compile::{
+        CompilationError, CompilationErrorReason, CompilationResult, CompileNode, CompileParams,
+    },
+};
+
+impl<'a> CompileNode<'a> for aria_parser::ast::MapLiteral {
+    fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
+        if self.entries.len() > u32::MAX as usize {
+            return Err(CompilationError {
+                loc: self.loc.clone(),
+                reason: CompilationErrorReason::ListTooLarge,
+            });
+        }
+
+        for entry in &self.entries {
+            entry.key.do_compile(params)?;
+            entry.value.do_compile(params)?;
+        }
+
+        params
+            .writer
+            .get_current_block()
+            .write_opcode_and_source_info(
+                CompilerOpcode::BuildMap(self.entries.len() as u32),
+                self.loc.clone(),
+            );
+        Ok(())
+    }
+}
@@ -1,12 +1,122 @@
 // SPDX-License-Identifier: Apache-2.0
-use crate::do_compile::{CompilationResult, CompileNode, CompileParams};
+use aria_parser::ast::{MatchPattern, SourcePointer};
 
-impl<'a> CompileNode<'a> for aria_parser::ast::MatchPattern {
-    fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
+use crate::{
+    builder::{block::BasicBlock, compiler_opcodes::CompilerOpcode},
+    constant_value::ConstantValue,
+    do_compile::{
+        CompilationError, CompilationErrorReason, CompilationResult, CompileParams, CompilePattern,
+    },
+};
+
+impl CompilePattern for MatchPattern {
+    fn compile_check(
+        &self,
+        subject: &str,
+        miss: &BasicBlock,
+        params: &mut CompileParams,
+    ) -> CompilationResult {
         match self {
-            Self::MatchPatternComp(e) => e.do_compile(params),
-            Self::MatchPatternRel(e) => e.do_compile(params),
-            Self::MatchPatternEnumCase(e) => e.do_compile(params),
+            Self::MatchPatternComp(e) => e.compile_check(subject, miss, params),
+            Self::MatchPatternRel(e) => e.compile_check(subject, miss, params),
+            Self::MatchPatternEnumCase(e) => e.compile_check(subject, miss, params),
+            Self::MatchPatternWildcard(_) => Ok(()),
+            Self::MatchPatternBinding(e) => e.compile_check(subject, miss, params),
+            Self::MatchPatternList(e) => e.compile_check(subject, miss, params),
+            Self::MatchPatternStruct(e) => e.compile_check(subject, miss, params),
         }
     }
 }
+
+// the name of a hidden variable that a pattern at loc needs
+pub(super) fn pattern_temp(what: &str, loc: &SourcePointer) -> String {
+    format!(
+        "__pattern_{what}_{}_{}",
+        loc.location.start, loc.location.stop
+    )
+}
+
+pub(super) fn insert_const(
+    params: &mut CompileParams,
+    value: ConstantValue,
+    loc: &SourcePointer,
+) -> CompilationResult<u16> {
+    params
+        .module
+        .constants
+        .insert(value)
+        .map_err(|_| CompilationError {
+            loc: loc.clone(),
+            reason: CompilationErrorReason::TooManyConstants,
+        })
+}
+
+// checks the value on top of the stack against a pattern nested in another
+// one, such as the payload of an enum case or an element of a list; the value
+// is consumed
+pub(super) fn compile_nested_check(
+    pattern: &MatchPattern,
+    miss: &BasicBlock,
+    params: &mut CompileParams,
+) -> CompilationResult {
+    match pattern {
+        MatchPattern::MatchPatternWildcard(w) => {
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(CompilerOpcode::Pop, w.loc.clone());
+            Ok(())
+        }
+        // nothing to check, so the value can be bound right away
+        MatchPattern::MatchPatternBinding(b) if b.id.ty.is_none() => {
+            params.scope.emit_untyped_define(
+                &b.id.name.value,
+                &mut params.module.constants,
+                params.writer.get_current_block(),
+                b.loc.clone(),
+            )?;
+            Ok(())
+        }
+        _ => {
+            let temp = pattern_temp("value", pattern.loc());
+            params.scope.emit_untyped_define(
+                &temp,
+                &mut params.module.constants,
+                params.writer.get_current_block(),
+                pattern.loc().clone(),
+            )?;
+            pattern.compile_check(&temp, miss, params)
+        }
+    }
+}
+
+// leaves hasattr(subject, name) on the stack
+pub(super) fn emit_hasattr(
+    subject: &str,
+    name: &str,
+    loc: &SourcePointer,
+    params: &mut CompileParams,
+) -> CompilationResult {
+    let name_idx = insert_const(params, ConstantValue::String(name.to_owned()), loc)?;
+    params
+        .writer
+        .get_current_block()
+        .write_opcode_and_source_info(CompilerOpcode::Push(name_idx), loc.clone());
+    params.scope.emit_read(
+        subject,
+        &mut params.module.constants,
+        params.writer.get_current_block(),
+        loc.clone(),
+    )?;
+    params.scope.emit_read(
+        "hasattr",
+        &mut params.module.constants,
+        params.writer.get_current_block(),
+        loc.clone(),
+    )?;
+    params
+        .writer
+        .get_current_block()
+        .write_opcode_and_source_info(CompilerOpcode::Call(2), loc.clone());
+    Ok(())
+}
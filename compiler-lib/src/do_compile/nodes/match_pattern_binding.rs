@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    builder::{block::BasicBlock, compiler_opcodes::CompilerOpcode},
+    do_compile::{CompilationResult, CompileNode, CompileParams, CompilePattern},
+};
+
+impl CompilePattern for aria_parser::ast::MatchPatternBinding {
+    fn compile_check(
+        &self,
+        subject: &str,
+        miss: &BasicBlock,
+        params: &mut CompileParams,
+    ) -> CompilationResult {
+        // a binding with a type only matches values of that type
+        if let Some(ty) = &self.id.ty {
+            params.scope.emit_read(
+                subject,
+                &mut params.module.constants,
+                params.writer.get_current_block(),
+                self.loc.clone(),
+            )?;
+            ty.do_compile(params)?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(CompilerOpcode::Isa, self.loc.clone())
+                .write_opcode_and_source_info(
+                    CompilerOpcode::JumpFalse(miss.clone()),
+                    self.loc.clone(),
+                );
+        }
+        params.scope.emit_read(
+            subject,
+            &mut params.module.constants,
+            params.writer.get_current_block(),
+            self.loc.clone(),
+        )?;
+        params.scope.emit_untyped_define(
+            &self.id.name.value,
+            &mut params.module.constants,
+            params.writer.get_current_block(),
+            self.loc.clone(),
+        )?;
+        Ok(())
+    }
+}
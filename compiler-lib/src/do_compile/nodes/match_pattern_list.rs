@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: Apache-2.0
+use aria_parser::ast::{MatchPatternList, MatchPatternRest, SourcePointer};
+use haxby_opcodes::BuiltinTypeId;
+
+use crate::{
+    builder::{block::BasicBlock, compiler_opcodes::CompilerOpcode},
+    constant_value::ConstantValue,
+    do_compile::{CompilationResult, CompileParams, CompilePattern},
+};
+
+use super::match_pattern::{compile_nested_check, emit_hasattr, insert_const, pattern_temp};
+
+fn emit_read(name: &str, loc: &SourcePointer, params: &mut CompileParams) -> CompilationResult {
+    params.scope.emit_read(
+        name,
+        &mut params.module.constants,
+        params.writer.get_current_block(),
+        loc.clone(),
+    )?;
+    Ok(())
+}
+
+fn emit_push_int(n: usize, loc: &SourcePointer, params: &mut CompileParams) -> CompilationResult {
+    let idx = insert_const(params, ConstantValue::Integer(n as i64), loc)?;
+    emit_op(CompilerOpcode::Push(idx), loc, params);
+    Ok(())
+}
+
+fn emit_op(op: CompilerOpcode, loc: &SourcePointer, params: &mut CompileParams) {
+    params
+        .writer
+        .get_current_block()
+        .write_opcode_and_source_info(op, loc.clone());
+}
+
+// binds the elements between the ones that the patterns before and after
+// the rest take, as a new list
+fn compile_rest(
+    list: &MatchPatternList,
+    rest: &MatchPatternRest,
+    subject: &str,
+    len: &str,
+    params: &mut CompileParams,
+) -> CompilationResult {
+    let Some(name) = &rest.name else {
+        return Ok(());
+    };
+    let counter = pattern_temp("rest_idx", &rest.loc);
+    emit_op(CompilerOpcode::BuildList(0), &rest.loc, params);
+    params.scope.emit_untyped_define(
+        &name.value,
+        &mut params.module.constants,
+        params.writer.get_current_block(),
+        rest.loc.clone(),
+    )?;
+    emit_push_int(list.before.len(), &rest.loc, params)?;
+    params.scope.emit_untyped_define(
+        &counter,
+        &mut params.module.constants,
+        params.writer.get_current_block(),
+        rest.loc.clone(),
+    )?;
+
+    let rest_loop = params
+        .writer
+        .append_block_at_end(&format!("pattern_rest_loop_{}", rest.loc));
+    let rest_done = params
+        .writer
+        .append_block_at_end(&format!("pattern_rest_done_{}", rest.loc));
+    emit_op(CompilerOpcode::Jump(rest_loop.clone()), &rest.loc, params);
+    params.writer.set_current_block(rest_loop.clone());
+
+    // while counter < len - after.len()
+    emit_read(&counter, &rest.loc, params)?;
+    emit_read(len, &rest.loc, params)?;
+    emit_push_int(list.after.len(), &rest.loc, params)?;
+    emit_op(CompilerOpcode::Sub, &rest.loc, params);
+    emit_op(CompilerOpcode::LessThan, &rest.loc, params);
+    emit_op(
+        CompilerOpcode::JumpFalse(rest_done.clone()),
+        &rest.loc,
+        params,
+    );
+
+    // rest.append(subject[counter])
+    emit_read(subject, &rest.loc, params)?;
+    emit_read(&counter, &rest.loc, params)?;
+    emit_op(CompilerOpcode::ReadIndex(1), &rest.loc, params);
+    emit_read(&name.value, &rest.loc, params)?;
+    let append_idx = insert_const(
+        params,
+        ConstantValue::String("append".to_owned()),
+        &rest.loc,
+    )?;
+    emit_op(CompilerOpcode::ReadAttribute(append_idx), &rest.loc, params);
+    emit_op(CompilerOpcode::Call(1), &rest.loc, params);
+    emit_op(CompilerOpcode::Pop, &rest.loc, params);
+
+    // counter += 1
+    emit_read(&counter, &rest.loc, params)?;
+    emit_op(CompilerOpcode::Push1, &rest.loc, params);
+    emit_op(CompilerOpcode::Add, &rest.loc, params);
+    params.scope.emit_write(
+        &counter,
+        &mut params.module.constants,
+        params.writer.get_current_block(),
+        rest.loc.clone(),
+    )?;
+    emit_op(CompilerOpcode::Jump(rest_loop), &rest.loc, params);
+    params.writer.set_current_block(rest_done);
+    Ok(())
+}
+
+impl CompilePattern for MatchPatternList {
+    fn compile_check(
+        &self,
+        subject: &str,
+        miss: &BasicBlock,
+        params: &mut CompileParams,
+    ) -> CompilationResult {
+        // anything that has a length and can be indexed can match, such as a
+        // list or the entries of a map, but not a string
+        emit_read(subject, &self.loc, params)?;
+        emit_op(
+            CompilerOpcode::PushBuiltinTy(BuiltinTypeId::String),
+            &self.loc,
+            params,
+        );
+        emit_op(CompilerOpcode::Isa, &self.loc, params);
+        emit_op(CompilerOpcode::JumpTrue(miss.clone()), &self.loc, params);
+        emit_hasattr(subject, "len", &self.loc, params)?;
+        emit_op(CompilerOpcode::JumpFalse(miss.clone()), &self.loc, params);
+
+        let len = pattern_temp("len", &self.loc);
+        let len_idx = insert_const(params, ConstantValue::String("len".to_owned()), &self.loc)?;
+        emit_read(subject, &self.loc, params)?;
+        emit_op(CompilerOpcode::ReadAttribute(len_idx), &self.loc, params);
+        emit_op(CompilerOpcode::Call(0), &self.loc, params);
+        params.scope.emit_untyped_define(
+            &len,
+            &mut params.module.constants,
+            params.writer.get_current_block(),
+            self.loc.clone(),
+        )?;
+
+        // without a rest the length must be exact, with one it is a minimum
+        emit_read(&len, &self.loc, params)?;
+        emit_push_int(self.before.len() + self.after.len(), &self.loc, params)?;
+        if self.rest.is_some() {
+            emit_op(CompilerOpcode::GreaterThanEqual, &self.loc, params);
+        } else {
+            emit_op(CompilerOpcode::Equal, &self.loc, params);
+        }
+        emit_op(CompilerOpcode::JumpFalse(miss.clone()), &self.loc, params);
+
+        for (i, pattern) in self.before.iter().enumerate() {
+            emit_read(subject, &self.loc, params)?;
+            emit_push_int(i, &self.loc, params)?;
+            emit_op(CompilerOpcode::ReadIndex(1), &self.loc, params);
+            compile_nested_check(pattern, miss, params)?;
+        }
+
+        if let Some(rest) = &self.rest {
+            compile_rest(self, rest, subject, &len, params)?;
+        }
+
+        // the patterns after a rest count back from the end
+        for (i, pattern) in self.after.iter().enumerate() {
+            emit_read(subject, &self.loc, params)?;
+            emit_read(&len, &self.loc, params)?;
+            emit_push_int(self.after.len() - i, &self.loc, params)?;
+            emit_op(CompilerOpcode::Sub, &self.loc, params);
+            emit_op(CompilerOpcode::ReadIndex(1), &self.loc, params);
+            compile_nested_check(pattern, miss, params)?;
+        }
+
+        Ok(())
+    }
+}
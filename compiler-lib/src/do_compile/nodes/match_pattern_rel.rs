@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
-    builder::compiler_opcodes::CompilerOpcode,
-    do_compile::{CompilationResult, CompileNode, CompileParams},
+    builder::{block::BasicBlock, compiler_opcodes::CompilerOpcode},
+    do_compile::{CompilationResult, CompileNode, CompileParams, CompilePattern},
 };
 
 impl<'a> CompileNode<'a> for aria_parser::ast::MatchPatternRel {
@@ -40,3 +40,29 @@ impl<'a> CompileNode<'a> for aria_parser::ast::MatchPatternRel {
         Ok(())
     }
 }
+
+impl CompilePattern for aria_parser::ast::MatchPatternRel {
+    fn compile_check(
+        &self,
+        subject: &str,
+        miss: &BasicBlock,
+        params: &mut CompileParams,
+    ) -> CompilationResult {
+        params.scope.emit_read(
+            subject,
+            &mut params.module.constants,
+            params.writer.get_current_block(),
+            self.loc.clone(),
+        )?;
+        // leaves true (hit) or false (miss)
+        self.do_compile(params)?;
+        params
+            .writer
+            .get_current_block()
+            .write_opcode_and_source_info(
+                CompilerOpcode::JumpFalse(miss.clone()),
+                self.loc.clone(),
+            );
+        Ok(())
+    }
+}
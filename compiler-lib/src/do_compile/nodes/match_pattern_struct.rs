@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: Apache-2.0
+use aria_parser::ast::MatchPatternStruct;
+
+use crate::{
+    builder::{block::BasicBlock, compiler_opcodes::CompilerOpcode},
+    constant_value::ConstantValue,
+    do_compile::{CompilationResult, CompileNode, CompileParams, CompilePattern},
+};
+
+use super::match_pattern::{compile_nested_check, emit_hasattr, insert_const};
+
+impl CompilePattern for MatchPatternStruct {
+    fn compile_check(
+        &self,
+        subject: &str,
+        miss: &BasicBlock,
+        params: &mut CompileParams,
+    ) -> CompilationResult {
+        if let Some(ty) = &self.ty {
+            params.scope.emit_read(
+                subject,
+                &mut params.module.constants,
+                params.writer.get_current_block(),
+                self.loc.clone(),
+            )?;
+            ty.do_compile(params)?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(CompilerOpcode::Isa, self.loc.clone())
+                .write_opcode_and_source_info(
+                    CompilerOpcode::JumpFalse(miss.clone()),
+                    self.loc.clone(),
+                );
+        }
+
+        for field in &self.fields {
+            // a value without the field does not match, rather than throw
+            emit_hasattr(subject, &field.name.value, &field.loc, params)?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(
+                    CompilerOpcode::JumpFalse(miss.clone()),
+                    field.loc.clone(),
+                );
+
+            let name_idx = insert_const(
+                params,
+                ConstantValue::String(field.name.value.clone()),
+                &field.loc,
+            )?;
+            params.scope.emit_read(
+                subject,
+                &mut params.module.constants,
+                params.writer.get_current_block(),
+                field.loc.clone(),
+            )?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(
+                    CompilerOpcode::ReadAttribute(name_idx),
+                    field.loc.clone(),
+                );
+            match &field.pattern {
+                Some(pattern) => compile_nested_check(pattern, miss, params)?,
+                None => params.scope.emit_untyped_define(
+                    &field.name.value,
+                    &mut params.module.constants,
+                    params.writer.get_current_block(),
+                    field.loc.clone(),
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+}
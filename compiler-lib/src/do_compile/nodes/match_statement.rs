@@ -1,10 +1,26 @@
 // SPDX-License-Identifier: Apache-2.0
+use aria_parser::ast::{
+    MatchPattern, MatchStatement,
+    prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+};
+
 use crate::{
     builder::compiler_opcodes::CompilerOpcode,
-    do_compile::{CompilationResult, CompileNode, CompileParams},
+    constant_value::ConstantValue,
+    do_compile::{CompilationResult, CompileNode, CompileParams, CompilePattern},
 };
 
-impl<'a> CompileNode<'a> for aria_parser::ast::MatchStatement {
+// true if every rule looks for a case of an enum
+fn is_over_enum(m: &MatchStatement) -> bool {
+    !m.rules.is_empty()
+        && m.rules.iter().all(|rule| {
+            rule.patterns
+                .iter()
+                .any(|p| matches!(p, MatchPattern::MatchPatternEnumCase(_)))
+        })
+}
+
+impl<'a> CompileNode<'a> for MatchStatement {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
         let c_scope = params.scope.child();
         let mut match_param = CompileParams {
@@ -48,21 +64,19 @@ impl<'a> CompileNode<'a> for aria_parser::ast::MatchStatement {
                 .insert_block_after(&format!("match_miss_{}", rule.loc), &match_hit);
 
             for pattern in &rule.patterns {
-                rule_param.scope.emit_read(
-                    "__match_control_expr",
-                    &mut rule_param.module.constants,
-                    rule_param.writer.get_current_block(),
-                    pattern.loc().clone(),
-                )?;
-                // pattern is going to leave true (hit) or false (miss)
-                // and may add local variables to the scope
-                pattern.do_compile(&mut rule_param)?;
+                // pattern is going to jump to the miss block if it does not
+                // match, and may add local variables to the scope
+                pattern.compile_check("__match_control_expr", &match_miss, &mut rule_param)?;
+            }
+            // the guard can see the variables bound by the patterns
+            if let Some(guard) = &rule.guard {
+                guard.do_compile(&mut rule_param)?;
                 rule_param
                     .writer
                     .get_current_block()
                     .write_opcode_and_source_info(
                         CompilerOpcode::JumpFalse(match_miss.clone()),
-                        pattern.loc().clone(),
+                        guard.loc().clone(),
                     );
             }
             rule_param
@@ -87,13 +101,26 @@ impl<'a> CompileNode<'a> for aria_parser::ast::MatchStatement {
         if let Some(els) = &self.els {
             els.then.do_compile(&mut match_param)?;
         }
+        let fallthrough = if self.els.is_none() && is_over_enum(self) {
+            // a match over the cases of an enum is expected to handle every
+            // value it is given, so falling through it is an error
+            let what = self
+                .expr
+                .prettyprint(PrintoutAccumulator::default())
+                .value();
+            let what_idx = self.insert_const_or_fail(
+                &mut match_param,
+                ConstantValue::String(what),
+                &self.loc,
+            )?;
+            CompilerOpcode::MatchFailed(what_idx)
+        } else {
+            CompilerOpcode::Jump(match_after.clone())
+        };
         match_param
             .writer
             .get_current_block()
-            .write_opcode_and_source_info(
-                CompilerOpcode::Jump(match_after.clone()),
-                self.loc.clone(),
-            );
+            .write_opcode_and_source_info(fallthrough, self.loc.clone());
         match_param.writer.set_current_block(match_after);
 
         Ok(())
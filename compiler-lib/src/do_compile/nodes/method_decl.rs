@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use aria_parser::ast::{DeclarationId, Identifier};
-use haxby_opcodes::function_attribs::{FUNC_ACCEPTS_VARARG, FUNC_IS_METHOD, METHOD_ATTRIBUTE_TYPE};
+use haxby_opcodes::function_attribs::{
+    FUNC_ACCEPTS_VARARG, FUNC_IS_GENERATOR, FUNC_IS_METHOD, METHOD_ATTRIBUTE_TYPE,
+};
 
 use crate::{
     builder::{compiler_opcodes::CompilerOpcode, func::FunctionBuilder},
@@ -15,7 +17,7 @@ use crate::{
 
 impl<'a> CompileNode<'a> for aria_parser::ast::MethodDecl {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
-        let attribute = if self.args.vararg {
+        let mut attribute = if self.args.vararg {
             FUNC_ACCEPTS_VARARG
         } else {
             0
@@ -55,8 +57,13 @@ impl<'a> CompileNode<'a> for aria_parser::ast::MethodDecl {
         self.return_unit_value(&mut c_params, &self.loc)?;
 
         let frame_size = c_params.scope.as_function_root().unwrap().num_locals();
+        let local_names = c_params.scope.as_function_root().unwrap().local_names();
 
-        let co = match writer.write(&params.module.constants, params.options) {
+        let co = match writer.write(
+            &self.name.value,
+            &mut params.module.constants,
+            params.options,
+        ) {
             Ok(c) => c,
             Err(er) => {
                 return Err(CompilationError {
@@ -65,6 +72,9 @@ impl<'a> CompileNode<'a> for aria_parser::ast::MethodDecl {
                 });
             }
         };
+        if writer.is_generator() {
+            attribute |= FUNC_IS_GENERATOR;
+        }
         let line_table = writer.write_line_table().clone();
         let cco = CompiledCodeObject {
             name: self.name.value.clone(),
@@ -75,6 +85,7 @@ impl<'a> CompileNode<'a> for aria_parser::ast::MethodDecl {
             loc: self.loc.clone(),
             line_table,
             frame_size,
+            local_names,
         };
         let cco_idx =
             self.insert_const_or_fail(params, ConstantValue::CompiledCodeObject(cco), &self.loc)?;
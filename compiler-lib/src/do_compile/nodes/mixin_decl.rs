@@ -27,6 +27,6 @@ impl<'a> CompileNode<'a> for aria_parser::ast::MixinDecl {
             self.loc.clone(),
         )?;
 
-        emit_type_members_compile(&self.body, params, true)
+        emit_type_members_compile(&self.body, &self.name.value, params, true)
     }
 }
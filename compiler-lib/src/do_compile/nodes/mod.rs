@@ -3,6 +3,7 @@ mod add_operation;
 mod assert_statement;
 mod assign_statement;
 mod break_statement;
+mod bytes_literal;
 mod code_block;
 mod comp_operation;
 mod continue_statement;
@@ -21,13 +22,18 @@ mod if_statement;
 mod import_from_statement;
 mod import_statement;
 mod int_literal;
+mod interpolated_string;
 mod lambda;
 mod list_literal;
 mod logical_operation;
+mod map_literal;
 mod match_pattern;
+mod match_pattern_binding;
 mod match_pattern_comp;
 mod match_pattern_enum_case;
+mod match_pattern_list;
 mod match_pattern_rel;
+mod match_pattern_struct;
 mod match_statement;
 mod method_decl;
 mod mixin_decl;
@@ -51,3 +57,4 @@ mod val_decl_entry;
 mod val_decl_statement;
 mod while_statement;
 mod write_opeq_statement;
+mod yield_statement;
@@ -133,7 +133,7 @@ impl<'a> CompileNode<'a, (), Vec<CompilationError>> for ParsedModule {
 
         let co = match params
             .writer
-            .write(&params.module.constants, params.options)
+            .write("__entry", &mut params.module.constants, params.options)
         {
             Ok(c) => c,
             Err(e) => {
@@ -156,6 +156,7 @@ impl<'a> CompileNode<'a, (), Vec<CompilationError>> for ParsedModule {
             loc: self.loc.clone(),
             line_table,
             frame_size,
+            local_names: vec![],
         };
 
         if let Err(e) = self.insert_const_or_fail(
@@ -8,7 +8,10 @@ impl<'a> CompileNode<'a> for aria_parser::ast::Primary {
             Self::FloatLiteral(fp) => fp.do_compile(params),
             Self::Identifier(id) => id.do_compile(params),
             Self::ListLiteral(ll) => ll.do_compile(params),
+            Self::MapLiteral(ml) => ml.do_compile(params),
             Self::StringLiteral(sl) => sl.do_compile(params),
+            Self::InterpolatedString(is) => is.do_compile(params),
+            Self::BytesLiteral(bl) => bl.do_compile(params),
             Self::ParenExpression(pe) => pe.do_compile(params),
         }
     }
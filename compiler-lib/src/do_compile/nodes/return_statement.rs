@@ -1,18 +1,25 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
     builder::compiler_opcodes::CompilerOpcode,
-    do_compile::{CompilationResult, CompileNode, CompileParams},
+    do_compile::{CompilationResult, CompileNode, CompileParams, emit_leave_handlers},
 };
 
 impl<'a> CompileNode<'a> for aria_parser::ast::ReturnStatement {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
         if let Some(val) = &self.val {
             val.do_compile(params)?;
+            // returning the result of a call lets the callee take over the
+            // frame, unless a try handler has to see what the call throws
+            if params.cflow.handlers.is_empty() {
+                params.writer.get_current_block().make_tail_call();
+            }
+            emit_leave_handlers(params, 0, &self.loc)?;
             params
                 .writer
                 .get_current_block()
                 .write_opcode_and_source_info(CompilerOpcode::Return, self.loc.clone());
         } else {
+            emit_leave_handlers(params, 0, &self.loc)?;
             self.return_unit_value(params, &self.loc)?;
         }
         Ok(())
@@ -3,7 +3,7 @@ use crate::do_compile::{CompilationResult, CompileNode, CompileParams, do_struct
 
 impl<'a> CompileNode<'a> for aria_parser::ast::StructDecl {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
-        do_struct_compile(self, params)?;
+        do_struct_compile(self, &self.name.value, params)?;
 
         params.scope.emit_untyped_define(
             &self.name.value,
@@ -7,10 +7,16 @@ use crate::{
 impl<'a> CompileNode<'a> for aria_parser::ast::ThrowStatement {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
         self.val.do_compile(params)?;
+        let op = if let Some(cause) = &self.cause {
+            cause.do_compile(params)?;
+            CompilerOpcode::ThrowFrom
+        } else {
+            CompilerOpcode::Throw
+        };
         params
             .writer
             .get_current_block()
-            .write_opcode_and_source_info(CompilerOpcode::Throw, self.loc.clone());
+            .write_opcode_and_source_info(op, self.loc.clone());
         Ok(())
     }
 }
@@ -1,21 +1,41 @@
 // SPDX-License-Identifier: Apache-2.0
+use std::rc::Rc;
+
+use aria_parser::ast::SourcePointer;
+
 use crate::{
     builder::compiler_opcodes::CompilerOpcode,
-    do_compile::{CompilationResult, CompileNode, CompileParams},
+    do_compile::{CompilationResult, CompileNode, CompileParams, FinallyBlock},
 };
 
 impl<'a> CompileNode<'a> for aria_parser::ast::TryBlock {
     fn do_compile(&self, params: &'a mut CompileParams) -> CompilationResult {
+        let finally = self.finally.as_ref().map(|body| {
+            Rc::new(FinallyBlock {
+                body: body.clone(),
+                scope: params.scope.clone(),
+                cflow: params.cflow.clone(),
+            })
+        });
+
         let try_block = params.writer.insert_block_after(
             &format!("try_{}", &self.body.loc),
             &params.writer.get_current_block(),
         );
-        let catch_block = params
-            .writer
-            .insert_block_after(&format!("catch_{}", &self.catch.loc), &try_block);
         let after_block = params
             .writer
-            .insert_block_after(&format!("try_after_catch_{}", &self.body.loc), &catch_block);
+            .insert_block_after(&format!("try_after_catch_{}", &self.body.loc), &try_block);
+        let catch_block = (!self.catches.is_empty()).then(|| {
+            params
+                .writer
+                .insert_block_after(&format!("catch_{}", &self.loc), &try_block)
+        });
+        let finally_block = finally.as_ref().map(|_| {
+            params.writer.insert_block_after(
+                &format!("finally_{}", &self.loc),
+                catch_block.as_ref().unwrap_or(&try_block),
+            )
+        });
 
         params
             .writer
@@ -25,52 +45,158 @@ impl<'a> CompileNode<'a> for aria_parser::ast::TryBlock {
                 self.loc.clone(),
             );
         params.writer.set_current_block(try_block);
-        params
-            .writer
-            .get_current_block()
-            .write_opcode_and_source_info(
-                CompilerOpcode::TryEnter(catch_block.clone()),
-                self.loc.clone(),
-            );
-        self.body.do_compile(params)?;
-        params
-            .writer
-            .get_current_block()
-            .write_opcode_and_source_info(CompilerOpcode::TryExit, self.loc.clone());
-        params
-            .writer
-            .get_current_block()
-            .write_opcode_and_source_info(
-                CompilerOpcode::Jump(after_block.clone()),
-                self.loc.clone(),
-            );
-        params.writer.set_current_block(catch_block);
 
-        let catch_scope = params.scope.child();
-        let mut catch_params = CompileParams {
+        // the finally handler is outside of the catch handler, so that it
+        // also sees what the catch clauses throw
+        let mut catch_cflow = params.cflow.clone();
+        if let Some(finally_block) = &finally_block {
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(
+                    CompilerOpcode::TryEnter(finally_block.clone()),
+                    self.loc.clone(),
+                );
+            catch_cflow = catch_cflow.with_handler(finally.clone());
+        }
+        let mut body_cflow = catch_cflow.clone();
+        if let Some(catch_block) = &catch_block {
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(
+                    CompilerOpcode::TryEnter(catch_block.clone()),
+                    self.loc.clone(),
+                );
+            body_cflow = body_cflow.with_handler(None);
+        }
+
+        let mut body_params = CompileParams {
             module: params.module,
-            scope: &catch_scope,
+            scope: params.scope,
             writer: params.writer,
-            cflow: params.cflow,
+            cflow: &body_cflow,
             options: params.options,
         };
-        catch_params.scope.emit_untyped_define(
-            &self.id.value,
-            &mut catch_params.module.constants,
-            catch_params.writer.get_current_block(),
-            self.id.loc.clone(),
-        )?;
-
-        self.catch.do_compile(&mut catch_params)?;
-        catch_params
+        self.body.do_compile(&mut body_params)?;
+        if catch_block.is_some() {
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(CompilerOpcode::TryExit, self.loc.clone());
+        }
+        leave_finally(&finally, params, &self.loc)?;
+        params
             .writer
             .get_current_block()
             .write_opcode_and_source_info(
                 CompilerOpcode::Jump(after_block.clone()),
                 self.loc.clone(),
             );
-        catch_params.writer.set_current_block(after_block);
 
+        // the thrown value is on the stack; each clause checks its type in
+        // turn, and a value that no clause takes is thrown again
+        if let Some(catch_block) = catch_block {
+            params.writer.set_current_block(catch_block);
+        }
+        for clause in &self.catches {
+            let miss = params.writer.insert_block_after(
+                &format!("catch_miss_{}", &clause.loc),
+                &params.writer.get_current_block(),
+            );
+            if let Some(ty) = &clause.id.ty {
+                params
+                    .writer
+                    .get_current_block()
+                    .write_opcode_and_source_info(CompilerOpcode::Dup, clause.loc.clone());
+                ty.do_compile(params)?;
+                params
+                    .writer
+                    .get_current_block()
+                    .write_opcode_and_source_info(CompilerOpcode::Isa, clause.loc.clone())
+                    .write_opcode_and_source_info(
+                        CompilerOpcode::JumpFalse(miss.clone()),
+                        clause.loc.clone(),
+                    );
+            }
+
+            let clause_scope = params.scope.child();
+            let mut clause_params = CompileParams {
+                module: params.module,
+                scope: &clause_scope,
+                writer: params.writer,
+                cflow: &catch_cflow,
+                options: params.options,
+            };
+            clause_params.scope.emit_untyped_define(
+                &clause.id.name.value,
+                &mut clause_params.module.constants,
+                clause_params.writer.get_current_block(),
+                clause.id.loc.clone(),
+            )?;
+            clause.body.do_compile(&mut clause_params)?;
+            leave_finally(&finally, params, &self.loc)?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(
+                    CompilerOpcode::Jump(after_block.clone()),
+                    self.loc.clone(),
+                );
+            params.writer.set_current_block(miss);
+        }
+        if !self.catches.is_empty() {
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(CompilerOpcode::Throw, self.loc.clone());
+        }
+
+        // when the try or a catch clause throws, run the finally block and
+        // throw the value again; the value waits in a hidden local, so that a
+        // finally body that leaves early does not leave it on the stack
+        if let (Some(finally_block), Some(finally)) = (finally_block, &finally) {
+            params.writer.set_current_block(finally_block);
+            let thrown = format!(
+                "__finally_thrown_{}_{}",
+                self.loc.location.start, self.loc.location.stop
+            );
+            params.scope.emit_untyped_define(
+                &thrown,
+                &mut params.module.constants,
+                params.writer.get_current_block(),
+                self.loc.clone(),
+            )?;
+            finally.compile(params)?;
+            params.scope.emit_read(
+                &thrown,
+                &mut params.module.constants,
+                params.writer.get_current_block(),
+                self.loc.clone(),
+            )?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(CompilerOpcode::Throw, self.loc.clone());
+        }
+
+        params.writer.set_current_block(after_block);
         Ok(())
     }
 }
+
+// exits the finally handler on the way out of the try or a catch clause
+fn leave_finally(
+    finally: &Option<Rc<FinallyBlock>>,
+    params: &mut CompileParams,
+    loc: &SourcePointer,
+) -> CompilationResult {
+    if let Some(finally) = finally {
+        params
+            .writer
+            .get_current_block()
+            .write_opcode_and_source_info(CompilerOpcode::TryExit, loc.clone());
+        finally.compile(params)?;
+    }
+    Ok(())
+}
@@ -3,8 +3,10 @@ use haxby_opcodes::BuiltinTypeId;
 
 use crate::{
     builder::compiler_opcodes::CompilerOpcode,
+    constant_value::ConstantValue,
     do_compile::{
         CompilationError, CompilationErrorReason, CompilationResult, CompileNode, CompileParams,
+        CompilePattern,
     },
 };
 
@@ -44,6 +46,50 @@ impl<'a> CompileNode<'a> for aria_parser::ast::ValDeclEntry {
             params.writer.get_current_block(),
             self.loc.clone(),
         )?;
+
+        if let Some(pattern) = &self.pattern {
+            // val [x, y] = pair; stores the pair under the hidden id and then
+            // takes it apart, throwing if it does not have the expected shape
+            let pattern_miss = params
+                .writer
+                .append_block_at_end(&format!("val_pattern_miss_{}", self.loc));
+            let pattern_hit = params
+                .writer
+                .append_block_at_end(&format!("val_pattern_hit_{}", self.loc));
+            pattern.compile_check(&self.id.name.value, &pattern_miss, params)?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(
+                    CompilerOpcode::Jump(pattern_hit.clone()),
+                    self.loc.clone(),
+                );
+
+            // throw RuntimeError::PatternMismatch("<the pattern>")
+            params.writer.set_current_block(pattern_miss);
+            let loc = pattern.loc();
+            let text = loc.buffer.content[loc.location.start..loc.location.stop].to_owned();
+            let text_idx = self.insert_const_or_fail(params, ConstantValue::String(text), loc)?;
+            let case_idx = self.insert_const_or_fail(
+                params,
+                ConstantValue::String("PatternMismatch".to_owned()),
+                loc,
+            )?;
+            params
+                .writer
+                .get_current_block()
+                .write_opcode_and_source_info(CompilerOpcode::Push(text_idx), loc.clone())
+                .write_opcode_and_source_info(
+                    CompilerOpcode::PushBuiltinTy(BuiltinTypeId::RuntimeError),
+                    loc.clone(),
+                )
+                .write_opcode_and_source_info(
+                    CompilerOpcode::NewEnumVal(true, case_idx),
+                    loc.clone(),
+                )
+                .write_opcode_and_source_info(CompilerOpcode::Throw, loc.clone());
+            params.writer.set_current_block(pattern_hit);
+        }
         Ok(())
     }
 }
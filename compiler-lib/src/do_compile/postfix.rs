@@ -6,6 +6,7 @@ use crate::{builder::compiler_opcodes::CompilerOpcode, constant_value::ConstantV
 
 use super::{
     CompilationError, CompilationErrorReason, CompilationResult, CompileNode, CompileParams,
+    emit_leave_handlers,
 };
 
 #[derive(Debug)]
@@ -172,7 +173,12 @@ impl<'a> PostfixValue {
                 Ok(())
             }
             PostfixValue::TryProtocol(base, tp) => {
+                // returning the error directly would skip finally blocks
+                let has_finally = params.cflow.handlers.iter().any(Option::is_some);
                 let mode = match tp.mode {
+                    aria_parser::ast::TryProtocolMode::Return if has_finally => {
+                        haxby_opcodes::try_unwrap_protocol_mode::FLAG_TO_CALLER
+                    }
                     aria_parser::ast::TryProtocolMode::Return => {
                         haxby_opcodes::try_unwrap_protocol_mode::PROPAGATE_ERROR
                     }
@@ -207,6 +213,32 @@ impl<'a> PostfixValue {
                         CompilerOpcode::TryUnwrapProtocol(mode),
                         tp.loc.clone(),
                     );
+
+                if mode == haxby_opcodes::try_unwrap_protocol_mode::FLAG_TO_CALLER {
+                    let error_block = params
+                        .writer
+                        .append_block_at_end(&format!("try_protocol_error_{}", tp.loc));
+                    let ok_block = params
+                        .writer
+                        .append_block_at_end(&format!("try_protocol_ok_{}", tp.loc));
+                    params
+                        .writer
+                        .get_current_block()
+                        .write_opcode_and_source_info(
+                            CompilerOpcode::JumpConditionally(
+                                ok_block.clone(),
+                                error_block.clone(),
+                            ),
+                            tp.loc.clone(),
+                        );
+                    params.writer.set_current_block(error_block);
+                    emit_leave_handlers(params, 0, &tp.loc)?;
+                    params
+                        .writer
+                        .get_current_block()
+                        .write_opcode_and_source_info(CompilerOpcode::Return, tp.loc.clone());
+                    params.writer.set_current_block(ok_block);
+                }
                 Ok(())
             }
         }
@@ -49,6 +49,7 @@ impl ModuleDump for ConstantValue {
     ) -> PrintoutAccumulator {
         match self {
             ConstantValue::Integer(n) => buffer << "int(" << n << ")",
+            ConstantValue::BigInteger(n) => buffer << "bigint(" << n.to_string().as_str() << ")",
             ConstantValue::String(s) => buffer << "str(\"" << s.as_str() << "\")",
             ConstantValue::Float(f) => buffer << "fp(" << f.raw_value() << ")",
             ConstantValue::CompiledCodeObject(cco) => cco.dump(resolver, buffer),
@@ -126,6 +126,30 @@ pub fn opcode_prettyprint(
                 << symbol_best_repr(resolver, idx)
                 << "]"
         }
+        Opcode::CallLocalMethod(x, idx, argc) => {
+            buffer
+                << "CALL_LOCAL_METHOD("
+                << x
+                << ",@"
+                << idx
+                << ","
+                << argc
+                << ") ["
+                << const_best_repr(resolver, idx)
+                << "]"
+        }
+        Opcode::CallLocalMethodSymbol(x, idx, argc) => {
+            buffer
+                << "CALL_LOCAL_METHOD_SYMBOL("
+                << x
+                << ",#"
+                << idx
+                << ","
+                << argc
+                << ") ["
+                << symbol_best_repr(resolver, idx)
+                << "]"
+        }
         Opcode::Import(idx) => {
             buffer << "IMPORT(@" << idx << ") [" << const_best_repr(resolver, idx) << "]"
         }
@@ -135,6 +159,12 @@ pub fn opcode_prettyprint(
         Opcode::Assert(idx) => {
             buffer << "ASSERT(@" << idx << ") [" << const_best_repr(resolver, idx) << "]"
         }
+        Opcode::MatchFailed(idx) => {
+            buffer << "MATCH_FAILED(@" << idx << ") [" << const_best_repr(resolver, idx) << "]"
+        }
+        Opcode::FormatValue(idx) => {
+            buffer << "FORMAT_VALUE(@" << idx << ") [" << const_best_repr(resolver, idx) << "]"
+        }
         Opcode::TryUnwrapProtocol(mode) => {
             buffer << "TRY_UNWRAP_PROTOCOL " << try_protocol_mode_to_str(mode)
         }
@@ -178,12 +208,16 @@ pub fn opcode_prettyprint(
         | Opcode::JumpConditionally(..)
         | Opcode::JumpIfArgSupplied(..)
         | Opcode::Call(_)
+        | Opcode::TailCall(_)
         | Opcode::Return
         | Opcode::ReturnUnit
+        | Opcode::Yield
         | Opcode::TryEnter(_)
         | Opcode::TryExit
         | Opcode::Throw
+        | Opcode::ThrowFrom
         | Opcode::BuildList(_)
+        | Opcode::BuildMap(_)
         | Opcode::BuildFunction
         | Opcode::StoreUplevel(_)
         | Opcode::BuildStruct
@@ -1,4 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
+use std::path::Path;
+
 use aria_parser::ast::{ParsedModule, SourceBuffer};
 use do_compile::{CompilationError, CompilationResult};
 use module::CompiledModule;
@@ -6,12 +8,15 @@ use module::CompiledModule;
 pub mod bc_reader;
 pub mod bc_writer;
 pub mod builder;
+pub mod checker;
 pub mod constant_value;
 pub mod do_compile;
 pub mod dump;
 pub mod line_table;
 pub mod module;
+pub mod module_cache;
 pub mod scope;
+pub mod widget;
 
 pub struct CompilationOptions {
     pub optimize: bool,
@@ -34,6 +39,29 @@ pub fn compile_from_source(
     do_compile::compile_from_source(src, options)
 }
 
+// Same as compile_from_source, but a valid cache entry in cache_dir is used
+// instead of compiling if one exists, and a fresh one is stored otherwise
+pub fn compile_from_source_cached(
+    src: &SourceBuffer,
+    options: &CompilationOptions,
+    cache_dir: Option<&Path>,
+) -> CompilationResult<CompiledModule, Vec<CompilationError>> {
+    let cache_dir = match cache_dir {
+        Some(dir) if !options.dump_builder => dir,
+        _ => return compile_from_source(src, options),
+    };
+
+    if let Some(module) = module_cache::load_cached_module(cache_dir, src, options) {
+        return Ok(module);
+    }
+
+    let module = compile_from_source(src, options)?;
+    // a cache that cannot be written is not an error, the module will simply
+    // be compiled again next time
+    let _ = module_cache::store_cached_module(cache_dir, src, &module, options);
+    Ok(module)
+}
+
 pub fn compile_from_ast(
     ast: &ParsedModule,
     options: &CompilationOptions,
@@ -1,5 +1,10 @@
 // SPDX-License-Identifier: Apache-2.0
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
 
 use aria_parser::ast::SourcePointer;
 
@@ -21,6 +26,18 @@ impl LineTable {
     pub fn get(&self, idx: u16) -> Option<SourcePointer> {
         self.imp.map.borrow().get(&idx).cloned()
     }
+
+    pub fn entries(&self) -> Vec<(u16, SourcePointer)> {
+        let mut entries = self
+            .imp
+            .map
+            .borrow()
+            .iter()
+            .map(|(idx, ptr)| (*idx, ptr.clone()))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(idx, _)| *idx);
+        entries
+    }
 }
 
 impl PartialEq for LineTable {
@@ -29,3 +46,9 @@ impl PartialEq for LineTable {
     }
 }
 impl Eq for LineTable {}
+
+impl Hash for LineTable {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.imp).hash(state);
+    }
+}
@@ -0,0 +1,480 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use aria_parser::ast::{Location, SourceBuffer, SourcePointer};
+use num_bigint::BigInt;
+
+use crate::{
+    CompilationOptions,
+    bc_reader::{BytecodeReader, DecodeError},
+    constant_value::{CompiledCodeObject, ConstantValue, ConstantValues},
+    line_table::LineTable,
+    module::CompiledModule,
+};
+
+// On-disk layout of a cached module (all integers little-endian):
+//
+//   magic            4 bytes, "ABCM"
+//   format version   u16
+//   flags            u16 (compilation options that affect codegen)
+//   compiler build   u16 length + utf-8 bytes, "<version>+<build id>"
+//   source hash      u64
+//   payload length   u64
+//   payload checksum u64
+//   payload          constants, code objects and line tables
+//
+// Anything that does not match exactly is rejected, and the caller is
+// expected to fall back to compiling from source. The build id is a hash of
+// the compiler, opcode and parser sources (see build.rs), so any change to
+// codegen invalidates existing caches; the format version only needs a bump
+// when this layout changes.
+
+pub const CACHE_MAGIC: [u8; 4] = *b"ABCM";
+pub const CACHE_FORMAT_VERSION: u16 = 5;
+pub const COMPILER_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const COMPILER_BUILD: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    "+",
+    env!("ARIA_COMPILER_BUILD_ID")
+);
+pub const CACHE_FILE_EXTENSION: &str = "ariac";
+
+const FLAG_OPTIMIZE: u16 = 1;
+
+const TAG_INTEGER: u8 = 0;
+const TAG_STRING: u8 = 1;
+const TAG_FLOAT: u8 = 2;
+const TAG_CODE_OBJECT: u8 = 3;
+const TAG_BIG_INTEGER: u8 = 4;
+
+#[derive(Clone, thiserror::Error, PartialEq, Eq, Debug)]
+pub enum CacheError {
+    #[error("not a module cache file")]
+    BadMagic,
+
+    #[error("cache format version {0} is not supported")]
+    UnsupportedFormat(u16),
+
+    #[error("cache was generated with different compilation options")]
+    OptionsMismatch,
+
+    #[error("cache was generated by compiler build {0}")]
+    CompilerMismatch(String),
+
+    #[error("cache does not match the current source")]
+    SourceMismatch,
+
+    #[error("cache payload is corrupted")]
+    ChecksumMismatch,
+
+    #[error("reached end of stream")]
+    EndOfStream,
+
+    #[error("cache contains an invalid string")]
+    InvalidString,
+
+    #[error("{0} is not a known constant tag")]
+    UnknownConstantTag(u8),
+
+    #[error("source location {0}..{1} is out of bounds")]
+    InvalidLocation(usize, usize),
+
+    #[error("cached module has no entry point")]
+    MissingEntry,
+
+    #[error("cached bytecode is invalid: {0}")]
+    InvalidBytecode(DecodeError),
+}
+
+pub type CacheResult<T> = Result<T, CacheError>;
+
+// FNV-1a is used instead of std's hashers because its output is stable
+// across Rust versions and platforms, which matters for data stored on disk
+pub fn stable_hash(data: &[u8]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    data.iter().fold(FNV_OFFSET_BASIS, |hash, b| {
+        (hash ^ (*b as u64)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+fn flags_for_options(options: &CompilationOptions) -> u16 {
+    if options.optimize { FLAG_OPTIMIZE } else { 0 }
+}
+
+#[derive(Default)]
+struct CacheWriter {
+    data: Vec<u8>,
+}
+
+impl CacheWriter {
+    fn write_u8(&mut self, val: u8) -> &mut Self {
+        self.data.push(val);
+        self
+    }
+
+    fn write_u16(&mut self, val: u16) -> &mut Self {
+        self.data.extend_from_slice(&val.to_le_bytes());
+        self
+    }
+
+    fn write_u32(&mut self, val: u32) -> &mut Self {
+        self.data.extend_from_slice(&val.to_le_bytes());
+        self
+    }
+
+    fn write_u64(&mut self, val: u64) -> &mut Self {
+        self.data.extend_from_slice(&val.to_le_bytes());
+        self
+    }
+
+    fn write_bytes(&mut self, val: &[u8]) -> &mut Self {
+        self.write_u32(val.len() as u32);
+        self.data.extend_from_slice(val);
+        self
+    }
+
+    fn write_str(&mut self, val: &str) -> &mut Self {
+        self.write_bytes(val.as_bytes())
+    }
+
+    fn write_location(&mut self, loc: &Location) -> &mut Self {
+        self.write_u64(loc.start as u64).write_u64(loc.stop as u64)
+    }
+
+    fn write_code_object(&mut self, cco: &CompiledCodeObject) -> &mut Self {
+        self.write_str(&cco.name)
+            .write_u8(cco.attribute)
+            .write_u8(cco.required_argc)
+            .write_u8(cco.default_argc)
+            .write_u8(cco.frame_size)
+            .write_location(&cco.loc.location)
+            .write_bytes(&cco.body);
+
+        self.write_u32(cco.local_names.len() as u32);
+        for name in &cco.local_names {
+            self.write_str(name);
+        }
+
+        let entries = cco.line_table.entries();
+        self.write_u32(entries.len() as u32);
+        for (idx, ptr) in &entries {
+            self.write_u16(*idx).write_location(&ptr.location);
+        }
+        self
+    }
+
+    fn write_constant(&mut self, cv: &ConstantValue) -> &mut Self {
+        match cv {
+            ConstantValue::Integer(n) => self.write_u8(TAG_INTEGER).write_u64(*n as u64),
+            ConstantValue::BigInteger(n) => self
+                .write_u8(TAG_BIG_INTEGER)
+                .write_bytes(&n.to_signed_bytes_le()),
+            ConstantValue::String(s) => self.write_u8(TAG_STRING).write_str(s),
+            ConstantValue::Float(f) => self.write_u8(TAG_FLOAT).write_u64(f.raw_value().to_bits()),
+            ConstantValue::CompiledCodeObject(cco) => {
+                self.write_u8(TAG_CODE_OBJECT).write_code_object(cco)
+            }
+        }
+    }
+}
+
+struct CacheReader<'a> {
+    data: &'a [u8],
+    idx: usize,
+    src: &'a SourceBuffer,
+}
+
+impl<'a> CacheReader<'a> {
+    fn new(data: &'a [u8], src: &'a SourceBuffer) -> Self {
+        Self { data, idx: 0, src }
+    }
+
+    fn read_slice(&mut self, len: usize) -> CacheResult<&'a [u8]> {
+        let end = self.idx.checked_add(len).ok_or(CacheError::EndOfStream)?;
+        if end <= self.data.len() {
+            let val = &self.data[self.idx..end];
+            self.idx = end;
+            Ok(val)
+        } else {
+            Err(CacheError::EndOfStream)
+        }
+    }
+
+    fn read_array<const N: usize>(&mut self) -> CacheResult<[u8; N]> {
+        let mut ret = [0u8; N];
+        ret.copy_from_slice(self.read_slice(N)?);
+        Ok(ret)
+    }
+
+    fn read_u8(&mut self) -> CacheResult<u8> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    fn read_u16(&mut self) -> CacheResult<u16> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_u32(&mut self) -> CacheResult<u32> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_u64(&mut self) -> CacheResult<u64> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_bytes(&mut self) -> CacheResult<&'a [u8]> {
+        let len = self.read_u32()? as usize;
+        self.read_slice(len)
+    }
+
+    fn read_string(&mut self) -> CacheResult<String> {
+        let bytes = self.read_bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| CacheError::InvalidString)
+    }
+
+    fn read_pointer(&mut self) -> CacheResult<SourcePointer> {
+        let start = self.read_u64()? as usize;
+        let stop = self.read_u64()? as usize;
+        let len = self.src.content.len();
+        if start > stop
+            || stop > len
+            || !self.src.content.is_char_boundary(start)
+            || !self.src.content.is_char_boundary(stop)
+        {
+            return Err(CacheError::InvalidLocation(start, stop));
+        }
+        Ok(SourcePointer {
+            location: Location { start, stop },
+            buffer: self.src.clone(),
+        })
+    }
+
+    fn read_code_object(&mut self) -> CacheResult<CompiledCodeObject> {
+        let name = self.read_string()?;
+        let attribute = self.read_u8()?;
+        let required_argc = self.read_u8()?;
+        let default_argc = self.read_u8()?;
+        let frame_size = self.read_u8()?;
+        let loc = self.read_pointer()?;
+        let body = self.read_bytes()?.to_vec();
+        validate_bytecode(&body)?;
+
+        let count = self.read_u32()?;
+        let local_names = (0..count)
+            .map(|_| self.read_string())
+            .collect::<CacheResult<Vec<_>>>()?;
+
+        let line_table = LineTable::default();
+        let count = self.read_u32()?;
+        for _ in 0..count {
+            let idx = self.read_u16()?;
+            let ptr = self.read_pointer()?;
+            line_table.insert(idx, ptr);
+        }
+
+        Ok(CompiledCodeObject {
+            name,
+            attribute,
+            body,
+            required_argc,
+            default_argc,
+            loc,
+            line_table,
+            frame_size,
+            local_names,
+        })
+    }
+
+    fn read_constant(&mut self) -> CacheResult<ConstantValue> {
+        match self.read_u8()? {
+            TAG_INTEGER => Ok(ConstantValue::Integer(self.read_u64()? as i64)),
+            TAG_BIG_INTEGER => Ok(ConstantValue::BigInteger(BigInt::from_signed_bytes_le(
+                self.read_bytes()?,
+            ))),
+            TAG_STRING => Ok(ConstantValue::String(self.read_string()?)),
+            TAG_FLOAT => Ok(ConstantValue::Float(
+                f64::from_bits(self.read_u64()?).into(),
+            )),
+            TAG_CODE_OBJECT => Ok(ConstantValue::CompiledCodeObject(self.read_code_object()?)),
+            tag => Err(CacheError::UnknownConstantTag(tag)),
+        }
+    }
+}
+
+fn validate_bytecode(body: &[u8]) -> CacheResult<()> {
+    let mut reader = BytecodeReader::try_from(body).map_err(CacheError::InvalidBytecode)?;
+    while reader.get_index() < reader.len() {
+        reader.read_opcode().map_err(CacheError::InvalidBytecode)?;
+    }
+    Ok(())
+}
+
+impl CompiledModule {
+    pub fn to_cache_bytes(&self, src: &SourceBuffer, options: &CompilationOptions) -> Vec<u8> {
+        let mut payload = CacheWriter::default();
+        match &self.widget_root_path {
+            Some(path) => payload.write_u8(1).write_str(&path.to_string_lossy()),
+            None => payload.write_u8(0),
+        };
+        payload.write_u32(self.constants.len() as u32);
+        for cv in self.constants.values() {
+            payload.write_constant(cv);
+        }
+
+        let mut container = CacheWriter::default();
+        container.data.extend_from_slice(&CACHE_MAGIC);
+        container
+            .write_u16(CACHE_FORMAT_VERSION)
+            .write_u16(flags_for_options(options))
+            .write_u16(COMPILER_BUILD.len() as u16);
+        container.data.extend_from_slice(COMPILER_BUILD.as_bytes());
+        container
+            .write_u64(stable_hash(src.content.as_bytes()))
+            .write_u64(payload.data.len() as u64)
+            .write_u64(stable_hash(&payload.data));
+        container.data.extend_from_slice(&payload.data);
+        container.data
+    }
+
+    pub fn from_cache_bytes(
+        data: &[u8],
+        src: &SourceBuffer,
+        options: &CompilationOptions,
+    ) -> CacheResult<CompiledModule> {
+        let mut reader = CacheReader::new(data, src);
+
+        if reader.read_array::<4>().map_err(|_| CacheError::BadMagic)? != CACHE_MAGIC {
+            return Err(CacheError::BadMagic);
+        }
+
+        let format_version = reader.read_u16()?;
+        if format_version != CACHE_FORMAT_VERSION {
+            return Err(CacheError::UnsupportedFormat(format_version));
+        }
+
+        if reader.read_u16()? != flags_for_options(options) {
+            return Err(CacheError::OptionsMismatch);
+        }
+
+        let version_len = reader.read_u16()? as usize;
+        let version = reader.read_slice(version_len)?;
+        if version != COMPILER_BUILD.as_bytes() {
+            return Err(CacheError::CompilerMismatch(
+                String::from_utf8_lossy(version).into_owned(),
+            ));
+        }
+
+        if reader.read_u64()? != stable_hash(src.content.as_bytes()) {
+            return Err(CacheError::SourceMismatch);
+        }
+
+        let payload_len = reader.read_u64()? as usize;
+        let payload_checksum = reader.read_u64()?;
+        let payload = reader.read_slice(payload_len)?;
+        if stable_hash(payload) != payload_checksum || reader.idx != data.len() {
+            return Err(CacheError::ChecksumMismatch);
+        }
+
+        let mut reader = CacheReader::new(payload, src);
+        let widget_root_path = match reader.read_u8()? {
+            0 => None,
+            _ => Some(PathBuf::from(reader.read_string()?)),
+        };
+
+        let count = reader.read_u32()?;
+        if count == 0 || count > u16::MAX as u32 {
+            return Err(CacheError::MissingEntry);
+        }
+        let mut values = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            values.push(reader.read_constant()?);
+        }
+        if !values.last().is_some_and(|cv| cv.is_compiled_code_object()) {
+            return Err(CacheError::MissingEntry);
+        }
+
+        Ok(CompiledModule {
+            constants: ConstantValues::from_values(values),
+            widget_root_path,
+        })
+    }
+}
+
+// The cache lives outside of the source tree, so that library directories
+// can be read-only; ARIA_CACHE_DIR overrides the default location
+pub fn default_cache_dir() -> Option<PathBuf> {
+    if let Ok(dir) = std::env::var("ARIA_CACHE_DIR")
+        && !dir.is_empty()
+    {
+        return Some(PathBuf::from(dir));
+    }
+
+    let base = match std::env::var("XDG_CACHE_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = std::env::var("HOME").ok().filter(|h| !h.is_empty())?;
+            PathBuf::from(home).join(".cache")
+        }
+    };
+
+    Some(base.join("aria").join("bytecode"))
+}
+
+// Only buffers that were read from a file have a stable identity that can
+// be used to name a cache entry
+pub fn cache_path_for_source(cache_dir: &Path, src: &SourceBuffer) -> Option<PathBuf> {
+    let src_path = Path::new(&src.name);
+    if !src_path.is_absolute() || !src_path.is_file() {
+        return None;
+    }
+
+    let stem = src_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Some(cache_dir.join(format!(
+        "{stem}-{:016x}.{CACHE_FILE_EXTENSION}",
+        stable_hash(src.name.as_bytes())
+    )))
+}
+
+pub fn load_cached_module(
+    cache_dir: &Path,
+    src: &SourceBuffer,
+    options: &CompilationOptions,
+) -> Option<CompiledModule> {
+    let path = cache_path_for_source(cache_dir, src)?;
+    let data = std::fs::read(path).ok()?;
+    CompiledModule::from_cache_bytes(&data, src, options).ok()
+}
+
+pub fn store_cached_module(
+    cache_dir: &Path,
+    src: &SourceBuffer,
+    module: &CompiledModule,
+    options: &CompilationOptions,
+) -> std::io::Result<()> {
+    let Some(path) = cache_path_for_source(cache_dir, src) else {
+        return Ok(());
+    };
+
+    std::fs::create_dir_all(cache_dir)?;
+
+    // write to a private file first and then move it into place, so that
+    // concurrent readers never observe a partially written cache entry
+    static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+    let tmp_path = path.with_extension(format!(
+        "{CACHE_FILE_EXTENSION}.{}.{}.tmp",
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&tmp_path, module.to_cache_bytes(src, options))?;
+    std::fs::rename(&tmp_path, &path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp_path);
+    })
+}
@@ -269,6 +269,8 @@ struct UplevelSymbolResolution {
 pub struct FunctionRootScope {
     symbols: RefCell<HashMap<String, u8>>,
     index_provider: RefCell<IndexProviderImpl<u8>>,
+    // the name each local slot was allocated for, in slot order
+    local_names: RefCell<Vec<String>>,
     parent: CompilationScope,
     lexical_parent: Option<(CompilationScope, BasicBlock)>,
     pub(crate) uplevels: RefCell<Vec<UplevelInfo>>,
@@ -279,6 +281,7 @@ impl FunctionRootScope {
         Self {
             symbols: Default::default(),
             index_provider: Default::default(),
+            local_names: Default::default(),
             parent: parent.get_module_scope().unwrap(),
             lexical_parent: None,
             uplevels: Default::default(),
@@ -289,6 +292,7 @@ impl FunctionRootScope {
         Self {
             symbols: Default::default(),
             index_provider: Default::default(),
+            local_names: Default::default(),
             parent: lexical_parent.0.get_module_scope().unwrap(),
             lexical_parent: Some(lexical_parent),
             uplevels: Default::default(),
@@ -299,6 +303,16 @@ impl FunctionRootScope {
         self.index_provider.borrow().get_max_index()
     }
 
+    pub fn local_names(&self) -> Vec<String> {
+        self.local_names.borrow().clone()
+    }
+
+    fn new_local(&self, name: &str) -> u8 {
+        let idx = self.index_provider.borrow_mut().next();
+        self.local_names.borrow_mut().push(name.to_owned());
+        idx
+    }
+
     pub fn emit_typed_define(
         &self,
         name: &str,
@@ -306,7 +320,7 @@ impl FunctionRootScope {
         dest: BasicBlock,
         loc: SourcePointer,
     ) -> ScopeResult {
-        let next_idx = self.index_provider.borrow_mut().next();
+        let next_idx = self.new_local(name);
         self.symbols.borrow_mut().insert(name.to_owned(), next_idx);
         dest.write_opcode_and_source_info(CompilerOpcode::TypedefLocal(next_idx), loc);
         Ok(())
@@ -349,7 +363,7 @@ impl FunctionRootScope {
                 reason: ScopeErrorReason::OverlyDeepClosure,
             });
         }
-        let index_in_local = self.index_provider.borrow_mut().next();
+        let index_in_local = self.new_local(name);
         self.symbols
             .borrow_mut()
             .insert(name.to_owned(), index_in_local);
@@ -449,7 +463,7 @@ impl FunctionChildScope {
         dest: BasicBlock,
         loc: SourcePointer,
     ) -> ScopeResult {
-        let next_idx = self.get_function_root().index_provider.borrow_mut().next();
+        let next_idx = self.get_function_root().new_local(name);
         self.symbols.borrow_mut().insert(name.to_owned(), next_idx);
         dest.write_opcode_and_source_info(CompilerOpcode::TypedefLocal(next_idx), loc);
         Ok(())
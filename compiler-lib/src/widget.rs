@@ -0,0 +1,332 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value, json};
+
+// A widget is a directory with a widget.json manifest:
+//
+//   {
+//       "name": "shapes",
+//       "version": "1.2.0",
+//       "entry": "main.aria",
+//       "aria": ">=0.9.20251222",
+//       "dependencies": {
+//           "geometry": { "path": "../geometry" },
+//           "colors": { "archive": "vendor/colors-0.3.0.tar" }
+//       }
+//   }
+//
+// Every field is optional. `aria widget` resolves the dependencies of a
+// widget, and of its dependencies, into widget.lock next to the manifest;
+// code in the widget, or in any of its dependencies, can then import
+// `geometry.module` from the dependency named geometry.
+
+pub const MANIFEST_FILE_NAME: &str = "widget.json";
+pub const LOCK_FILE_NAME: &str = "widget.lock";
+pub const LOCK_FORMAT_VERSION: u64 = 1;
+
+// names that already mean something at the start of an import path
+const RESERVED_NAMES: &[&str] = &["aria", "widget"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum WidgetError {
+    #[error("cannot read {0}: {1}")]
+    Io(PathBuf, std::io::Error),
+    #[error("{0} is not valid JSON: {1}")]
+    Json(PathBuf, serde_json::Error),
+    #[error("{0}: {1}")]
+    Invalid(PathBuf, String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DependencySource {
+    // a directory with a widget.json, relative to the depending widget
+    Path(PathBuf),
+    // a tar archive of a widget, relative to the depending widget
+    Archive(PathBuf),
+}
+
+impl std::fmt::Display for DependencySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DependencySource::Path(p) => write!(f, "path:{}", p.display()),
+            DependencySource::Archive(p) => write!(f, "archive:{}", p.display()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub entry: Option<String>,
+    // the versions of Aria the widget works with, e.g. ">=0.9, <1.0"
+    pub aria: Option<String>,
+    pub dependencies: BTreeMap<String, DependencySource>,
+}
+
+fn optional_string(
+    obj: &Map<String, Value>,
+    key: &str,
+    path: &Path,
+) -> Result<Option<String>, WidgetError> {
+    match obj.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(WidgetError::Invalid(
+            path.to_path_buf(),
+            format!("\"{key}\" must be a string"),
+        )),
+    }
+}
+
+pub fn is_valid_widget_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with("__")
+        && !RESERVED_NAMES.contains(&name)
+}
+
+impl Manifest {
+    pub fn parse(text: &str, path: &Path) -> Result<Self, WidgetError> {
+        let invalid = |msg: String| WidgetError::Invalid(path.to_path_buf(), msg);
+
+        let value: Value =
+            serde_json::from_str(text).map_err(|e| WidgetError::Json(path.to_path_buf(), e))?;
+        let Value::Object(obj) = value else {
+            return Err(invalid("the manifest must be a JSON object".to_owned()));
+        };
+
+        let mut dependencies = BTreeMap::new();
+        match obj.get("dependencies") {
+            None | Some(Value::Null) => {}
+            Some(Value::Object(deps)) => {
+                for (name, spec) in deps {
+                    if !is_valid_widget_name(name) {
+                        return Err(invalid(format!(
+                            "\"{name}\" cannot be used as the name of a dependency"
+                        )));
+                    }
+                    let source = match spec {
+                        Value::Object(spec) => match (spec.get("path"), spec.get("archive")) {
+                            (Some(Value::String(p)), None) => {
+                                DependencySource::Path(PathBuf::from(p))
+                            }
+                            (None, Some(Value::String(a))) => {
+                                DependencySource::Archive(PathBuf::from(a))
+                            }
+                            _ => {
+                                return Err(invalid(format!(
+                                    "dependency \"{name}\" needs either a \"path\" or an \"archive\""
+                                )));
+                            }
+                        },
+                        _ => {
+                            return Err(invalid(format!(
+                                "dependency \"{name}\" must be a JSON object"
+                            )));
+                        }
+                    };
+                    dependencies.insert(name.clone(), source);
+                }
+            }
+            Some(_) => return Err(invalid("\"dependencies\" must be an object".to_owned())),
+        }
+
+        let name = optional_string(&obj, "name", path)?;
+        if let Some(name) = &name
+            && !is_valid_widget_name(name)
+        {
+            return Err(invalid(format!(
+                "\"{name}\" cannot be used as a widget name"
+            )));
+        }
+        let aria = optional_string(&obj, "aria", path)?;
+        if let Some(req) = &aria {
+            version_matches(req, "0").map_err(invalid)?;
+        }
+
+        Ok(Self {
+            name,
+            version: optional_string(&obj, "version", path)?,
+            entry: optional_string(&obj, "entry", path)?,
+            aria,
+            dependencies,
+        })
+    }
+
+    // the manifest of the widget rooted at dir
+    pub fn load(dir: &Path) -> Result<Self, WidgetError> {
+        let path = dir.join(MANIFEST_FILE_NAME);
+        let text = std::fs::read_to_string(&path).map_err(|e| WidgetError::Io(path.clone(), e))?;
+        Self::parse(&text, &path)
+    }
+}
+
+// the closest directory at or above path that has a widget.json
+pub fn find_widget_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(MANIFEST_FILE_NAME).is_file())
+        .map(Path::to_path_buf)
+}
+
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parts = |v: &str| {
+        v.split('.')
+            .map(|p| p.parse::<u64>().unwrap_or(0))
+            .collect::<Vec<_>>()
+    };
+    let (mut a, mut b) = (parts(a), parts(b));
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a.cmp(&b)
+}
+
+// whether version satisfies a requirement such as ">=0.9, <1.0"; a version
+// without an operator must match exactly
+pub fn version_matches(req: &str, version: &str) -> Result<bool, String> {
+    let mut result = true;
+    for clause in req.split(',') {
+        let clause = clause.trim();
+        let (op, wanted) = [">=", "<=", ">", "<", "="]
+            .iter()
+            .find_map(|op| clause.strip_prefix(op).map(|rest| (*op, rest.trim())))
+            .unwrap_or(("=", clause));
+        if wanted.is_empty()
+            || !wanted
+                .split('.')
+                .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+        {
+            return Err(format!("\"{req}\" is not a valid version requirement"));
+        }
+        let ord = compare_versions(version, wanted);
+        result &= match op {
+            ">=" => ord.is_ge(),
+            "<=" => ord.is_le(),
+            ">" => ord.is_gt(),
+            "<" => ord.is_lt(),
+            _ => ord.is_eq(),
+        };
+    }
+    Ok(result)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockedWidget {
+    pub version: Option<String>,
+    // where the widget came from, as written in the manifest that asked for it
+    pub source: String,
+    // the directory of the widget, relative to the locking widget
+    pub root: PathBuf,
+    // sha256 of the archive, for widgets that came from one
+    pub checksum: Option<String>,
+    pub dependencies: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    pub widgets: BTreeMap<String, LockedWidget>,
+}
+
+impl Lockfile {
+    pub fn parse(text: &str, path: &Path) -> Result<Self, WidgetError> {
+        let invalid = |msg: &str| WidgetError::Invalid(path.to_path_buf(), msg.to_owned());
+
+        let value: Value =
+            serde_json::from_str(text).map_err(|e| WidgetError::Json(path.to_path_buf(), e))?;
+        if value["version"].as_u64() != Some(LOCK_FORMAT_VERSION) {
+            return Err(invalid("unsupported lockfile version"));
+        }
+        let Some(entries) = value["widgets"].as_object() else {
+            return Err(invalid("\"widgets\" must be an object"));
+        };
+
+        let mut widgets = BTreeMap::new();
+        for (name, entry) in entries {
+            let (Some(source), Some(root)) = (entry["source"].as_str(), entry["root"].as_str())
+            else {
+                return Err(invalid("every widget needs a \"source\" and a \"root\""));
+            };
+            widgets.insert(
+                name.clone(),
+                LockedWidget {
+                    version: entry["version"].as_str().map(str::to_owned),
+                    source: source.to_owned(),
+                    root: PathBuf::from(root),
+                    checksum: entry["checksum"].as_str().map(str::to_owned),
+                    dependencies: entry["dependencies"]
+                        .as_array()
+                        .map(|deps| {
+                            deps.iter()
+                                .filter_map(|d| d.as_str().map(str::to_owned))
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                },
+            );
+        }
+        Ok(Self { widgets })
+    }
+
+    // the lockfile of the widget rooted at dir, if it has one
+    pub fn load(dir: &Path) -> Result<Option<Self>, WidgetError> {
+        let path = dir.join(LOCK_FILE_NAME);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text, &path).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(WidgetError::Io(path, e)),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let widgets = self
+            .widgets
+            .iter()
+            .map(|(name, w)| {
+                let mut entry = json!({
+                    "source": w.source,
+                    "root": w.root.to_string_lossy().replace('\\', "/"),
+                    "dependencies": w.dependencies,
+                });
+                if let Some(version) = &w.version {
+                    entry["version"] = json!(version);
+                }
+                if let Some(checksum) = &w.checksum {
+                    entry["checksum"] = json!(checksum);
+                }
+                (name.clone(), entry)
+            })
+            .collect::<Map<_, _>>();
+        let doc = json!({
+            "version": LOCK_FORMAT_VERSION,
+            "widgets": widgets,
+        });
+        let mut text = serde_json::to_string_pretty(&doc).unwrap_or_default();
+        text.push('\n');
+        text
+    }
+
+    // the file an import path such as geometry.shapes.circle names, if
+    // geometry is a locked widget; lock_dir is the directory of the lockfile
+    pub fn resolve_import(&self, lock_dir: &Path, ipath: &str) -> Option<PathBuf> {
+        let (name, rest) = ipath.split_once('.')?;
+        let widget = self.widgets.get(name)?;
+        Some(
+            lock_dir
+                .join(&widget.root)
+                .join(format!("{}.aria", rest.replace('.', "/"))),
+        )
+    }
+
+    // the directory of a locked widget
+    pub fn widget_root(&self, lock_dir: &Path, name: &str) -> Option<PathBuf> {
+        self.widgets.get(name).map(|w| lock_dir.join(&w.root))
+    }
+}
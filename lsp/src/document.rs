@@ -1,11 +1,101 @@
 // SPDX-License-Identifier: Apache-2.0
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::lexer::SyntaxKind as K;
+use crate::modules::ModuleLoader;
 use crate::parser::{self, Parse, SyntaxNode, SyntaxToken};
+use crate::symbols::{self, Decl, DeclKind};
+use aria_parser::{ast::SourceBuffer, format::format_source};
 use line_index::{LineCol, LineIndex};
 use rowan::{TextRange, TextSize};
 
+const KEYWORDS: &[&str] = &[
+    "and",
+    "assert",
+    "break",
+    "case",
+    "catch",
+    "continue",
+    "else",
+    "elsif",
+    "enum",
+    "extension",
+    "false",
+    "flag",
+    "for",
+    "from",
+    "func",
+    "if",
+    "import",
+    "in",
+    "include",
+    "instance",
+    "isa",
+    "match",
+    "mixin",
+    "operator",
+    "return",
+    "reverse",
+    "struct",
+    "throw",
+    "true",
+    "try",
+    "type",
+    "val",
+    "while",
+    "yield",
+];
+
+#[derive(Clone, Debug)]
+pub struct ImportEntry {
+    pub path: String,
+    pub path_range: TextRange,
+    // empty for `import a.b.c;`, which binds the module itself
+    pub names: Vec<(String, TextRange)>,
+    pub star: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompletionKind {
+    Decl(DeclKind),
+    Module,
+    Keyword,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+    pub detail: Option<String>,
+}
+
+impl Completion {
+    fn from_decl(decl: &Decl) -> Self {
+        Self {
+            label: decl.name.clone(),
+            kind: CompletionKind::Decl(decl.kind),
+            detail: Some(decl.signature.clone()),
+        }
+    }
+
+    fn named(label: &str, kind: CompletionKind) -> Self {
+        Self {
+            label: label.to_owned(),
+            kind,
+            detail: None,
+        }
+    }
+}
+
+// a definition in some other file, as found through an import
+pub struct ExternalDef {
+    pub path: PathBuf,
+    pub doc: Arc<DocumentState>,
+    pub range: TextRange,
+}
+
 #[derive(Clone)]
 pub struct DocumentState {
     text: Arc<String>,
@@ -13,6 +103,7 @@ pub struct DocumentState {
     line_index: Arc<LineIndex>,
     parse: Arc<Parse>,
     defs: HashMap<String, Vec<DefEntry>>,
+    imports: Vec<ImportEntry>,
 }
 
 impl DocumentState {
@@ -21,12 +112,14 @@ impl DocumentState {
         let parse = parser::parse(&text);
         let syntax = parse.syntax();
         let defs = build_index(&syntax);
+        let imports = build_imports(&syntax);
         Self {
             text_size: TextSize::of(&text),
             text: Arc::new(text),
             parse: Arc::new(parse),
             line_index: Arc::new(line_index),
             defs,
+            imports,
         }
     }
 
@@ -40,14 +133,13 @@ impl DocumentState {
 
         self.parse = Arc::new(parse);
         self.defs = build_index(&syntax);
+        self.imports = build_imports(&syntax);
     }
 
     pub fn token_at_line_col(&self, line: u32, col: u32) -> Option<SyntaxToken> {
         let line_col = line_index::LineCol { line, col };
         let offset = self.line_index.offset(line_col)?;
 
-        use crate::lexer::SyntaxKind as K;
-
         if offset > self.text_size {
             return None;
         }
@@ -82,36 +174,420 @@ impl DocumentState {
         self.line_index.offset(lc)
     }
 
+    // the document in the canonical layout, if it parses and is not already
+    // formatted that way
+    pub fn formatted(&self) -> Option<String> {
+        let src = SourceBuffer::stdin(&self.text);
+        format_source(&src).ok().filter(|f| *f != *self.text)
+    }
+
+    pub fn imports(&self) -> &[ImportEntry] {
+        &self.imports
+    }
+
+    fn ident_at(&self, line: u32, col: u32) -> Option<SyntaxToken> {
+        self.token_at_line_col(line, col)
+            .filter(|tok| tok.kind() == K::Identifier)
+    }
+
+    fn def_entry(&self, tok: &SyntaxToken) -> Option<&DefEntry> {
+        let at = tok.text_range().start();
+        let entries = self.defs.get(tok.text())?;
+        let mut candidates: Vec<&DefEntry> = entries
+            .iter()
+            .filter(|e| e.scope_range.contains(at) && (e.hoisted || e.decl_start <= at))
+            .collect();
+
+        if candidates.is_empty() {
+            candidates = entries.iter().filter(|e| e.hoisted).collect();
+        }
+
+        candidates.into_iter().min_by(|a, b| {
+            use std::cmp::Ordering;
+            let len_ord = a.scope_range.len().cmp(&b.scope_range.len());
+            if len_ord != Ordering::Equal {
+                return len_ord;
+            }
+            // Prefer later declaration start (descending)
+            b.decl_start.cmp(&a.decl_start)
+        })
+    }
+
     pub fn def(&self, line: u32, col: u32) -> Option<TextRange> {
-        let tok = self.token_at_line_col(line, col)?;
-        if tok.kind() == crate::lexer::SyntaxKind::Identifier {
-            let name = tok.text();
-            let at = tok.text_range().start();
-            let entries = self.defs.get(name)?;
-            let mut candidates: Vec<&DefEntry> = entries
+        let tok = self.ident_at(line, col)?;
+        self.def_entry(&tok).map(|e| e.def_range)
+    }
+
+    fn decl_at_range(&self, def_range: TextRange) -> Option<Decl> {
+        let root = self.parse.syntax();
+        let name = match root.token_at_offset(def_range.start()) {
+            rowan::TokenAtOffset::Single(tok) => tok,
+            rowan::TokenAtOffset::Between(_, right) => right,
+            rowan::TokenAtOffset::None => return None,
+        };
+        symbols::decl_for_name(&name.parent()?, &name)
+    }
+
+    pub fn outline(&self) -> Vec<Decl> {
+        symbols::outline(&self.parse.syntax())
+    }
+
+    pub fn decl_named(&self, name: &str) -> Option<Decl> {
+        self.outline().into_iter().find(|d| d.name == name)
+    }
+
+    // the members of the type called name, including the ones added to it
+    // by extensions in this file
+    fn members_of_type(&self, name: &str) -> Vec<Decl> {
+        self.outline()
+            .into_iter()
+            .filter(|d| {
+                d.name == name
+                    && matches!(
+                        d.kind,
+                        DeclKind::Struct | DeclKind::Enum | DeclKind::Mixin | DeclKind::Extension
+                    )
+            })
+            .flat_map(|d| d.members)
+            .collect()
+    }
+
+    fn import_of(&self, name: &str) -> Option<&ImportEntry> {
+        self.imports
+            .iter()
+            .rev()
+            .find(|i| i.names.iter().any(|(n, _)| n == name))
+    }
+
+    fn module_import_of(&self, receiver: &[String]) -> Option<&ImportEntry> {
+        let path = receiver.join(".");
+        self.imports
+            .iter()
+            .find(|i| i.names.is_empty() && !i.star && i.path == path)
+    }
+
+    fn enclosing_type(&self, tok: &SyntaxToken) -> Option<String> {
+        let ty = tok
+            .parent_ancestors()
+            .find(|n| matches!(n.kind(), K::Struct | K::Enum | K::Mixin | K::Extension))?;
+        match ty.kind() {
+            K::Extension => self
+                .outline()
                 .iter()
-                .filter(|e| e.scope_range.contains(at) && (e.hoisted || e.decl_start <= at))
-                .collect();
+                .flat_map(|d| std::iter::once(d).chain(d.members.iter()))
+                .find(|d| d.full_range == ty.text_range())
+                .map(|d| d.name.clone()),
+            _ => symbols::declared_names(&ty)
+                .first()
+                .map(|t| t.text().to_owned()),
+        }
+    }
 
-            if candidates.is_empty() {
-                candidates = entries.iter().filter(|e| e.hoisted).collect();
-            }
+    // the declarations reachable as receiver.<member>
+    fn receiver_members(
+        &self,
+        anchor: &SyntaxToken,
+        receiver: &[String],
+        modules: &mut ModuleLoader,
+        importer: Option<&Path>,
+    ) -> Option<Vec<Decl>> {
+        if let Some(import) = self.module_import_of(receiver) {
+            let (_, module) = modules.load(&import.path, importer)?;
+            return Some(module.outline());
+        }
 
-            candidates
-                .into_iter()
-                .min_by(|a, b| {
-                    use std::cmp::Ordering;
-                    let len_ord = a.scope_range.len().cmp(&b.scope_range.len());
-                    if len_ord != Ordering::Equal {
-                        return len_ord;
-                    }
-                    // Prefer later declaration start (descending)
-                    b.decl_start.cmp(&a.decl_start)
-                })
-                .map(|e| e.def_range)
+        let [name] = receiver else {
+            return None;
+        };
+        let name = if name == "this" {
+            self.enclosing_type(anchor)?
         } else {
+            name.clone()
+        };
+
+        let mut members = self.members_of_type(&name);
+        if let Some(import) = self.import_of(&name)
+            && let Some((_, module)) = modules.load(&import.path, importer)
+        {
+            members.extend(module.members_of_type(&name));
+        }
+        if members.is_empty() {
             None
+        } else {
+            Some(members)
+        }
+    }
+
+    // the decl that an identifier refers to when it is not declared in this
+    // file, i.e. an imported name or a member of an imported type
+    fn external_decl(
+        &self,
+        tok: &SyntaxToken,
+        modules: &mut ModuleLoader,
+        importer: Option<&Path>,
+    ) -> Option<(PathBuf, Arc<DocumentState>, Decl)> {
+        let name = tok.text();
+        if let Some(receiver) = receiver_before(tok) {
+            let module_import = self.module_import_of(&receiver).cloned();
+            let type_import = match receiver.as_slice() {
+                [ty] => self.import_of(ty).cloned(),
+                _ => None,
+            };
+            if let Some(import) = module_import {
+                let (path, module) = modules.load(&import.path, importer)?;
+                let decl = module.decl_named(name)?;
+                return Some((path, module, decl));
+            }
+            if let (Some(import), [ty]) = (type_import, receiver.as_slice()) {
+                let (path, module) = modules.load(&import.path, importer)?;
+                let decl = module
+                    .members_of_type(ty)
+                    .into_iter()
+                    .find(|m| m.name == name)?;
+                return Some((path, module, decl));
+            }
+            return None;
+        }
+
+        let import = self.import_of(name)?.clone();
+        let (path, module) = modules.load(&import.path, importer)?;
+        let decl = module.decl_named(name)?;
+        Some((path, module, decl))
+    }
+
+    fn import_path_at(&self, tok: &SyntaxToken) -> Option<&ImportEntry> {
+        let at = tok.text_range().start();
+        self.imports.iter().find(|i| i.path_range.contains(at))
+    }
+
+    pub fn hover(
+        &self,
+        line: u32,
+        col: u32,
+        modules: &mut ModuleLoader,
+        importer: Option<&Path>,
+    ) -> Option<String> {
+        let tok = self.ident_at(line, col)?;
+        if let Some(import) = self.import_path_at(&tok) {
+            return Some(format!("```aria\nimport {}\n```", import.path));
+        }
+        if receiver_before(&tok).is_none()
+            && let Some(entry) = self.def_entry(&tok)
+        {
+            return self.decl_at_range(entry.def_range).map(|d| d.hover_text());
+        }
+        if let Some((_, _, decl)) = self.external_decl(&tok, modules, importer) {
+            return Some(decl.hover_text());
+        }
+        self.def_entry(&tok)
+            .and_then(|entry| self.decl_at_range(entry.def_range))
+            .map(|d| d.hover_text())
+    }
+
+    pub fn external_def(
+        &self,
+        line: u32,
+        col: u32,
+        modules: &mut ModuleLoader,
+        importer: Option<&Path>,
+    ) -> Option<ExternalDef> {
+        let tok = self.ident_at(line, col)?;
+        if let Some(import) = self.import_path_at(&tok) {
+            let (path, doc) = modules.load(&import.path, importer)?;
+            return Some(ExternalDef {
+                path,
+                doc,
+                range: TextRange::empty(0.into()),
+            });
+        }
+
+        let (path, doc, decl) = self.external_decl(&tok, modules, importer)?;
+        Some(ExternalDef {
+            path,
+            doc,
+            range: decl.name_range,
+        })
+    }
+
+    // every use of the identifier at line:col that resolves to the same
+    // definition, including the definition itself
+    pub fn references(&self, line: u32, col: u32) -> Vec<TextRange> {
+        let Some(tok) = self.ident_at(line, col) else {
+            return vec![];
+        };
+        let target = self.def_entry(&tok).map(|e| e.def_range);
+
+        self.parse
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|e| e.into_token())
+            .filter(|t| t.kind() == K::Identifier && t.text() == tok.text())
+            .filter(|t| self.def_entry(t).map(|e| e.def_range) == target)
+            .map(|t| t.text_range())
+            .collect()
+    }
+
+    // the range that a rename at line:col would change; only names that are
+    // declared in this file can be renamed
+    pub fn rename_range(&self, line: u32, col: u32) -> Option<TextRange> {
+        let tok = self.ident_at(line, col)?;
+        self.def_entry(&tok)?;
+        Some(tok.text_range())
+    }
+
+    pub fn rename(&self, line: u32, col: u32, new_name: &str) -> Option<Vec<TextRange>> {
+        self.rename_range(line, col)?;
+        if !is_identifier(new_name) {
+            return None;
         }
+        Some(self.references(line, col))
+    }
+
+    pub fn completions(
+        &self,
+        line: u32,
+        col: u32,
+        modules: &mut ModuleLoader,
+        importer: Option<&Path>,
+    ) -> Vec<Completion> {
+        let Some(offset) = self.offset_at_line_col(line, col) else {
+            return vec![];
+        };
+        let root = self.parse.syntax();
+        let Some(tok) = root.token_at_offset(offset).left_biased() else {
+            return self.scope_completions(offset, modules, importer);
+        };
+
+        // the token just before whatever is being typed
+        let anchor = if tok.kind() == K::Identifier || symbols::is_trivia(tok.kind()) {
+            prev_significant(&tok)
+        } else {
+            Some(tok.clone())
+        };
+
+        let in_import = anchor
+            .as_ref()
+            .filter(|a| a.kind() != K::Semicolon)
+            .and_then(|a| a.parent_ancestors().find(|n| n.kind() == K::StmtImport));
+        if let Some(import) = in_import {
+            return self.import_completions(&import, offset, modules, importer);
+        }
+
+        match anchor {
+            Some(dot) if dot.kind() == K::Dot => {
+                let receiver = receiver_chain(&dot);
+                if let Some(members) = self.receiver_members(&dot, &receiver, modules, importer) {
+                    return dedup(members.iter().map(Completion::from_decl).collect());
+                }
+
+                // a prefix of a module path, as in aria.core.<...>
+                if let Some(import) = self.imports.iter().find(|i| {
+                    i.names.is_empty() && i.path.starts_with(&format!("{}.", receiver.join(".")))
+                }) && !receiver.is_empty()
+                {
+                    let next = import.path.split('.').nth(receiver.len());
+                    return next
+                        .map(|n| Completion::named(n, CompletionKind::Module))
+                        .into_iter()
+                        .collect();
+                }
+
+                // without types to go by, any member of any type will do
+                let mut all = vec![];
+                for decl in self.outline() {
+                    all.extend(decl.members.iter().map(Completion::from_decl));
+                }
+                dedup(all)
+            }
+            _ => self.scope_completions(offset, modules, importer),
+        }
+    }
+
+    fn import_completions(
+        &self,
+        import: &SyntaxNode,
+        offset: TextSize,
+        modules: &mut ModuleLoader,
+        importer: Option<&Path>,
+    ) -> Vec<Completion> {
+        let path_node = import.children().find(|n| n.kind() == K::ImportPath);
+        let from_kwd = import
+            .descendants_with_tokens()
+            .filter_map(|e| e.into_token())
+            .find(|t| t.kind() == K::FromKwd);
+
+        // names being picked out of a module, as in `import <...> from a.b;`
+        if let Some(from_kwd) = from_kwd
+            && offset <= from_kwd.text_range().start()
+        {
+            let Some(path) = path_node.map(|n| path_text(&n, None)) else {
+                return vec![];
+            };
+            return match modules.load(&path, importer) {
+                Some((_, module)) => module.outline().iter().map(Completion::from_decl).collect(),
+                None => vec![],
+            };
+        }
+
+        let typed = path_node
+            .map(|n| path_text(&n, Some(offset)))
+            .unwrap_or_default();
+        let prefix = typed.rsplit_once('.').map(|(p, _)| p).unwrap_or("");
+        modules
+            .submodules(prefix, importer)
+            .iter()
+            .map(|m| Completion::named(m, CompletionKind::Module))
+            .collect()
+    }
+
+    fn scope_completions(
+        &self,
+        offset: TextSize,
+        modules: &mut ModuleLoader,
+        importer: Option<&Path>,
+    ) -> Vec<Completion> {
+        let mut completions = vec![];
+        for (name, entries) in &self.defs {
+            let visible = entries.iter().find(|e| {
+                !e.member && e.scope_range.contains(offset) && (e.hoisted || e.decl_start <= offset)
+            });
+            if let Some(entry) = visible {
+                completions.push(Completion {
+                    label: name.clone(),
+                    kind: CompletionKind::Decl(entry.kind),
+                    detail: self.decl_at_range(entry.def_range).map(|d| d.signature),
+                });
+            }
+        }
+
+        for import in &self.imports {
+            if import.star {
+                if let Some((_, module)) = modules.load(&import.path, importer) {
+                    completions.extend(module.outline().iter().map(Completion::from_decl));
+                }
+            } else if import.names.is_empty() {
+                if let Some(root) = import.path.split('.').next() {
+                    completions.push(Completion::named(root, CompletionKind::Module));
+                }
+            } else {
+                let module = modules.load(&import.path, importer);
+                for (name, _) in &import.names {
+                    match module.as_ref().and_then(|(_, m)| m.decl_named(name)) {
+                        Some(decl) => completions.push(Completion::from_decl(&decl)),
+                        None => completions
+                            .push(Completion::named(name, CompletionKind::Decl(DeclKind::Val))),
+                    }
+                }
+            }
+        }
+
+        completions.extend(
+            KEYWORDS
+                .iter()
+                .map(|k| Completion::named(k, CompletionKind::Keyword)),
+        );
+        completions.sort_by(|a, b| a.label.cmp(&b.label));
+        dedup(completions)
     }
 
     pub fn parse_error_ranges(&self) -> Vec<(TextRange, String)> {
@@ -133,58 +609,150 @@ impl DocumentState {
     }
 }
 
+fn dedup(completions: Vec<Completion>) -> Vec<Completion> {
+    let mut seen = std::collections::HashSet::new();
+    completions
+        .into_iter()
+        .filter(|c| seen.insert(c.label.clone()))
+        .collect()
+}
+
+fn is_identifier(name: &str) -> bool {
+    let tokens = crate::lexer::lex(name);
+    matches!(tokens.as_slice(), [Ok((K::Identifier, _, _))])
+}
+
+fn prev_significant(tok: &SyntaxToken) -> Option<SyntaxToken> {
+    let mut prev = tok.prev_token();
+    while let Some(t) = prev {
+        if !symbols::is_trivia(t.kind()) {
+            return Some(t);
+        }
+        prev = t.prev_token();
+    }
+    None
+}
+
+// the dotted names before a `.`, i.e. [a, b] for `a.b.`
+fn receiver_chain(dot: &SyntaxToken) -> Vec<String> {
+    let mut chain = vec![];
+    let mut cur = prev_significant(dot);
+    while let Some(ident) = cur.filter(|t| t.kind() == K::Identifier) {
+        chain.push(ident.text().to_owned());
+        match prev_significant(&ident) {
+            Some(t) if t.kind() == K::Dot => cur = prev_significant(&t),
+            _ => break,
+        }
+    }
+    chain.reverse();
+    chain
+}
+
+// the receiver of a member access, if tok is the member being accessed
+fn receiver_before(tok: &SyntaxToken) -> Option<Vec<String>> {
+    let dot = prev_significant(tok).filter(|t| t.kind() == K::Dot)?;
+    let chain = receiver_chain(&dot);
+    if chain.is_empty() { None } else { Some(chain) }
+}
+
+// the dotted path in an import, up to offset if one is given
+fn path_text(path: &SyntaxNode, offset: Option<TextSize>) -> String {
+    path.descendants_with_tokens()
+        .filter_map(|e| e.into_token())
+        .filter(|t| matches!(t.kind(), K::Identifier | K::Dot))
+        .filter(|t| offset.is_none_or(|o| t.text_range().start() < o))
+        .map(|t| match offset {
+            Some(o) if t.text_range().contains_inclusive(o) => {
+                let len = o - t.text_range().start();
+                t.text()[..usize::from(len)].to_owned()
+            }
+            _ => t.text().to_owned(),
+        })
+        .collect()
+}
+
 #[derive(Clone, Copy, Debug)]
 struct DefEntry {
     def_range: TextRange,
     scope_range: TextRange,
     decl_start: TextSize,
     hoisted: bool,
+    member: bool,
+    kind: DeclKind,
 }
 
 fn build_index(root: &SyntaxNode) -> HashMap<String, Vec<DefEntry>> {
-    use crate::lexer::SyntaxKind as K;
     let mut defs: HashMap<String, Vec<DefEntry>> = HashMap::new();
 
     for node in root.descendants() {
-        match node.kind() {
-            K::StmtVal | K::Param | K::Func => {
-                if let Some(tok) = node
-                    .children_with_tokens()
-                    .filter_map(|e| e.into_token())
-                    .find(|t| t.kind() == K::Identifier)
-                {
-                    let name = tok.text().to_string();
-
-                    let mut scope_owner = node.parent();
-                    while let Some(parent) = scope_owner.clone() {
-                        match parent.kind() {
-                            K::Func | K::Block => break,
-                            _ => scope_owner = parent.parent(),
-                        }
-                    }
-
-                    let (scope_range, hoisted) = match scope_owner.as_ref() {
-                        Some(owner) => (owner.text_range(), false),
-                        None => (root.text_range(), true),
-                    };
-
-                    let entry = DefEntry {
-                        def_range: tok.text_range(),
-                        scope_range,
-                        decl_start: tok.text_range().start(),
-                        hoisted,
-                    };
+        let Some(kind) = symbols::decl_kind(&node) else {
+            continue;
+        };
 
-                    defs.entry(name).or_default().push(entry);
-                }
+        // a loop or catch variable is only visible in its own statement
+        let mut scope_owner = if matches!(node.kind(), K::StmtFor | K::CatchClause) {
+            Some(node.clone())
+        } else {
+            node.parent()
+        };
+        while let Some(parent) = scope_owner.clone() {
+            match parent.kind() {
+                K::Func | K::Lambda | K::Block | K::StmtFor | K::CatchClause => break,
+                _ => scope_owner = parent.parent(),
             }
-            _ => {}
+        }
+
+        let (scope_range, hoisted) = match scope_owner.as_ref() {
+            Some(owner) => (owner.text_range(), false),
+            None => (root.text_range(), true),
+        };
+
+        for tok in symbols::declared_names(&node) {
+            let entry = DefEntry {
+                def_range: tok.text_range(),
+                scope_range,
+                decl_start: tok.text_range().start(),
+                hoisted,
+                member: symbols::is_member(&node),
+                kind,
+            };
+
+            defs.entry(tok.text().to_string()).or_default().push(entry);
         }
     }
 
     defs
 }
 
+fn build_imports(root: &SyntaxNode) -> Vec<ImportEntry> {
+    let mut imports = vec![];
+    for node in root.children().filter(|n| n.kind() == K::StmtImport) {
+        let Some(path) = node.children().find(|n| n.kind() == K::ImportPath) else {
+            continue;
+        };
+        let names = node
+            .children()
+            .find(|n| n.kind() == K::IdentList)
+            .map(|list| {
+                list.children_with_tokens()
+                    .filter_map(|e| e.into_token())
+                    .filter(|t| t.kind() == K::Identifier)
+                    .map(|t| (t.text().to_owned(), t.text_range()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let star = node.children_with_tokens().any(|e| e.kind() == K::Star);
+
+        imports.push(ImportEntry {
+            path: path_text(&path, None),
+            path_range: path.text_range(),
+            names,
+            star,
+        });
+    }
+    imports
+}
+
 #[cfg(test)]
 mod tests {
     use crate::lexer::SyntaxKind;
@@ -218,6 +786,21 @@ mod tests {
         assert_eq!(func_tok.kind(), SyntaxKind::FuncKwd);
     }
 
+    #[test]
+    fn formatted_normalizes_layout() {
+        let doc = DocumentState::new("func foo(a,b){\nreturn a+b;\n}\n".to_string());
+        assert_eq!(
+            doc.formatted().as_deref(),
+            Some("func foo(a, b) {\n    return a + b;\n}\n")
+        );
+
+        let doc = DocumentState::new("func foo(a, b) {\n    return a + b;\n}\n".to_string());
+        assert!(doc.formatted().is_none());
+
+        let doc = DocumentState::new("func foo(a, b {".to_string());
+        assert!(doc.formatted().is_none());
+    }
+
     #[test]
     fn parse_errors_include_expected_tokens() {
         let text = "val x".to_string();
@@ -231,4 +814,198 @@ mod tests {
             errs
         );
     }
+
+    fn module_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("aria-lsp-{name}-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("pkg")).expect("create module dir");
+        std::fs::write(
+            dir.join("pkg").join("shapes.aria"),
+            "# a point in the plane\nstruct Point {\n    type func new(x, y) = alloc(This);\n    func norm() = 0;\n}\n",
+        )
+        .expect("write module");
+        dir
+    }
+
+    fn labels(completions: &[Completion]) -> Vec<&str> {
+        completions.iter().map(|c| c.label.as_str()).collect()
+    }
+
+    #[test]
+    fn hover_shows_signature_and_doc_comment() {
+        let text = "# adds two numbers\n# and returns the sum\nfunc add(a, b: Int) {\n    return a + b;\n}\nval z = add(1, 2);\n";
+        let doc = DocumentState::new(text.to_string());
+        let mut modules = ModuleLoader::with_search_paths(vec![]);
+        let hover = doc.hover(5, 9, &mut modules, None).expect("hover on add");
+        assert_eq!(
+            hover,
+            "```aria\nfunc add(a, b: Int)\n```\n\nadds two numbers\nand returns the sum"
+        );
+
+        let hover = doc.hover(3, 11, &mut modules, None).expect("hover on a");
+        assert_eq!(hover, "```aria\na\n```");
+    }
+
+    #[test]
+    fn trailing_comment_is_not_documentation() {
+        let text = "val x = 1; # not about f\nfunc f() {}\n";
+        let doc = DocumentState::new(text.to_string());
+        let outline = doc.outline();
+        assert_eq!(outline[1].name, "f");
+        assert_eq!(outline[1].doc, None);
+    }
+
+    #[test]
+    fn outline_nests_members() {
+        let text = "struct Pair {\n    type val zero = 0;\n    func first() = 1;\n    operator +(rhs) = 2;\n}\nenum Color {\n    case Red,\n    case Green,\n}\nextension Pair {\n    func second() = 2;\n}\n";
+        let doc = DocumentState::new(text.to_string());
+        let outline = doc.outline();
+        let summary = outline
+            .iter()
+            .map(|d| {
+                let members = d
+                    .members
+                    .iter()
+                    .map(|m| m.name.as_str())
+                    .collect::<Vec<_>>();
+                (d.name.as_str(), d.kind, members)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "Pair",
+                    DeclKind::Struct,
+                    vec!["zero", "first", "operator +"]
+                ),
+                ("Color", DeclKind::Enum, vec!["Red", "Green"]),
+                ("Pair", DeclKind::Extension, vec!["second"]),
+            ]
+        );
+        assert_eq!(outline[0].members[0].signature, "type val zero");
+        assert_eq!(outline[0].members[1].kind, DeclKind::Method);
+    }
+
+    #[test]
+    fn references_respect_shadowing() {
+        let text = "val x = 1;\nfunc f() {\n    val x = 2;\n    return x;\n}\nval y = x;\n";
+        let doc = DocumentState::new(text.to_string());
+
+        let outer = doc.references(0, 4);
+        let starts = outer
+            .iter()
+            .map(|r| doc.line_col(r.start()).line)
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![0, 5]);
+
+        let inner = doc.references(3, 11);
+        let starts = inner
+            .iter()
+            .map(|r| doc.line_col(r.start()).line)
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![2, 3]);
+    }
+
+    #[test]
+    fn rename_needs_a_local_definition_and_a_valid_name() {
+        let text = "func f(a) {\n    return a + println;\n}\n";
+        let doc = DocumentState::new(text.to_string());
+        assert_eq!(doc.rename(1, 11, "b").map(|r| r.len()), Some(2));
+        assert!(doc.rename(1, 11, "not valid").is_none());
+        assert!(doc.rename(1, 11, "val").is_none());
+        assert!(doc.rename(1, 15, "say").is_none());
+    }
+
+    #[test]
+    fn loop_variables_are_scoped_to_their_loop() {
+        let text = "func f(l) {\n    for item in l {\n        println(item);\n    }\n    val item = 1;\n}\n";
+        let doc = DocumentState::new(text.to_string());
+        let in_loop = doc.def(2, 17).expect("item in loop");
+        assert_eq!(doc.line_col(in_loop.start()).line, 1);
+    }
+
+    #[test]
+    fn destructured_names_are_definitions() {
+        let text = "func f(m) {\n    val [head, ...tail] = [1, 2, 3];\n    val {.x, .y = [_, z]} = m;\n    for [key, value] in m {\n        println(key, value);\n    }\n    match m {\n        Some(n) if n > 0 => { println(n); },\n    }\n    return head + x + z + tail.len();\n}\n";
+        let doc = DocumentState::new(text.to_string());
+        assert!(doc.parse_error_ranges().is_empty());
+
+        for (col, def) in [(11, (1, 9)), (18, (2, 10)), (22, (2, 22)), (26, (1, 18))] {
+            let found = doc.def(9, col).expect("destructured name");
+            let found = doc.line_col(found.start());
+            assert_eq!((found.line, found.col), def);
+        }
+        let key = doc.def(4, 17).expect("key in loop");
+        assert_eq!(doc.line_col(key.start()).line, 3);
+    }
+
+    #[test]
+    fn completes_names_in_scope() {
+        let text = "val top = 1;\nfunc f(param) {\n    val local = 2;\n    \n}\nfunc g() {}\n";
+        let doc = DocumentState::new(text.to_string());
+        let mut modules = ModuleLoader::with_search_paths(vec![]);
+        let completions = doc.completions(3, 4, &mut modules, None);
+        let names = labels(&completions);
+        for expected in ["top", "f", "g", "param", "local", "while"] {
+            assert!(names.contains(&expected), "missing {expected} in {names:?}");
+        }
+
+        let completions = doc.completions(5, 10, &mut modules, None);
+        let names = labels(&completions);
+        assert!(!names.contains(&"local"), "local leaked into {names:?}");
+    }
+
+    #[test]
+    fn completes_members_of_types() {
+        let text = "struct Stack {\n    type func new() = 1;\n    func push(x) {\n        this.\n    }\n}\nval s = Stack.\n";
+        let doc = DocumentState::new(text.to_string());
+        let mut modules = ModuleLoader::with_search_paths(vec![]);
+
+        let completions = doc.completions(3, 13, &mut modules, None);
+        assert_eq!(labels(&completions), vec!["new", "push"]);
+
+        let completions = doc.completions(6, 14, &mut modules, None);
+        assert_eq!(labels(&completions), vec!["new", "push"]);
+    }
+
+    #[test]
+    fn resolves_imported_names() {
+        let dir = module_dir("imports");
+        let mut modules = ModuleLoader::with_search_paths(vec![dir.clone()]);
+        let text = "import Point from pkg.shapes;\nval p = Point.new(1, 2);\nval q = Point.\n";
+        let doc = DocumentState::new(text.to_string());
+
+        let hover = doc.hover(1, 9, &mut modules, None).expect("hover on Point");
+        assert_eq!(hover, "```aria\nstruct Point\n```\n\na point in the plane");
+
+        let hover = doc.hover(1, 15, &mut modules, None).expect("hover on new");
+        assert_eq!(hover, "```aria\ntype func new(x, y)\n```");
+
+        let def = doc
+            .external_def(1, 15, &mut modules, None)
+            .expect("def of new");
+        assert_eq!(def.path, dir.join("pkg").join("shapes.aria"));
+        assert_eq!(def.doc.line_col(def.range.start()).line, 2);
+
+        let completions = doc.completions(2, 14, &mut modules, None);
+        assert_eq!(labels(&completions), vec!["new", "norm"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn completes_import_paths_and_names() {
+        let dir = module_dir("import-paths");
+        let mut modules = ModuleLoader::with_search_paths(vec![dir.clone()]);
+
+        let doc = DocumentState::new("import pkg.sh".to_string());
+        let completions = doc.completions(0, 13, &mut modules, None);
+        assert_eq!(labels(&completions), vec!["shapes"]);
+
+        let doc = DocumentState::new("import  from pkg.shapes;".to_string());
+        let completions = doc.completions(0, 7, &mut modules, None);
+        assert_eq!(labels(&completions), vec!["Point"]);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
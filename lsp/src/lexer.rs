@@ -22,6 +22,8 @@ pub enum SyntaxKind {
     EnumKwd,
     #[token("extension")]
     ExtensionKwd,
+    #[token("finally")]
+    FinallyKwd,
     #[token("flag")]
     FlagKwd,
     #[token("for")]
@@ -64,6 +66,8 @@ pub enum SyntaxKind {
     ValKwd,
     #[token("while")]
     WhileKwd,
+    #[token("yield")]
+    YieldKwd,
     #[token("and")]
     AndKwd,
 
@@ -175,6 +179,14 @@ pub enum SyntaxKind {
 
     #[regex(r#""([^"\\]|\\.)*""#)]
     #[regex(r#"'([^'\\]|\\.)*'"#)]
+    #[token("f\"", interpolated_string)]
+    #[token("f'", interpolated_string)]
+    #[token("\"\"\"", triple_quoted_string)]
+    #[token("r\"\"\"", triple_quoted_string)]
+    #[regex(r#"r"[^"]*""#)]
+    #[regex(r#"r'[^']*'"#)]
+    #[regex(r#"b"([^"\\]|\\.)*""#)]
+    #[regex(r#"b'([^'\\]|\\.)*'"#)]
     StringLiteral,
 
     #[regex(
@@ -234,17 +246,77 @@ pub enum SyntaxKind {
     Operator,
     Guard,
     TryBlock,
+    CatchClause,
     MatchRule,
     MatchPattern,
+    PatternBinding,
     IdentList,
     QualifiedIdent,
     ImportPath,
     ArgList,
     ListLiteral,
+    MapLiteral,
     ModuleFlag,
     Eof,
 }
 
+// the length of the rest of an f-string, after its opening quote: it ends at
+// the first quote outside of its {values}, which may contain strings of their own
+fn interpolated_string_len(rest: &str, quote: char) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut chars = rest.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            _ if c == quote && depth == 0 => return Some(idx + c.len_utf8()),
+            '{' if depth == 0 && chars.next_if(|&(_, n)| n == '{').is_some() => {}
+            '}' if depth == 0 && chars.next_if(|&(_, n)| n == '}').is_some() => {}
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '"' | '\'' if depth > 0 => {
+                let prefixed = rest[..idx].ends_with('f');
+                let len = if prefixed {
+                    interpolated_string_len(&rest[idx + 1..], c)?
+                } else {
+                    let end = rest[idx + 1..].find(c)?;
+                    end + 1
+                };
+                while chars.next_if(|&(n, _)| n <= idx + len).is_some() {}
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn interpolated_string(lex: &mut logos::Lexer<SyntaxKind>) -> bool {
+    let quote = if lex.slice().ends_with('"') {
+        '"'
+    } else {
+        '\''
+    };
+    match interpolated_string_len(lex.remainder(), quote) {
+        Some(len) => {
+            lex.bump(len);
+            true
+        }
+        None => false,
+    }
+}
+
+// a triple quoted string ends at the next """, as escapes cannot make one
+fn triple_quoted_string(lex: &mut logos::Lexer<SyntaxKind>) -> bool {
+    match lex.remainder().find("\"\"\"") {
+        Some(len) => {
+            lex.bump(len + 3);
+            true
+        }
+        None => false,
+    }
+}
+
 pub fn lex(s: &str) -> Vec<Result<(SyntaxKind, &str, logos::Span), LexError>> {
     let mut lexer = SyntaxKind::lexer(s);
     let mut tokens = Vec::new();
@@ -320,6 +392,31 @@ mod tests {
         assert_eq!(tokens[6], SyntaxKind::StringLiteral);
     }
 
+    #[test]
+    fn test_interpolated_strings() {
+        let tokens = non_trivia_tokens(r#"f"a {x + "}"} {{b}}" f'{f"{y:>3}"}' fx"#);
+        assert_eq!(
+            tokens,
+            [
+                SyntaxKind::StringLiteral,
+                SyntaxKind::StringLiteral,
+                SyntaxKind::Identifier
+            ]
+        );
+    }
+
+    #[test]
+    fn test_triple_quoted_raw_and_byte_strings() {
+        let tokens = non_trivia_tokens(
+            r#""""
+  a "b"
+""" r"\d+" r'\' r"""x""" b"\x00\"" b'z' r"#,
+        );
+        assert_eq!(tokens.len(), 7);
+        assert!(tokens[..6].iter().all(|t| *t == SyntaxKind::StringLiteral));
+        assert_eq!(tokens[6], SyntaxKind::Identifier);
+    }
+
     #[test]
     fn test_operators() {
         let tokens = non_trivia_tokens("+ - * / % == != <= >= << >> && ||");
@@ -378,8 +475,8 @@ mod tests {
             if path.extension().and_then(|s| s.to_str()) == Some("aria") {
                 let filename = path.file_name().unwrap().to_str().unwrap();
 
-                let content =
-                    fs::read_to_string(&path).expect(&format!("Failed to read file: {}", filename));
+                let content = fs::read_to_string(&path)
+                    .unwrap_or_else(|_| panic!("Failed to read file: {}", filename));
 
                 let tokens = lex(&content);
 
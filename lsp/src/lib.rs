@@ -1,4 +1,6 @@
 // SPDX-License-Identifier: Apache-2.0
 pub mod document;
 pub mod lexer;
+pub mod modules;
 pub mod parser;
+pub mod symbols;
@@ -1,7 +1,9 @@
 // SPDX-License-Identifier: Apache-2.0
 use line_index::{LineCol, LineIndex};
-use lsp::document::DocumentState;
-use rowan::TextRange;
+use lsp::document::{CompletionKind, DocumentState};
+use lsp::modules::ModuleLoader;
+use lsp::symbols::{Decl, DeclKind};
+use rowan::{TextRange, TextSize};
 use std::collections::HashMap;
 use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
 use tower_lsp::jsonrpc::Result;
@@ -33,6 +35,7 @@ struct Backend {
     logger: Logger,
     client: Client,
     documents: parking_lot::Mutex<HashMap<Url, DocumentState>>,
+    modules: parking_lot::Mutex<ModuleLoader>,
 }
 
 impl Backend {
@@ -53,6 +56,60 @@ fn to_lsp_range(doc: &DocumentState, range: TextRange) -> Range {
     )
 }
 
+fn to_symbol_kind(kind: DeclKind, is_member: bool) -> SymbolKind {
+    match kind {
+        DeclKind::Func => SymbolKind::FUNCTION,
+        DeclKind::Method => SymbolKind::METHOD,
+        DeclKind::Operator => SymbolKind::OPERATOR,
+        DeclKind::Val if is_member => SymbolKind::FIELD,
+        DeclKind::Val | DeclKind::Param => SymbolKind::VARIABLE,
+        DeclKind::Struct => SymbolKind::STRUCT,
+        DeclKind::Enum => SymbolKind::ENUM,
+        DeclKind::EnumCase => SymbolKind::ENUM_MEMBER,
+        DeclKind::Mixin => SymbolKind::INTERFACE,
+        DeclKind::Extension => SymbolKind::CLASS,
+    }
+}
+
+fn to_completion_kind(kind: CompletionKind) -> CompletionItemKind {
+    match kind {
+        CompletionKind::Decl(DeclKind::Func) => CompletionItemKind::FUNCTION,
+        CompletionKind::Decl(DeclKind::Method) => CompletionItemKind::METHOD,
+        CompletionKind::Decl(DeclKind::Operator) => CompletionItemKind::OPERATOR,
+        CompletionKind::Decl(DeclKind::Val | DeclKind::Param) => CompletionItemKind::VARIABLE,
+        CompletionKind::Decl(DeclKind::Struct) => CompletionItemKind::STRUCT,
+        CompletionKind::Decl(DeclKind::Enum) => CompletionItemKind::ENUM,
+        CompletionKind::Decl(DeclKind::EnumCase) => CompletionItemKind::ENUM_MEMBER,
+        CompletionKind::Decl(DeclKind::Mixin) => CompletionItemKind::INTERFACE,
+        CompletionKind::Decl(DeclKind::Extension) => CompletionItemKind::CLASS,
+        CompletionKind::Module => CompletionItemKind::MODULE,
+        CompletionKind::Keyword => CompletionItemKind::KEYWORD,
+    }
+}
+
+#[allow(deprecated)]
+fn to_document_symbol(doc: &DocumentState, decl: &Decl, is_member: bool) -> DocumentSymbol {
+    let children = decl
+        .members
+        .iter()
+        .map(|m| to_document_symbol(doc, m, true))
+        .collect::<Vec<_>>();
+    DocumentSymbol {
+        name: decl.name.clone(),
+        detail: Some(decl.signature.clone()),
+        kind: to_symbol_kind(decl.kind, is_member),
+        tags: None,
+        deprecated: None,
+        range: to_lsp_range(doc, decl.full_range),
+        selection_range: to_lsp_range(doc, decl.name_range),
+        children: if children.is_empty() {
+            None
+        } else {
+            Some(children)
+        },
+    }
+}
+
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
     async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
@@ -64,6 +121,18 @@ impl LanguageServer for Backend {
                     TextDocumentSyncKind::INCREMENTAL,
                 )),
                 definition_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![".".to_string()]),
+                    ..CompletionOptions::default()
+                }),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                })),
                 ..ServerCapabilities::default()
             },
         })
@@ -218,10 +287,164 @@ impl LanguageServer for Backend {
             return Ok(Some(GotoDefinitionResponse::Scalar(loc)));
         }
 
+        let importer = uri.to_file_path().ok();
+        let mut modules = self.modules.lock();
+        if let Some(def) = doc.external_def(
+            position.line,
+            position.character,
+            &mut modules,
+            importer.as_deref(),
+        ) && let Ok(def_uri) = Url::from_file_path(&def.path)
+        {
+            let loc = Location::new(def_uri, to_lsp_range(&def.doc, def.range));
+            self.info(format!("found an imported definition at {loc:?}"));
+            return Ok(Some(GotoDefinitionResponse::Scalar(loc)));
+        }
+
         self.info("no definitions found".to_string());
 
         Ok(None)
     }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let docs = self.documents.lock();
+        let Some(doc) = docs.get(&uri) else {
+            return Ok(None);
+        };
+
+        let importer = uri.to_file_path().ok();
+        let mut modules = self.modules.lock();
+        let hover = doc
+            .hover(
+                position.line,
+                position.character,
+                &mut modules,
+                importer.as_deref(),
+            )
+            .map(|text| Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: text,
+                }),
+                range: None,
+            });
+        Ok(hover)
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        let docs = self.documents.lock();
+        let Some(doc) = docs.get(&uri) else {
+            return Ok(None);
+        };
+
+        let importer = uri.to_file_path().ok();
+        let mut modules = self.modules.lock();
+        let items = doc
+            .completions(
+                position.line,
+                position.character,
+                &mut modules,
+                importer.as_deref(),
+            )
+            .into_iter()
+            .map(|c| CompletionItem {
+                label: c.label,
+                kind: Some(to_completion_kind(c.kind)),
+                detail: c.detail,
+                ..CompletionItem::default()
+            })
+            .collect();
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let docs = self.documents.lock();
+        let Some(doc) = docs.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let symbols = doc
+            .outline()
+            .iter()
+            .map(|decl| to_document_symbol(doc, decl, false))
+            .collect();
+        Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        let docs = self.documents.lock();
+        let Some(doc) = docs.get(&uri) else {
+            return Ok(None);
+        };
+
+        let locations = doc
+            .references(position.line, position.character)
+            .into_iter()
+            .map(|range| Location::new(uri.clone(), to_lsp_range(doc, range)))
+            .collect();
+        Ok(Some(locations))
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let docs = self.documents.lock();
+        let Some(doc) = docs.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        Ok(doc
+            .rename_range(params.position.line, params.position.character)
+            .map(|range| PrepareRenameResponse::Range(to_lsp_range(doc, range))))
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        let docs = self.documents.lock();
+        let Some(doc) = docs.get(&uri) else {
+            return Ok(None);
+        };
+
+        let Some(ranges) = doc.rename(position.line, position.character, &params.new_name) else {
+            return Ok(None);
+        };
+        let edits = ranges
+            .into_iter()
+            .map(|range| TextEdit::new(to_lsp_range(doc, range), params.new_name.clone()))
+            .collect();
+        Ok(Some(WorkspaceEdit::new(HashMap::from([(uri, edits)]))))
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let docs = self.documents.lock();
+        let Some(doc) = docs.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+
+        let Some(formatted) = doc.formatted() else {
+            return Ok(Some(vec![]));
+        };
+        let whole = TextRange::up_to(TextSize::of(doc.text().as_str()));
+        Ok(Some(vec![TextEdit::new(
+            to_lsp_range(doc, whole),
+            formatted,
+        )]))
+    }
 }
 
 #[tokio::main]
@@ -235,6 +458,7 @@ async fn main() {
             logger,
             client: client.clone(),
             documents: parking_lot::Mutex::new(HashMap::new()),
+            modules: parking_lot::Mutex::new(ModuleLoader::default()),
         }
     })
     .finish();
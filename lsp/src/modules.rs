@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use haxby_vm::vm::VirtualMachine;
+
+use crate::document::DocumentState;
+
+// finds and parses the modules named by import statements; a parsed module
+// is reused for as long as the file on disk does not change
+pub struct ModuleLoader {
+    search_paths: Vec<PathBuf>,
+    cache: HashMap<PathBuf, (Option<SystemTime>, Arc<DocumentState>)>,
+}
+
+impl Default for ModuleLoader {
+    fn default() -> Self {
+        Self::with_search_paths(VirtualMachine::get_aria_library_paths().clone())
+    }
+}
+
+impl ModuleLoader {
+    pub fn with_search_paths(search_paths: Vec<PathBuf>) -> Self {
+        Self {
+            search_paths,
+            cache: HashMap::new(),
+        }
+    }
+
+    // widget.a.b imports are relative to the closest directory above the
+    // importing file that has a widget.json, like the compiler does
+    fn roots_for(&self, ipath: &str, importer: Option<&Path>) -> (Vec<PathBuf>, String) {
+        let widget_path = if ipath == "widget" {
+            Some("")
+        } else {
+            ipath.strip_prefix("widget.")
+        };
+        if let Some(rest) = widget_path {
+            let root = importer.and_then(|importer| {
+                importer
+                    .ancestors()
+                    .find(|dir| dir.join("widget.json").exists())
+                    .map(Path::to_path_buf)
+            });
+            (root.into_iter().collect(), rest.to_owned())
+        } else {
+            (self.search_paths.clone(), ipath.to_owned())
+        }
+    }
+
+    pub fn resolve(&self, ipath: &str, importer: Option<&Path>) -> Option<PathBuf> {
+        let (roots, ipath) = self.roots_for(ipath, importer);
+        let relative = format!("{}.aria", ipath.replace('.', "/"));
+        roots
+            .iter()
+            .map(|root| root.join(&relative))
+            .find(|path| path.is_file())
+    }
+
+    pub fn load(
+        &mut self,
+        ipath: &str,
+        importer: Option<&Path>,
+    ) -> Option<(PathBuf, Arc<DocumentState>)> {
+        let path = self.resolve(ipath, importer)?;
+        let mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if let Some((cached_mtime, doc)) = self.cache.get(&path)
+            && *cached_mtime == mtime
+        {
+            return Some((path, doc.clone()));
+        }
+
+        let text = std::fs::read_to_string(&path).ok()?;
+        let doc = Arc::new(DocumentState::new(text));
+        self.cache.insert(path.clone(), (mtime, doc.clone()));
+        Some((path, doc))
+    }
+
+    // the modules and packages that can follow prefix in an import path
+    pub fn submodules(&self, prefix: &str, importer: Option<&Path>) -> Vec<String> {
+        let (roots, prefix) = self.roots_for(prefix, importer);
+        let relative = prefix
+            .split('.')
+            .filter(|p| !p.is_empty())
+            .collect::<PathBuf>();
+
+        let mut names = vec![];
+        for root in roots {
+            let Ok(entries) = std::fs::read_dir(root.join(&relative)) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let name = if path.is_dir() {
+                    path.file_name()
+                } else if path.extension().is_some_and(|ext| ext == "aria") {
+                    path.file_stem()
+                } else {
+                    None
+                };
+                if let Some(name) = name.and_then(|n| n.to_str()) {
+                    names.push(name.to_owned());
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+}
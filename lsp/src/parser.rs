@@ -463,7 +463,8 @@ pub fn parse(text: &str) -> Parse {
                 MatchKwd => self.stmt_match(),
                 WhileKwd => self.stmt_while(),
                 ForKwd => self.stmt_for(),
-                ThrowKwd => self.stmt_kwd_with_expr(ThrowKwd),
+                ThrowKwd => self.stmt_throw(),
+                YieldKwd => self.stmt_kwd_with_expr(YieldKwd),
                 ReturnKwd => self.stmt_return(),
                 LeftBrace => self.block(),
                 TryKwd => self.try_catch(),
@@ -504,7 +505,11 @@ pub fn parse(text: &str) -> Parse {
             let m = self.open();
 
             self.expect(ForKwd);
-            self.expect(Identifier);
+            if self.at(Identifier) && self.nth(1) == InKwd {
+                self.expect(Identifier);
+            } else {
+                self.match_pattern();
+            }
             self.expect(InKwd);
             let _ = self.expr();
             self.block();
@@ -566,6 +571,13 @@ pub fn parse(text: &str) -> Parse {
                 self.match_pattern();
             }
 
+            if self.at(IfKwd) {
+                let g = self.open();
+                self.expect(IfKwd);
+                let _ = self.expr();
+                self.close(g, Guard);
+            }
+
             self.expect(Arrow); // "=>"
             self.block();
 
@@ -581,11 +593,7 @@ pub fn parse(text: &str) -> Parse {
                     self.expect(Identifier);
                     if self.at(LeftParen) {
                         self.expect(LeftParen);
-                        self.expect(Identifier);
-                        if self.at(Colon) {
-                            self.expect(Colon);
-                            let _ = self.expr();
-                        }
+                        self.match_pattern();
                         self.expect(RightParen);
                     }
                 }
@@ -597,12 +605,97 @@ pub fn parse(text: &str) -> Parse {
                     self.advance(); // relational operator
                     let _ = self.expr();
                 }
+                HexIntLiteral | OctIntLiteral | BinIntLiteral | DecIntLiteral | FloatLiteral
+                | StringLiteral | TrueKwd | FalseKwd => self.advance(),
+                Minus | UnaryMinus => {
+                    self.advance();
+                    self.advance(); // the number being negated
+                }
+                LeftBracket => self.pattern_list(),
+                LeftBrace => self.pattern_fields(),
+                _ if self.at(Identifier) => match self.nth(1) {
+                    // Some(x), as a shorthand for case Some(x)
+                    LeftParen => {
+                        self.expect(Identifier);
+                        self.expect(LeftParen);
+                        self.match_pattern();
+                        self.expect(RightParen);
+                    }
+                    // Point{.x, .y}, with the type of the struct in front
+                    Dot | LeftBrace => {
+                        self.qualified_ident();
+                        self.pattern_fields();
+                    }
+                    _ => self.pattern_binding(),
+                },
                 _ => self.advance_with_error(MatchPattern),
             }
 
             self.close(m, MatchPattern);
         }
 
+        // a name that a pattern binds, with an optional type: x or x: Int
+        fn pattern_binding(&mut self) {
+            let m = self.open();
+
+            self.expect(Identifier);
+            if self.at(Colon) {
+                self.expect(Colon);
+                let _ = self.expr();
+            }
+
+            self.close(m, PatternBinding);
+        }
+
+        fn pattern_list(&mut self) {
+            self.assert_tok(LeftBracket);
+            self.expect(LeftBracket);
+
+            while !self.at(RightBracket) && !self.eof() {
+                if self.at(Ellipsis) {
+                    self.expect(Ellipsis);
+                    if self.at(Identifier) {
+                        let b = self.open();
+                        self.expect(Identifier);
+                        self.close(b, PatternBinding);
+                    }
+                } else {
+                    self.match_pattern();
+                }
+
+                if !self.at(RightBracket) {
+                    self.expect(Comma);
+                }
+            }
+
+            self.expect(RightBracket);
+        }
+
+        fn pattern_fields(&mut self) {
+            self.assert_tok(LeftBrace);
+            self.expect(LeftBrace);
+
+            while !self.at(RightBrace) && !self.eof() {
+                self.expect(Dot);
+                if self.nth(1) == Assign {
+                    self.expect(Identifier);
+                    self.expect(Assign);
+                    self.match_pattern();
+                } else {
+                    // .x binds the field to a name of its own
+                    let b = self.open();
+                    self.expect(Identifier);
+                    self.close(b, PatternBinding);
+                }
+
+                if !self.at(RightBrace) {
+                    self.expect(Comma);
+                }
+            }
+
+            self.expect(RightBrace);
+        }
+
         fn stmt_while(&mut self) {
             assert!(self.at(WhileKwd));
             let m = self.open();
@@ -626,11 +719,44 @@ pub fn parse(text: &str) -> Parse {
 
             self.expect(TryKwd);
             self.block();
+            if !self.at(FinallyKwd) {
+                self.catch_clause();
+            }
+            while self.at(CatchKwd) {
+                self.catch_clause();
+            }
+            if self.eat(FinallyKwd) {
+                self.block();
+            }
+
+            self.close(m, TryBlock);
+        }
+
+        fn catch_clause(&mut self) {
+            let m = self.open();
+
             self.expect(CatchKwd);
             self.expect(Identifier);
+            if self.at(Colon) {
+                self.type_annotation();
+            }
             self.block();
 
-            self.close(m, TryBlock);
+            self.close(m, CatchClause);
+        }
+
+        fn stmt_throw(&mut self) {
+            assert!(self.at(ThrowKwd));
+            let m = self.open();
+
+            self.expect(ThrowKwd);
+            let _ = self.expr();
+            if self.eat(FromKwd) {
+                let _ = self.expr();
+            }
+            self.expect(Semicolon);
+
+            self.close(m, StmtAssert);
         }
 
         fn stmt_import(&mut self) {
@@ -677,7 +803,15 @@ pub fn parse(text: &str) -> Parse {
             self.expect(ValKwd);
 
             loop {
-                self.expect(Identifier);
+                // val [a, b] = ... and val Point{.x, .y} = ... destructure the value
+                let destructures = self.at(LeftBracket)
+                    || self.at(LeftBrace)
+                    || (self.at(Identifier) && matches!(self.nth(1), Dot | LeftBrace));
+                if destructures {
+                    self.match_pattern();
+                } else {
+                    self.expect(Identifier);
+                }
 
                 if self.at(Colon) {
                     self.type_annotation();
@@ -1080,6 +1214,11 @@ pub fn parse(text: &str) -> Parse {
                     self.close(m, ListLiteral)
                 }
 
+                LeftBrace => {
+                    self.map_literal();
+                    self.close(m, MapLiteral)
+                }
+
                 op if prefix_binding_power(op).is_some() => {
                     let ((), r_bp) = prefix_binding_power(op).unwrap();
                     self.advance();
@@ -1115,6 +1254,19 @@ pub fn parse(text: &str) -> Parse {
             self.expect(right_delim);
         }
 
+        fn map_literal(&mut self) {
+            self.expect(LeftBrace);
+            while !self.at(RightBrace) && !self.eof() {
+                self.expr();
+                self.expect(Colon);
+                self.expr();
+                if !self.at(RightBrace) {
+                    self.expect(Comma);
+                }
+            }
+            self.expect(RightBrace);
+        }
+
         fn arg_list(&mut self) {
             assert!(self.at(LeftParen));
             let m = self.open();
@@ -1224,6 +1376,7 @@ pub fn parse(text: &str) -> Parse {
                     | ElsifKwd
                     | EnumKwd
                     | ExtensionKwd
+                    | FinallyKwd
                     | FlagKwd
                     | ForKwd
                     | FromKwd
@@ -1245,6 +1398,7 @@ pub fn parse(text: &str) -> Parse {
                     | TypeKwd
                     | ValKwd
                     | WhileKwd
+                    | YieldKwd
                     | AndKwd
                     | TrueKwd
                     | FalseKwd
@@ -1682,6 +1836,38 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_map_literal() {
+        expect_tree(
+            "func test() { val x = {1: 2}; }",
+            &[
+                "File@0..23",
+                "  Func@0..23",
+                "    FuncKwd@0..4 \"func\"",
+                "    Identifier@4..8 \"test\"",
+                "    ParamList@8..10",
+                "      LeftParen@8..9 \"(\"",
+                "      RightParen@9..10 \")\"",
+                "    Block@10..23",
+                "      LeftBrace@10..11 \"{\"",
+                "      StmtVal@11..22",
+                "        ValKwd@11..14 \"val\"",
+                "        Identifier@14..15 \"x\"",
+                "        Assign@15..16 \"=\"",
+                "        MapLiteral@16..21",
+                "          LeftBrace@16..17 \"{\"",
+                "          ExprLiteral@17..18",
+                "            DecIntLiteral@17..18 \"1\"",
+                "          Colon@18..19 \":\"",
+                "          ExprLiteral@19..20",
+                "            DecIntLiteral@19..20 \"2\"",
+                "          RightBrace@20..21 \"}\"",
+                "        Semicolon@21..22 \";\"",
+                "      RightBrace@22..23 \"}\"",
+            ],
+        )
+    }
+
     #[test]
     fn test_list_literal_nested() {
         expect_tree(
@@ -1887,6 +2073,25 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_try_with_typed_catches_and_finally() {
+        let result = parse(
+            "func f() { try { g(); } catch e: Oops { throw Wrapped.new() from e; } catch e { } finally { h(); } }",
+        );
+        assert!(result.errors.is_empty());
+        let clauses = result
+            .syntax()
+            .descendants()
+            .filter(|n| n.kind() == CatchClause)
+            .count();
+        assert_eq!(clauses, 2);
+        assert!(
+            parse("func f() { try { g(); } finally { h(); } }")
+                .errors
+                .is_empty()
+        );
+    }
+
     fn test_files_in_directory_parse(dir: &str, dir_should_error: Vec<String>) {
         use std::fs;
         use std::path::Path;
@@ -1901,7 +2106,8 @@ mod tests {
         let expect_err =
             dir_should_error.contains(&test_dir.file_name().unwrap().to_str().unwrap().to_owned());
 
-        let entries = fs::read_dir(test_dir).expect(&format!("Failed to read directory: {}", dir));
+        let entries =
+            fs::read_dir(test_dir).unwrap_or_else(|_| panic!("Failed to read directory: {}", dir));
 
         for entry in entries {
             let entry = entry.expect("Failed to read directory entry");
@@ -1912,8 +2118,8 @@ mod tests {
 
                 println!("Parsing {}", filename);
 
-                let content =
-                    fs::read_to_string(&path).expect(&format!("Failed to read file: {}", filename));
+                let content = fs::read_to_string(&path)
+                    .unwrap_or_else(|_| panic!("Failed to read file: {}", filename));
 
                 let parse_result = parse(&content);
 
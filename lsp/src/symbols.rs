@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: Apache-2.0
+use rowan::TextRange;
+
+use crate::lexer::SyntaxKind as K;
+use crate::parser::{SyntaxNode, SyntaxToken};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeclKind {
+    Func,
+    Method,
+    Operator,
+    Val,
+    Param,
+    Struct,
+    Enum,
+    EnumCase,
+    Mixin,
+    Extension,
+}
+
+// a named declaration, along with what an editor shows for it
+#[derive(Clone, Debug)]
+pub struct Decl {
+    pub name: String,
+    pub kind: DeclKind,
+    pub name_range: TextRange,
+    pub full_range: TextRange,
+    pub signature: String,
+    pub doc: Option<String>,
+    pub members: Vec<Decl>,
+}
+
+impl Decl {
+    pub fn hover_text(&self) -> String {
+        let mut text = format!("```aria\n{}\n```", self.signature);
+        if let Some(doc) = &self.doc {
+            text.push_str("\n\n");
+            text.push_str(doc);
+        }
+        text
+    }
+
+    pub fn find(&self, name_range: TextRange) -> Option<&Decl> {
+        if self.name_range == name_range {
+            return Some(self);
+        }
+        self.members.iter().find_map(|m| m.find(name_range))
+    }
+}
+
+pub(crate) fn is_trivia(kind: K) -> bool {
+    matches!(kind, K::Whitespace | K::LineComment)
+}
+
+fn first_significant_token(node: &SyntaxNode) -> Option<SyntaxToken> {
+    node.descendants_with_tokens()
+        .filter_map(|e| e.into_token())
+        .find(|t| !is_trivia(t.kind()))
+}
+
+// the identifiers a node declares directly, as opposed to the ones nested
+// in its expressions
+pub(crate) fn declared_names(node: &SyntaxNode) -> Vec<SyntaxToken> {
+    let direct = || {
+        node.children_with_tokens()
+            .filter_map(|e| e.into_token())
+            .filter(|t| t.kind() == K::Identifier)
+    };
+    match node.kind() {
+        K::Func | K::Param | K::CatchClause | K::EnumCase => direct().take(1).collect(),
+        K::StmtFor => direct().take(1).chain(pattern_names(node)).collect(),
+        K::StmtVal => direct().chain(pattern_names(node)).collect(),
+        K::Struct | K::Enum | K::Mixin => node
+            .children()
+            .find(|n| n.kind() == K::QualifiedIdent)
+            .and_then(|n| {
+                n.children_with_tokens()
+                    .filter_map(|e| e.into_token())
+                    .filter(|t| t.kind() == K::Identifier)
+                    .last()
+            })
+            .into_iter()
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// the names bound by the destructuring patterns of a val or for, but not
+// those of nested statements; _ binds nothing
+fn pattern_names(node: &SyntaxNode) -> impl Iterator<Item = SyntaxToken> {
+    node.children()
+        .filter(|n| n.kind() == K::MatchPattern)
+        .flat_map(|n| n.descendants())
+        .filter(|n| n.kind() == K::PatternBinding)
+        .filter_map(|n| {
+            n.children_with_tokens()
+                .filter_map(|e| e.into_token())
+                .find(|t| t.kind() == K::Identifier)
+        })
+        .filter(|t| t.text() != "_")
+}
+
+// consecutive # comments right above a declaration, without a blank line
+// in between, make up its documentation
+pub(crate) fn doc_comment(node: &SyntaxNode) -> Option<String> {
+    let mut tok = first_significant_token(node)?.prev_token();
+    let mut lines = vec![];
+    while let Some(t) = tok {
+        match t.kind() {
+            K::LineComment => {
+                // a comment trailing some code belongs to that code
+                let own_line = t
+                    .prev_token()
+                    .is_none_or(|p| p.kind() == K::Whitespace && p.text().contains('\n'));
+                if !own_line {
+                    break;
+                }
+                let line = t.text().trim_start_matches('#');
+                lines.push(
+                    line.strip_prefix(' ')
+                        .unwrap_or(line)
+                        .trim_end()
+                        .to_string(),
+                );
+            }
+            K::Whitespace if t.text().matches('\n').count() <= 1 => {}
+            _ => break,
+        }
+        tok = t.prev_token();
+    }
+
+    // the license header is not documentation
+    lines.retain(|l| !l.starts_with("SPDX-License-Identifier"));
+    if lines.is_empty() {
+        None
+    } else {
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+}
+
+fn squash_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// the source text of node from its first significant token up to (but not
+// including) the first child that satisfies stop
+fn text_until(node: &SyntaxNode, stop: impl Fn(K) -> bool) -> String {
+    let mut text = String::new();
+    let mut started = false;
+    for element in node.children_with_tokens() {
+        if stop(element.kind()) {
+            break;
+        }
+        match element {
+            rowan::NodeOrToken::Token(t) => {
+                if t.kind() == K::LineComment || (!started && is_trivia(t.kind())) {
+                    continue;
+                }
+                started = true;
+                text.push_str(t.text());
+            }
+            rowan::NodeOrToken::Node(n) => {
+                started = true;
+                text.push_str(&n.text().to_string());
+            }
+        }
+    }
+    squash_whitespace(&text)
+}
+
+fn val_signature(node: &SyntaxNode, name: &SyntaxToken) -> String {
+    let mut prefix = String::new();
+    for t in node.children_with_tokens().filter_map(|e| e.into_token()) {
+        match t.kind() {
+            K::TypeKwd => prefix.push_str("type "),
+            K::InstanceKwd => prefix.push_str("instance "),
+            _ => {}
+        }
+    }
+
+    let annotation = name
+        .siblings_with_tokens(rowan::Direction::Next)
+        .skip(1)
+        .take_while(|e| !matches!(e.kind(), K::Assign | K::Comma))
+        .find_map(|e| e.into_node().filter(|n| n.kind() == K::ExprType));
+    match annotation {
+        Some(ty) => format!(
+            "{prefix}val {}: {}",
+            name.text(),
+            squash_whitespace(&ty.text().to_string())
+        ),
+        None => format!("{prefix}val {}", name.text()),
+    }
+}
+
+fn extension_name(node: &SyntaxNode) -> Option<(String, TextRange)> {
+    let target = node.children().next()?;
+    let last = target
+        .descendants_with_tokens()
+        .filter_map(|e| e.into_token())
+        .filter(|t| t.kind() == K::Identifier)
+        .last()?;
+    Some((
+        squash_whitespace(&target.text().to_string()),
+        last.text_range(),
+    ))
+}
+
+pub(crate) fn decl_kind(node: &SyntaxNode) -> Option<DeclKind> {
+    Some(match node.kind() {
+        K::Func if is_member(node) => DeclKind::Method,
+        K::Func => DeclKind::Func,
+        K::StmtVal | K::StmtFor | K::CatchClause => DeclKind::Val,
+        K::Param => DeclKind::Param,
+        K::EnumCase => DeclKind::EnumCase,
+        K::Struct => DeclKind::Struct,
+        K::Enum => DeclKind::Enum,
+        K::Mixin => DeclKind::Mixin,
+        K::Operator => DeclKind::Operator,
+        K::Extension => DeclKind::Extension,
+        _ => return None,
+    })
+}
+
+// true for the fields, methods and cases of a type, which are only ever
+// reached through a value or the type itself
+pub(crate) fn is_member(node: &SyntaxNode) -> bool {
+    node.kind() == K::EnumCase || node.parent().is_some_and(|p| p.kind() == K::StructEntry)
+}
+
+pub(crate) fn decl_for_name(node: &SyntaxNode, name: &SyntaxToken) -> Option<Decl> {
+    let kind = decl_kind(node)?;
+    let signature = match node.kind() {
+        K::Func => text_until(node, |k| matches!(k, K::Block | K::Assign)),
+        K::StmtVal => val_signature(node, name),
+        K::Param => text_until(node, |k| matches!(k, K::Assign | K::Comma)),
+        K::StmtFor | K::CatchClause => format!("val {}", name.text()),
+        K::EnumCase => text_until(node, |_| false),
+        K::Struct | K::Enum | K::Mixin => text_until(node, |k| k == K::LeftBrace),
+        _ => return None,
+    };
+
+    Some(Decl {
+        name: name.text().to_string(),
+        kind,
+        name_range: name.text_range(),
+        full_range: node.text_range(),
+        signature,
+        doc: doc_comment(node),
+        members: members_of(node),
+    })
+}
+
+fn operator_decl(node: &SyntaxNode) -> Option<Decl> {
+    let name = text_until(node, |k| matches!(k, K::ParamList | K::Block | K::Assign));
+    let name_range = node
+        .children_with_tokens()
+        .filter_map(|e| e.into_token())
+        .find(|t| t.kind() == K::OperatorKwd)?
+        .text_range();
+    Some(Decl {
+        name,
+        kind: DeclKind::Operator,
+        name_range,
+        full_range: node.text_range(),
+        signature: text_until(node, |k| matches!(k, K::Block | K::Assign)),
+        doc: doc_comment(node),
+        members: vec![],
+    })
+}
+
+fn members_of(node: &SyntaxNode) -> Vec<Decl> {
+    if !matches!(node.kind(), K::Struct | K::Enum | K::Mixin | K::Extension) {
+        return vec![];
+    }
+
+    let mut members = vec![];
+    for child in node.children() {
+        let entries = match child.kind() {
+            K::StructEntry => child.children().collect::<Vec<_>>(),
+            K::EnumCase => vec![child],
+            K::EnumEntry => child
+                .children()
+                .flat_map(|e| e.children().collect::<Vec<_>>())
+                .collect(),
+            _ => continue,
+        };
+        for entry in entries {
+            members.extend(decls_of(&entry));
+        }
+    }
+    members
+}
+
+fn decls_of(node: &SyntaxNode) -> Vec<Decl> {
+    match node.kind() {
+        K::Operator => operator_decl(node).into_iter().collect(),
+        K::Extension => {
+            let Some((name, name_range)) = extension_name(node) else {
+                return vec![];
+            };
+            vec![Decl {
+                name,
+                kind: DeclKind::Extension,
+                name_range,
+                full_range: node.text_range(),
+                signature: text_until(node, |k| k == K::LeftBrace),
+                doc: doc_comment(node),
+                members: members_of(node),
+            }]
+        }
+        _ => declared_names(node)
+            .iter()
+            .filter_map(|name| decl_for_name(node, name))
+            .collect(),
+    }
+}
+
+// the declarations visible at the top level of a file, with their members
+pub fn outline(root: &SyntaxNode) -> Vec<Decl> {
+    root.children().flat_map(|n| decls_of(&n)).collect()
+}
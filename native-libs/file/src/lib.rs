@@ -114,7 +114,16 @@ impl BuiltinFunctionImpl for New {
         let the_path = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_string().cloned())?;
         let the_mode = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_integer().cloned())?;
 
-        let opts = open_options_from_int(*the_mode.raw_value());
+        let mode = the_mode.wrapping_i64();
+        let path = std::path::Path::new(the_path.raw_value());
+        if (mode & FILE_MODE_READ) != 0 {
+            vm.options.permissions.check_read(path)?;
+        }
+        if (mode & (FILE_MODE_WRITE | FILE_MODE_APPEND)) != 0 {
+            vm.options.permissions.check_write(path)?;
+        }
+
+        let opts = open_options_from_int(mode);
         match opts.open(the_path.raw_value()) {
             Ok(file) => {
                 let file = MutableFile {
@@ -240,7 +249,7 @@ impl BuiltinFunctionImpl for ReadCount {
 
         let rust_file_obj = mut_file_from_aria(&aria_file, &vm.globals)?;
 
-        let mut bytes = vec![0u8; *count.raw_value() as usize];
+        let mut bytes = vec![0u8; count.saturating_i64() as usize];
         {
             let mut file_ref = rust_file_obj.file.borrow_mut();
             match file_ref.read_exact(&mut bytes) {
@@ -372,7 +381,7 @@ impl BuiltinFunctionImpl for SetPos {
 
         let mut rfo = rust_file_obj.file.borrow_mut();
 
-        match rfo.seek(std::io::SeekFrom::Start(*offset.raw_value() as u64)) {
+        match rfo.seek(std::io::SeekFrom::Start(offset.saturating_i64() as u64)) {
             Ok(n) => {
                 frame.stack.push(RuntimeValue::Integer((n as i64).into()));
                 Ok(RunloopExit::Ok(()))
@@ -1,12 +1,42 @@
 // SPDX-License-Identifier: Apache-2.0
 use haxby_opcodes::function_attribs::FUNC_IS_METHOD;
 use haxby_vm::{
-    error::dylib_load::LoadResult,
+    error::{dylib_load::LoadResult, vm_error::VmErrorReason},
     runtime_module::RuntimeModule,
     runtime_value::{RuntimeValue, list::List, object::Object},
+    sandbox::Permissions,
     vm::ExecutionResult,
 };
 
+fn check_url(permissions: &Permissions, url: &reqwest::Url) -> Result<(), VmErrorReason> {
+    permissions.check_net(
+        url.host_str().unwrap_or_default(),
+        url.port_or_known_default(),
+    )
+}
+
+// a client for url that only follows redirects to hosts the sandbox allows
+fn sandboxed_client(
+    permissions: &Permissions,
+    url: &str,
+) -> Result<reqwest::blocking::Client, VmErrorReason> {
+    match reqwest::Url::parse(url) {
+        Ok(url) => check_url(permissions, &url)?,
+        Err(_) => permissions.check_net(url, None)?,
+    }
+    let permissions = permissions.clone();
+    reqwest::blocking::Client::builder()
+        .redirect(reqwest::redirect::Policy::custom(
+            move |attempt| match check_url(&permissions, attempt.url()) {
+                Ok(()) if attempt.previous().len() < 10 => attempt.follow(),
+                Ok(()) => attempt.error("too many redirects"),
+                Err(e) => attempt.error(e),
+            },
+        ))
+        .build()
+        .map_err(|e| VmErrorReason::OperationFailed(e.to_string()))
+}
+
 #[derive(Default)]
 struct RequestGet {}
 impl haxby_vm::runtime_value::function::BuiltinFunctionImpl for RequestGet {
@@ -67,7 +97,7 @@ impl haxby_vm::runtime_value::function::BuiltinFunctionImpl for RequestGet {
                 field.as_struct().cloned()
             })?;
 
-        let mut client = reqwest::blocking::Client::new()
+        let mut client = sandboxed_client(&vm.options.permissions, this_url.raw_value())?
             .get(this_url.raw_value())
             .timeout(std::time::Duration::from_secs_f64(
                 *this_timeout.raw_value(),
@@ -225,7 +255,7 @@ impl haxby_vm::runtime_value::function::BuiltinFunctionImpl for RequestPost {
                 field.as_struct().cloned()
             })?;
 
-        let mut client = reqwest::blocking::Client::new()
+        let mut client = sandboxed_client(&vm.options.permissions, this_url.raw_value())?
             .post(this_url.raw_value())
             .body(payload.raw_value().to_owned())
             .timeout(std::time::Duration::from_secs_f64(
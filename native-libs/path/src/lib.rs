@@ -83,6 +83,30 @@ fn mut_path_from_aria(
         .ok_or(VmErrorReason::UnexpectedVmState)
 }
 
+// the path held by aria_object, if the sandbox lets the program read it
+fn readable_path(
+    aria_object: &Object,
+    vm: &vm::VirtualMachine,
+) -> Result<Rc<MutablePath>, VmErrorReason> {
+    let path = mut_path_from_aria(aria_object, &vm.globals)?;
+    vm.options
+        .permissions
+        .check_read(path.content.borrow().as_path())?;
+    Ok(path)
+}
+
+// the path held by aria_object, if the sandbox lets the program write it
+fn writable_path(
+    aria_object: &Object,
+    vm: &vm::VirtualMachine,
+) -> Result<Rc<MutablePath>, VmErrorReason> {
+    let path = mut_path_from_aria(aria_object, &vm.globals)?;
+    vm.options
+        .permissions
+        .check_write(path.content.borrow().as_path())?;
+    Ok(path)
+}
+
 fn path_symbol(vm: &mut vm::VirtualMachine) -> Symbol {
     vm.globals
         .intern_symbol("__path")
@@ -158,6 +182,19 @@ impl BuiltinFunctionImpl for Glob {
         let glob_expr = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_string().cloned())?;
         let path_sym = path_symbol(vm);
 
+        // matching reads every directory below the part without wildcards
+        let searched = std::path::Path::new(glob_expr.raw_value())
+            .components()
+            .take_while(|c| !c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+            .collect::<PathBuf>();
+        vm.options
+            .permissions
+            .check_read(if searched.as_os_str().is_empty() {
+                std::path::Path::new(".")
+            } else {
+                &searched
+            })?;
+
         let val = match glob::glob(glob_expr.raw_value()) {
             Ok(path) => {
                 let iterator_sym = vm
@@ -379,7 +416,7 @@ impl BuiltinFunctionImpl for Exists {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         frame
@@ -411,7 +448,7 @@ impl BuiltinFunctionImpl for IsDirectory {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         frame
@@ -443,7 +480,7 @@ impl BuiltinFunctionImpl for IsFile {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         frame
@@ -475,7 +512,7 @@ impl BuiltinFunctionImpl for IsSymlink {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         frame
@@ -508,7 +545,7 @@ impl BuiltinFunctionImpl for Canonical {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
         let path_sym = path_symbol(vm);
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         let val = match rfo.canonicalize() {
@@ -548,7 +585,7 @@ impl BuiltinFunctionImpl for Size {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         let val = match rfo.metadata() {
@@ -585,7 +622,7 @@ impl BuiltinFunctionImpl for CreatedTime {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         let val = match rfo.metadata() {
@@ -630,7 +667,7 @@ impl BuiltinFunctionImpl for AccessedTime {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         let val = match rfo.metadata() {
@@ -675,7 +712,7 @@ impl BuiltinFunctionImpl for ModifiedTime {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         let val = match rfo.metadata() {
@@ -815,7 +852,7 @@ impl BuiltinFunctionImpl for Entries {
                 f.as_struct().cloned()
             })?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = readable_path(&aria_object, vm)?;
         let rfo = rust_obj.content.borrow_mut();
 
         if let Ok(rd) = rfo.read_dir() {
@@ -867,7 +904,7 @@ impl BuiltinFunctionImpl for MakeDirectory {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = writable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         frame.stack.push(RuntimeValue::Boolean(
@@ -899,7 +936,7 @@ impl BuiltinFunctionImpl for MakeDirectories {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = writable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         frame.stack.push(RuntimeValue::Boolean(
@@ -931,7 +968,7 @@ impl BuiltinFunctionImpl for RemoveDirectory {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = writable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         frame.stack.push(RuntimeValue::Boolean(
@@ -963,7 +1000,7 @@ impl BuiltinFunctionImpl for RemoveFile {
     ) -> vm::ExecutionResult<RunloopExit> {
         let aria_object = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let rust_obj = mut_path_from_aria(&aria_object, &vm.globals)?;
+        let rust_obj = writable_path(&aria_object, vm)?;
 
         let rfo = rust_obj.content.borrow_mut();
         frame.stack.push(RuntimeValue::Boolean(
@@ -996,8 +1033,8 @@ impl BuiltinFunctionImpl for Copy {
         let this_path = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
         let other_path = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
 
-        let this_path = mut_path_from_aria(&this_path, &vm.globals)?;
-        let other_path = mut_path_from_aria(&other_path, &vm.globals)?;
+        let this_path = readable_path(&this_path, vm)?;
+        let other_path = writable_path(&other_path, vm)?;
 
         let this_path = this_path.content.borrow_mut();
         let other_path = other_path.content.borrow_mut();
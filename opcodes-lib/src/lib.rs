@@ -57,6 +57,8 @@ pub const OPCODE_THROW: u8 = 74;
 pub const OPCODE_CALL: u8 = 75;
 pub const OPCODE_RETURN: u8 = 76;
 pub const OPCODE_RETURN_UNIT: u8 = 77;
+pub const OPCODE_YIELD: u8 = 78;
+pub const OPCODE_TAIL_CALL: u8 = 79;
 // ...
 pub const OPCODE_BUILD_LIST: u8 = 80;
 pub const OPCODE_BUILD_FUNCTION: u8 = 81;
@@ -64,18 +66,24 @@ pub const OPCODE_STORE_UPLEVEL: u8 = 82;
 pub const OPCODE_BUILD_STRUCT: u8 = 83;
 pub const OPCODE_BUILD_ENUM: u8 = 84;
 pub const OPCODE_BUILD_MIXIN: u8 = 85;
+pub const OPCODE_BUILD_MAP: u8 = 86;
 pub const OPCODE_BIND_CASE: u8 = 87;
 pub const OPCODE_INCLUDE_MIXIN: u8 = 88;
 pub const OPCODE_NEW_ENUM_VAL: u8 = 89;
 pub const OPCODE_ENUM_CHECK_IS_CASE: u8 = 90;
 pub const OPCODE_ENUM_TRY_EXTRACT_PAYLOAD: u8 = 91;
 pub const OPCODE_TRY_UNWRAP_PROTOCOL: u8 = 92;
+pub const OPCODE_MATCH_FAILED: u8 = 93;
+pub const OPCODE_FORMAT_VALUE: u8 = 94;
+pub const OPCODE_THROW_FROM: u8 = 95;
+pub const OPCODE_CALL_LOCAL_METHOD: u8 = 96;
 // ..
 pub const OPCODE_READ_ATTRIBUTE_SYMBOL: u8 = 100;
 pub const OPCODE_WRITE_ATTRIBUTE_SYMBOL: u8 = 101;
 pub const OPCODE_NEW_ENUM_VAL_SYMBOL: u8 = 102;
 pub const OPCODE_ENUM_CHECK_IS_CASE_SYMBOL: u8 = 103;
 pub const OPCODE_BIND_CASE_SYMBOL: u8 = 104;
+pub const OPCODE_CALL_LOCAL_METHOD_SYMBOL: u8 = 105;
 // ...
 pub const OPCODE_IMPORT: u8 = 250;
 pub const OPCODE_LIFT_MODULE: u8 = 251;
@@ -88,6 +96,7 @@ pub mod function_attribs {
     pub const FUNC_IS_METHOD:            u8 = 1_u8 << 0;
     pub const METHOD_ATTRIBUTE_TYPE:     u8 = 1_u8 << 1;
     pub const FUNC_ACCEPTS_VARARG:       u8 = 1_u8 << 2;
+    pub const FUNC_IS_GENERATOR:         u8 = 1_u8 << 3;
 }
 
 #[allow(unused_imports)]
@@ -138,6 +147,8 @@ pub enum BuiltinTypeId {
     Float = 10,
     List = 11,
     Type = 12,
+    Generator = 13,
+    Map = 14,
 }
 
 impl BuiltinTypeId {
@@ -146,7 +157,7 @@ impl BuiltinTypeId {
     }
 
     pub fn last() -> Self {
-        BuiltinTypeId::Type
+        BuiltinTypeId::Map
     }
 
     pub fn name(&self) -> &'static str {
@@ -164,6 +175,8 @@ impl BuiltinTypeId {
             BuiltinTypeId::Unit => "Unit",
             BuiltinTypeId::Result => "Result",
             BuiltinTypeId::Type => "Type",
+            BuiltinTypeId::Generator => "Generator",
+            BuiltinTypeId::Map => "Map",
         }
     }
 }
@@ -186,6 +199,8 @@ impl TryFrom<u8> for BuiltinTypeId {
             10 => Ok(BuiltinTypeId::Float),
             11 => Ok(BuiltinTypeId::List),
             12 => Ok(BuiltinTypeId::Type),
+            13 => Ok(BuiltinTypeId::Generator),
+            14 => Ok(BuiltinTypeId::Map),
             _ => Err(()),
         }
     }
@@ -261,12 +276,18 @@ pub enum Opcode {
     JumpConditionally(u16, u16),
     JumpIfArgSupplied(u8, u16),
     Call(u8),
+    TailCall(u8),
+    CallLocalMethod(u8, u16, u8),
+    CallLocalMethodSymbol(u8, u32, u8),
     Return,
     ReturnUnit,
+    Yield,
     TryEnter(u16),
     TryExit,
     Throw,
+    ThrowFrom,
     BuildList(u32),
+    BuildMap(u32),
     BuildFunction,
     StoreUplevel(u8),
     BuildStruct,
@@ -281,6 +302,8 @@ pub enum Opcode {
     EnumCheckIsCaseSymbol(u32),
     EnumTryExtractPayload,
     TryUnwrapProtocol(u8),
+    MatchFailed(u16),
+    FormatValue(u16),
     Isa,
     Import(u16),
     LiftModule,
@@ -342,12 +365,22 @@ impl std::fmt::Display for Opcode {
             Self::JumpConditionally(arg0, arg1) => write!(f, "JUMP_CONDITIONALLY {arg0} {arg1}"),
             Self::JumpIfArgSupplied(arg0, arg1) => write!(f, "JUMP_IF_ARG_SUPPLIED {arg0} {arg1}"),
             Self::Call(arg0) => write!(f, "CALL {arg0}"),
+            Self::TailCall(arg0) => write!(f, "TAIL_CALL {arg0}"),
+            Self::CallLocalMethod(arg0, arg1, arg2) => {
+                write!(f, "CALL_LOCAL_METHOD {arg0} @{arg1} {arg2}")
+            }
+            Self::CallLocalMethodSymbol(arg0, arg1, arg2) => {
+                write!(f, "CALL_LOCAL_METHOD_SYM {arg0} #{arg1} {arg2}")
+            }
             Self::Return => write!(f, "RETURN"),
             Self::ReturnUnit => write!(f, "RETURN_UNIT"),
+            Self::Yield => write!(f, "YIELD"),
             Self::TryEnter(arg0) => write!(f, "ENTER_TRY {arg0}"),
             Self::TryExit => write!(f, "EXIT_TRY"),
             Self::Throw => write!(f, "THROW"),
+            Self::ThrowFrom => write!(f, "THROW_FROM"),
             Self::BuildList(arg0) => write!(f, "BUILD_LIST {arg0}"),
+            Self::BuildMap(arg0) => write!(f, "BUILD_MAP {arg0}"),
             Self::BuildFunction => write!(f, "BUILD_FUNC"),
             Self::StoreUplevel(arg0) => write!(f, "STORE_UPLEVEL {arg0}"),
             Self::BuildStruct => write!(f, "BUILD_STRUCT"),
@@ -362,6 +395,8 @@ impl std::fmt::Display for Opcode {
             Self::EnumCheckIsCaseSymbol(arg0) => write!(f, "ENUM_CHECK_IS_CASE_SYM #{arg0}"),
             Self::EnumTryExtractPayload => write!(f, "ENUM_TRY_EXTRACT_PAYLOAD"),
             Self::TryUnwrapProtocol(mode) => write!(f, "TRY_UNWRAP_PROTOCOL {mode}"),
+            Self::MatchFailed(arg0) => write!(f, "MATCH_FAILED @{arg0}"),
+            Self::FormatValue(arg0) => write!(f, "FORMAT_VALUE @{arg0}"),
             Self::Isa => write!(f, "ISA"),
             Self::Import(arg0) => write!(f, "IMPORT @{arg0}"),
             Self::LiftModule => write!(f, "LIFT_MODULE"),
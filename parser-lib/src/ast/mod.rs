@@ -186,6 +186,32 @@ pub struct StringLiteral {
     pub value: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytesLiteral {
+    pub loc: SourcePointer,
+    pub value: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpolatedValue {
+    pub loc: SourcePointer,
+    pub value: Box<Expression>,
+    // the text after the colon in {value:spec}
+    pub spec: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterpolatedStringPart {
+    Text(String),
+    Value(InterpolatedValue),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpolatedString {
+    pub loc: SourcePointer,
+    pub parts: Vec<InterpolatedStringPart>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Identifier {
     pub loc: SourcePointer,
@@ -228,6 +254,19 @@ pub struct ListLiteral {
     pub items: ExpressionList,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapLiteralEntry {
+    pub loc: SourcePointer,
+    pub key: Expression,
+    pub value: Expression,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapLiteral {
+    pub loc: SourcePointer,
+    pub entries: Vec<MapLiteralEntry>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParenExpression {
     pub loc: SourcePointer,
@@ -249,7 +288,10 @@ pub enum Primary {
     FloatLiteral(FloatLiteral),
     Identifier(Identifier),
     ListLiteral(ListLiteral),
+    MapLiteral(MapLiteral),
     StringLiteral(StringLiteral),
+    BytesLiteral(BytesLiteral),
+    InterpolatedString(InterpolatedString),
     ParenExpression(ParenExpression),
 }
 
@@ -260,7 +302,10 @@ impl Primary {
             Self::FloatLiteral(fp) => &fp.loc,
             Self::Identifier(id) => &id.loc,
             Self::ListLiteral(ll) => &ll.loc,
+            Self::MapLiteral(ml) => &ml.loc,
             Self::StringLiteral(sl) => &sl.loc,
+            Self::BytesLiteral(bl) => &bl.loc,
+            Self::InterpolatedString(is) => &is.loc,
             Self::ParenExpression(pe) => &pe.loc,
         }
     }
@@ -867,6 +912,9 @@ pub struct ExpressionStatement {
 pub struct ValDeclEntry {
     pub loc: SourcePointer,
     pub id: DeclarationId,
+    // set for a destructuring declaration such as val [x, y] = pair; id is
+    // then a hidden name that holds the whole value
+    pub pattern: Option<MatchPattern>,
     pub val: Expression,
 }
 
@@ -950,7 +998,51 @@ impl MatchPatternComp {
 pub struct MatchPatternEnumCase {
     pub loc: SourcePointer,
     pub case: Identifier,
-    pub payload: Option<DeclarationId>,
+    pub payload: Option<Box<MatchPattern>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchPatternWildcard {
+    pub loc: SourcePointer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchPatternBinding {
+    pub loc: SourcePointer,
+    pub id: DeclarationId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchPatternRest {
+    pub loc: SourcePointer,
+    pub name: Option<Identifier>,
+}
+
+// [first, second, ...rest, last]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchPatternList {
+    pub loc: SourcePointer,
+    pub before: Vec<MatchPattern>,
+    pub rest: Option<MatchPatternRest>,
+    pub after: Vec<MatchPattern>,
+}
+
+// .name binds the field to a local of the same name, .name = pattern
+// matches the field against the pattern instead
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchPatternField {
+    pub loc: SourcePointer,
+    pub name: Identifier,
+    pub pattern: Option<MatchPattern>,
+}
+
+// Type { .field, ... } or just { .field, ... } to match any value that has
+// the fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchPatternStruct {
+    pub loc: SourcePointer,
+    pub ty: Option<Expression>,
+    pub fields: Vec<MatchPatternField>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -958,6 +1050,10 @@ pub enum MatchPattern {
     MatchPatternComp(MatchPatternComp),
     MatchPatternRel(MatchPatternRel),
     MatchPatternEnumCase(MatchPatternEnumCase),
+    MatchPatternWildcard(MatchPatternWildcard),
+    MatchPatternBinding(MatchPatternBinding),
+    MatchPatternList(MatchPatternList),
+    MatchPatternStruct(MatchPatternStruct),
 }
 
 impl MatchPattern {
@@ -966,14 +1062,28 @@ impl MatchPattern {
             Self::MatchPatternComp(e) => &e.loc,
             Self::MatchPatternRel(e) => &e.loc,
             Self::MatchPatternEnumCase(c) => &c.loc,
+            Self::MatchPatternWildcard(w) => &w.loc,
+            Self::MatchPatternBinding(b) => &b.loc,
+            Self::MatchPatternList(l) => &l.loc,
+            Self::MatchPatternStruct(s) => &s.loc,
         }
     }
 }
 
+impl From<&DeclarationId> for MatchPattern {
+    fn from(value: &DeclarationId) -> Self {
+        Self::MatchPatternBinding(MatchPatternBinding {
+            loc: value.loc.clone(),
+            id: value.clone(),
+        })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MatchRule {
     pub loc: SourcePointer,
     pub patterns: Vec<MatchPattern>,
+    pub guard: Option<Expression>,
     pub then: CodeBlock,
 }
 
@@ -993,7 +1103,7 @@ impl MatchRule {
             loc: loc.clone(),
             value: case.to_owned(),
         };
-        let payload = payload.map(|p| DeclarationId::from(&p));
+        let payload = payload.map(|p| Box::new(MatchPattern::from(&DeclarationId::from(&p))));
         let case_pattern = MatchPattern::MatchPatternEnumCase(MatchPatternEnumCase {
             loc: enumm.loc.clone(),
             case,
@@ -1007,6 +1117,7 @@ impl MatchRule {
         Self {
             loc,
             patterns: vec![isa_pattern, case_pattern],
+            guard: None,
             then,
         }
     }
@@ -1032,6 +1143,9 @@ pub struct WhileStatement {
 pub struct ForStatement {
     pub loc: SourcePointer,
     pub id: Identifier,
+    // set for a destructuring loop such as for [k, v] in map; id is then a
+    // hidden name that holds each value
+    pub pattern: Option<MatchPattern>,
     pub expr: Expression,
     pub then: CodeBlock,
     pub els: Option<ElsePiece>,
@@ -1056,6 +1170,13 @@ impl From<&Expression> for ReturnStatement {
 pub struct ThrowStatement {
     pub loc: SourcePointer,
     pub val: Expression,
+    pub cause: Option<Expression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YieldStatement {
+    pub loc: SourcePointer,
+    pub val: Expression,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1074,12 +1195,19 @@ pub struct ContinueStatement {
     pub loc: SourcePointer,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatchClause {
+    pub loc: SourcePointer,
+    pub id: DeclarationId,
+    pub body: CodeBlock,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TryBlock {
     pub loc: SourcePointer,
     pub body: CodeBlock,
-    pub id: Identifier,
-    pub catch: CodeBlock,
+    pub catches: Vec<CatchClause>,
+    pub finally: Option<CodeBlock>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -1095,6 +1223,7 @@ pub enum Statement {
     CodeBlock(CodeBlock),
     ReturnStatement(ReturnStatement),
     ThrowStatement(ThrowStatement),
+    YieldStatement(YieldStatement),
     TryBlock(TryBlock),
     AssertStatement(AssertStatement),
     ExpressionStatement(ExpressionStatement),
@@ -1118,6 +1247,7 @@ impl Statement {
             Self::CodeBlock(a) => &a.loc,
             Self::ReturnStatement(a) => &a.loc,
             Self::ThrowStatement(a) => &a.loc,
+            Self::YieldStatement(a) => &a.loc,
             Self::TryBlock(a) => &a.loc,
             Self::AssertStatement(a) => &a.loc,
             Self::ExpressionStatement(a) => &a.loc,
@@ -1460,11 +1590,12 @@ pub fn source_to_ast(source: &SourceBuffer) -> ParserResult<ParsedModule> {
             source,
         )),
         Err(err) => {
-            let loc = From::from(&err.location);
-            let ptr = source.pointer(loc);
+            let loc: Location = From::from(&err.location);
+            let (loc, msg) = nodes::string_literal::diagnose_string_literals(input, loc.start)
+                .unwrap_or_else(|| (loc, err.variant.message().to_string()));
             Err(ParserError {
-                loc: ptr,
-                msg: err.variant.message().to_string(),
+                loc: source.pointer(loc),
+                msg,
             })
         }
     }
@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    ast::{
+        BytesLiteral, SourceBuffer,
+        derive::Derive,
+        prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+    },
+    grammar::Rule,
+};
+
+use super::string_literal::{Unescaped, unescape};
+
+impl Derive for BytesLiteral {
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::bytes_literal);
+        let loc = From::from(&p.as_span());
+        let text = p.as_str();
+        let mut value = vec![];
+        for u in unescape(&text[2..text.len() - 1]) {
+            match u {
+                Unescaped::Byte(b) => value.push(b),
+                Unescaped::Char(c) => {
+                    value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes())
+                }
+            }
+        }
+        Self {
+            loc: source.pointer(loc),
+            value,
+        }
+    }
+}
+
+impl PrettyPrintable for BytesLiteral {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        buffer << "b\"" << self.value.escape_ascii().to_string() << "\""
+    }
+}
@@ -1,7 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
     ast::{
-        CodeBlock, ElsePiece, Expression, ForStatement, Identifier,
+        CodeBlock, ElsePiece, Expression, ForStatement, Identifier, MatchPattern, MatchPatternList,
+        MatchPatternStruct,
         derive::Derive,
         prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
     },
@@ -16,7 +17,24 @@ impl Derive for ForStatement {
         assert!(p.as_rule() == Rule::for_stmt);
         let loc = From::from(&p.as_span());
         let mut inner = p.into_inner();
-        let id = Identifier::from_parse_tree(inner.next().expect("need identifier"), source);
+        let target = inner.next().expect("need identifier");
+        let pattern = match target.as_rule() {
+            Rule::match_pattern_list => Some(MatchPattern::MatchPatternList(
+                MatchPatternList::from_parse_tree(target.clone(), source),
+            )),
+            Rule::match_pattern_struct => Some(MatchPattern::MatchPatternStruct(
+                MatchPatternStruct::from_parse_tree(target.clone(), source),
+            )),
+            _ => None,
+        };
+        let id = if pattern.is_some() {
+            Identifier {
+                loc: source.pointer(From::from(&target.as_span())),
+                value: format!("__for_pattern_{}", target.as_span().start()),
+            }
+        } else {
+            Identifier::from_parse_tree(target, source)
+        };
         let expr = Expression::from_parse_tree(inner.next().expect("need expression"), source);
         let then = CodeBlock::from_parse_tree(inner.next().expect("need then block"), source);
         let els = inner
@@ -26,6 +44,7 @@ impl Derive for ForStatement {
         Self {
             loc: source.pointer(loc),
             id,
+            pattern,
             expr,
             then,
             els,
@@ -35,6 +54,11 @@ impl Derive for ForStatement {
 
 impl PrettyPrintable for ForStatement {
     fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
-        buffer << "for( " << &self.id << " in " << &self.expr << ") " << &self.then
+        let buffer = if let Some(pattern) = &self.pattern {
+            buffer << "for( " << pattern
+        } else {
+            buffer << "for( " << &self.id
+        };
+        buffer << " in " << &self.expr << ") " << &self.then
     }
 }
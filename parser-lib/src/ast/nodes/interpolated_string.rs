@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    ast::{
+        Expression, InterpolatedString, InterpolatedStringPart, InterpolatedValue, SourceBuffer,
+        derive::Derive,
+        prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+    },
+    grammar::Rule,
+};
+
+use super::string_literal::process_string_escapes;
+
+impl Derive for InterpolatedValue {
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::fstr_value);
+        let loc = From::from(&p.as_span());
+        let mut inner = p.into_inner();
+        let value = Box::new(Expression::from_parse_tree(
+            inner.next().expect("need value"),
+            source,
+        ));
+        let spec = inner.next().map(|spec| spec.as_str()[1..].to_owned());
+        Self {
+            loc: source.pointer(loc),
+            value,
+            spec,
+        }
+    }
+}
+
+impl PrettyPrintable for InterpolatedValue {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        let buffer = buffer << "{" << &self.value;
+        match &self.spec {
+            Some(spec) => buffer << ":" << spec.as_str() << "}",
+            None => buffer << "}",
+        }
+    }
+}
+
+impl Derive for InterpolatedString {
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::fstr_literal);
+        let loc = From::from(&p.as_span());
+        let parts = p
+            .into_inner()
+            .map(|part| match part.as_rule() {
+                Rule::fstr_value => {
+                    InterpolatedStringPart::Value(InterpolatedValue::from_parse_tree(part, source))
+                }
+                _ => InterpolatedStringPart::Text(process_string_escapes(
+                    &part.as_str().replace("{{", "{").replace("}}", "}"),
+                )),
+            })
+            .collect();
+        Self {
+            loc: source.pointer(loc),
+            parts,
+        }
+    }
+}
+
+impl PrettyPrintable for InterpolatedString {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        let mut buffer = buffer << "f\"";
+        for part in &self.parts {
+            buffer = match part {
+                InterpolatedStringPart::Text(text) => {
+                    buffer << text.replace('{', "{{").replace('}', "}}")
+                }
+                InterpolatedStringPart::Value(value) => value.prettyprint(buffer),
+            };
+        }
+        buffer << "\""
+    }
+}
@@ -1,20 +1,73 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
     ast::{
-        MatchPattern, MatchPatternComp, MatchPatternEnumCase, MatchPatternRel,
+        CompSymbol, Expression, FloatLiteral, Identifier, IntLiteral, MatchPattern,
+        MatchPatternBinding, MatchPatternComp, MatchPatternEnumCase, MatchPatternList,
+        MatchPatternRel, MatchPatternStruct, MatchPatternWildcard, Primary, SourceBuffer,
+        StringLiteral,
         derive::Derive,
         prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
     },
-    gen_from_options,
+    grammar::Rule,
 };
 
+// a literal pattern matches values equal to the literal, so it is the same
+// as an == pattern
+fn literal_pattern(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> MatchPattern {
+    assert!(p.as_rule() == Rule::match_pattern_literal);
+    let loc = source.pointer(From::from(&p.as_span()));
+    let primary = match p.clone().into_inner().next() {
+        Some(lit) => match lit.as_rule() {
+            Rule::fp_literal => Primary::FloatLiteral(FloatLiteral::from_parse_tree(lit, source)),
+            Rule::int_literal => Primary::IntLiteral(IntLiteral::from_parse_tree(lit, source)),
+            Rule::str_literal => {
+                Primary::StringLiteral(StringLiteral::from_parse_tree(lit, source))
+            }
+            _ => panic!("invalid literal"),
+        },
+        // true and false
+        None => Primary::Identifier(Identifier {
+            loc: loc.clone(),
+            value: p.as_str().trim_end().to_owned(),
+        }),
+    };
+    MatchPattern::MatchPatternComp(MatchPatternComp {
+        loc,
+        op: CompSymbol::Equal,
+        expr: Expression::from(&primary),
+    })
+}
+
 impl Derive for MatchPattern {
-    gen_from_options!(
-        match_pattern;
-        (match_pattern_comp, MatchPatternComp),
-        (match_pattern_enum_case, MatchPatternEnumCase),
-        (match_pattern_rel, MatchPatternRel),
-    );
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::match_pattern);
+        let next = p.into_inner().next().expect("need content");
+        match next.as_rule() {
+            Rule::match_pattern_comp => {
+                Self::MatchPatternComp(MatchPatternComp::from_parse_tree(next, source))
+            }
+            Rule::match_pattern_rel => {
+                Self::MatchPatternRel(MatchPatternRel::from_parse_tree(next, source))
+            }
+            Rule::match_pattern_enum_case => {
+                Self::MatchPatternEnumCase(MatchPatternEnumCase::from_parse_tree(next, source))
+            }
+            Rule::match_pattern_wildcard => Self::MatchPatternWildcard(MatchPatternWildcard {
+                loc: source.pointer(From::from(&next.as_span())),
+            }),
+            Rule::match_pattern_list => {
+                Self::MatchPatternList(MatchPatternList::from_parse_tree(next, source))
+            }
+            Rule::match_pattern_struct => {
+                Self::MatchPatternStruct(MatchPatternStruct::from_parse_tree(next, source))
+            }
+            Rule::match_pattern_literal => literal_pattern(next, source),
+            Rule::match_pattern_binding => {
+                Self::MatchPatternBinding(MatchPatternBinding::from_parse_tree(next, source))
+            }
+            _ => panic!("invalid node"),
+        }
+    }
 }
 
 impl PrettyPrintable for MatchPattern {
@@ -23,6 +76,10 @@ impl PrettyPrintable for MatchPattern {
             Self::MatchPatternComp(e) => e.prettyprint(buffer),
             Self::MatchPatternRel(e) => e.prettyprint(buffer),
             Self::MatchPatternEnumCase(e) => e.prettyprint(buffer),
+            Self::MatchPatternWildcard(_) => buffer << "_",
+            Self::MatchPatternBinding(e) => e.prettyprint(buffer),
+            Self::MatchPatternList(e) => e.prettyprint(buffer),
+            Self::MatchPatternStruct(e) => e.prettyprint(buffer),
         }
     }
 }
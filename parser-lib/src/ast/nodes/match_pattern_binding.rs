@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    ast::{
+        DeclarationId, MatchPatternBinding,
+        derive::Derive,
+        prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+    },
+    gen_from_components,
+};
+
+impl Derive for MatchPatternBinding {
+    gen_from_components!(match_pattern_binding; id: DeclarationId);
+}
+
+impl PrettyPrintable for MatchPatternBinding {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        buffer << &self.id
+    }
+}
@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
     ast::{
-        DeclarationId, Identifier, MatchPatternEnumCase, SourceBuffer,
+        Identifier, MatchPattern, MatchPatternEnumCase, SourceBuffer,
         derive::Derive,
         prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
     },
@@ -16,7 +16,7 @@ impl Derive for MatchPatternEnumCase {
         let case = Identifier::from_parse_tree(inner.next().expect("need expression"), source);
         let payload = inner
             .next()
-            .map(|next| DeclarationId::from_parse_tree(next, source));
+            .map(|next| Box::new(MatchPattern::from_parse_tree(next, source)));
         Self {
             loc: source.pointer(loc),
             case,
@@ -29,7 +29,7 @@ impl PrettyPrintable for MatchPatternEnumCase {
     fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
         let buffer = buffer << " case " << &self.case;
         if let Some(p) = &self.payload {
-            buffer << "(" << p << ")"
+            buffer << "(" << p.as_ref() << ")"
         } else {
             buffer
         }
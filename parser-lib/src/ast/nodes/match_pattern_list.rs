@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    ast::{
+        Identifier, MatchPattern, MatchPatternList, MatchPatternRest, SourceBuffer,
+        derive::Derive,
+        prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+    },
+    grammar::Rule,
+};
+
+impl Derive for MatchPatternRest {
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::match_pattern_rest);
+        let loc = From::from(&p.as_span());
+        let name = p
+            .into_inner()
+            .next()
+            .map(|next| Identifier::from_parse_tree(next, source));
+        Self {
+            loc: source.pointer(loc),
+            name,
+        }
+    }
+}
+
+impl PrettyPrintable for MatchPatternRest {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        let buffer = buffer << "...";
+        if let Some(name) = &self.name {
+            buffer << name
+        } else {
+            buffer
+        }
+    }
+}
+
+impl Derive for MatchPatternList {
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::match_pattern_list);
+        let loc = From::from(&p.as_span());
+        let mut before = vec![];
+        let mut rest = None;
+        let mut after = vec![];
+        for next in p.into_inner() {
+            match next.as_rule() {
+                Rule::match_pattern_rest => {
+                    rest = Some(MatchPatternRest::from_parse_tree(next, source));
+                }
+                Rule::match_pattern if rest.is_none() => {
+                    before.push(MatchPattern::from_parse_tree(next, source));
+                }
+                Rule::match_pattern => {
+                    after.push(MatchPattern::from_parse_tree(next, source));
+                }
+                _ => panic!("invalid list pattern entry"),
+            }
+        }
+        Self {
+            loc: source.pointer(loc),
+            before,
+            rest,
+            after,
+        }
+    }
+}
+
+impl PrettyPrintable for MatchPatternList {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        let mut buffer = (buffer << "[").write_separated_list(&self.before, ", ");
+        if let Some(rest) = &self.rest {
+            if !self.before.is_empty() {
+                buffer = buffer << ", ";
+            }
+            buffer = buffer << rest;
+            if !self.after.is_empty() {
+                buffer = buffer << ", ";
+            }
+        }
+        buffer.write_separated_list(&self.after, ", ") << "]"
+    }
+}
@@ -0,0 +1,87 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    ast::{
+        Expression, Identifier, MatchPattern, MatchPatternField, MatchPatternStruct,
+        PostfixExpression, PostfixTerm, PostfixTermAttribute, Primary, SourceBuffer,
+        derive::Derive,
+        prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+    },
+    grammar::Rule,
+};
+
+impl Derive for MatchPatternField {
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::match_pattern_field);
+        let loc = From::from(&p.as_span());
+        let mut inner = p.into_inner();
+        let name = Identifier::from_parse_tree(inner.next().expect("need identifier"), source);
+        let pattern = inner
+            .next()
+            .map(|next| MatchPattern::from_parse_tree(next, source));
+        Self {
+            loc: source.pointer(loc),
+            name,
+            pattern,
+        }
+    }
+}
+
+impl PrettyPrintable for MatchPatternField {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        let buffer = buffer << "." << &self.name;
+        if let Some(p) = &self.pattern {
+            buffer << " = " << p
+        } else {
+            buffer
+        }
+    }
+}
+
+impl Derive for MatchPatternStruct {
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::match_pattern_struct);
+        let loc = source.pointer(From::from(&p.as_span()));
+        let mut ty: Option<PostfixExpression> = None;
+        let mut fields = vec![];
+        for next in p.into_inner() {
+            match next.as_rule() {
+                // the type is a dotted name, such as Point or geometry.Point
+                Rule::identifier => {
+                    let id = Identifier::from_parse_tree(next, source);
+                    ty = Some(match ty {
+                        None => PostfixExpression::from(&Primary::Identifier(id)),
+                        Some(mut base) => {
+                            base.terms.push(PostfixTerm::PostfixTermAttribute(
+                                PostfixTermAttribute {
+                                    loc: id.loc.clone(),
+                                    id,
+                                },
+                            ));
+                            base
+                        }
+                    });
+                }
+                Rule::match_pattern_field => {
+                    fields.push(MatchPatternField::from_parse_tree(next, source));
+                }
+                _ => panic!("invalid struct pattern entry"),
+            }
+        }
+        Self {
+            loc,
+            ty: ty.map(|t| Expression::from(&t)),
+            fields,
+        }
+    }
+}
+
+impl PrettyPrintable for MatchPatternStruct {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        let buffer = if let Some(ty) = &self.ty {
+            buffer << ty
+        } else {
+            buffer
+        };
+        (buffer << "{").write_separated_list(&self.fields, ", ") << "}"
+    }
+}
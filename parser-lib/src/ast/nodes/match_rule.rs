@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
     ast::{
-        CodeBlock, MatchPattern, MatchRule, SourceBuffer,
+        CodeBlock, Expression, MatchPattern, MatchRule, SourceBuffer,
         derive::Derive,
         prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
     },
@@ -14,6 +14,7 @@ impl Derive for MatchRule {
         let loc = From::from(&p.as_span());
         let mut inner = p.into_inner();
         let mut patterns = vec![];
+        let mut guard = None;
         let then = {
             loop {
                 let next = inner.next().expect("need rules");
@@ -21,6 +22,10 @@ impl Derive for MatchRule {
                     Rule::match_pattern => {
                         patterns.push(MatchPattern::from_parse_tree(next, source));
                     }
+                    Rule::match_guard => {
+                        let cond = next.into_inner().next().expect("need expression");
+                        guard = Some(Expression::from_parse_tree(cond, source));
+                    }
                     Rule::code_block => {
                         break CodeBlock::from_parse_tree(next, source);
                     }
@@ -31,6 +36,7 @@ impl Derive for MatchRule {
         Self {
             loc: source.pointer(loc),
             patterns,
+            guard,
             then,
         }
     }
@@ -38,6 +44,12 @@ impl Derive for MatchRule {
 
 impl PrettyPrintable for MatchRule {
     fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
-        buffer.write_separated_list(&self.patterns, " and ") << " => " << &self.then
+        let buffer = buffer.write_separated_list(&self.patterns, " and ");
+        let buffer = if let Some(guard) = &self.guard {
+            buffer << " if " << guard
+        } else {
+            buffer
+        };
+        buffer << " => " << &self.then
     }
 }
@@ -7,6 +7,7 @@ mod argument_list;
 mod assert_statement;
 mod assign_statement;
 mod break_statement;
+mod bytes_literal;
 mod code_block;
 mod comp_operation;
 mod comp_symbol;
@@ -35,15 +36,20 @@ mod import_path;
 mod import_statement;
 mod import_target;
 mod int_literal;
+mod interpolated_string;
 mod lambda_body;
 mod lambda_function;
 mod list_literal;
 mod log_operation;
 mod log_symbol;
+mod map_literal;
 mod match_pattern;
+mod match_pattern_binding;
 mod match_pattern_comp;
 mod match_pattern_enum_case;
+mod match_pattern_list;
 mod match_pattern_rel;
+mod match_pattern_struct;
 mod match_rule;
 mod match_statement;
 mod method_access;
@@ -78,7 +84,7 @@ mod return_statement;
 mod shift_operation;
 mod shift_symbol;
 mod statement;
-mod string_literal;
+pub(crate) mod string_literal;
 mod struct_decl;
 mod struct_entry;
 mod ternary_expression;
@@ -92,3 +98,4 @@ mod val_decl_entry;
 mod val_decl_statement;
 mod while_statement;
 mod write_op_eq_statement;
+mod yield_statement;
@@ -8,21 +8,27 @@ use crate::{
     gen_from_options,
 };
 
+use crate::ast::BytesLiteral;
 use crate::ast::FloatLiteral;
 use crate::ast::Identifier;
 use crate::ast::IntLiteral;
+use crate::ast::InterpolatedString;
 use crate::ast::ListLiteral;
+use crate::ast::MapLiteral;
 use crate::ast::ParenExpression;
 use crate::ast::StringLiteral;
 
 impl Derive for Primary {
     gen_from_options!(
         primary;
+        (fstr_literal, InterpolatedString),
+        (str_literal, StringLiteral),
+        (bytes_literal, BytesLiteral),
         (int_literal, IntLiteral),
         (fp_literal, FloatLiteral),
         (identifier, Identifier),
         (list_literal, ListLiteral),
-        (str_literal, StringLiteral),
+        (map_literal, MapLiteral),
         (paren_expr, ParenExpression)
     );
 }
@@ -34,7 +40,10 @@ impl PrettyPrintable for Primary {
             Self::FloatLiteral(fp) => fp.prettyprint(buffer),
             Self::Identifier(id) => id.prettyprint(buffer),
             Self::ListLiteral(ll) => ll.prettyprint(buffer),
+            Self::MapLiteral(ml) => ml.prettyprint(buffer),
             Self::StringLiteral(sl) => sl.prettyprint(buffer),
+            Self::BytesLiteral(bl) => bl.prettyprint(buffer),
+            Self::InterpolatedString(is) => is.prettyprint(buffer),
             Self::ParenExpression(pe) => pe.prettyprint(buffer),
         }
     }
@@ -4,7 +4,7 @@ use crate::{
         AssertStatement, AssignStatement, BreakStatement, CodeBlock, ContinueStatement, EnumDecl,
         ExpressionStatement, ForStatement, FunctionDecl, IfStatement, MatchStatement,
         ReturnStatement, Statement, StructDecl, ThrowStatement, TryBlock, ValDeclStatement,
-        WhileStatement, WriteOpEqStatement,
+        WhileStatement, WriteOpEqStatement, YieldStatement,
         derive::Derive,
         prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
     },
@@ -29,6 +29,7 @@ impl Derive for Statement {
         (val_decl_stmt, ValDeclStatement),
         (val_write_stmt, AssignStatement),
         (while_stmt, WhileStatement),
+        (yield_stmt, YieldStatement),
         (struct_decl, StructDecl),
         (enum_decl, EnumDecl),
         (function_decl, FunctionDecl),
@@ -48,6 +49,7 @@ impl PrettyPrintable for Statement {
             Self::CodeBlock(c) => c.prettyprint(buffer),
             Self::ReturnStatement(r) => r.prettyprint(buffer),
             Self::ThrowStatement(t) => t.prettyprint(buffer),
+            Self::YieldStatement(y) => y.prettyprint(buffer),
             Self::TryBlock(t) => t.prettyprint(buffer),
             Self::AssertStatement(a) => a.prettyprint(buffer),
             Self::ExpressionStatement(e) => e.prettyprint(buffer),
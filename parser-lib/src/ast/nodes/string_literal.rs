@@ -1,87 +1,317 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
     ast::{
-        SourceBuffer, StringLiteral,
+        Location, SourceBuffer, StringLiteral,
         derive::Derive,
         prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
     },
     grammar::Rule,
 };
 
-// TODO: process string literals in the compiler code, not the parser
-// the parser has no good way to report an error, so complete the processing
-// in the compiler where we can fail on an invalid escape sequence
+// a character or, from a \x escape, a single byte
+pub(crate) enum Unescaped {
+    Char(char),
+    Byte(u8),
+}
+
+// the grammar only accepts well-formed \x and \u escapes, so all that is
+// left to reject here is a \u escape that is not a valid code point
+pub(crate) fn unescape(s: &str) -> Vec<Unescaped> {
+    let mut result = vec![];
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(Unescaped::Char(c));
+            continue;
+        }
+        let Some(next) = chars.next() else {
+            result.push(Unescaped::Char(c));
+            break;
+        };
+        let escaped = match next {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            '0' => '\0',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            '\\' | '"' | '\'' => next,
+            'X' | 'x' => {
+                let digits = chars.by_ref().take(2).collect::<String>();
+                if let Ok(byte) = u8::from_str_radix(&digits, 16) {
+                    result.push(Unescaped::Byte(byte));
+                }
+                continue;
+            }
+            'U' | 'u' => {
+                let digits = chars
+                    .by_ref()
+                    .skip(1)
+                    .take_while(|&c| c != '}')
+                    .collect::<String>();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER)
+            }
+            _ => {
+                result.push(Unescaped::Char(c));
+                next
+            }
+        };
+        result.push(Unescaped::Char(escaped));
+    }
+
+    result
+}
+
+pub(crate) fn process_string_escapes(s: &str) -> String {
+    unescape(s)
+        .into_iter()
+        .map(|u| match u {
+            Unescaped::Char(c) => c,
+            Unescaped::Byte(b) => b as char,
+        })
+        .collect()
+}
+
+// the lines of a multi-line string, without the line break after the opening
+// quotes, without the line of the closing quotes if nothing else is on it,
+// and without the indentation that they all have in common
+fn strip_indentation(s: &str) -> String {
+    if !s.contains('\n') {
+        return s.to_owned();
+    }
+    let s = s
+        .strip_prefix("\r\n")
+        .or_else(|| s.strip_prefix('\n'))
+        .unwrap_or(s);
+    let mut lines = s
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect::<Vec<_>>();
+    let is_blank = |line: &str| line.trim_start_matches([' ', '\t']).is_empty();
+    let indent_of = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+
+    // the closing quotes count towards the common indentation
+    let mut indent = usize::MAX;
+    if let Some(last) = lines.last()
+        && is_blank(last)
+    {
+        indent = last.len();
+        lines.pop();
+    }
+    indent = lines
+        .iter()
+        .filter(|line| !is_blank(line))
+        .map(|line| indent_of(line))
+        .fold(indent, usize::min);
+
+    lines
+        .iter()
+        .map(|line| line.get(indent.min(indent_of(line))..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// the value of a string literal, from its source text with the quotes
 fn process_string_literal(s: &str) -> String {
-    fn process_string_escapes(s: &str) -> String {
-        let mut result = String::new();
-        let mut chars = s.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            if c == '\\' {
-                if let Some(next) = chars.peek() {
-                    match next {
-                        'n' => {
-                            result.push('\n');
-                            chars.next();
-                        }
-                        'r' => {
-                            result.push('\r');
-                            chars.next();
-                        }
-                        't' => {
-                            result.push('\t');
-                            chars.next();
-                        }
-                        '\\' => {
-                            result.push('\\');
-                            chars.next();
-                        }
-                        'X' | 'x' => {
-                            let _ = chars.next();
-                            if let (Some(high), Some(low)) = (chars.next(), chars.next())
-                                && let (Some(high), Some(low)) =
-                                    (high.to_digit(16), low.to_digit(16))
-                            {
-                                result.push((high << 4 | low) as u8 as char);
-                            }
-                        }
-                        'U' | 'u' => {
-                            let _ = chars.next();
-                            if chars.peek() == Some(&'{') {
-                                let _ = chars.next();
-                                let mut hex_digits = String::new();
-                                while let Some(&next) = chars.peek() {
-                                    if next == '}' {
-                                        let _ = chars.next();
-                                        break;
-                                    } else {
-                                        hex_digits.push(chars.next().unwrap());
-                                    }
-                                }
-                                if let Ok(codepoint) = u32::from_str_radix(&hex_digits, 16)
-                                    && let Some(chr) = char::from_u32(codepoint)
-                                {
-                                    result.push(chr);
-                                }
-                            }
-                        }
-                        _ => {
-                            result.push(c);
-                        }
+    if let Some(raw) = s.strip_prefix('r') {
+        match raw.strip_prefix("\"\"\"") {
+            Some(multi_line) => strip_indentation(&multi_line[..multi_line.len() - 3]),
+            None => raw[1..raw.len() - 1].to_owned(),
+        }
+    } else if let Some(multi_line) = s.strip_prefix("\"\"\"") {
+        process_string_escapes(&strip_indentation(&multi_line[..multi_line.len() - 3]))
+    } else {
+        process_string_escapes(&s[1..s.len() - 1])
+    }
+}
+
+// when a module does not parse, a malformed string literal is a likelier
+// culprit than whatever the parser tripped over afterwards, so this looks for
+// one that starts before the parse error: a bad \x or \u escape, or a string
+// that never ends, usually because \" does not close it
+pub(crate) fn diagnose_string_literals(src: &str, error_pos: usize) -> Option<(Location, String)> {
+    let mut scanner = StringScanner {
+        src: src.as_bytes(),
+        pos: 0,
+        error_pos,
+    };
+    scanner.scan_code(false).err()
+}
+
+type StringIssue = (Location, String);
+
+struct StringScanner<'a> {
+    src: &'a [u8],
+    pos: usize,
+    error_pos: usize,
+}
+
+impl StringScanner<'_> {
+    fn at(&self, offset: usize) -> Option<u8> {
+        self.src.get(self.pos + offset).copied()
+    }
+
+    fn is_prefix(&self, offset: usize, c: u8) -> bool {
+        self.pos >= offset
+            && self.src[self.pos - offset] == c
+            && (self.pos == offset || !is_identifier_byte(self.src[self.pos - offset - 1]))
+    }
+
+    // code outside of string literals, or inside the braces of an f-string
+    // (in which case this returns past the closing brace)
+    fn scan_code(&mut self, in_braces: bool) -> Result<(), StringIssue> {
+        let mut depth = 0;
+        while let Some(c) = self.at(0) {
+            if self.pos > self.error_pos {
+                break;
+            }
+            match c {
+                b'#' => {
+                    while self.at(0).is_some_and(|c| c != b'\n') {
+                        self.pos += 1;
                     }
-                } else {
-                    result.push(c);
+                    continue;
                 }
-            } else {
-                result.push(c);
+                b'"' | b'\'' => {
+                    let raw = self.is_prefix(1, b'r');
+                    let fmt = self.is_prefix(1, b'f');
+                    self.scan_string(raw, fmt)?;
+                    continue;
+                }
+                b'{' if in_braces => depth += 1,
+                b'}' if in_braces => {
+                    if depth == 0 {
+                        self.pos += 1;
+                        return Ok(());
+                    }
+                    depth -= 1;
+                }
+                _ => {}
             }
+            self.pos += 1;
         }
+        Ok(())
+    }
 
-        result
+    fn scan_string(&mut self, raw: bool, fmt: bool) -> Result<(), StringIssue> {
+        let start = self.pos;
+        let quote = self.src[start];
+        let delimiter: &[u8] = if self.src[start..].starts_with(b"\"\"\"") {
+            b"\"\"\""
+        } else {
+            &self.src[start..=start]
+        };
+        self.pos += delimiter.len();
+
+        let mut escaped_quote = None;
+        loop {
+            let Some(c) = self.at(0) else {
+                return Err(match escaped_quote {
+                    Some(at) => escaped_quote_issue(at),
+                    None => (
+                        Location {
+                            start,
+                            stop: start + 1,
+                        },
+                        "unterminated string literal".to_owned(),
+                    ),
+                });
+            };
+            if self.src[self.pos..].starts_with(delimiter) {
+                self.pos += delimiter.len();
+                return Ok(());
+            }
+            match c {
+                b'\\' if !raw => {
+                    self.check_escape()?;
+                    if self.at(1) == Some(quote) && escaped_quote.is_none() {
+                        escaped_quote = Some(self.pos);
+                    }
+                    self.pos += 2;
+                    continue;
+                }
+                b'\n' if delimiter.len() == 1 => {
+                    if let Some(at) = escaped_quote {
+                        return Err(escaped_quote_issue(at));
+                    }
+                }
+                b'{' if fmt => {
+                    self.pos += 1;
+                    if self.at(0) != Some(b'{') {
+                        self.scan_code(true)?;
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn check_escape(&self) -> Result<(), StringIssue> {
+        let is_hex = |offset| self.at(offset).is_some_and(|c: u8| c.is_ascii_hexdigit());
+        let (valid, msg) = match self.at(1) {
+            Some(b'x' | b'X') => (
+                is_hex(2) && is_hex(3),
+                "invalid escape sequence: \\x takes exactly two hex digits, e.g. \\x41",
+            ),
+            Some(b'u' | b'U') => {
+                let digits = (0..).take_while(|i| is_hex(3 + i)).count();
+                if self.at(2) == Some(b'{')
+                    && (1..=6).contains(&digits)
+                    && self.at(3 + digits) == Some(b'}')
+                {
+                    let hex = &self.src[self.pos + 3..self.pos + 3 + digits];
+                    (
+                        std::str::from_utf8(hex)
+                            .ok()
+                            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                            .and_then(char::from_u32)
+                            .is_some(),
+                        "invalid escape sequence: \\u{...} takes a code point up to 10FFFF, \
+                         other than the surrogates D800 to DFFF",
+                    )
+                } else {
+                    (
+                        false,
+                        "invalid escape sequence: \\u takes one to six hex digits in braces, e.g. \\u{1F600}",
+                    )
+                }
+            }
+            _ => (true, ""),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err((
+                Location {
+                    start: self.pos,
+                    stop: self.pos + 2,
+                },
+                msg.to_owned(),
+            ))
+        }
     }
+}
+
+fn is_identifier_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
 
-    let s = &s[1..s.len() - 1];
-    process_string_escapes(s)
+fn escaped_quote_issue(at: usize) -> StringIssue {
+    (
+        Location {
+            start: at,
+            stop: at + 2,
+        },
+        "unterminated string literal: an escaped quote does not end the string; \
+         write \\\\ for a backslash before the closing quote, or use a raw string r\"...\""
+            .to_owned(),
+    )
 }
 
 impl Derive for StringLiteral {
@@ -15,9 +15,9 @@ impl Derive for StructDecl {
         let mut inner = p.into_inner();
         let name = Identifier::from_parse_tree(inner.next().expect("need identifier"), source);
         let inherits = if let Some(next) = inner.peek() {
-            if next.as_rule() == Rule::expr_list {
-                let expr_list = inner.next().unwrap();
-                expr_list
+            if next.as_rule() == Rule::inherit_list {
+                let inherit_list = inner.next().unwrap();
+                inherit_list
                     .into_inner()
                     .map(|expr| Expression::from_parse_tree(expr, source))
                     .collect()
@@ -1,19 +1,34 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
     ast::{
-        Expression, ThrowStatement,
+        Expression, SourceBuffer, ThrowStatement,
         derive::Derive,
         prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
     },
-    gen_from_components,
+    grammar::Rule,
 };
 
 impl Derive for ThrowStatement {
-    gen_from_components!(throw_stmt; val: Expression);
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::throw_stmt);
+        let loc = From::from(&p.as_span());
+        let mut inner = p.into_inner();
+        let val = Expression::from_parse_tree(inner.next().expect("need value"), source);
+        let cause = inner.next().map(|p| Expression::from_parse_tree(p, source));
+        Self {
+            loc: source.pointer(loc),
+            val,
+            cause,
+        }
+    }
 }
 
 impl PrettyPrintable for ThrowStatement {
     fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
-        buffer << "throw " << &self.val << ";"
+        let mut buffer = buffer << "throw " << &self.val;
+        if let Some(cause) = &self.cause {
+            buffer = buffer << " from " << cause;
+        }
+        buffer << ";"
     }
 }
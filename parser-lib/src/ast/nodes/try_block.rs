@@ -1,19 +1,60 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
     ast::{
-        CodeBlock, Identifier, TryBlock,
+        CatchClause, CodeBlock, DeclarationId, SourceBuffer, TryBlock,
         derive::Derive,
         prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
     },
     gen_from_components,
+    grammar::Rule,
 };
 
+impl Derive for CatchClause {
+    gen_from_components!(catch_clause; id: DeclarationId, body: CodeBlock);
+}
+
+impl PrettyPrintable for CatchClause {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        buffer << " catch " << &self.id << " " << &self.body
+    }
+}
+
 impl Derive for TryBlock {
-    gen_from_components!(try_block; body: CodeBlock, id: Identifier, catch: CodeBlock);
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::try_block);
+        let loc = From::from(&p.as_span());
+        let mut inner = p.into_inner();
+        let body = CodeBlock::from_parse_tree(inner.next().expect("need body"), source);
+        let mut catches = vec![];
+        let mut finally = None;
+        for clause in inner {
+            match clause.as_rule() {
+                Rule::catch_clause => catches.push(CatchClause::from_parse_tree(clause, source)),
+                Rule::finally_clause => {
+                    let block = clause.into_inner().next().expect("need finally block");
+                    finally = Some(CodeBlock::from_parse_tree(block, source));
+                }
+                _ => panic!("try block should only contain catch and finally clauses"),
+            }
+        }
+        Self {
+            loc: source.pointer(loc),
+            body,
+            catches,
+            finally,
+        }
+    }
 }
 
 impl PrettyPrintable for TryBlock {
     fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
-        buffer << "try " << &self.body << " catch (" << &self.id << ") " << &self.catch
+        let mut buffer = buffer << "try " << &self.body;
+        for catch in &self.catches {
+            buffer = catch.prettyprint(buffer);
+        }
+        if let Some(finally) = &self.finally {
+            buffer = buffer << " finally " << finally;
+        }
+        buffer
     }
 }
@@ -1,19 +1,53 @@
 // SPDX-License-Identifier: Apache-2.0
 use crate::{
     ast::{
-        DeclarationId, Expression, ValDeclEntry,
+        DeclarationId, Expression, Identifier, MatchPattern, MatchPatternList, MatchPatternStruct,
+        SourceBuffer, ValDeclEntry,
         derive::Derive,
         prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
     },
-    gen_from_components,
+    grammar::Rule,
 };
 
 impl Derive for ValDeclEntry {
-    gen_from_components!(val_decl_entry; id: DeclarationId, val: Expression);
+    fn from_parse_tree(p: pest::iterators::Pair<'_, Rule>, source: &SourceBuffer) -> Self {
+        assert!(p.as_rule() == Rule::val_decl_entry);
+        let loc = source.pointer(From::from(&p.as_span()));
+        let mut inner = p.into_inner();
+        let target = inner.next().expect("need declaration");
+        let pattern = match target.as_rule() {
+            Rule::match_pattern_list => Some(MatchPattern::MatchPatternList(
+                MatchPatternList::from_parse_tree(target.clone(), source),
+            )),
+            Rule::match_pattern_struct => Some(MatchPattern::MatchPatternStruct(
+                MatchPatternStruct::from_parse_tree(target.clone(), source),
+            )),
+            _ => None,
+        };
+        let id = if pattern.is_some() {
+            DeclarationId::from(&Identifier {
+                loc: loc.clone(),
+                value: format!("__val_pattern_{}", loc.location.start),
+            })
+        } else {
+            DeclarationId::from_parse_tree(target, source)
+        };
+        let val = Expression::from_parse_tree(inner.next().expect("need val"), source);
+        Self {
+            loc,
+            id,
+            pattern,
+            val,
+        }
+    }
 }
 
 impl PrettyPrintable for ValDeclEntry {
     fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
-        buffer << &self.id << " = " << &self.val
+        if let Some(pattern) = &self.pattern {
+            buffer << pattern << " = " << &self.val
+        } else {
+            buffer << &self.id << " = " << &self.val
+        }
     }
 }
@@ -0,0 +1,19 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    ast::{
+        Expression, YieldStatement,
+        derive::Derive,
+        prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+    },
+    gen_from_components,
+};
+
+impl Derive for YieldStatement {
+    gen_from_components!(yield_stmt; val: Expression);
+}
+
+impl PrettyPrintable for YieldStatement {
+    fn prettyprint(&self, buffer: PrintoutAccumulator) -> PrintoutAccumulator {
+        buffer << "yield " << &self.val << ";"
+    }
+}
@@ -0,0 +1,447 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::collections::HashSet;
+
+use pest::{Parser, iterators::Pair};
+
+use crate::{
+    ast::{
+        ParserError, ParserResult, SourceBuffer,
+        prettyprint::{PrettyPrintable, printout_accumulator::PrintoutAccumulator},
+        source_to_ast,
+    },
+    grammar::{HaxbyParser, Rule},
+};
+
+mod tokens;
+
+use tokens::{Token, TokenKind, tokenize};
+
+const INDENT: &str = "    ";
+
+// the innermost grammar rule that matched a token
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Owner {
+    rule: Rule,
+    start: usize,
+    // the rule has no inner rules, so its tokens are a single unit
+    leaf: bool,
+    // the token is part of a type annotation
+    in_type: bool,
+}
+
+// tokens that start a statement or a declaration never continue the line
+// before them, even when that line does not end in a separator
+const LINE_STARTS: &[Rule] = &[
+    Rule::statement,
+    Rule::top_level_entry,
+    Rule::struct_entry,
+    Rule::enum_decl_entry,
+    Rule::match_rule,
+    Rule::module_flag,
+    Rule::elsif_piece,
+    Rule::else_piece,
+];
+
+// a comma separated sequence inside brackets; ends are the last tokens of
+// the elements that should be followed by a comma, closer is the bracket
+struct Sequence {
+    ends: Vec<usize>,
+    closer: usize,
+}
+
+struct Classifier<'a> {
+    tokens: &'a [Token],
+    owners: Vec<Option<Owner>>,
+    line_starts: HashSet<usize>,
+    sequences: Vec<Sequence>,
+}
+
+impl Classifier<'_> {
+    fn token_at(&self, pos: usize) -> usize {
+        self.tokens.partition_point(|t| t.start < pos)
+    }
+
+    // the last token of a span that is not empty
+    fn last_token_before(&self, end: usize) -> usize {
+        self.token_at(end) - 1
+    }
+
+    fn is_punct(&self, idx: usize, text: &str, src: &str) -> bool {
+        let t = &self.tokens[idx];
+        t.kind == TokenKind::Punct && &src[t.start..t.end] == text
+    }
+
+    fn classify(&mut self, pair: Pair<Rule>, in_type: bool, src: &str) {
+        let rule = pair.as_rule();
+        let span = pair.as_span();
+        let owner = Owner {
+            rule,
+            start: span.start(),
+            leaf: pair.clone().into_inner().next().is_none(),
+            in_type,
+        };
+        let first = self.token_at(span.start());
+        for idx in first..self.tokens.len() {
+            if self.tokens[idx].start >= span.end() {
+                break;
+            }
+            self.owners[idx] = Some(owner);
+        }
+        if LINE_STARTS.contains(&rule) && first < self.tokens.len() {
+            self.line_starts.insert(first);
+        }
+
+        let mut elements = vec![];
+        for child in pair.into_inner() {
+            let child_rule = child.as_rule();
+            let child_end = child.as_span().end();
+            let child_in_type = in_type
+                || (matches!(rule, Rule::decl_id | Rule::enum_case_decl)
+                    && child_rule == Rule::expression);
+            if rule == Rule::match_stmt && child_rule == Rule::match_rule {
+                elements.push(self.last_token_before(child_end));
+            }
+            if rule == Rule::enum_decl
+                && child_rule == Rule::enum_decl_entry
+                && let Some(case) = child
+                    .clone()
+                    .into_inner()
+                    .find(|c| c.as_rule() == Rule::enum_case_decl)
+            {
+                elements.push(self.last_token_before(case.as_span().end()));
+            }
+            self.classify(child, child_in_type, src);
+        }
+
+        match rule {
+            Rule::match_stmt => {
+                let closer = (0..=self.last_token_before(span.end()))
+                    .rev()
+                    .find(|&idx| {
+                        self.is_punct(idx, "}", src)
+                            && self.owners[idx].is_some_and(|o| o.rule == Rule::match_stmt)
+                    })
+                    .expect("match without a closing brace");
+                self.sequences.push(Sequence {
+                    ends: elements,
+                    closer,
+                });
+            }
+            Rule::enum_decl => self.sequences.push(Sequence {
+                ends: elements,
+                closer: self.last_token_before(span.end()),
+            }),
+            _ => {}
+        }
+    }
+
+    // lists in brackets whose last element may be followed by a comma
+    fn bracket_lists(&mut self, src: &str) {
+        let mut open = vec![];
+        for idx in 0..self.tokens.len() {
+            let Some(owner) = self.owners[idx] else {
+                continue;
+            };
+            if self.tokens[idx].kind != TokenKind::Punct || owner.rule == Rule::operator_symbol {
+                continue;
+            }
+            match &src[self.tokens[idx].start..self.tokens[idx].end] {
+                "(" | "[" | "{" => open.push(idx),
+                ")" | "]" | "}" => {
+                    let opener = open.pop().expect("unbalanced brackets");
+                    let is_list = matches!(
+                        self.owners[opener].map(|o| o.rule),
+                        Some(
+                            Rule::postfix_term_call
+                                | Rule::list_literal
+                                | Rule::map_literal
+                                | Rule::postfix_term_index
+                                | Rule::postfix_term_index_write
+                                | Rule::postfix_term_object_write
+                                | Rule::match_pattern_list
+                                | Rule::match_pattern_struct
+                                | Rule::function_decl
+                                | Rule::method_decl
+                                | Rule::operator_decl
+                        )
+                    );
+                    let mut last = idx - 1;
+                    while self.tokens[last].kind == TokenKind::Comment {
+                        last -= 1;
+                    }
+                    if self.is_punct(last, ",", src) {
+                        last -= 1;
+                    }
+                    if is_list && last != opener {
+                        self.sequences.push(Sequence {
+                            ends: vec![last],
+                            closer: idx,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// a token as it will be written out
+struct Item<'a> {
+    text: &'a str,
+    start: usize,
+    kind: TokenKind,
+    owner: Option<Owner>,
+    newlines_before: usize,
+    line_start: bool,
+}
+
+impl Item<'_> {
+    fn is(&self, text: &str) -> bool {
+        self.kind == TokenKind::Punct && self.text == text
+    }
+
+    fn owned_by(&self, rules: &[Rule]) -> bool {
+        self.owner.is_some_and(|o| rules.contains(&o.rule))
+    }
+
+    fn is_bracket(&self) -> bool {
+        self.kind == TokenKind::Punct && !self.owned_by(&[Rule::operator_symbol])
+    }
+
+    fn is_opener(&self) -> bool {
+        self.is_bracket() && matches!(self.text, "(" | "[" | "{")
+    }
+
+    fn is_closer(&self) -> bool {
+        self.is_bracket() && matches!(self.text, ")" | "]" | "}")
+    }
+
+    fn is_lambda_bar(&self, opening: bool) -> bool {
+        self.is("|")
+            && self
+                .owner
+                .is_some_and(|o| o.rule == Rule::lambda_f && (o.start == self.start) == opening)
+    }
+}
+
+fn same_leaf(a: &Item, b: &Item) -> bool {
+    match (a.owner, b.owner) {
+        (Some(a), Some(b)) => a.leaf && a == b,
+        _ => false,
+    }
+}
+
+// whether two tokens on the same line are separated by a space
+fn space_between(prev: &Item, cur: &Item) -> bool {
+    if cur.kind == TokenKind::Comment {
+        return true;
+    }
+    if same_leaf(prev, cur) {
+        return false;
+    }
+    if cur.is(",") || cur.is(";") {
+        return false;
+    }
+    if prev.is(",") || prev.is(";") {
+        return true;
+    }
+    if (prev.is_opener() && !prev.is("{")) || (cur.is_closer() && !cur.is("}")) {
+        return false;
+    }
+    if prev.is("{") || cur.is("}") {
+        return !(prev.is("{") && cur.is("}"));
+    }
+    if prev.is(".") || prev.is("::") || prev.is("...") || cur.is(".") || cur.is("::") {
+        return false;
+    }
+    if cur.is("(")
+        && cur.owned_by(&[
+            Rule::postfix_term_call,
+            Rule::function_decl,
+            Rule::method_decl,
+            Rule::operator_decl,
+            Rule::match_pattern_enum_case,
+            Rule::enum_case_decl,
+            Rule::postfix_term_enum_case,
+            Rule::module_flag,
+        ])
+    {
+        return false;
+    }
+    if cur.is("[") && cur.owned_by(&[Rule::postfix_term_index, Rule::postfix_term_index_write]) {
+        return false;
+    }
+    if cur.owned_by(&[
+        Rule::postfix_term_try_protocol_assert_token,
+        Rule::postfix_term_try_protocol_return_token,
+    ]) {
+        return false;
+    }
+    if prev.owned_by(&[Rule::unary_op]) {
+        return false;
+    }
+    if prev.is_lambda_bar(true) || cur.is_lambda_bar(false) {
+        return false;
+    }
+    if cur.is(":") {
+        return cur.owned_by(&[Rule::ternary_expr, Rule::struct_decl, Rule::extension_decl]);
+    }
+    let in_type = |item: &Item| item.owner.is_some_and(|o| o.in_type);
+    if in_type(prev)
+        && in_type(cur)
+        && (prev.owned_by(&[Rule::log_op]) || cur.owned_by(&[Rule::log_op]))
+    {
+        return false;
+    }
+    true
+}
+
+fn layout(items: &[Item]) -> String {
+    let mut out = String::new();
+    // the indentation of the line each open bracket is on
+    let mut brackets: Vec<usize> = vec![];
+    let mut prev: Option<&Item> = None;
+    let mut prev_code: Option<&Item> = None;
+    let mut line_indent = 0;
+
+    for item in items {
+        let new_line =
+            prev.is_some_and(|p| item.newlines_before > 0 || p.kind == TokenKind::Comment);
+        match prev {
+            Some(p) if new_line => {
+                let blank = item.newlines_before > 1 && !p.is_opener() && !item.is_closer();
+                out.push_str(if blank { "\n\n" } else { "\n" });
+
+                let base = brackets.last().map_or(0, |i| i + 1);
+                line_indent = if item.is_closer() {
+                    brackets.last().copied().unwrap_or(0)
+                } else if item.kind == TokenKind::Comment
+                    || item.line_start
+                    || item.is("{")
+                    || prev_code
+                        .is_none_or(|p| p.is(";") || p.is(",") || p.is_opener() || p.is("}"))
+                {
+                    base
+                } else {
+                    base + 1
+                };
+                for _ in 0..line_indent {
+                    out.push_str(INDENT);
+                }
+            }
+            Some(p) if space_between(p, item) => out.push(' '),
+            _ => {}
+        }
+
+        if item.kind == TokenKind::Comment {
+            out.push_str(item.text.trim_end());
+        } else {
+            out.push_str(item.text);
+            prev_code = Some(item);
+        }
+
+        if item.is_opener() {
+            brackets.push(line_indent);
+        } else if item.is_closer() {
+            brackets.pop();
+        }
+        prev = Some(item);
+    }
+
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+fn text_of(src: &SourceBuffer) -> ParserResult<String> {
+    Ok(source_to_ast(src)?
+        .prettyprint(PrintoutAccumulator::default())
+        .value())
+}
+
+// formats the source into the canonical layout, keeping comments and blank
+// lines between statements; the source must parse, and the result is checked
+// to parse into the same program
+pub fn format_source(src: &SourceBuffer) -> ParserResult<String> {
+    let before = text_of(src)?;
+
+    let input = src.content.as_str();
+    let tokens = tokenize(input);
+    let tree = HaxbyParser::parse(Rule::module, input)
+        .expect("source already parsed")
+        .next_back()
+        .expect("invalid parse tree");
+
+    let mut classifier = Classifier {
+        tokens: &tokens,
+        owners: vec![None; tokens.len()],
+        line_starts: HashSet::new(),
+        sequences: vec![],
+    };
+    classifier.classify(tree, false, input);
+    classifier.bracket_lists(input);
+
+    // which tokens to drop, and which ones to follow with a new comma
+    let mut drop = HashSet::new();
+    let mut add_comma = HashSet::new();
+    for seq in &classifier.sequences {
+        let multi_line = seq.closer < tokens.len()
+            && (tokens[seq.closer].newlines_before > 0
+                || tokens[seq.closer - 1].kind == TokenKind::Comment);
+        for &end in &seq.ends {
+            let mut next = end + 1;
+            while next < tokens.len() && tokens[next].kind == TokenKind::Comment {
+                next += 1;
+            }
+            let has_comma = next < tokens.len() && classifier.is_punct(next, ",", input);
+            let after = if has_comma { next + 1 } else { next };
+            let is_last = after == seq.closer;
+            if is_last && !multi_line {
+                if has_comma {
+                    drop.insert(next);
+                }
+            } else if !has_comma {
+                add_comma.insert(end);
+            }
+        }
+    }
+
+    let mut items = vec![];
+    let mut carried_newlines = 0;
+    for (idx, tok) in tokens.iter().enumerate() {
+        if drop.contains(&idx) {
+            carried_newlines += tok.newlines_before;
+            continue;
+        }
+        items.push(Item {
+            text: &input[tok.start..tok.end],
+            start: tok.start,
+            kind: tok.kind,
+            owner: classifier.owners[idx],
+            newlines_before: tok.newlines_before + carried_newlines,
+            line_start: classifier.line_starts.contains(&idx),
+        });
+        carried_newlines = 0;
+        if add_comma.contains(&idx) {
+            items.push(Item {
+                text: ",",
+                start: tok.end,
+                kind: TokenKind::Punct,
+                owner: None,
+                newlines_before: 0,
+                line_start: false,
+            });
+        }
+    }
+
+    let formatted = layout(&items);
+    let after = text_of(&SourceBuffer::stdin_with_name(&formatted, &src.name));
+    if after.as_ref() != Ok(&before) {
+        return Err(ParserError {
+            loc: src.pointer_to_whole_buffer(),
+            msg: "formatting would change the meaning of this code".to_owned(),
+        });
+    }
+    Ok(formatted)
+}
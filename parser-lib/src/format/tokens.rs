@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: Apache-2.0
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Word,
+    Number,
+    String,
+    Comment,
+    Punct,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub start: usize,
+    pub end: usize,
+    // line breaks in the whitespace right before this token
+    pub newlines_before: usize,
+}
+
+// longest first, so that the first match is the right one
+const PUNCTUATION: &[&str] = &[
+    "<<=", ">>=", "...", "==", "!=", "<=", ">=", "&&", "||", "<<", ">>", "+=", "-=", "*=", "/=",
+    "%=", "=>", "::", "??",
+];
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$' || (!c.is_ascii() && !c.is_whitespace())
+}
+
+// the length of the f, r or b prefix of a string literal that starts here
+fn string_prefix_len(rest: &str) -> Option<usize> {
+    match rest.chars().next()? {
+        '"' | '\'' => Some(0),
+        'f' | 'r' | 'b' if rest[1..].starts_with(['"', '\'']) => Some(1),
+        _ => None,
+    }
+}
+
+// where the string literal at start ends; an f-string ends at the first
+// quote outside of its {values}, which may contain strings of their own
+fn string_end(src: &str, start: usize) -> usize {
+    let prefix = string_prefix_len(&src[start..]).unwrap_or(0);
+    let interpolated = src[start..].starts_with('f');
+    let raw = src[start..].starts_with('r');
+    let open = start + prefix;
+    let quote = if !interpolated && src[open..].starts_with("\"\"\"") {
+        "\"\"\""
+    } else {
+        &src[open..open + 1]
+    };
+    let mut depth = 0;
+    let mut idx = open + quote.len();
+    while let Some(c) = src[idx..].chars().next() {
+        let rest = &src[idx..];
+        if depth == 0 && rest.starts_with(quote) {
+            return idx + quote.len();
+        }
+        if !raw && depth == 0 && c == '\\' {
+            if (rest.starts_with("\\u{") || rest.starts_with("\\U{"))
+                && let Some(close) = rest.find('}')
+            {
+                idx += close + 1;
+            } else {
+                idx += 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            }
+            continue;
+        }
+        if interpolated && depth == 0 && (rest.starts_with("{{") || rest.starts_with("}}")) {
+            idx += 2;
+            continue;
+        }
+        if interpolated {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ if depth > 0
+                    && string_prefix_len(rest).is_some()
+                    && !src[..idx].ends_with(is_word_char) =>
+                {
+                    idx = string_end(src, idx);
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        idx += c.len_utf8();
+    }
+    src.len()
+}
+
+// splits source text that is known to parse into tokens; whitespace is not
+// kept, other than the number of line breaks before each token
+pub(crate) fn tokenize(src: &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut newlines = 0;
+    let mut chars = src.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            if c == '\n' {
+                newlines += 1;
+            }
+            chars.next();
+            continue;
+        }
+
+        let kind = if c == '#' {
+            while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            TokenKind::Comment
+        } else if string_prefix_len(&src[start..]).is_some() {
+            let end = string_end(src, start);
+            while chars.next_if(|&(idx, _)| idx < end).is_some() {}
+            TokenKind::String
+        } else if c.is_ascii_digit() {
+            let mut prev = c;
+            chars.next();
+            while let Some(&(idx, n)) = chars.peek() {
+                let exponent_sign = (n == '+' || n == '-')
+                    && (prev == 'e' || prev == 'E')
+                    && !src[start..idx].starts_with("0x");
+                let fraction = n == '.' && src[idx + 1..].starts_with(|d: char| d.is_ascii_digit());
+                if !(n.is_ascii_alphanumeric() || n == '_' || exponent_sign || fraction) {
+                    break;
+                }
+                prev = n;
+                chars.next();
+            }
+            TokenKind::Number
+        } else if is_word_char(c) {
+            while chars.next_if(|&(_, n)| is_word_char(n)).is_some() {}
+            TokenKind::Word
+        } else {
+            let len = PUNCTUATION
+                .iter()
+                .find(|p| src[start..].starts_with(*p))
+                .map_or(c.len_utf8(), |p| p.len());
+            while chars.next_if(|&(idx, _)| idx < start + len).is_some() {}
+            TokenKind::Punct
+        };
+
+        let end = chars.peek().map_or(src.len(), |&(idx, _)| idx);
+        tokens.push(Token {
+            kind,
+            start,
+            end,
+            newlines_before: newlines,
+        });
+        newlines = 0;
+    }
+
+    tokens
+}
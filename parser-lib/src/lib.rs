@@ -1,3 +1,5 @@
 // SPDX-License-Identifier: Apache-2.0
 pub mod ast;
+pub mod format;
 pub mod grammar;
+pub mod tags;
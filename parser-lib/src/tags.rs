@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{collections::HashSet, sync::LazyLock};
+
+use regex::Regex;
+
+static TAGS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^\s*###\s*TAGS:\s*(.+)\s*$").unwrap());
+
+// test files declare tags with lines of the form ### TAGS: a, b, c anywhere in
+// the file; tags are case insensitive and returned uppercased
+pub fn parse_tags(text: &str) -> HashSet<String> {
+    let mut tags = HashSet::new();
+    for line in text.lines() {
+        if let Some(cap) = TAGS_RE.captures(line)
+            && let Some(list) = cap.get(1)
+        {
+            for t in list.as_str().split(',') {
+                let t = t.trim();
+                if !t.is_empty() {
+                    tags.insert(t.to_ascii_uppercase());
+                }
+            }
+        }
+    }
+    tags
+}
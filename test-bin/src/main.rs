@@ -3,16 +3,20 @@
 use std::{
     collections::HashSet,
     fmt::Display,
+    path::{Path, PathBuf},
     process::{ExitCode, Termination, exit},
     time::{Duration, Instant},
 };
 
 use aria_compiler::compile_from_source;
-use aria_parser::ast::SourceBuffer;
+use aria_parser::{ast::SourceBuffer, tags::parse_tags};
 use clap::Parser;
 use enum_as_inner::EnumAsInner;
 use glob::Paths;
-use haxby_vm::vm::VirtualMachine;
+use haxby_vm::{
+    coverage::{Coverage, CoverageOptions},
+    vm::VirtualMachine,
+};
 use rayon::prelude::*;
 use regex::Regex;
 
@@ -44,6 +48,23 @@ struct Args {
     /// Skip tests whose file name matches any of these regexes. May repeat.
     #[arg(long = "skip-pattern")]
     skip_pattern: Vec<String>,
+    /// Record which lines the tests run, and write them to this file as an lcov tracefile
+    #[arg(long = "coverage-lcov")]
+    coverage_lcov: Option<PathBuf>,
+    /// Record which lines the tests run, and print how much of each file ran
+    #[arg(long = "coverage-summary")]
+    coverage_summary: bool,
+    /// Also record which way each condition went, as branch coverage
+    #[arg(long = "coverage-branches")]
+    coverage_branches: bool,
+}
+
+impl Args {
+    fn coverage_options(&self) -> Option<CoverageOptions> {
+        (self.coverage_lcov.is_some() || self.coverage_summary).then_some(CoverageOptions {
+            branches: self.coverage_branches,
+        })
+    }
 }
 
 #[derive(Clone, EnumAsInner)]
@@ -77,6 +98,7 @@ struct TestCaseResult {
     test: String,
     duration: Duration,
     result: TestCaseOutcome,
+    coverage: Option<Coverage>,
 }
 
 impl TestCaseResult {
@@ -85,6 +107,7 @@ impl TestCaseResult {
             test: test.to_owned(),
             duration,
             result: TestCaseOutcome::Pass,
+            coverage: None,
         }
     }
 
@@ -93,6 +116,7 @@ impl TestCaseResult {
             test: test.to_owned(),
             duration,
             result: TestCaseOutcome::Fail(reason),
+            coverage: None,
         }
     }
 
@@ -101,6 +125,7 @@ impl TestCaseResult {
             test: test.to_owned(),
             duration,
             result: TestCaseOutcome::XFail(reason),
+            coverage: None,
         }
     }
 }
@@ -127,30 +152,12 @@ fn should_skip_file_name(path: &std::path::Path, skip_regex: &[Regex]) -> bool {
 }
 
 fn parse_tags_from_file(path: &str) -> HashSet<String> {
-    let mut tags = HashSet::new();
-    let Ok(text) = std::fs::read_to_string(path) else {
-        return tags;
-    };
-
-    static TAGS_RE: once_cell::sync::Lazy<Regex> =
-        once_cell::sync::Lazy::new(|| Regex::new(r"(?i)^\s*###\s*TAGS:\s*(.+)\s*$").unwrap());
-
-    for line in text.lines() {
-        if let Some(cap) = TAGS_RE.captures(line)
-            && let Some(list) = cap.get(1)
-        {
-            for t in list.as_str().split(',') {
-                let t = t.trim();
-                if !t.is_empty() {
-                    tags.insert(t.to_ascii_uppercase());
-                }
-            }
-        }
-    }
-    tags
+    std::fs::read_to_string(path)
+        .map(|text| parse_tags(&text))
+        .unwrap_or_default()
 }
 
-fn run_test_from_pattern(path: &str) -> TestCaseResult {
+fn run_test_from_pattern(path: &str, coverage: Option<&CoverageOptions>) -> TestCaseResult {
     let tags = parse_tags_from_file(path);
     let start_wall = Instant::now();
 
@@ -181,6 +188,9 @@ fn run_test_from_pattern(path: &str) -> TestCaseResult {
         };
 
         let mut vm = VirtualMachine::default();
+        if let Some(options) = coverage {
+            vm.start_coverage(options.clone());
+        }
 
         let entry_rm = match vm.load_module("", entry_cm) {
             Ok(rle) => match rle {
@@ -194,7 +204,7 @@ fn run_test_from_pattern(path: &str) -> TestCaseResult {
             Err(err) => return TestCaseResult::fail(path, start.elapsed(), err.prettyprint(None)),
         };
 
-        match vm.execute_module(&entry_rm) {
+        let result = match vm.execute_module(&entry_rm) {
             Ok(rle) => match rle {
                 haxby_vm::vm::RunloopExit::Ok(_) => TestCaseResult::pass(path, start.elapsed()),
                 haxby_vm::vm::RunloopExit::Exception(e) => {
@@ -206,6 +216,10 @@ fn run_test_from_pattern(path: &str) -> TestCaseResult {
             Err(err) => {
                 TestCaseResult::fail(path, start.elapsed(), err.prettyprint(Some(entry_rm)))
             }
+        };
+        TestCaseResult {
+            coverage: vm.stop_coverage(),
+            ..result
         }
     };
 
@@ -227,7 +241,10 @@ fn run_test_from_pattern(path: &str) -> TestCaseResult {
                 );
             }
             TestCaseOutcome::Fail(reason) => {
-                return TestCaseResult::xfail(path, start_wall.elapsed(), reason.clone());
+                return TestCaseResult {
+                    coverage: outcome.coverage,
+                    ..TestCaseResult::xfail(path, start_wall.elapsed(), reason.clone())
+                };
             }
             _ => {
                 panic!("test runner should only produce pass/fail")
@@ -244,6 +261,8 @@ struct SuiteReport {
     fails: Vec<TestCaseResult>,
     xfails: Vec<TestCaseResult>,
     duration: Duration,
+    // of all the tests together
+    coverage: Coverage,
 }
 
 impl SuiteReport {
@@ -283,9 +302,9 @@ impl SuiteReport {
                 self.xfails.sort_by(|a, b| a.test.cmp(&b.test));
             }
             SortBy::Duration => {
-                self.passes.sort_by(|a, b| a.duration.cmp(&b.duration));
-                self.fails.sort_by(|a, b| a.duration.cmp(&b.duration));
-                self.xfails.sort_by(|a, b| a.duration.cmp(&b.duration));
+                self.passes.sort_by_key(|a| a.duration);
+                self.fails.sort_by_key(|a| a.duration);
+                self.xfails.sort_by_key(|a| a.duration);
             }
         }
 
@@ -345,7 +364,7 @@ fn run_tests_from_pattern(patterns: Paths, args: &Args, skip_regex: &[Regex]) ->
             if args.verbose {
                 println!("Running {test_name} (at {test_path})");
             }
-            let result = run_test_from_pattern(test_path);
+            let result = run_test_from_pattern(test_path, args.coverage_options().as_ref());
             if args.fail_fast && result.result.is_fail() {
                 ret.push(result);
                 break;
@@ -361,7 +380,7 @@ fn run_tests_from_pattern(patterns: Paths, args: &Args, skip_regex: &[Regex]) ->
             .par_bridge()
             .map(|path| {
                 let test_path = path.as_os_str().to_str().unwrap();
-                run_test_from_pattern(test_path)
+                run_test_from_pattern(test_path, args.coverage_options().as_ref())
             })
             .collect::<_>()
     };
@@ -369,6 +388,9 @@ fn run_tests_from_pattern(patterns: Paths, args: &Args, skip_regex: &[Regex]) ->
     results.duration = start.elapsed();
 
     for result in outcomes {
+        if let Some(coverage) = &result.coverage {
+            results.coverage.merge(coverage);
+        }
         match &result.result {
             TestCaseOutcome::Pass => results.pass(result),
             TestCaseOutcome::Fail(_) => {
@@ -383,6 +405,19 @@ fn run_tests_from_pattern(patterns: Paths, args: &Args, skip_regex: &[Regex]) ->
     results
 }
 
+fn write_coverage(coverage: &mut Coverage, args: &Args) -> Result<(), String> {
+    // test files and the library they exercise, but not code without a file
+    coverage.retain_files(|file| Path::new(file).is_file());
+    if let Some(dest) = &args.coverage_lcov {
+        std::fs::write(dest, coverage.lcov())
+            .map_err(|e| format!("could not write {}: {e}", dest.display()))?;
+    }
+    if args.coverage_summary {
+        print!("{}", coverage.summary());
+    }
+    Ok(())
+}
+
 fn main() -> SuiteReport {
     let args = Args::parse();
     if args.fail_fast && !args.sequential {
@@ -407,6 +442,13 @@ fn main() -> SuiteReport {
             exit(1);
         }
     };
+    if args.coverage_options().is_some()
+        && let Err(err) = write_coverage(&mut results.coverage, &args)
+    {
+        eprintln!("{err}");
+        exit(1);
+    }
+
     if results.num_fails() == 0 && !args.verbose {
         println!("All tests passed; --verbose to print full report");
         exit(0);
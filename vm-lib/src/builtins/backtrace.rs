@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::cell::OnceCell;
+
+use crate::{
+    builtins::VmGlobals,
+    error::{
+        backtrace::Backtrace as CallBacktrace,
+        vm_error::{VmError, VmErrorReason},
+    },
+    frame::Frame,
+    runtime_value::{
+        RuntimeValue, function::BuiltinFunctionImpl, list::List, object::Object, structure::Struct,
+    },
+    vm::RunloopExit,
+};
+
+#[derive(Default)]
+struct Backtrace {
+    frame_struct: OnceCell<Struct>,
+}
+
+impl Backtrace {
+    fn frame_struct(&self, vm: &mut crate::vm::VirtualMachine) -> Result<&Struct, VmError> {
+        if let Some(frame_struct) = self.frame_struct.get() {
+            return Ok(frame_struct);
+        }
+
+        let backtrace_mod = vm
+            .find_imported_module("aria.core.backtrace")
+            .ok_or_else(|| {
+                VmErrorReason::ImportNotAvailable(
+                    "aria.core.backtrace".to_owned(),
+                    "module not found".to_owned(),
+                )
+            })?;
+        let frame_struct = backtrace_mod
+            .load_named_value("StackFrame")
+            .ok_or_else(|| {
+                VmErrorReason::NoSuchIdentifier("aria.core.backtrace.StackFrame".to_owned())
+            })?;
+        let frame_struct = frame_struct
+            .as_struct()
+            .ok_or(VmErrorReason::UnexpectedType)?;
+        let _ = self.frame_struct.set(frame_struct.clone());
+        Ok(self.frame_struct.get().unwrap())
+    }
+}
+
+impl BuiltinFunctionImpl for Backtrace {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        vm: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let frame_struct = self.frame_struct(vm)?.clone();
+        let frames = List::from(&[]);
+        for entry in CallBacktrace::capture(vm).entries_iter() {
+            let function = entry.function.as_deref().unwrap_or_default();
+            let module = match &entry.module {
+                Some(module) => vm
+                    .globals
+                    .create_maybe_some(RuntimeValue::String(module.as_str().into()))?,
+                None => vm.globals.create_maybe_none()?,
+            };
+            let fields = [
+                ("function", RuntimeValue::String(function.into())),
+                ("module", module),
+                ("file", RuntimeValue::String(entry.file().into())),
+                ("line", RuntimeValue::Integer((entry.line() as i64).into())),
+                (
+                    "column",
+                    RuntimeValue::Integer((entry.column() as i64).into()),
+                ),
+                ("source", RuntimeValue::String(entry.source_line().into())),
+                (
+                    "tail_calls",
+                    RuntimeValue::Integer((entry.tail_calls as i64).into()),
+                ),
+            ];
+
+            let obj = Object::new(&frame_struct);
+            for (name, val) in fields {
+                let sym = vm.globals.intern_symbol(name)?;
+                obj.write(&mut vm.globals, sym, val);
+            }
+            frames.append(RuntimeValue::Object(obj));
+        }
+
+        frame.stack.push(RuntimeValue::List(frames));
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::zero()
+    }
+
+    fn name(&self) -> &str {
+        "backtrace"
+    }
+}
+
+pub(super) fn insert_builtins(builtins: &mut VmGlobals) {
+    builtins.insert_builtin::<Backtrace>();
+}
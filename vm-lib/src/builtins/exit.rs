@@ -10,10 +10,11 @@ impl BuiltinFunctionImpl for Exit {
     fn eval(
         &self,
         frame: &mut Frame,
-        _: &mut crate::vm::VirtualMachine,
+        vm: &mut crate::vm::VirtualMachine,
     ) -> crate::vm::ExecutionResult<RunloopExit> {
         let code = VmGlobals::extract_arg(frame, |x| x.as_integer().cloned())?;
-        std::process::exit(*code.raw_value() as i32);
+        vm.options.permissions.check_exit()?;
+        std::process::exit(code.wrapping_i64() as i32);
     }
 
     fn arity(&self) -> crate::arity::Arity {
@@ -1,11 +1,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use haxby_opcodes::function_attribs::FUNC_IS_METHOD;
+use num_bigint::BigInt;
+use num_traits::FromPrimitive;
 
 use crate::{
     frame::Frame,
     runtime_value::{
-        RuntimeValue, function::BuiltinFunctionImpl, kind::RuntimeValueType,
+        RuntimeValue, function::BuiltinFunctionImpl, integer::IntegerValue, kind::RuntimeValueType,
         rust_native_type::RustNativeType,
     },
     vm::RunloopExit,
@@ -103,8 +105,16 @@ impl BuiltinFunctionImpl for FpInt {
         _: &mut crate::vm::VirtualMachine,
     ) -> crate::vm::ExecutionResult<RunloopExit> {
         let this = VmGlobals::extract_arg(frame, |x| x.as_float().cloned())?;
-        let iv = *this.raw_value() as i64;
-        frame.stack.push(RuntimeValue::Integer(iv.into()));
+        let fv = *this.raw_value();
+        // values outside the i64 range become big integers, NaN and infinities saturate
+        let iv = if (i64::MIN as f64..i64::MAX as f64).contains(&fv) {
+            IntegerValue::from(fv as i64)
+        } else {
+            BigInt::from_f64(fv)
+                .map(IntegerValue::from)
+                .unwrap_or_else(|| IntegerValue::from(fv as i64))
+        };
+        frame.stack.push(RuntimeValue::Integer(iv));
         Ok(RunloopExit::Ok(()))
     }
 
@@ -0,0 +1,89 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    builtins::VmGlobals,
+    frame::Frame,
+    runtime_value::{
+        RuntimeValue, function::BuiltinFunctionImpl, object::Object, structure::Struct,
+    },
+    vm::RunloopExit,
+};
+
+#[derive(Default)]
+struct Gc {}
+impl BuiltinFunctionImpl for Gc {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        _: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let collected = crate::gc::collect();
+        frame
+            .stack
+            .push(RuntimeValue::Integer((collected as i64).into()));
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::zero()
+    }
+
+    fn name(&self) -> &str {
+        "gc"
+    }
+}
+
+struct GcStats {
+    stats_struct: Struct,
+}
+
+impl Default for GcStats {
+    fn default() -> Self {
+        Self {
+            stats_struct: Struct::new("GcStats"),
+        }
+    }
+}
+
+impl BuiltinFunctionImpl for GcStats {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        vm: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let stats = crate::gc::stats();
+        let fields = [
+            ("allocations", stats.allocations as i64),
+            ("tracked", stats.tracked as i64),
+            (
+                "allocations_since_collection",
+                stats.allocations_since_collection as i64,
+            ),
+            ("threshold", stats.threshold as i64),
+            ("collections", stats.collections as i64),
+            ("collected", stats.collected as i64),
+            ("last_collected", stats.last_collected as i64),
+        ];
+
+        let obj = Object::new(&self.stats_struct);
+        for (name, val) in fields {
+            let sym = vm.globals.intern_symbol(name)?;
+            obj.write(&mut vm.globals, sym, RuntimeValue::Integer(val.into()));
+        }
+
+        frame.stack.push(RuntimeValue::Object(obj));
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::zero()
+    }
+
+    fn name(&self) -> &str {
+        "gc_stats"
+    }
+}
+
+pub(super) fn insert_builtins(builtins: &mut VmGlobals) {
+    builtins.insert_builtin::<Gc>();
+    builtins.insert_builtin::<GcStats>();
+}
@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::cell::RefCell;
+
+use haxby_opcodes::{BuiltinTypeId, function_attribs::FUNC_IS_METHOD};
+
+use crate::{
+    arity::Arity,
+    builtins::VmGlobals,
+    error::vm_error::VmErrorReason,
+    frame::Frame,
+    runtime_value::{
+        RuntimeValue,
+        function::{BuiltinFunctionImpl, Function},
+        kind::RuntimeValueType,
+        object::Object,
+        opaque::OpaqueValue,
+        structure::Struct,
+    },
+    symbol::INTERNED_ATTR_IMPL,
+    vm::{ExecutionResult, RunloopExit, VirtualMachine},
+};
+
+// the frame of a generator outlives the call that created it; it is parked
+// here between calls to next(), and handed back to the VM once the body
+// has run to completion
+enum GeneratorState {
+    Suspended(Box<Frame>),
+    Running,
+    Finished,
+}
+
+struct GeneratorImpl {
+    func: Function,
+    state: RefCell<GeneratorState>,
+}
+
+impl GeneratorImpl {
+    fn resume(
+        &self,
+        vm: &mut VirtualMachine,
+    ) -> ExecutionResult<RunloopExit<Option<RuntimeValue>>> {
+        let mut frame = match self.state.replace(GeneratorState::Running) {
+            GeneratorState::Suspended(frame) => frame,
+            GeneratorState::Running => {
+                return Err(VmErrorReason::OperationFailed(
+                    "generator is already running".to_owned(),
+                )
+                .into());
+            }
+            GeneratorState::Finished => {
+                self.state.replace(GeneratorState::Finished);
+                return Ok(RunloopExit::Ok(None));
+            }
+        };
+
+        let argc = frame.argc;
+        let result = self.func.eval_in_frame(argc, &mut frame, vm);
+        match result {
+            Ok(RunloopExit::Ok(_)) if frame.is_suspended() => {
+                let val = frame.stack.try_pop().ok_or(VmErrorReason::EmptyStack)?;
+                self.state.replace(GeneratorState::Suspended(frame));
+                Ok(RunloopExit::Ok(Some(val)))
+            }
+            // returning from the body ends the iteration, the value is discarded
+            Ok(RunloopExit::Ok(_)) => {
+                self.finish(frame, vm);
+                Ok(RunloopExit::Ok(None))
+            }
+            Ok(RunloopExit::Exception(e)) => {
+                self.finish(frame, vm);
+                Ok(RunloopExit::Exception(e))
+            }
+            Err(e) => {
+                self.finish(frame, vm);
+                Err(e)
+            }
+        }
+    }
+
+    fn finish(&self, frame: Box<Frame>, vm: &mut VirtualMachine) {
+        self.state.replace(GeneratorState::Finished);
+        vm.release_frame(frame);
+    }
+}
+
+// wraps a frame that has been set up for a call to func, but not run yet
+pub(crate) fn create_generator(
+    func: &Function,
+    frame: Box<Frame>,
+    builtins: &mut VmGlobals,
+) -> Result<RuntimeValue, VmErrorReason> {
+    let generator_type = builtins.get_builtin_type_by_id(BuiltinTypeId::Generator);
+    let generator_struct = generator_type
+        .as_struct()
+        .ok_or(VmErrorReason::UnexpectedType)?;
+
+    let obj = Object::new(generator_struct);
+    let imp = OpaqueValue::new(GeneratorImpl {
+        func: func.clone(),
+        state: RefCell::new(GeneratorState::Suspended(frame)),
+    });
+    obj.write(builtins, INTERNED_ATTR_IMPL, RuntimeValue::Opaque(imp));
+    Ok(RuntimeValue::Object(obj))
+}
+
+#[derive(Default)]
+struct Next {}
+impl BuiltinFunctionImpl for Next {
+    fn eval(&self, frame: &mut Frame, vm: &mut VirtualMachine) -> ExecutionResult<RunloopExit> {
+        let aria_this = VmGlobals::extract_arg(frame, |x: RuntimeValue| x.as_object().cloned())?;
+        let generator = aria_this
+            .read(&vm.globals, INTERNED_ATTR_IMPL)
+            .ok_or(VmErrorReason::UnexpectedVmState)?
+            .as_opaque_concrete::<GeneratorImpl>()
+            .ok_or(VmErrorReason::UnexpectedVmState)?;
+
+        match generator.resume(vm)? {
+            RunloopExit::Ok(Some(val)) => frame.stack.push(vm.globals.create_maybe_some(val)?),
+            RunloopExit::Ok(None) => frame.stack.push(vm.globals.create_maybe_none()?),
+            RunloopExit::Exception(e) => return Ok(RunloopExit::Exception(e)),
+        }
+
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::required(1)
+    }
+
+    fn name(&self) -> &str {
+        "next"
+    }
+}
+
+#[derive(Default)]
+struct IteratorFn {}
+impl BuiltinFunctionImpl for IteratorFn {
+    fn eval(&self, frame: &mut Frame, _: &mut VirtualMachine) -> ExecutionResult<RunloopExit> {
+        let aria_this = VmGlobals::extract_arg(frame, Some)?;
+        frame.stack.push(aria_this);
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD
+    }
+
+    fn arity(&self) -> Arity {
+        Arity::required(1)
+    }
+
+    fn name(&self) -> &str {
+        "iterator"
+    }
+}
+
+pub(super) fn insert_generator_builtins(builtins: &mut VmGlobals) {
+    let generator_struct = Struct::new("Generator");
+    generator_struct.insert_builtin::<Next>(builtins);
+    generator_struct.insert_builtin::<IteratorFn>(builtins);
+
+    builtins.register_builtin_type(
+        BuiltinTypeId::Generator,
+        RuntimeValueType::Struct(generator_struct),
+    );
+}
@@ -15,6 +15,7 @@ impl BuiltinFunctionImpl for Getenv {
         vm: &mut crate::vm::VirtualMachine,
     ) -> crate::vm::ExecutionResult<RunloopExit> {
         let var_name = VmGlobals::extract_arg(frame, |x| x.as_string().cloned())?;
+        vm.options.permissions.check_env()?;
         match std::env::var(var_name.raw_value()).map(|s| RuntimeValue::String(s.into())) {
             Ok(s) => match vm.globals.create_maybe_some(s) {
                 Ok(s) => {
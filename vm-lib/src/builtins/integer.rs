@@ -5,7 +5,10 @@ use haxby_opcodes::function_attribs::FUNC_IS_METHOD;
 use crate::{
     frame::Frame,
     runtime_value::{
-        RuntimeValue, function::BuiltinFunctionImpl, kind::RuntimeValueType,
+        RuntimeValue,
+        function::BuiltinFunctionImpl,
+        integer::{IntegerRepr, IntegerValue},
+        kind::RuntimeValueType,
         rust_native_type::RustNativeType,
     },
     vm::RunloopExit,
@@ -13,7 +16,10 @@ use crate::{
 
 use super::VmGlobals;
 
-fn int_format(n: i64, fmt: &str) -> String {
+fn int_format<T>(n: T, fmt: &str) -> String
+where
+    T: std::fmt::Display + std::fmt::LowerHex + std::fmt::UpperHex,
+{
     // Determine if format ends with 'x' or 'X' for hexadecimal formatting
     let (base, digits_spec) = if let Some(stripped) = fmt.strip_suffix('x') {
         (16, (stripped, false)) // lowercase hex
@@ -50,7 +56,10 @@ impl BuiltinFunctionImpl for Prettyprint {
     ) -> crate::vm::ExecutionResult<RunloopExit> {
         let this = VmGlobals::extract_arg(frame, |x| x.as_integer().cloned())?;
         let format_style = VmGlobals::extract_arg(frame, |x| x.as_string().cloned())?;
-        let output_string = int_format(*this.raw_value(), format_style.raw_value());
+        let output_string = match this.raw_value() {
+            IntegerRepr::Small(n) => int_format(*n, format_style.raw_value()),
+            IntegerRepr::Big(n) => int_format(n, format_style.raw_value()),
+        };
         frame.stack.push(RuntimeValue::String(output_string.into()));
         Ok(RunloopExit::Ok(()))
     }
@@ -68,11 +77,57 @@ impl BuiltinFunctionImpl for Prettyprint {
     }
 }
 
+// the wrapping operations truncate both operands and the result to 64 bits,
+// for hashes and random number generators that rely on two's complement overflow
+macro_rules! wrapping_op {
+    ($name:ident, $aria_name:literal, $op:expr) => {
+        #[derive(Default)]
+        struct $name {}
+        impl BuiltinFunctionImpl for $name {
+            fn eval(
+                &self,
+                frame: &mut Frame,
+                _: &mut crate::vm::VirtualMachine,
+            ) -> crate::vm::ExecutionResult<RunloopExit> {
+                let this = VmGlobals::extract_arg(frame, |x| x.as_integer().cloned())?;
+                let other = VmGlobals::extract_arg(frame, |x| x.as_integer().cloned())?;
+                let result: i64 = $op(this.wrapping_i64(), other.wrapping_i64());
+                frame
+                    .stack
+                    .push(RuntimeValue::Integer(IntegerValue::from(result)));
+                Ok(RunloopExit::Ok(()))
+            }
+
+            fn attrib_byte(&self) -> u8 {
+                FUNC_IS_METHOD
+            }
+
+            fn arity(&self) -> crate::arity::Arity {
+                crate::arity::Arity::required(2)
+            }
+
+            fn name(&self) -> &str {
+                $aria_name
+            }
+        }
+    };
+}
+
+wrapping_op!(WrappingAdd, "wrapping_add", i64::wrapping_add);
+wrapping_op!(WrappingSub, "wrapping_sub", i64::wrapping_sub);
+wrapping_op!(WrappingMul, "wrapping_mul", i64::wrapping_mul);
+wrapping_op!(WrappingShl, "wrapping_shl", |a: i64, b: i64| a
+    .wrapping_shl(b as u32));
+
 pub(super) fn insert_integer_builtins(builtins: &mut VmGlobals) {
     let int_builtin =
         RustNativeType::new(crate::runtime_value::rust_native_type::RustNativeValueKind::Integer);
 
     int_builtin.insert_builtin::<Prettyprint>(builtins);
+    int_builtin.insert_builtin::<WrappingAdd>(builtins);
+    int_builtin.insert_builtin::<WrappingSub>(builtins);
+    int_builtin.insert_builtin::<WrappingMul>(builtins);
+    int_builtin.insert_builtin::<WrappingShl>(builtins);
 
     builtins.register_builtin_type(
         haxby_opcodes::BuiltinTypeId::Int,
@@ -0,0 +1,302 @@
+// SPDX-License-Identifier: Apache-2.0
+use haxby_opcodes::function_attribs::{FUNC_IS_METHOD, METHOD_ATTRIBUTE_TYPE};
+
+use crate::{
+    frame::Frame,
+    runtime_value::{
+        RuntimeValue, function::BuiltinFunctionImpl, kind::RuntimeValueType, list::List, map::Map,
+        rust_native_type::RustNativeType,
+    },
+    vm::RunloopExit,
+};
+
+use super::VmGlobals;
+
+#[derive(Default)]
+struct MapLen {}
+impl BuiltinFunctionImpl for MapLen {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        _: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let this = VmGlobals::extract_arg(frame, |x| x.as_map().cloned())?;
+        let len = this.len() as i64;
+        frame.stack.push(RuntimeValue::Integer(len.into()));
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::required(1)
+    }
+
+    fn name(&self) -> &str {
+        "len"
+    }
+}
+
+#[derive(Default)]
+struct MapSet {}
+impl BuiltinFunctionImpl for MapSet {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        vm: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let this = VmGlobals::extract_arg(frame, |x| x.as_map().cloned())?;
+        let key = frame.stack.pop();
+        let value = frame.stack.pop();
+        match this.insert(key, value, frame, vm)? {
+            RunloopExit::Ok(is_new) => {
+                frame.stack.push(RuntimeValue::Boolean(is_new.into()));
+                Ok(RunloopExit::Ok(()))
+            }
+            RunloopExit::Exception(e) => Ok(RunloopExit::Exception(e)),
+        }
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::required(3)
+    }
+
+    fn name(&self) -> &str {
+        "set"
+    }
+}
+
+#[derive(Default)]
+struct MapGet {}
+impl BuiltinFunctionImpl for MapGet {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        vm: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let this = VmGlobals::extract_arg(frame, |x| x.as_map().cloned())?;
+        let key = frame.stack.pop();
+        match this.get(&key, frame, vm)? {
+            RunloopExit::Ok(Some(val)) => frame.stack.push(vm.globals.create_maybe_some(val)?),
+            RunloopExit::Ok(None) => frame.stack.push(vm.globals.create_maybe_none()?),
+            RunloopExit::Exception(e) => return Ok(RunloopExit::Exception(e)),
+        }
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::required(2)
+    }
+
+    fn name(&self) -> &str {
+        "get"
+    }
+}
+
+#[derive(Default)]
+struct MapContains {}
+impl BuiltinFunctionImpl for MapContains {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        vm: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let this = VmGlobals::extract_arg(frame, |x| x.as_map().cloned())?;
+        let key = frame.stack.pop();
+        match this.get(&key, frame, vm)? {
+            RunloopExit::Ok(val) => {
+                frame
+                    .stack
+                    .push(RuntimeValue::Boolean(val.is_some().into()));
+                Ok(RunloopExit::Ok(()))
+            }
+            RunloopExit::Exception(e) => Ok(RunloopExit::Exception(e)),
+        }
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::required(2)
+    }
+
+    fn name(&self) -> &str {
+        "contains"
+    }
+}
+
+#[derive(Default)]
+struct MapRemove {}
+impl BuiltinFunctionImpl for MapRemove {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        vm: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let this = VmGlobals::extract_arg(frame, |x| x.as_map().cloned())?;
+        let key = frame.stack.pop();
+        match this.remove(&key, frame, vm)? {
+            RunloopExit::Ok(removed) => {
+                frame.stack.push(RuntimeValue::Boolean(removed.into()));
+                Ok(RunloopExit::Ok(()))
+            }
+            RunloopExit::Exception(e) => Ok(RunloopExit::Exception(e)),
+        }
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::required(2)
+    }
+
+    fn name(&self) -> &str {
+        "remove"
+    }
+}
+
+#[derive(Default)]
+struct MapKeys {}
+impl BuiltinFunctionImpl for MapKeys {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        _: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let this = VmGlobals::extract_arg(frame, |x| x.as_map().cloned())?;
+        frame
+            .stack
+            .push(RuntimeValue::List(List::from(&this.keys())));
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::required(1)
+    }
+
+    fn name(&self) -> &str {
+        "keys"
+    }
+}
+
+#[derive(Default)]
+struct MapValues {}
+impl BuiltinFunctionImpl for MapValues {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        _: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let this = VmGlobals::extract_arg(frame, |x| x.as_map().cloned())?;
+        frame
+            .stack
+            .push(RuntimeValue::List(List::from(&this.values())));
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::required(1)
+    }
+
+    fn name(&self) -> &str {
+        "values"
+    }
+}
+
+#[derive(Default)]
+struct New {}
+impl BuiltinFunctionImpl for New {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        _: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let _ = frame.stack.pop(); // ignore Map type, we know who we are
+        frame.stack.push(RuntimeValue::Map(Map::default()));
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD | METHOD_ATTRIBUTE_TYPE
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::required(1)
+    }
+
+    fn name(&self) -> &str {
+        "new"
+    }
+}
+
+#[derive(Default)]
+struct NewWithCapacity {}
+impl BuiltinFunctionImpl for NewWithCapacity {
+    fn eval(
+        &self,
+        frame: &mut Frame,
+        _: &mut crate::vm::VirtualMachine,
+    ) -> crate::vm::ExecutionResult<RunloopExit> {
+        let _ = frame.stack.pop(); // ignore Map type, we know who we are
+        let capacity = VmGlobals::extract_arg(frame, |x| x.as_integer().cloned())?.saturating_i64();
+        let capacity = if capacity < 0 { 0 } else { capacity } as usize;
+        frame
+            .stack
+            .push(RuntimeValue::Map(Map::new_with_capacity(capacity)));
+        Ok(RunloopExit::Ok(()))
+    }
+
+    fn attrib_byte(&self) -> u8 {
+        FUNC_IS_METHOD | METHOD_ATTRIBUTE_TYPE
+    }
+
+    fn arity(&self) -> crate::arity::Arity {
+        crate::arity::Arity::required(2)
+    }
+
+    fn name(&self) -> &str {
+        "new_with_capacity"
+    }
+}
+
+pub(super) fn insert_map_builtins(builtins: &mut VmGlobals) {
+    let map_builtin =
+        RustNativeType::new(crate::runtime_value::rust_native_type::RustNativeValueKind::Map);
+
+    map_builtin.insert_builtin::<MapLen>(builtins);
+    map_builtin.insert_builtin::<MapSet>(builtins);
+    map_builtin.insert_builtin::<MapGet>(builtins);
+    map_builtin.insert_builtin::<MapContains>(builtins);
+    map_builtin.insert_builtin::<MapRemove>(builtins);
+    map_builtin.insert_builtin::<MapKeys>(builtins);
+    map_builtin.insert_builtin::<MapValues>(builtins);
+    map_builtin.insert_builtin::<New>(builtins);
+    map_builtin.insert_builtin::<NewWithCapacity>(builtins);
+
+    builtins.register_builtin_type(
+        haxby_opcodes::BuiltinTypeId::Map,
+        RuntimeValueType::RustNative(map_builtin),
+    );
+}
@@ -18,15 +18,19 @@ use crate::{
 
 mod alloc;
 mod arity;
+mod backtrace;
 mod boolean;
 mod cmdline_args;
 mod exit;
 mod float;
+mod gc;
+pub(crate) mod generator;
 mod getenv;
 mod hasattr;
 mod integer;
 mod list;
 mod listattrs;
+mod map;
 mod maybe;
 pub mod native_iterator;
 mod now;
@@ -142,10 +146,12 @@ impl Default for VmGlobals {
 
         alloc::insert_builtins(&mut this);
         arity::insert_builtins(&mut this);
+        backtrace::insert_builtins(&mut this);
         boolean::insert_boolean_builtins(&mut this);
         cmdline_args::insert_builtins(&mut this);
         exit::insert_builtins(&mut this);
         float::insert_float_builtins(&mut this);
+        gc::insert_builtins(&mut this);
         getenv::insert_builtins(&mut this);
         hasattr::insert_builtins(&mut this);
         list::insert_list_builtins(&mut this);
@@ -163,6 +169,9 @@ impl Default for VmGlobals {
         typeof_builtin::insert_builtins(&mut this);
         writeattr::insert_builtins(&mut this);
 
+        generator::insert_generator_builtins(&mut this); // Generator comes after Type
+        map::insert_map_builtins(&mut this); // and Map after Generator
+
         this
     }
 }
@@ -22,6 +22,8 @@ pub const RUNTIME_ERR_CASE_NO_SUCH_CASE_IDX: usize = 4;
 pub const RUNTIME_ERR_CASE_NO_SUCH_IDENTIFIER_IDX: usize = 5;
 pub const RUNTIME_ERR_CASE_OPERATION_FAILED_IDX: usize = 6;
 pub const RUNTIME_ERR_CASE_UNEXPECTED_TYPE_IDX: usize = 7;
+pub const RUNTIME_ERR_CASE_CALL_DEPTH_EXCEEDED_IDX: usize = 8;
+pub const RUNTIME_ERR_CASE_PERMISSION_DENIED_IDX: usize = 9;
 
 pub(super) fn insert_runtime_error_builtins(builtins: &mut VmGlobals) {
     let argc_mismatch = Struct::new("ArgcMismatch");
@@ -51,6 +53,15 @@ pub(super) fn insert_runtime_error_builtins(builtins: &mut VmGlobals) {
     let unexpected_type_sym = builtins
         .intern_symbol("UnexpectedType")
         .expect("too many symbols interned");
+    let call_depth_exceeded_sym = builtins
+        .intern_symbol("CallDepthExceeded")
+        .expect("too many symbols interned");
+    let permission_denied_sym = builtins
+        .intern_symbol("PermissionDenied")
+        .expect("too many symbols interned");
+    let pattern_mismatch_sym = builtins
+        .intern_symbol("PatternMismatch")
+        .expect("too many symbols interned");
 
     let rt_err_enum = RuntimeValue::Type(RuntimeValueType::Enum(Enum::new_with_cases(
         "RuntimeError",
@@ -89,6 +100,18 @@ pub(super) fn insert_runtime_error_builtins(builtins: &mut VmGlobals) {
                 name: unexpected_type_sym,
                 payload_type: None,
             },
+            EnumCase {
+                name: call_depth_exceeded_sym,
+                payload_type: Some(IsaCheckable::Type(int.clone())),
+            },
+            EnumCase {
+                name: permission_denied_sym,
+                payload_type: Some(IsaCheckable::Type(str.clone())),
+            },
+            EnumCase {
+                name: pattern_mismatch_sym,
+                payload_type: Some(IsaCheckable::Type(str.clone())),
+            },
         ],
         builtins,
     )));
@@ -14,6 +14,7 @@ impl BuiltinFunctionImpl for Setenv {
     ) -> crate::vm::ExecutionResult<RunloopExit> {
         let var_name = VmGlobals::extract_arg(frame, |x| x.as_string().cloned())?;
         let var_value = VmGlobals::extract_arg(frame, |x| x.as_string().cloned())?;
+        vm.options.permissions.check_env()?;
         if var_name.is_empty() || var_value.is_empty() {
             return Err(VmErrorReason::OperationFailed("empty key or value".into()).into());
         }
@@ -15,7 +15,8 @@ impl BuiltinFunctionImpl for Sleep {
         cur_frame: &mut Frame,
         vm: &mut crate::vm::VirtualMachine,
     ) -> crate::vm::ExecutionResult<RunloopExit> {
-        let duration = *VmGlobals::extract_arg(cur_frame, |x| x.as_integer().cloned())?.raw_value();
+        let duration =
+            VmGlobals::extract_arg(cur_frame, |x| x.as_integer().cloned())?.saturating_i64();
         if duration >= 0 {
             std::thread::sleep(Duration::from_millis(duration as u64));
         } else {
@@ -311,7 +311,7 @@ impl BuiltinFunctionImpl for FromBytes {
         for i in 0..list.len() {
             let item = list.get_at(i).expect("invalid list");
             if let Some(byte) = item.as_integer() {
-                bytes.push(*byte.raw_value() as u8);
+                bytes.push(byte.wrapping_i64() as u8);
             } else {
                 return Err(VmErrorReason::UnexpectedType.into());
             }
@@ -569,7 +569,7 @@ impl BuiltinFunctionImpl for GetAt {
     ) -> crate::vm::ExecutionResult<RunloopExit> {
         let this = VmGlobals::extract_arg(frame, |x| x.as_string().cloned())?;
         let index = VmGlobals::extract_arg(frame, |x| x.as_integer().cloned())?;
-        let index = *index.raw_value() as usize;
+        let index = index.saturating_i64() as usize;
         match this.get_at(index) {
             Some(v) => {
                 frame.stack.push(v);
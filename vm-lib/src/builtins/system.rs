@@ -26,6 +26,7 @@ impl BuiltinFunctionImpl for System {
         vm: &mut crate::vm::VirtualMachine,
     ) -> crate::vm::ExecutionResult<RunloopExit> {
         let command = VmGlobals::extract_arg(cur_frame, |x| x.as_string().cloned())?;
+        vm.options.permissions.check_run()?;
 
         let output = Command::new(get_shell_path())
             .arg("-c")
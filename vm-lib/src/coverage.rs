@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+use aria_compiler::line_table::LineTable;
+use haxby_opcodes::Opcode;
+
+use crate::{debugger::LineIndex, frame::Frame, runtime_value::RuntimeValue, vm::VirtualMachine};
+
+// Line and branch coverage of Aria code. While recording, the VM counts how
+// many times each instruction runs; every line that some instruction maps to
+// in a line table is coverable, so lines of functions that never run show up
+// with no hits. Branches are the conditional jumps, counted by which way the
+// condition went.
+#[derive(Clone, Debug, Default)]
+pub struct CoverageOptions {
+    // also record the outcome of JUMP_TRUE, JUMP_FALSE and JUMP_CONDITIONALLY
+    pub branches: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileCoverage {
+    // hits by 1-based line number
+    pub lines: BTreeMap<usize, u64>,
+    // how many times each condition was true and false, by the line, source
+    // offset and instruction index of its jump
+    pub branches: BTreeMap<(usize, usize, usize), [u64; 2]>,
+}
+
+impl FileCoverage {
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|&&n| n > 0).count()
+    }
+
+    // each branch has two outcomes, which are counted separately
+    pub fn branches_found(&self) -> usize {
+        self.branches.len() * 2
+    }
+
+    pub fn branches_hit(&self) -> usize {
+        self.branches.values().flatten().filter(|&&n| n > 0).count()
+    }
+
+    fn merge(&mut self, other: &FileCoverage) {
+        for (line, hits) in &other.lines {
+            *self.lines.entry(*line).or_default() += hits;
+        }
+        for (key, [t, f]) in &other.branches {
+            let counts = self.branches.entry(*key).or_default();
+            counts[0] += t;
+            counts[1] += f;
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    // by source file name
+    pub files: BTreeMap<String, FileCoverage>,
+}
+
+fn percent(hit: usize, found: usize) -> String {
+    if found == 0 {
+        "-".to_owned()
+    } else {
+        format!("{:.1}%", 100.0 * hit as f64 / found as f64)
+    }
+}
+
+impl Coverage {
+    // adds the hits of another run, e.g. of a different test, to these
+    pub fn merge(&mut self, other: &Coverage) {
+        for (file, coverage) in &other.files {
+            self.files.entry(file.clone()).or_default().merge(coverage);
+        }
+    }
+
+    pub fn retain_files(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.files.retain(|file, _| keep(file));
+    }
+
+    // an lcov tracefile, as read by genhtml, codecov and most editors
+    pub fn lcov(&self) -> String {
+        let mut out = String::new();
+        for (file, coverage) in &self.files {
+            let _ = writeln!(out, "TN:\nSF:{file}");
+            let mut last_line = None;
+            let mut block = 0;
+            for ((line, _, _), counts) in &coverage.branches {
+                block = if last_line == Some(*line) {
+                    block + 1
+                } else {
+                    0
+                };
+                last_line = Some(*line);
+                for (branch, taken) in counts.iter().enumerate() {
+                    // "-" tells an unreached condition from one that never went this way
+                    let taken = if counts == &[0, 0] {
+                        "-".to_owned()
+                    } else {
+                        taken.to_string()
+                    };
+                    let _ = writeln!(out, "BRDA:{line},{block},{branch},{taken}");
+                }
+            }
+            if !coverage.branches.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "BRF:{}\nBRH:{}",
+                    coverage.branches_found(),
+                    coverage.branches_hit()
+                );
+            }
+            for (line, hits) in &coverage.lines {
+                let _ = writeln!(out, "DA:{line},{hits}");
+            }
+            let _ = writeln!(
+                out,
+                "LF:{}\nLH:{}\nend_of_record",
+                coverage.lines.len(),
+                coverage.lines_hit()
+            );
+        }
+        out
+    }
+
+    // a table of line and branch coverage per file, with the totals
+    pub fn summary(&self) -> String {
+        let width = self
+            .files
+            .keys()
+            .map(|f| f.len())
+            .chain(std::iter::once(5))
+            .max()
+            .unwrap_or_default();
+        let mut out = format!(
+            "{:width$} {:>15} {:>7} {:>15} {:>7}\n",
+            "file", "lines", "", "branches", ""
+        );
+        let mut row = |name: &str, lines: (usize, usize), branches: (usize, usize)| {
+            let _ = writeln!(
+                out,
+                "{name:width$} {:>15} {:>7} {:>15} {:>7}",
+                format!("{}/{}", lines.0, lines.1),
+                percent(lines.0, lines.1),
+                format!("{}/{}", branches.0, branches.1),
+                percent(branches.0, branches.1),
+            );
+        };
+
+        let (mut lines, mut branches) = ((0, 0), (0, 0));
+        for (file, coverage) in &self.files {
+            let file_lines = (coverage.lines_hit(), coverage.lines.len());
+            let file_branches = (coverage.branches_hit(), coverage.branches_found());
+            row(file, file_lines, file_branches);
+            lines = (lines.0 + file_lines.0, lines.1 + file_lines.1);
+            branches = (branches.0 + file_branches.0, branches.1 + file_branches.1);
+        }
+        row("total", lines, branches);
+        out
+    }
+}
+
+// what has been recorded for one code object, which is identified by its
+// line table
+#[derive(Default)]
+struct CodeHits {
+    // by instruction index
+    ops: Vec<u64>,
+    // true and false counts by the instruction index of a conditional jump
+    branches: HashMap<usize, [u64; 2]>,
+}
+
+pub(crate) struct CoverageSession {
+    options: CoverageOptions,
+    code: HashMap<LineTable, CodeHits>,
+}
+
+impl CoverageSession {
+    fn new(options: CoverageOptions) -> Self {
+        Self {
+            options,
+            code: HashMap::new(),
+        }
+    }
+
+    // called as code is loaded, so that code which never runs is reported too
+    pub(crate) fn register(&mut self, line_table: &LineTable, body: &[Opcode]) {
+        let hits = self.code.entry(line_table.clone()).or_default();
+        hits.ops.resize(body.len(), 0);
+        if self.options.branches {
+            for (idx, op) in body.iter().enumerate() {
+                if matches!(
+                    op,
+                    Opcode::JumpTrue(_) | Opcode::JumpFalse(_) | Opcode::JumpConditionally(..)
+                ) {
+                    hits.branches.entry(idx).or_default();
+                }
+            }
+        }
+    }
+
+    // called before the VM runs the instruction at op_idx
+    pub(crate) fn instruction(&mut self, op_idx: usize, op: Opcode, frame: &mut Frame) {
+        let Some(line_table) = frame.get_line_table() else {
+            return;
+        };
+        let hits = match self.code.get_mut(line_table) {
+            Some(hits) => hits,
+            None => self.code.entry(line_table.clone()).or_default(),
+        };
+        if hits.ops.len() <= op_idx {
+            hits.ops.resize(op_idx + 1, 0);
+        }
+        hits.ops[op_idx] += 1;
+
+        if self.options.branches
+            && matches!(
+                op,
+                Opcode::JumpTrue(_) | Opcode::JumpFalse(_) | Opcode::JumpConditionally(..)
+            )
+            && let Some(RuntimeValue::Boolean(b)) = frame.stack.peek()
+        {
+            let outcome = if *b.raw_value() { 0 } else { 1 };
+            hits.branches.entry(op_idx).or_default()[outcome] += 1;
+        }
+    }
+
+    fn finish(self) -> Coverage {
+        let mut lines = LineIndex::default();
+        let mut coverage = Coverage::default();
+        for (line_table, hits) in self.code {
+            for (idx, sp) in line_table.entries() {
+                let loc = lines.line_of(&sp);
+                let count = hits.ops.get(idx as usize).copied().unwrap_or_default();
+                let file = coverage.files.entry(loc.file).or_default();
+                // a line is run as often as the instruction on it that runs the most
+                let line_hits = file.lines.entry(loc.line).or_default();
+                *line_hits = (*line_hits).max(count);
+
+                if let Some(counts) = hits.branches.get(&(idx as usize)) {
+                    file.branches
+                        .insert((loc.line, sp.location.start, idx as usize), *counts);
+                }
+            }
+        }
+        coverage
+    }
+}
+
+impl VirtualMachine {
+    // starts recording which code runs; only code loaded from now on has its
+    // unexecuted lines reported, so this is best called before loading any
+    pub fn start_coverage(&mut self, options: CoverageOptions) {
+        self.coverage = Some(CoverageSession::new(options));
+    }
+
+    // stops recording and returns the coverage of everything that was loaded
+    pub fn stop_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take().map(CoverageSession::finish)
+    }
+}
@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{collections::HashMap, rc::Rc};
+
+use aria_compiler::{CompilationOptions, compile_from_source};
+use aria_parser::ast::{SourceBuffer, SourcePointer};
+
+use crate::{
+    frame::Frame,
+    runtime_module::RuntimeModule,
+    runtime_value::{RuntimeValue, function::Function},
+    vm::{RunloopExit, VirtualMachine},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Entry,
+    Breakpoint,
+    Step,
+    Pause,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResumeMode {
+    Continue,
+    StepIn,
+    StepOver,
+    StepOut,
+}
+
+// a position in a source file, with a 1-based line number
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SourceLine {
+    pub file: String,
+    pub line: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct StackFrameInfo {
+    pub name: String,
+    pub location: Option<SourceLine>,
+}
+
+// the front end of a debugging session (e.g. a DAP server); the VM asks it
+// about breakpoints at the start of each source line, and hands control over
+// to it whenever execution stops
+pub trait DebugHandler {
+    fn has_breakpoint(&self, file: &str, line: usize) -> bool;
+
+    // polled at the start of each line, so a pause can be requested while
+    // the program runs
+    fn pause_requested(&self) -> bool {
+        false
+    }
+
+    fn stopped(&mut self, reason: StopReason, vm: &mut PausedVm<'_>) -> ResumeMode;
+}
+
+// what the debugger knows about a running bytecode frame; outer frames are
+// not reachable from the run loop, so their locals and stack are copied at
+// the point where they make a call
+struct ShadowFrame {
+    frame_id: usize,
+    func: Option<Function>,
+    location: Option<SourceLine>,
+    locals: Vec<RuntimeValue>,
+    stack: Vec<RuntimeValue>,
+}
+
+#[derive(Default)]
+pub(crate) struct LineIndex {
+    // keyed by the address of the buffer contents, which are kept alive here
+    buffers: HashMap<usize, (Rc<String>, Vec<usize>)>,
+}
+
+impl LineIndex {
+    pub(crate) fn line_of(&mut self, sp: &SourcePointer) -> SourceLine {
+        let content = &sp.buffer.content;
+        let (_, starts) = self
+            .buffers
+            .entry(Rc::as_ptr(content) as usize)
+            .or_insert_with(|| {
+                let starts = std::iter::once(0)
+                    .chain(content.match_indices('\n').map(|(idx, _)| idx + 1))
+                    .collect();
+                (content.clone(), starts)
+            });
+        SourceLine {
+            file: sp.buffer.name.clone(),
+            line: starts.partition_point(|&start| start <= sp.location.start),
+        }
+    }
+}
+
+pub(crate) struct DebugSession {
+    handler: Box<dyn DebugHandler>,
+    frames: Vec<ShadowFrame>,
+    step: Option<(ResumeMode, usize)>,
+    stop_on_entry: bool,
+    lines: LineIndex,
+}
+
+impl DebugSession {
+    pub(crate) fn new(handler: Box<dyn DebugHandler>, stop_on_entry: bool) -> Self {
+        Self {
+            handler,
+            frames: vec![],
+            step: None,
+            stop_on_entry,
+            lines: Default::default(),
+        }
+    }
+
+    pub(crate) fn enter_frame(&mut self, frame: &Frame) {
+        self.frames.push(ShadowFrame {
+            frame_id: frame as *const Frame as usize,
+            func: frame.func.clone(),
+            location: None,
+            locals: vec![],
+            stack: vec![],
+        });
+    }
+
+    pub(crate) fn leave_frame(&mut self) {
+        self.frames.pop();
+    }
+
+    // called when a function is about to be invoked from frame
+    pub(crate) fn calling_from(&mut self, frame: &Frame) {
+        if let Some(top) = self.frames.last_mut()
+            && top.frame_id == frame as *const Frame as usize
+        {
+            top.locals = frame.locals.iter().map(|l| l.val.clone()).collect();
+            top.stack = frame.stack.as_slice().to_vec();
+        }
+    }
+
+    // returns why execution should stop before running the instruction at
+    // op_idx, if it should
+    fn check_instruction(&mut self, op_idx: usize, frame: &Frame) -> Option<StopReason> {
+        let sp = frame.get_line_entry_at_pos(op_idx as u16)?;
+        let location = self.lines.line_of(&sp);
+        let depth = self.frames.len();
+        let top = self.frames.last_mut()?;
+        // only the first instruction of each line is a place to stop at
+        if top.location.as_ref() == Some(&location) {
+            return None;
+        }
+        top.location = Some(location.clone());
+
+        if std::mem::take(&mut self.stop_on_entry) {
+            return Some(StopReason::Entry);
+        }
+        if self.handler.pause_requested() {
+            return Some(StopReason::Pause);
+        }
+        let step_done = match self.step {
+            Some((ResumeMode::StepIn, _)) => true,
+            Some((ResumeMode::StepOver, from)) => depth <= from,
+            Some((ResumeMode::StepOut, from)) => depth < from,
+            _ => false,
+        };
+        if step_done {
+            return Some(StopReason::Step);
+        }
+        if self.handler.has_breakpoint(&location.file, location.line) {
+            return Some(StopReason::Breakpoint);
+        }
+        None
+    }
+}
+
+impl VirtualMachine {
+    // install a debugger; it takes effect for code that starts running
+    // after this call
+    pub fn attach_debugger(&mut self, handler: Box<dyn DebugHandler>, stop_on_entry: bool) {
+        self.debugger = Some(DebugSession::new(handler, stop_on_entry));
+    }
+
+    pub fn detach_debugger(&mut self) {
+        self.debugger = None;
+    }
+
+    pub(crate) fn debug_hook(&mut self, op_idx: usize, frame: &Frame) {
+        let Some(session) = self.debugger.as_mut() else {
+            return;
+        };
+        let Some(reason) = session.check_instruction(op_idx, frame) else {
+            return;
+        };
+
+        // code evaluated while paused must not hit breakpoints itself, so the
+        // session is detached until the handler resumes
+        let mut session = self.debugger.take().unwrap();
+        session.step = None;
+        let mode = {
+            let mut paused = PausedVm {
+                vm: self,
+                frame,
+                frames: &session.frames,
+            };
+            session.handler.stopped(reason, &mut paused)
+        };
+        if mode != ResumeMode::Continue {
+            session.step = Some((mode, session.frames.len()));
+        }
+        self.debugger = Some(session);
+    }
+}
+
+// a view of the VM while it is stopped; depth 0 is the innermost frame
+pub struct PausedVm<'a> {
+    vm: &'a mut VirtualMachine,
+    frame: &'a Frame,
+    frames: &'a [ShadowFrame],
+}
+
+impl PausedVm<'_> {
+    pub fn vm(&mut self) -> &mut VirtualMachine {
+        self.vm
+    }
+
+    fn shadow(&self, depth: usize) -> Option<&ShadowFrame> {
+        self.frames.iter().rev().nth(depth)
+    }
+
+    pub fn stack_frames(&self) -> Vec<StackFrameInfo> {
+        self.frames
+            .iter()
+            .rev()
+            .map(|sf| StackFrameInfo {
+                name: sf
+                    .func
+                    .as_ref()
+                    .map_or("<unknown>".to_owned(), |f| f.name().to_owned()),
+                location: sf.location.clone(),
+            })
+            .collect()
+    }
+
+    // the named locals of a frame, in declaration order; compiler generated
+    // temporaries are left out
+    pub fn locals(&self, depth: usize) -> Vec<(String, RuntimeValue)> {
+        let Some(sf) = self.shadow(depth) else {
+            return vec![];
+        };
+        let Some(func) = &sf.func else {
+            return vec![];
+        };
+        let values = if depth == 0 {
+            self.frame.locals.iter().map(|l| l.val.clone()).collect()
+        } else {
+            sf.locals.clone()
+        };
+        func.local_names()
+            .iter()
+            .zip(values)
+            .filter(|(name, _)| !name.starts_with("__"))
+            .map(|(name, val)| (name.clone(), val))
+            .collect()
+    }
+
+    // the operand stack of a frame, top of the stack first
+    pub fn operand_stack(&self, depth: usize) -> Vec<RuntimeValue> {
+        let values = if depth == 0 {
+            self.frame.stack.as_slice().to_vec()
+        } else {
+            self.shadow(depth).map_or(vec![], |sf| sf.stack.clone())
+        };
+        values.into_iter().rev().collect()
+    }
+
+    pub fn describe(&mut self, val: &RuntimeValue) -> String {
+        let mut scratch = Frame::default();
+        val.prettyprint(&mut scratch, self.vm)
+    }
+
+    // the values nested inside val, for displaying it as a tree
+    pub fn children(&mut self, val: &RuntimeValue) -> Vec<(String, RuntimeValue)> {
+        if let Some(list) = val.as_list() {
+            return (0..list.len())
+                .filter_map(|idx| list.get_at(idx).map(|v| (format!("[{idx}]"), v)))
+                .collect();
+        }
+        if let Some(map) = val.as_map() {
+            let keys = map.keys();
+            let values = map.values();
+            return keys
+                .iter()
+                .zip(values)
+                .map(|(k, v)| (format!("[{}]", self.describe(k)), v))
+                .collect();
+        }
+        if let Some(obj) = val.as_object() {
+            let globals = &self.vm.globals;
+            let mut children = obj
+                .list_attributes(globals)
+                .into_iter()
+                .filter_map(|sym| {
+                    let name = globals.resolve_symbol(sym)?.to_owned();
+                    let v = val.read_attribute(sym, globals).ok()?;
+                    Some((name, v))
+                })
+                .collect::<Vec<_>>();
+            children.sort_by(|a, b| a.0.cmp(&b.0));
+            return children;
+        }
+        vec![]
+    }
+
+    // evaluate an expression as if it appeared in the frame at depth; the
+    // frame's locals are visible but assigning to them has no effect
+    pub fn evaluate(&mut self, depth: usize, expr: &str) -> Result<RuntimeValue, String> {
+        const RESULT: &str = "debugger_eval_result";
+
+        let src = SourceBuffer::stdin_with_name(&format!("val {RESULT} = ({expr});"), "<eval>");
+        let cm = compile_from_source(&src, &CompilationOptions::default()).map_err(|errs| {
+            errs.iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+        let r_mod = RuntimeModule::new(self.vm, cm).map_err(|e| e.to_string())?;
+
+        if let Some(module) = self
+            .shadow(depth)
+            .and_then(|sf| sf.func.as_ref())
+            .and_then(|f| f.module())
+        {
+            r_mod
+                .lift_all_symbols_from_other(module, self.vm)
+                .map_err(|e| e.to_string())?;
+        }
+        for (name, val) in self.locals(depth) {
+            r_mod.store_named_value(&name, val);
+        }
+
+        match self.vm.load_into_module("", r_mod) {
+            Ok(RunloopExit::Ok(info)) => info
+                .module
+                .load_named_value(RESULT)
+                .ok_or_else(|| "expression produced no value".to_owned()),
+            Ok(RunloopExit::Exception(e)) => Err(self.describe(&e.value)),
+            Err(e) => Err(e.reason.to_string()),
+        }
+    }
+}
@@ -0,0 +1,187 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// A high-level API for hosting Aria inside a Rust program:
+//
+//   let mut vm = VirtualMachine::default();
+//   vm.register_function("log", Arity::required(1), |vm, args| {
+//       println!("{}", args[0]);
+//       Ok(vm.globals.create_unit_object()?)
+//   });
+//   let module = vm.load_source("rules", "func score(x) { log(x); return x * 2; }")?;
+//   let score: i64 = vm.call_function(&module, "score", &[21.into()])?.try_into()?;
+//
+// Values move between Rust and Aria through the From and TryFrom impls in
+// runtime_value::convert.
+
+use std::rc::Rc;
+
+use aria_compiler::compile_from_source;
+use aria_parser::ast::SourceBuffer;
+
+use crate::{
+    arity::Arity,
+    error::{
+        exception::VmException,
+        vm_error::{VmError, VmErrorReason},
+    },
+    frame::Frame,
+    runtime_module::RuntimeModule,
+    runtime_value::{
+        CallResult, RuntimeValue,
+        convert::ConversionError,
+        function::{BuiltinFunctionImpl, Function},
+    },
+    vm::{ExecutionResult, RunloopExit, VirtualMachine},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum EmbedError {
+    #[error("{0}")]
+    Compile(String),
+    #[error("{}", .0.prettyprint(None))]
+    Vm(Box<VmError>),
+    // an exception that Aria code threw and did not catch
+    #[error("uncaught exception: {message}")]
+    Exception {
+        message: String,
+        value: Box<RuntimeValue>,
+    },
+    #[error("no function named {0}")]
+    NoSuchFunction(String),
+    #[error(transparent)]
+    Conversion(#[from] ConversionError),
+}
+
+impl From<VmError> for EmbedError {
+    fn from(value: VmError) -> Self {
+        Self::Vm(Box::new(value))
+    }
+}
+
+impl From<VmErrorReason> for EmbedError {
+    fn from(value: VmErrorReason) -> Self {
+        Self::Vm(Box::new(value.into()))
+    }
+}
+
+pub type NativeFunctionBody =
+    dyn Fn(&mut VirtualMachine, &[RuntimeValue]) -> Result<RuntimeValue, EmbedError>;
+
+// a builtin implemented by a Rust closure
+struct NativeFunction {
+    name: String,
+    arity: Arity,
+    body: Rc<NativeFunctionBody>,
+}
+
+impl BuiltinFunctionImpl for NativeFunction {
+    fn eval(&self, frame: &mut Frame, vm: &mut VirtualMachine) -> ExecutionResult<RunloopExit> {
+        // the first argument is on top of the stack
+        let args = frame.stack.pop_count(frame.stack.len());
+        match (self.body)(vm, &args) {
+            Ok(val) => {
+                frame.stack.push(val);
+                Ok(RunloopExit::Ok(()))
+            }
+            // rethrown, so that Aria code can catch what it threw
+            Err(EmbedError::Exception { value, .. }) => {
+                Ok(RunloopExit::Exception(VmException::from_value(*value)))
+            }
+            Err(EmbedError::Vm(err)) => Err(*err),
+            Err(err) => Err(VmErrorReason::OperationFailed(err.to_string()).into()),
+        }
+    }
+
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Function {
+    // a function value that runs a Rust closure when Aria code calls it;
+    // the closure receives exactly as many arguments as the caller passed
+    pub fn native<F>(name: &str, arity: Arity, body: F) -> Self
+    where
+        F: Fn(&mut VirtualMachine, &[RuntimeValue]) -> Result<RuntimeValue, EmbedError> + 'static,
+    {
+        Function::builtin_from(NativeFunction {
+            name: name.to_owned(),
+            arity,
+            body: Rc::new(body),
+        })
+    }
+}
+
+impl VirtualMachine {
+    // compiles source and runs its top-level code; the module is
+    // registered under name, which also names it in error messages
+    pub fn load_source(&mut self, name: &str, source: &str) -> Result<RuntimeModule, EmbedError> {
+        let buffer = SourceBuffer::stdin_with_name(source, name);
+        let module = compile_from_source(&buffer, &Default::default()).map_err(|errs| {
+            EmbedError::Compile(
+                errs.iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            )
+        })?;
+        match self.load_module(name, module)? {
+            RunloopExit::Ok(mli) => Ok(mli.module),
+            RunloopExit::Exception(e) => Err(self.uncaught(e)),
+        }
+    }
+
+    // calls anything Aria code could call: functions, bound methods and
+    // objects with an operator ()
+    pub fn call(
+        &mut self,
+        callee: &RuntimeValue,
+        args: &[RuntimeValue],
+    ) -> Result<RuntimeValue, EmbedError> {
+        let argc = u8::try_from(args.len()).map_err(|_| {
+            VmErrorReason::OperationFailed("too many arguments in a call".to_owned())
+        })?;
+        let mut frame = Frame::default();
+        for arg in args.iter().rev() {
+            frame.stack.push(arg.clone());
+        }
+        match callee.eval(argc, &mut frame, self, true)? {
+            CallResult::Ok(val) => Ok(val),
+            CallResult::Exception(e) => Err(self.uncaught(e)),
+        }
+    }
+
+    pub fn call_function(
+        &mut self,
+        module: &RuntimeModule,
+        name: &str,
+        args: &[RuntimeValue],
+    ) -> Result<RuntimeValue, EmbedError> {
+        let Some(callee) = module.load_named_value(name) else {
+            return Err(EmbedError::NoSuchFunction(name.to_owned()));
+        };
+        self.call(&callee, args)
+    }
+
+    // makes a Rust closure available to all Aria code as a global function
+    pub fn register_function<F>(&mut self, name: &str, arity: Arity, body: F)
+    where
+        F: Fn(&mut VirtualMachine, &[RuntimeValue]) -> Result<RuntimeValue, EmbedError> + 'static,
+    {
+        self.globals.insert(
+            name,
+            RuntimeValue::Function(Function::native(name, arity, body)),
+        );
+    }
+
+    fn uncaught(&mut self, e: VmException) -> EmbedError {
+        EmbedError::Exception {
+            message: e.value.prettyprint(&mut Frame::default(), self),
+            value: Box::new(e.value),
+        }
+    }
+}
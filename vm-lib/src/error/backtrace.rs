@@ -1,23 +1,111 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::{collections::VecDeque, rc::Rc};
+
 use aria_parser::ast::SourcePointer;
 
+use crate::{frame::Frame, runtime_value::function::Function, vm::VirtualMachine};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BacktraceEntry {
+    pub loc: SourcePointer,
+    // the function that was running, e.g. main or Point.distance
+    pub function: Option<String>,
+    // the import path of the module the function belongs to
+    pub module: Option<String>,
+    // the calls that were left out of the backtrace between this one and the
+    // one that called it, at the start of a long chain of tail calls
+    pub tail_calls: usize,
+}
+
+impl BacktraceEntry {
+    pub fn new(loc: SourcePointer, func: Option<&Function>) -> Self {
+        Self {
+            loc,
+            function: func.map(|f| f.name().to_owned()),
+            module: func.and_then(|f| f.module()).and_then(|m| m.name()),
+            tail_calls: 0,
+        }
+    }
+
+    pub fn file(&self) -> &str {
+        &self.loc.buffer.name
+    }
+
+    // 1-based, like the line numbers in error reports
+    pub fn line(&self) -> usize {
+        1 + self
+            .loc
+            .buffer
+            .line_index_for_position(self.loc.location.start)
+    }
+
+    // 1-based, counted in characters
+    pub fn column(&self) -> usize {
+        let (line_start, _) = self
+            .loc
+            .buffer
+            .indices_for_position(self.loc.location.start);
+        1 + self.loc.buffer.content[line_start..self.loc.location.start]
+            .chars()
+            .count()
+    }
+
+    pub fn source_line(&self) -> String {
+        self.loc.buffer.line_for_position(self.loc.location.start)
+    }
+}
+
+impl From<SourcePointer> for BacktraceEntry {
+    fn from(loc: SourcePointer) -> Self {
+        Self::new(loc, None)
+    }
+}
+
+impl std::fmt::Display for BacktraceEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ",
+            self.function.as_deref().unwrap_or("<unknown function>")
+        )?;
+        if let Some(module) = &self.module {
+            write!(f, "in {module} ")?;
+        }
+        write!(f, "at {}:{}:{}", self.file(), self.line(), self.column())
+    }
+}
+
+// innermost call first
 #[derive(Clone, Debug, Default)]
 pub struct Backtrace {
-    entries: Vec<SourcePointer>,
+    entries: Vec<BacktraceEntry>,
 }
 
 impl Backtrace {
-    pub fn first_entry(&self) -> Option<SourcePointer> {
-        self.entries.first().cloned()
+    pub fn first_entry(&self) -> Option<&BacktraceEntry> {
+        self.entries.first()
     }
 
-    pub fn entries_iter(&self) -> std::slice::Iter<'_, SourcePointer> {
+    pub fn entries_iter(&self) -> std::slice::Iter<'_, BacktraceEntry> {
         self.entries.iter()
     }
 
-    pub fn push(&mut self, loc: SourcePointer) {
-        self.entries.push(loc);
+    pub fn push(&mut self, entry: BacktraceEntry) {
+        self.entries.push(entry);
+    }
+
+    // the entry of a frame, followed by the frames that tail calls replaced
+    // on the way to it
+    pub(crate) fn push_frame(&mut self, loc: SourcePointer, frame: &Frame) {
+        self.push(BacktraceEntry::new(loc, frame.func.as_ref()));
+        self.push_tail_callers(frame);
+    }
+
+    pub(crate) fn push_tail_callers(&mut self, frame: &Frame) {
+        if let Some(tail_callers) = &frame.tail_callers {
+            self.entries.extend(tail_callers.entries());
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -27,4 +115,76 @@ impl Backtrace {
     pub fn is_empty(&self) -> bool {
         self.entries.is_empty()
     }
+
+    // the calls that are running right now; builtins and calls made from
+    // outside of Aria code have no location, and are left out
+    pub fn capture(vm: &VirtualMachine) -> Self {
+        let entries = vm
+            .call_stack
+            .iter()
+            .rev()
+            .flat_map(|site| {
+                let tail_callers = site.tail_callers.iter().flat_map(|t| t.entries());
+                entry_at(site.func.as_ref(), site.pc)
+                    .into_iter()
+                    .chain(tail_callers)
+            })
+            .collect();
+        Self { entries }
+    }
+}
+
+// a function call that has not returned yet: the function that made the
+// call, and the index of the call instruction in it
+pub(crate) struct CallSite {
+    pub(crate) func: Option<Function>,
+    pub(crate) pc: usize,
+    pub(crate) tail_callers: Option<Rc<TailCallers>>,
+}
+
+// the frames that tail calls replaced on the way to a frame, oldest first;
+// only the most recent ones are kept, so that tail recursion still runs in
+// constant space
+#[derive(Clone, Default)]
+pub(crate) struct TailCallers {
+    calls: VecDeque<(Option<Function>, usize)>,
+    // how many older ones were let go
+    dropped: usize,
+}
+
+impl TailCallers {
+    const MAX_KEPT: usize = 16;
+
+    pub(crate) fn push(&mut self, func: Option<Function>, pc: usize) {
+        if self.calls.len() == Self::MAX_KEPT {
+            self.calls.pop_front();
+            self.dropped += 1;
+        }
+        self.calls.push_back((func, pc));
+    }
+
+    // newest first, like a backtrace; the oldest one counts the calls that
+    // were let go
+    fn entries(&self) -> impl Iterator<Item = BacktraceEntry> + '_ {
+        self.calls
+            .iter()
+            .enumerate()
+            .rev()
+            .filter_map(|(i, (func, pc))| {
+                let entry = entry_at(func.as_ref(), *pc)?;
+                Some(match i {
+                    0 => BacktraceEntry {
+                        tail_calls: self.dropped,
+                        ..entry
+                    },
+                    _ => entry,
+                })
+            })
+    }
+}
+
+fn entry_at(func: Option<&Function>, pc: usize) -> Option<BacktraceEntry> {
+    let func = func?;
+    let loc = func.line_table()?.get(pc as u16)?;
+    Some(BacktraceEntry::new(loc, Some(func)))
 }
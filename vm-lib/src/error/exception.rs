@@ -6,11 +6,14 @@ use haxby_opcodes::BuiltinTypeId;
 use crate::{
     builtins::VmGlobals,
     error::{
-        backtrace::Backtrace,
+        backtrace::{Backtrace, BacktraceEntry},
         vm_error::{VmError, VmErrorReason},
     },
     runtime_value::{RuntimeValue, list::List, object::Object},
-    symbol::{INTERNED_ATTR_ACTUAL, INTERNED_ATTR_BACKTRACE, INTERNED_ATTR_EXPECTED, Symbol},
+    symbol::{
+        INTERNED_ATTR_ACTUAL, INTERNED_ATTR_BACKTRACE, INTERNED_ATTR_CAUSE, INTERNED_ATTR_EXPECTED,
+        Symbol,
+    },
     vm::VirtualMachine,
 };
 
@@ -30,40 +33,48 @@ impl VmException {
     pub fn from_value_and_loc(value: RuntimeValue, loc: Option<SourcePointer>) -> Self {
         let mut this = VmException::from_value(value);
         if let Some(loc) = loc {
-            this = this.thrown_at(loc);
+            this = this.thrown_at(loc.into());
         }
 
         this
     }
 
-    pub fn thrown_at(self, loc: SourcePointer) -> Self {
-        if self.backtrace.len() == 1 && self.backtrace.first_entry().unwrap() == loc {
-            self
-        } else {
-            let mut new_bt = self.backtrace.clone();
-            new_bt.push(loc);
-            Self {
-                value: self.value.clone(),
-                backtrace: new_bt,
-            }
+    pub fn thrown_at(mut self, entry: BacktraceEntry) -> Self {
+        // the frame that threw can add the location it was thrown at a second
+        // time, this time with the name of the function
+        if self.backtrace.len() == 1 && self.backtrace.first_entry().unwrap().loc == entry.loc {
+            self.backtrace = Backtrace::default();
         }
+        self.backtrace.push(entry);
+        self
     }
 
     pub fn is_builtin_unimplemented(&self, vm: &mut VirtualMachine) -> bool {
         self.value.is_builtin_unimplemented(vm)
     }
+
+    // the values attached with throw ... from, nearest first; a chain that
+    // leads back to a value already in it is cut off after a while
+    pub fn causes(&self, vm: &VirtualMachine) -> Vec<RuntimeValue> {
+        const MAX_CAUSES: usize = 64;
+        let mut causes = vec![];
+        let mut current = self.value.clone();
+        while causes.len() < MAX_CAUSES
+            && let Ok(cause) = current.read_attribute(INTERNED_ATTR_CAUSE, &vm.globals)
+        {
+            causes.push(cause.clone());
+            current = cause;
+        }
+        causes
+    }
 }
 
 impl VmException {
     pub(crate) fn fill_in_backtrace(&self, builtins: &mut VmGlobals) {
         let bt_list = List::from(&[]);
         for bt_entry in self.backtrace.entries_iter() {
-            let buf_name = bt_entry.buffer.name.clone();
-            let buf_line = bt_entry
-                .buffer
-                .line_index_for_position(bt_entry.location.start);
-            let buf_name = RuntimeValue::String(buf_name.into());
-            let buf_line = RuntimeValue::Integer((buf_line as i64).into());
+            let buf_name = RuntimeValue::String(bt_entry.file().into());
+            let buf_line = RuntimeValue::Integer((bt_entry.line() as i64 - 1).into());
             bt_list.append(RuntimeValue::List(List::from(&[buf_name, buf_line])));
         }
         let _ = self.value.write_attribute(
@@ -86,10 +97,11 @@ impl VmException {
         }
 
         use crate::builtins::runtime_error::{
-            RUNTIME_ERR_CASE_DIVISION_BY_ZERO_IDX, RUNTIME_ERR_CASE_ENUM_WITHOUT_PAYLOAD_IDX,
-            RUNTIME_ERR_CASE_INDEX_OUT_OF_BOUNDS_IDX, RUNTIME_ERR_CASE_MISMATCHED_ARGC_IDX,
-            RUNTIME_ERR_CASE_NO_SUCH_CASE_IDX, RUNTIME_ERR_CASE_NO_SUCH_IDENTIFIER_IDX,
-            RUNTIME_ERR_CASE_OPERATION_FAILED_IDX, RUNTIME_ERR_CASE_UNEXPECTED_TYPE_IDX,
+            RUNTIME_ERR_CASE_CALL_DEPTH_EXCEEDED_IDX, RUNTIME_ERR_CASE_DIVISION_BY_ZERO_IDX,
+            RUNTIME_ERR_CASE_ENUM_WITHOUT_PAYLOAD_IDX, RUNTIME_ERR_CASE_INDEX_OUT_OF_BOUNDS_IDX,
+            RUNTIME_ERR_CASE_MISMATCHED_ARGC_IDX, RUNTIME_ERR_CASE_NO_SUCH_CASE_IDX,
+            RUNTIME_ERR_CASE_NO_SUCH_IDENTIFIER_IDX, RUNTIME_ERR_CASE_OPERATION_FAILED_IDX,
+            RUNTIME_ERR_CASE_PERMISSION_DENIED_IDX, RUNTIME_ERR_CASE_UNEXPECTED_TYPE_IDX,
         };
 
         let rt_err_type = builtins.get_builtin_type_by_id(BuiltinTypeId::RuntimeError);
@@ -170,6 +182,14 @@ impl VmException {
                 case: RUNTIME_ERR_CASE_UNEXPECTED_TYPE_IDX,
                 payload: None,
             },
+            VmErrorReason::CallDepthExceeded(depth) => ExceptionData {
+                case: RUNTIME_ERR_CASE_CALL_DEPTH_EXCEEDED_IDX,
+                payload: Some(RuntimeValue::Integer((*depth as i64).into())),
+            },
+            VmErrorReason::PermissionDenied(s) => ExceptionData {
+                case: RUNTIME_ERR_CASE_PERMISSION_DENIED_IDX,
+                payload: Some(RuntimeValue::String(s.clone().into())),
+            },
             _ => {
                 return Err(err);
             }
@@ -81,6 +81,9 @@ pub enum VmErrorReason {
     #[error("unexpected value type")]
     UnexpectedType,
 
+    #[error("no rule matches the value of {0}, and the match has no else")]
+    NonExhaustiveMatch(String),
+
     #[error("VM execution is not a valid state")]
     UnexpectedVmState,
 
@@ -104,6 +107,24 @@ pub enum VmErrorReason {
 
     #[error("VM execution halted")]
     VmHalted,
+
+    #[error("the instruction budget of {0} instructions is exhausted")]
+    InstructionBudgetExhausted(u64),
+
+    #[error("the execution deadline has passed")]
+    DeadlineExceeded,
+
+    #[error("the maximum call depth of {0} is exceeded")]
+    CallDepthExceeded(usize),
+
+    #[error("the heap limit of {0} bytes is exceeded")]
+    HeapLimitExceeded(usize),
+
+    #[error("execution was interrupted")]
+    Interrupted,
+
+    #[error("permission denied: {0}")]
+    PermissionDenied(String),
 }
 
 impl From<DecodeError> for VmErrorReason {
@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: Apache-2.0
+use crate::{
+    error::vm_error::VmErrorReason,
+    frame::Frame,
+    runtime_value::{CallResult, RuntimeValue},
+    symbol::INTERNED_OP_PRETTYPRINT,
+    vm::{ExecutionResult, RunloopExit, VirtualMachine},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// How a value in an interpolated string is laid out, from the text after the
+// colon in f"{value:spec}": [[fill]align][width][style]. The style is given
+// to the prettyprint(fmt) of the value, so that f"{x:.2}" means the same as
+// "{0:.2}".format(x); a width that starts with 0 is part of the style, so that
+// zero padding is left to the value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FormatSpec<'a> {
+    pub fill: char,
+    pub align: Option<Align>,
+    pub width: usize,
+    pub style: &'a str,
+}
+
+fn align_of(c: char) -> Option<Align> {
+    match c {
+        '<' => Some(Align::Left),
+        '^' => Some(Align::Center),
+        '>' => Some(Align::Right),
+        _ => None,
+    }
+}
+
+impl<'a> FormatSpec<'a> {
+    pub fn parse(spec: &'a str) -> Self {
+        let mut chars = spec.chars();
+        let (fill, align, rest) = match (chars.next(), chars.next()) {
+            (Some(fill), Some(a)) if align_of(a).is_some() => {
+                (fill, align_of(a), &spec[fill.len_utf8() + 1..])
+            }
+            (Some(a), _) if align_of(a).is_some() => (' ', align_of(a), &spec[1..]),
+            _ => (' ', None, spec),
+        };
+        let digits = if align.is_none() && rest.starts_with('0') {
+            0
+        } else {
+            rest.find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len())
+        };
+        Self {
+            fill,
+            align,
+            width: rest[..digits].parse().unwrap_or(0),
+            style: &rest[digits..],
+        }
+    }
+
+    // numbers line up on the right unless the spec says otherwise
+    pub fn pad(&self, text: String, is_number: bool) -> String {
+        let len = text.chars().count();
+        if len >= self.width {
+            return text;
+        }
+        let padding = self.width - len;
+        let default = if is_number { Align::Right } else { Align::Left };
+        let (before, after) = match self.align.unwrap_or(default) {
+            Align::Left => (0, padding),
+            Align::Center => (padding / 2, padding - padding / 2),
+            Align::Right => (padding, 0),
+        };
+        let fill = |n| std::iter::repeat_n(self.fill, n).collect::<String>();
+        fill(before) + &text + &fill(after)
+    }
+}
+
+// whether a prettyprint method can be called with a style
+fn takes_style(pp: &RuntimeValue) -> bool {
+    let (f, receiver) = if let Some(bf) = pp.as_bound_function() {
+        (bf.func(), 1)
+    } else if let Some(f) = pp.as_function() {
+        (f, 0)
+    } else {
+        return false;
+    };
+    let arity = f.arity();
+    arity.required <= receiver + 1 && (f.varargs() || arity.required + arity.optional > receiver)
+}
+
+// the text of an interpolated value, as laid out by its spec
+pub(crate) fn format_value(
+    value: &RuntimeValue,
+    spec: &str,
+    frame: &mut Frame,
+    vm: &mut VirtualMachine,
+) -> ExecutionResult<RunloopExit<String>> {
+    let spec = FormatSpec::parse(spec);
+    let styled = if spec.style.is_empty() {
+        None
+    } else {
+        value
+            .read_attribute(INTERNED_OP_PRETTYPRINT, &vm.globals)
+            .ok()
+            .filter(takes_style)
+    };
+
+    let text = match styled {
+        Some(pp) => {
+            frame
+                .stack
+                .push(RuntimeValue::String(spec.style.to_owned().into()));
+            match pp.eval(1, frame, vm, true)? {
+                CallResult::Ok(rv) => match rv.as_string() {
+                    Some(s) => s.raw_value().clone(),
+                    None => return Err(VmErrorReason::UnexpectedType.into()),
+                },
+                CallResult::Exception(e) => return Ok(RunloopExit::Exception(e)),
+            }
+        }
+        None => value.prettyprint(frame, vm),
+    };
+    let is_number = matches!(value, RuntimeValue::Integer(_) | RuntimeValue::Float(_));
+    Ok(RunloopExit::Ok(spec.pad(text, is_number)))
+}
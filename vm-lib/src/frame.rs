@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
+use std::rc::Rc;
+
 use aria_compiler::line_table::LineTable;
 use aria_parser::ast::SourcePointer;
 
 use crate::{
+    error::backtrace::TailCallers,
     runtime_value::{RuntimeValue, function::Function, isa::IsaCheckable},
     stack::Stack,
     vm::VirtualMachine,
@@ -35,6 +38,12 @@ pub struct Frame {
     pub(crate) locals: Vec<LocalVariable>,
     pub(crate) func: Option<Function>,
     pub argc: u8,
+    // set when a generator yields, so the next run picks up where it left off
+    pub(crate) resume_at: Option<usize>,
+    // the instruction that is running, which is the call while a callee runs
+    pub(crate) pc: usize,
+    // the frames that tail calls replaced on the way to this one
+    pub(crate) tail_callers: Option<Rc<TailCallers>>,
 }
 
 impl Frame {
@@ -67,6 +76,9 @@ impl Frame {
             locals: Vec::with_capacity(n as usize),
             func: None,
             argc: 0,
+            resume_at: None,
+            pc: 0,
+            tail_callers: None,
         };
         for _ in 0..n {
             this.locals.push(LocalVariable::default())
@@ -79,6 +91,10 @@ impl Frame {
         self
     }
 
+    pub(crate) fn is_suspended(&self) -> bool {
+        self.resume_at.is_some()
+    }
+
     pub(crate) fn set_line_table(&mut self, lt: Option<&LineTable>) -> &mut Self {
         self.line_table = lt.cloned();
         self
@@ -101,20 +117,25 @@ impl Frame {
         self.ctrl_blocks.clear();
         self.func = Some(f.clone());
         self.argc = 0;
+        self.resume_at = None;
+        self.pc = 0;
+        self.tail_callers = None;
         self.set_line_table(f.line_table());
         let locals = f.frame_size() as usize;
         self.locals.clear();
         self.locals.resize_with(locals, LocalVariable::default);
     }
 
-    pub(crate) fn reset_for_pool(mut self) -> Self {
+    pub(crate) fn reset_for_pool(&mut self) {
         self.stack.clear();
         self.ctrl_blocks.clear();
         self.locals.clear();
         self.func = None;
         self.argc = 0;
+        self.resume_at = None;
+        self.pc = 0;
+        self.tail_callers = None;
         self.line_table = None;
-        self
     }
 }
 
@@ -0,0 +1,340 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+use rustc_data_structures::fx::FxHashMap;
+
+use crate::runtime_value::RuntimeValue;
+
+// Values are reference counted, which frees everything except cycles (an object
+// storing itself in a field, a closure capturing itself, ...). To reclaim those,
+// every allocation that can be part of a cycle registers itself here, and the
+// collector periodically runs a trial deletion pass over all of them:
+// - for each tracked node, count how many references come from other tracked nodes;
+// - a node whose strong count exceeds that is referenced from somewhere else
+//   (a frame, a module, a type, Rust code) and is a root;
+// - everything reachable from a root is alive, everything else can only be
+//   reached from garbage, so its contents are dropped to break the cycles.
+// This needs no knowledge of VM roots, so it is safe to run at any point where
+// no Rust code is holding a borrow into the contents of a value.
+
+pub(crate) trait Traceable {
+    // call visit with the identity of every value this node holds a strong reference to
+    fn trace(&self, visit: &mut dyn FnMut(usize));
+
+    // release every value held by this node; only ever called on garbage
+    fn clear(&self, _sink: &mut Vec<RuntimeValue>) {}
+
+    // roughly how many bytes this node owns outside of its own allocation
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+// the memory a value stored in a list, map or object accounts for; the
+// contents of strings and big integers are charged when they are allocated
+pub(crate) const SLOT_SIZE: usize = std::mem::size_of::<RuntimeValue>();
+
+pub(crate) fn identity<T: ?Sized>(rc: &Rc<T>) -> usize {
+    Rc::as_ptr(rc) as *const () as usize
+}
+
+const MINIMUM_COLLECTION_THRESHOLD: usize = 100_000;
+const MINIMUM_PRUNE_THRESHOLD: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GcStats {
+    // number of tracked values allocated since the thread started
+    pub allocations: u64,
+    // number of tracked values that may still be alive
+    pub tracked: usize,
+    // tracked allocations since the last collection
+    pub allocations_since_collection: usize,
+    // allocations after which the VM will run a collection
+    pub threshold: usize,
+    pub collections: u64,
+    // number of values freed by the collector since the thread started
+    pub collected: u64,
+    // number of values freed by the most recent collection
+    pub last_collected: usize,
+}
+
+struct Heap {
+    // each node with the owner it was allocated under
+    nodes: Vec<(Weak<dyn Traceable>, HeapOwner)>,
+    prune_at: usize,
+    allocations: u64,
+    allocations_since_collection: usize,
+    threshold: usize,
+    collections: u64,
+    collected: u64,
+    last_collected: usize,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self {
+            nodes: Default::default(),
+            prune_at: MINIMUM_PRUNE_THRESHOLD,
+            allocations: 0,
+            allocations_since_collection: 0,
+            threshold: MINIMUM_COLLECTION_THRESHOLD,
+            collections: 0,
+            collected: 0,
+            last_collected: 0,
+        }
+    }
+}
+
+impl Heap {
+    fn track(&mut self, node: Weak<dyn Traceable>, owner: HeapOwner) {
+        // registrations of values that have since been freed by reference counting
+        // still pin their allocation, so drop those before the list grows too much
+        if self.nodes.len() >= self.prune_at {
+            self.nodes.retain(|(n, _)| n.strong_count() > 0);
+            self.prune_at = MINIMUM_PRUNE_THRESHOLD.max(2 * self.nodes.len());
+        }
+
+        self.nodes.push((node, owner));
+        self.allocations += 1;
+        self.allocations_since_collection += 1;
+    }
+}
+
+// The heap is shared by every VM on the thread, so that values can move
+// between them, but a VM with a heap limit only answers for what it
+// allocated: while it runs, it is the owner that new containers are tagged
+// with and that the contents of new strings and big integers are charged
+// to, until they are freed.
+pub(crate) type HeapOwner = u32;
+
+// allocations made while no VM with a heap limit is running
+pub(crate) const NO_OWNER: HeapOwner = 0;
+
+struct Charges {
+    bytes: usize,
+    // the heap limit of the owner, past which it should check its heap
+    budget: usize,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = RefCell::new(Heap::default());
+    static OWNER: Cell<HeapOwner> = const { Cell::new(NO_OWNER) };
+    static NEXT_OWNER: Cell<HeapOwner> = const { Cell::new(NO_OWNER + 1) };
+    static CHARGED: RefCell<FxHashMap<HeapOwner, Charges>> = RefCell::new(Default::default());
+    // a string or big integer took an owner past its budget since the last heap_size
+    static HEAP_CHECK_DUE: Cell<bool> = const { Cell::new(false) };
+}
+
+pub(crate) fn new_owner(budget: usize) -> HeapOwner {
+    let owner = NEXT_OWNER.with(|next| {
+        let owner = next.get();
+        next.set(owner.wrapping_add(1).max(NO_OWNER + 1));
+        owner
+    });
+    CHARGED.with(|charged| {
+        charged
+            .borrow_mut()
+            .insert(owner, Charges { bytes: 0, budget })
+    });
+    owner
+}
+
+// forgets what is still charged to an owner that will not run again
+pub(crate) fn release_owner(owner: HeapOwner) {
+    CHARGED.with(|charged| charged.borrow_mut().remove(&owner));
+}
+
+// makes owner the one allocations are charged to, until the guard is dropped
+pub(crate) fn enter_owner(owner: HeapOwner) -> OwnerGuard {
+    OwnerGuard {
+        previous: OWNER.with(|current| current.replace(owner)),
+    }
+}
+
+pub(crate) struct OwnerGuard {
+    previous: HeapOwner,
+}
+
+impl Drop for OwnerGuard {
+    fn drop(&mut self) {
+        OWNER.with(|current| current.set(self.previous));
+    }
+}
+
+// charges bytes to the current owner, and returns it so that uncharge can
+// give them back to the same one
+pub(crate) fn charge(bytes: usize) -> HeapOwner {
+    let owner = OWNER.with(|current| current.get());
+    if owner != NO_OWNER {
+        CHARGED.with(|charged| {
+            if let Some(charges) = charged.borrow_mut().get_mut(&owner) {
+                charges.bytes += bytes;
+                // a string that keeps doubling outgrows any check interval
+                if charges.bytes > charges.budget {
+                    HEAP_CHECK_DUE.with(|due| due.set(true));
+                }
+            }
+        });
+    }
+    owner
+}
+
+pub(crate) fn uncharge(owner: HeapOwner, bytes: usize) {
+    if owner != NO_OWNER {
+        // values can outlive the map when the thread exits
+        let _ = CHARGED.try_with(|charged| {
+            if let Some(charges) = charged.borrow_mut().get_mut(&owner) {
+                charges.bytes = charges.bytes.saturating_sub(bytes);
+            }
+        });
+    }
+}
+
+pub(crate) fn heap_check_due() -> bool {
+    HEAP_CHECK_DUE.with(|due| due.get())
+}
+
+pub(crate) fn track<T: Traceable + 'static>(rc: &Rc<T>) {
+    let weak: Weak<dyn Traceable> = Rc::downgrade(rc) as Weak<dyn Traceable>;
+    let owner = OWNER.with(|current| current.get());
+    HEAP.with(|heap| heap.borrow_mut().track(weak, owner));
+}
+
+pub(crate) fn should_collect() -> bool {
+    HEAP.with(|heap| {
+        let heap = heap.borrow();
+        heap.allocations_since_collection >= heap.threshold
+    })
+}
+
+pub fn stats() -> GcStats {
+    HEAP.with(|heap| {
+        let heap = heap.borrow();
+        GcStats {
+            allocations: heap.allocations,
+            tracked: heap
+                .nodes
+                .iter()
+                .filter(|(n, _)| n.strong_count() > 0)
+                .count(),
+            allocations_since_collection: heap.allocations_since_collection,
+            threshold: heap.threshold,
+            collections: heap.collections,
+            collected: heap.collected,
+            last_collected: heap.last_collected,
+        }
+    })
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HeapSize {
+    // number of tracked values that are alive
+    pub values: usize,
+    // an estimate of the memory they hold, plus the strings and big integers
+    // that are alive
+    pub bytes: usize,
+}
+
+// what is alive of the allocations made under owner; this walks every
+// tracked value, so it takes time proportional to the heap
+pub(crate) fn heap_size(owner: HeapOwner) -> HeapSize {
+    let nodes = HEAP.with(|heap| {
+        heap.borrow()
+            .nodes
+            .iter()
+            .filter(|(_, o)| *o == owner)
+            .filter_map(|(n, _)| n.upgrade())
+            .collect::<Vec<_>>()
+    });
+    HEAP_CHECK_DUE.with(|due| due.set(false));
+    let charged = CHARGED.with(|charged| charged.borrow().get(&owner).map_or(0, |c| c.bytes));
+    nodes.iter().fold(
+        HeapSize {
+            values: 0,
+            bytes: charged,
+        },
+        |size, node| HeapSize {
+            values: size.values + 1,
+            bytes: size.bytes + std::mem::size_of_val(node.as_ref()) + node.heap_size(),
+        },
+    )
+}
+
+// returns the number of values that were found to be garbage
+pub fn collect() -> usize {
+    let weak_nodes = HEAP.with(|heap| std::mem::take(&mut heap.borrow_mut().nodes));
+    let (nodes, owners): (Vec<_>, Vec<_>) = weak_nodes
+        .iter()
+        .filter_map(|(n, owner)| Some((n.upgrade()?, *owner)))
+        .unzip();
+    drop(weak_nodes);
+
+    let index = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (identity(n), i))
+        .collect::<FxHashMap<_, _>>();
+
+    let mut internal_refs = vec![0usize; nodes.len()];
+    for node in &nodes {
+        node.trace(&mut |id| {
+            if let Some(&i) = index.get(&id) {
+                internal_refs[i] += 1;
+            }
+        });
+    }
+
+    // every node holds one extra strong reference via the upgraded Weak above
+    let mut alive = vec![false; nodes.len()];
+    let mut worklist = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if Rc::strong_count(node) - 1 > internal_refs[i] {
+            alive[i] = true;
+            worklist.push(i);
+        }
+    }
+
+    while let Some(i) = worklist.pop() {
+        nodes[i].trace(&mut |id| {
+            if let Some(&c) = index.get(&id)
+                && !alive[c]
+            {
+                alive[c] = true;
+                worklist.push(c);
+            }
+        });
+    }
+
+    let mut sink = Vec::new();
+    let mut survivors = Vec::with_capacity(nodes.len());
+    let mut garbage_count = 0;
+    for (i, node) in nodes.iter().enumerate() {
+        if alive[i] {
+            survivors.push((Rc::downgrade(node), owners[i]));
+        } else {
+            garbage_count += 1;
+            node.clear(&mut sink);
+        }
+    }
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        survivors.append(&mut heap.nodes);
+        heap.nodes = survivors;
+        heap.prune_at = MINIMUM_PRUNE_THRESHOLD.max(2 * heap.nodes.len());
+        heap.threshold = MINIMUM_COLLECTION_THRESHOLD.max(2 * heap.nodes.len());
+        heap.allocations_since_collection = 0;
+        heap.collections += 1;
+        heap.collected += garbage_count as u64;
+        heap.last_collected = garbage_count;
+    });
+
+    // dropping the contents of the garbage is what actually frees the cycles
+    drop(sink);
+    drop(nodes);
+
+    garbage_count
+}
@@ -5,12 +5,20 @@ use vm::{ExecutionResult, RunloopExit, VirtualMachine, VmOptions};
 pub mod arity;
 pub mod builtins;
 pub mod console;
+pub mod coverage;
+pub mod debugger;
+pub mod embed;
 pub mod error;
+pub mod format;
 pub mod frame;
+pub mod gc;
+pub mod limits;
 pub mod mixin_includer;
 pub mod opcodes;
+pub mod profiler;
 pub mod runtime_module;
 pub mod runtime_value;
+pub mod sandbox;
 pub mod shape;
 pub mod stack;
 pub mod symbol;
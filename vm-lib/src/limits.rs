@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use crate::{
+    error::vm_error::VmErrorReason,
+    gc::{HeapOwner, OwnerGuard},
+    vm::VirtualMachine,
+};
+
+// Limits on what a program may consume, for running code that cannot be
+// trusted to terminate on its own. Exceeding the instruction budget, the
+// deadline or the heap limit, or being interrupted, stops the program with
+// an error that Aria code cannot catch; exceeding the call depth throws a
+// RuntimeError::CallDepthExceeded, which unwinds the stack and can be caught.
+// Only the call depth is limited by default.
+#[derive(Clone, Debug)]
+pub struct ExecutionLimits {
+    // the number of instructions the VM may execute, over its whole lifetime
+    pub instruction_budget: Option<u64>,
+    pub deadline: Option<Instant>,
+    // the number of Aria and builtin function calls that may be in progress
+    pub max_call_depth: Option<usize>,
+    // an estimate of the memory held by the lists, maps, objects, closures,
+    // strings and big integers that this VM allocated and are still alive
+    pub max_heap_bytes: Option<usize>,
+}
+
+// calls between Aria functions take no native stack, so this is about how
+// much memory runaway recursion gets to use before it is stopped
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 100_000;
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        Self {
+            instruction_budget: None,
+            deadline: None,
+            max_call_depth: Some(DEFAULT_MAX_CALL_DEPTH),
+            max_heap_bytes: None,
+        }
+    }
+}
+
+// Stops a running VM from another thread. The VM notices at the next limit
+// check, a few hundred instructions later at most, and stays interrupted
+// until reset() is called.
+#[derive(Clone, Debug, Default)]
+pub struct InterruptHandle {
+    flag: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    pub fn interrupt(&self) {
+        self.flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.flag.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+}
+
+// instructions between checks of the interrupt flag and the deadline
+const CHECK_INTERVAL: u64 = 256;
+// the heap is measured by walking its containers, so this happens much less often
+const HEAP_CHECK_INTERVAL: u64 = 64 * 1024;
+
+#[derive(Default)]
+pub(crate) struct LimitState {
+    pub(crate) instructions: u64,
+    pub(crate) next_check: u64,
+    next_heap_check: u64,
+    pub(crate) call_depth: usize,
+    pub(crate) interrupt: InterruptHandle,
+    // what this VM allocates is charged to, once it has a heap limit
+    heap_owner: Option<HeapOwner>,
+}
+
+impl LimitState {
+    // while the guard is alive, allocations count against this VM's heap limit
+    pub(crate) fn charge_allocations(&mut self, limits: &ExecutionLimits) -> Option<OwnerGuard> {
+        let max = limits.max_heap_bytes?;
+        let owner = *self
+            .heap_owner
+            .get_or_insert_with(|| crate::gc::new_owner(max));
+        Some(crate::gc::enter_owner(owner))
+    }
+
+    // checked by the run loop after every instruction
+    #[inline]
+    pub(crate) fn check_due(&self) -> bool {
+        self.instructions >= self.next_check
+            || (self.heap_owner.is_some() && crate::gc::heap_check_due())
+    }
+}
+
+impl Drop for LimitState {
+    fn drop(&mut self) {
+        if let Some(owner) = self.heap_owner {
+            crate::gc::release_owner(owner);
+        }
+    }
+}
+
+impl VirtualMachine {
+    pub fn interrupt_handle(&self) -> InterruptHandle {
+        self.limit_state.interrupt.clone()
+    }
+
+    // the number of instructions this VM has executed so far
+    pub fn instructions_executed(&self) -> u64 {
+        self.limit_state.instructions
+    }
+
+    // called by the run loop once check_due says so
+    pub(crate) fn check_limits(&mut self) -> Result<(), VmErrorReason> {
+        let limits = &self.options.limits;
+        let state = &mut self.limit_state;
+        let executed = state.instructions;
+
+        if let Some(budget) = limits.instruction_budget
+            && executed > budget
+        {
+            return Err(VmErrorReason::InstructionBudgetExhausted(budget));
+        }
+        if state.interrupt.is_interrupted() {
+            return Err(VmErrorReason::Interrupted);
+        }
+        if let Some(deadline) = limits.deadline
+            && Instant::now() >= deadline
+        {
+            return Err(VmErrorReason::DeadlineExceeded);
+        }
+        if let Some(max) = limits.max_heap_bytes
+            && let Some(owner) = state.heap_owner
+            && (executed >= state.next_heap_check || crate::gc::heap_check_due())
+        {
+            let mut usage = crate::gc::heap_size(owner);
+            if usage.bytes > max {
+                // cycles are not freed until a collection, so give that a chance
+                crate::gc::collect();
+                usage = crate::gc::heap_size(owner);
+                if usage.bytes > max {
+                    return Err(VmErrorReason::HeapLimitExceeded(max));
+                }
+            }
+            // walking a large heap is expensive, so check it less often
+            state.next_heap_check = executed + HEAP_CHECK_INTERVAL.max(usage.values as u64);
+        }
+
+        state.next_check = executed + CHECK_INTERVAL;
+        if let Some(budget) = limits.instruction_budget {
+            state.next_check = state.next_check.min(budget + 1);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use aria_compiler::line_table::LineTable;
+use haxby_opcodes::Opcode;
+
+use crate::{
+    debugger::{LineIndex, SourceLine},
+    frame::Frame,
+    runtime_value::function::Function,
+    vm::VirtualMachine,
+};
+
+// A sampling profiler for Aria code: at every interval it records the Aria
+// call stack, as function names and the line each of them is running, so
+// that reports point at the program rather than at the interpreter. Time
+// spent in a builtin is charged to the builtin, called from its caller's line.
+#[derive(Clone, Debug)]
+pub struct ProfilerOptions {
+    pub interval: Duration,
+    // also count how many times each opcode runs; this costs a hash lookup
+    // per instruction
+    pub count_opcodes: bool,
+}
+
+impl Default for ProfilerOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(1),
+            count_opcodes: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProfileFrame {
+    pub function: String,
+    // where the function was when the sample was taken; builtins have none
+    pub location: Option<SourceLine>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionTime {
+    pub function: String,
+    // the file that defines the function; builtins have none
+    pub file: Option<String>,
+    // samples with the function anywhere on the stack, and at the top of it
+    pub inclusive: u64,
+    pub exclusive: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub interval: Duration,
+    pub duration: Duration,
+    // each distinct stack, outermost frame first, with the number of samples
+    // that found it
+    pub stacks: Vec<(Vec<ProfileFrame>, u64)>,
+    // by opcode name, most frequent first; empty unless opcodes were counted
+    pub opcodes: Vec<(String, u64)>,
+}
+
+// a function and the file it is defined in
+fn key(frame: &ProfileFrame) -> (&str, Option<&str>) {
+    (
+        frame.function.as_str(),
+        frame.location.as_ref().map(|l| l.file.as_str()),
+    )
+}
+
+fn frame_label(frame: &ProfileFrame) -> String {
+    match &frame.location {
+        Some(loc) => format!("{} ({}:{})", frame.function, loc.file, loc.line),
+        None => frame.function.clone(),
+    }
+}
+
+impl Profile {
+    pub fn total_samples(&self) -> u64 {
+        self.stacks.iter().map(|(_, n)| n).sum()
+    }
+
+    // one "outer;inner count" line per stack, as read by flamegraph.pl,
+    // inferno and speedscope
+    pub fn folded(&self) -> String {
+        let mut out = String::new();
+        for (stack, count) in &self.stacks {
+            let labels = stack.iter().map(frame_label).collect::<Vec<_>>();
+            let _ = writeln!(out, "{} {count}", labels.join(";"));
+        }
+        out
+    }
+
+    // per-function sample counts, by exclusive samples and then inclusive ones
+    pub fn function_times(&self) -> Vec<FunctionTime> {
+        let mut times: HashMap<(&str, Option<&str>), (u64, u64)> = HashMap::new();
+        for (stack, count) in &self.stacks {
+            let mut seen = vec![];
+            for frame in stack {
+                // recursive functions count once per sample
+                if !seen.contains(&key(frame)) {
+                    seen.push(key(frame));
+                    times.entry(key(frame)).or_default().0 += count;
+                }
+            }
+            if let Some(top) = stack.last() {
+                times.entry(key(top)).or_default().1 += count;
+            }
+        }
+
+        let mut times = times
+            .into_iter()
+            .map(|((function, file), (inclusive, exclusive))| FunctionTime {
+                function: function.to_owned(),
+                file: file.map(str::to_owned),
+                inclusive,
+                exclusive,
+            })
+            .collect::<Vec<_>>();
+        times.sort_by(|a, b| {
+            (b.exclusive, b.inclusive, &a.function).cmp(&(a.exclusive, a.inclusive, &b.function))
+        });
+        times
+    }
+
+    // a table of function times, followed by the opcode counts if there are any
+    pub fn summary(&self) -> String {
+        let ms = |samples: u64| (self.interval * samples as u32).as_secs_f64() * 1000.0;
+        let total = self.total_samples().max(1) as f64;
+
+        let mut out = format!(
+            "{} samples every {}us over {:.3}s\n\n{:>12} {:>7} {:>12} {:>7}  function\n",
+            self.total_samples(),
+            self.interval.as_micros(),
+            self.duration.as_secs_f64(),
+            "self ms",
+            "self %",
+            "total ms",
+            "total %",
+        );
+        for t in self.function_times() {
+            let _ = writeln!(
+                out,
+                "{:>12.1} {:>6.1}% {:>12.1} {:>6.1}%  {}{}",
+                ms(t.exclusive),
+                100.0 * t.exclusive as f64 / total,
+                ms(t.inclusive),
+                100.0 * t.inclusive as f64 / total,
+                t.function,
+                t.file.map(|f| format!(" ({f})")).unwrap_or_default(),
+            );
+        }
+
+        if !self.opcodes.is_empty() {
+            let _ = writeln!(out, "\n{:>14}  opcode", "count");
+            for (name, count) in &self.opcodes {
+                let _ = writeln!(out, "{count:>14}  {name}");
+            }
+        }
+        out
+    }
+}
+
+// what the profiler knows about a function that is running
+struct ShadowFrame {
+    func: Option<Function>,
+    line_table: Option<LineTable>,
+    op_idx: usize,
+}
+
+pub(crate) struct ProfileSession {
+    options: ProfilerOptions,
+    started: Instant,
+    // advanced by the sampler thread once per interval
+    ticks: Arc<AtomicU64>,
+    stop: Arc<AtomicBool>,
+    seen_ticks: u64,
+    frames: Vec<ShadowFrame>,
+    stacks: HashMap<Vec<ProfileFrame>, u64>,
+    opcodes: HashMap<std::mem::Discriminant<Opcode>, (Opcode, u64)>,
+    lines: LineIndex,
+}
+
+impl ProfileSession {
+    fn new(options: ProfilerOptions) -> Self {
+        let ticks = Arc::new(AtomicU64::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        std::thread::spawn({
+            let ticks = ticks.clone();
+            let stop = stop.clone();
+            let interval = options.interval;
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    std::thread::sleep(interval);
+                    ticks.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+
+        Self {
+            options,
+            started: Instant::now(),
+            ticks,
+            stop,
+            seen_ticks: 0,
+            frames: vec![],
+            stacks: HashMap::new(),
+            opcodes: HashMap::new(),
+            lines: Default::default(),
+        }
+    }
+
+    pub(crate) fn enter_frame(&mut self, frame: &Frame) {
+        self.frames.push(ShadowFrame {
+            func: frame.func.clone(),
+            line_table: frame.get_line_table().cloned(),
+            op_idx: 0,
+        });
+    }
+
+    pub(crate) fn enter_builtin(&mut self, func: &Function) {
+        self.frames.push(ShadowFrame {
+            func: Some(func.clone()),
+            line_table: None,
+            op_idx: 0,
+        });
+    }
+
+    pub(crate) fn leave_frame(&mut self) {
+        self.sample();
+        self.frames.pop();
+    }
+
+    // called before the VM runs the instruction at op_idx
+    pub(crate) fn instruction(&mut self, op_idx: usize, op: Opcode) {
+        if let Some(top) = self.frames.last_mut() {
+            top.op_idx = op_idx;
+        }
+        if self.options.count_opcodes {
+            self.opcodes
+                .entry(std::mem::discriminant(&op))
+                .or_insert((op, 0))
+                .1 += 1;
+        }
+        self.sample();
+    }
+
+    // records the current stack once for every interval that ended since
+    // the last sample
+    fn sample(&mut self) {
+        let ticks = self.ticks.load(Ordering::Relaxed);
+        if ticks == self.seen_ticks {
+            return;
+        }
+        let count = ticks - self.seen_ticks;
+        self.seen_ticks = ticks;
+
+        let stack = self
+            .frames
+            .iter()
+            .map(|sf| ProfileFrame {
+                function: sf
+                    .func
+                    .as_ref()
+                    .map_or("<unknown>".to_owned(), |f| f.name().to_owned()),
+                location: sf
+                    .line_table
+                    .as_ref()
+                    .and_then(|lt| lt.get(sf.op_idx as u16))
+                    .map(|sp| self.lines.line_of(&sp)),
+            })
+            .collect::<Vec<_>>();
+        *self.stacks.entry(stack).or_default() += count;
+    }
+
+    fn finish(mut self) -> Profile {
+        let mut stacks = std::mem::take(&mut self.stacks)
+            .into_iter()
+            .collect::<Vec<_>>();
+        stacks.sort_by(|a, b| a.0.iter().map(frame_label).cmp(b.0.iter().map(frame_label)));
+
+        let mut opcodes = std::mem::take(&mut self.opcodes)
+            .into_values()
+            .map(|(op, count)| {
+                // the mnemonic, without the operands
+                let name = op.to_string();
+                let name = name.split(' ').next().unwrap_or_default().to_owned();
+                (name, count)
+            })
+            .collect::<Vec<_>>();
+        opcodes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        Profile {
+            interval: self.options.interval,
+            duration: self.started.elapsed(),
+            stacks,
+            opcodes,
+        }
+    }
+}
+
+impl Drop for ProfileSession {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+impl VirtualMachine {
+    // starts sampling code that runs from now on, replacing any profile
+    // that is being recorded
+    pub fn start_profiling(&mut self, options: ProfilerOptions) {
+        self.profiler = Some(ProfileSession::new(options));
+    }
+
+    // stops sampling and returns what was recorded
+    pub fn stop_profiling(&mut self) -> Option<Profile> {
+        self.profiler.take().map(ProfileSession::finish)
+    }
+}
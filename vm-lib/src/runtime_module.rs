@@ -27,6 +27,8 @@ struct RuntimeModuleImpl {
     indexed_constants: Vec<RuntimeValue>,
     values: RefCell<FxHashMap<String, NamedValue>>,
     entry_co: crate::runtime_value::runtime_code_object::CodeObject,
+    // the import path for imported modules, or the name it was loaded under
+    name: RefCell<Option<String>>,
 }
 
 fn byte_array_to_opcode_array(bytes: &[u8]) -> aria_compiler::bc_reader::DecodeResult<Vec<Opcode>> {
@@ -66,6 +68,15 @@ macro_rules! replace_const_with_symbol {
         };
         *$opcode = Opcode::$target_variant($arg0, n_as_sym.0);
     }};
+    ($vm:expr, $cm:expr, $arg0:expr, $n:expr, $arg2:expr, $opcode:expr, $target_variant:ident) => {{
+        let n_const = $cm.load_indexed_const($n).expect("missing constant");
+        let n_as_str = n_const.as_string().expect("expected string constant");
+        let n_as_sym = match $vm.globals.intern_symbol(&n_as_str) {
+            Ok(s) => s,
+            Err(_) => return Err(VmErrorReason::UnexpectedVmState),
+        };
+        *$opcode = Opcode::$target_variant($arg0, n_as_sym.0, $arg2);
+    }};
 }
 
 fn replace_attribute_access_with_interned(
@@ -97,8 +108,12 @@ fn replace_attribute_access_with_interned(
             Opcode::EnumCheckIsCase(n) => {
                 replace_const_with_symbol!(vm, cm, *n, opcode, EnumCheckIsCaseSymbol)
             }
+            Opcode::CallLocalMethod(x, n, argc) => {
+                replace_const_with_symbol!(vm, cm, *x, *n, *argc, opcode, CallLocalMethodSymbol)
+            }
             Opcode::BindCaseSymbol(..)
             | Opcode::NewEnumValSymbol(..)
+            | Opcode::CallLocalMethodSymbol(..)
             | Opcode::EnumCheckIsCaseSymbol(_) => {
                 return Err(VmErrorReason::UnexpectedVmState);
             }
@@ -115,6 +130,9 @@ fn compiled_code_object_to_runtime_code_object(
 ) -> Result<crate::runtime_value::runtime_code_object::CodeObject, VmErrorReason> {
     let mut ops = byte_array_to_opcode_array(cco.body.as_slice())?;
     replace_attribute_access_with_interned(vm, cm, &mut ops)?;
+    if let Some(coverage) = vm.coverage.as_mut() {
+        coverage.register(&cco.line_table, &ops);
+    }
     let body: Rc<[Opcode]> = ops.into();
 
     Ok(crate::runtime_value::runtime_code_object::CodeObject {
@@ -126,6 +144,7 @@ fn compiled_code_object_to_runtime_code_object(
         frame_size: cco.frame_size,
         loc: cco.loc.clone(),
         line_table: Rc::from(cco.line_table.clone()),
+        local_names: cco.local_names.into(),
     })
 }
 
@@ -135,10 +154,11 @@ fn compiled_constant_to_runtime_value(
     value: aria_compiler::constant_value::ConstantValue,
 ) -> Result<RuntimeValue, VmErrorReason> {
     use aria_compiler::constant_value::ConstantValue::{
-        CompiledCodeObject, Float, Integer, String,
+        BigInteger, CompiledCodeObject, Float, Integer, String,
     };
     match value {
         Integer(n) => Ok(RuntimeValue::Integer(From::from(n))),
+        BigInteger(n) => Ok(RuntimeValue::Integer(From::from(n))),
         String(s) => Ok(RuntimeValue::String(s.into())),
         CompiledCodeObject(cco) => Ok(RuntimeValue::CodeObject(
             compiled_code_object_to_runtime_code_object(vm, cm, cco)?,
@@ -157,6 +177,7 @@ impl RuntimeModuleImpl {
             indexed_constants: Vec::new(),
             values: Default::default(),
             entry_co,
+            name: Default::default(),
         };
 
         let mut i = 0;
@@ -267,6 +288,14 @@ impl RuntimeModule {
         self.imp.named_values_of_this()
     }
 
+    pub fn name(&self) -> Option<String> {
+        self.imp.name.borrow().clone()
+    }
+
+    pub(crate) fn set_name(&self, name: &str) {
+        *self.imp.name.borrow_mut() = Some(name.to_owned());
+    }
+
     pub(crate) fn get_compiled_module(&self) -> &CompiledModule {
         &self.imp.compiled_module
     }
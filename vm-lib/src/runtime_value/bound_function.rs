@@ -3,27 +3,37 @@ use std::rc::Rc;
 
 use crate::{
     frame::Frame,
+    gc::Traceable,
     runtime_value::function::PartialFunctionApplication,
     vm::{ExecutionResult, VirtualMachine},
 };
 
 use super::{CallResult, RuntimeValue, function::Function};
 
-struct BoundFunctionImpl {
+pub(super) struct BoundFunctionImpl {
     this: RuntimeValue,
     func: Function,
 }
 
 #[derive(Clone)]
 pub struct BoundFunction {
-    imp: Rc<BoundFunctionImpl>,
+    pub(super) imp: Rc<BoundFunctionImpl>,
+}
+
+impl Traceable for BoundFunctionImpl {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        if let Some(id) = self.this.gc_identity() {
+            visit(id);
+        }
+        visit(crate::gc::identity(&self.func.imp));
+    }
 }
 
 impl BoundFunction {
     pub(super) fn bind(this: RuntimeValue, func: Function) -> Self {
-        Self {
-            imp: Rc::new(BoundFunctionImpl { this, func }),
-        }
+        let imp = Rc::new(BoundFunctionImpl { this, func });
+        crate::gc::track(&imp);
+        Self { imp }
     }
 
     pub fn this(&self) -> &RuntimeValue {
@@ -4,22 +4,58 @@ use std::rc::Rc;
 use haxby_opcodes::BuiltinTypeId;
 use rustc_data_structures::fx::FxHashSet;
 
-use crate::{builtins::VmGlobals, symbol::Symbol};
+use crate::{builtins::VmGlobals, gc::HeapOwner, symbol::Symbol};
 
-use super::object::ObjectBox;
+use super::{integer::IntegerRepr, object::ObjectBox};
 
 pub(crate) struct BuiltinValueImpl<T>
 where
-    T: Clone,
+    T: Clone + HeapBytes,
 {
     pub(crate) val: T,
     id: BuiltinTypeId,
+    // the owner whose heap limit the contents of val count against
+    charged_to: HeapOwner,
     pub(crate) boxx: ObjectBox,
 }
 
+impl<T> Drop for BuiltinValueImpl<T>
+where
+    T: Clone + HeapBytes,
+{
+    fn drop(&mut self) {
+        crate::gc::uncharge(self.charged_to, self.val.heap_bytes());
+    }
+}
+
+// the memory a builtin value owns outside of its own allocation
+pub trait HeapBytes {
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
+
+impl HeapBytes for bool {}
+impl HeapBytes for f64 {}
+
+impl HeapBytes for String {
+    fn heap_bytes(&self) -> usize {
+        self.len()
+    }
+}
+
+impl HeapBytes for IntegerRepr {
+    fn heap_bytes(&self) -> usize {
+        match self {
+            IntegerRepr::Small(_) => 0,
+            IntegerRepr::Big(n) => n.bits().div_ceil(8) as usize,
+        }
+    }
+}
+
 impl<T> BuiltinValueImpl<T>
 where
-    T: Clone,
+    T: Clone + HeapBytes,
 {
     fn list_attributes(&self, builtins: &VmGlobals) -> FxHashSet<Symbol> {
         self.boxx.list_attributes(builtins)
@@ -29,7 +65,7 @@ where
 #[derive(Clone)]
 pub struct BuiltinValue<T>
 where
-    T: Clone,
+    T: Clone + HeapBytes,
 {
     pub(crate) imp: Rc<BuiltinValueImpl<T>>,
 }
@@ -38,7 +74,7 @@ trait GetBuiltinTypeId {
     fn get_builtin_type_id() -> BuiltinTypeId;
 }
 
-impl GetBuiltinTypeId for i64 {
+impl GetBuiltinTypeId for IntegerRepr {
     #[inline]
     fn get_builtin_type_id() -> BuiltinTypeId {
         BuiltinTypeId::Int
@@ -65,13 +101,19 @@ impl GetBuiltinTypeId for f64 {
 
 impl<T> From<T> for BuiltinValueImpl<T>
 where
-    T: Clone + GetBuiltinTypeId,
+    T: Clone + GetBuiltinTypeId + HeapBytes,
 {
     #[inline]
     fn from(val: T) -> Self {
+        let bytes = val.heap_bytes();
         Self {
             val,
             id: T::get_builtin_type_id(),
+            charged_to: if bytes == 0 {
+                crate::gc::NO_OWNER
+            } else {
+                crate::gc::charge(bytes)
+            },
             boxx: Default::default(),
         }
     }
@@ -79,7 +121,7 @@ where
 
 impl<T> From<T> for BuiltinValue<T>
 where
-    T: Clone + GetBuiltinTypeId,
+    T: Clone + GetBuiltinTypeId + HeapBytes,
 {
     #[inline]
     fn from(val: T) -> Self {
@@ -91,7 +133,7 @@ where
 
 impl<T> BuiltinValue<T>
 where
-    T: Clone,
+    T: Clone + HeapBytes,
 {
     #[inline]
     pub fn builtin_type_id(&self) -> BuiltinTypeId {
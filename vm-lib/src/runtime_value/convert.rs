@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: Apache-2.0
+
+// conversions between Aria values and plain Rust values, for code that
+// embeds the VM; values that need the VM to exist (Unit, Maybe, objects)
+// are built through VmGlobals instead
+
+use super::{RuntimeValue, integer::IntegerRepr, list::List};
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("expected {expected}, found {found}")]
+pub struct ConversionError {
+    pub expected: &'static str,
+    pub found: &'static str,
+}
+
+impl From<std::convert::Infallible> for ConversionError {
+    fn from(value: std::convert::Infallible) -> Self {
+        match value {}
+    }
+}
+
+impl RuntimeValue {
+    // a short description of the kind of value, for error messages
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Self::Integer(_) => "Int",
+            Self::String(_) => "String",
+            Self::Float(_) => "Float",
+            Self::Boolean(_) => "Bool",
+            Self::Object(_) => "object",
+            Self::EnumValue(_) => "enum value",
+            Self::CodeObject(_) => "code object",
+            Self::Function(_) | Self::BoundFunction(_) => "function",
+            Self::List(_) => "List",
+            Self::Map(_) => "Map",
+            Self::Mixin(_) => "mixin",
+            Self::Type(_) | Self::TypeCheck(_) => "type",
+            Self::Module(_) => "module",
+            Self::Opaque(_) => "opaque value",
+        }
+    }
+
+    fn conversion_error(&self, expected: &'static str) -> ConversionError {
+        ConversionError {
+            expected,
+            found: self.kind_name(),
+        }
+    }
+}
+
+impl From<i64> for RuntimeValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<f64> for RuntimeValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value.into())
+    }
+}
+
+impl From<bool> for RuntimeValue {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value.into())
+    }
+}
+
+impl From<&str> for RuntimeValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<String> for RuntimeValue {
+    fn from(value: String) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl<T: Into<RuntimeValue>> From<Vec<T>> for RuntimeValue {
+    fn from(value: Vec<T>) -> Self {
+        let list = List::new_with_capacity(value.len());
+        value.into_iter().for_each(|v| list.append(v.into()));
+        Self::List(list)
+    }
+}
+
+impl TryFrom<RuntimeValue> for i64 {
+    type Error = ConversionError;
+
+    fn try_from(value: RuntimeValue) -> Result<Self, Self::Error> {
+        match &value {
+            RuntimeValue::Integer(n) => match n.raw_value() {
+                IntegerRepr::Small(n) => Ok(*n),
+                IntegerRepr::Big(_) => Err(value.conversion_error("Int that fits in 64 bits")),
+            },
+            _ => Err(value.conversion_error("Int")),
+        }
+    }
+}
+
+// Aria mixes Int and Float freely in arithmetic, so either one converts
+impl TryFrom<RuntimeValue> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: RuntimeValue) -> Result<Self, Self::Error> {
+        match &value {
+            RuntimeValue::Float(f) => Ok(*f.raw_value()),
+            RuntimeValue::Integer(n) => Ok(*n.to_fp().raw_value()),
+            _ => Err(value.conversion_error("Float")),
+        }
+    }
+}
+
+impl TryFrom<RuntimeValue> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: RuntimeValue) -> Result<Self, Self::Error> {
+        match &value {
+            RuntimeValue::Boolean(b) => Ok(*b.raw_value()),
+            _ => Err(value.conversion_error("Bool")),
+        }
+    }
+}
+
+impl TryFrom<RuntimeValue> for String {
+    type Error = ConversionError;
+
+    fn try_from(value: RuntimeValue) -> Result<Self, Self::Error> {
+        match &value {
+            RuntimeValue::String(s) => Ok(s.raw_value().clone()),
+            _ => Err(value.conversion_error("String")),
+        }
+    }
+}
+
+impl<T> TryFrom<RuntimeValue> for Vec<T>
+where
+    T: TryFrom<RuntimeValue>,
+    ConversionError: From<T::Error>,
+{
+    type Error = ConversionError;
+
+    fn try_from(value: RuntimeValue) -> Result<Self, Self::Error> {
+        match &value {
+            RuntimeValue::List(list) => (0..list.len())
+                .filter_map(|i| list.get_at(i))
+                .map(|v| T::try_from(v).map_err(ConversionError::from))
+                .collect(),
+            _ => Err(value.conversion_error("List")),
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 use std::rc::Rc;
 
-use crate::{builtins::VmGlobals, frame::Frame, vm::VirtualMachine};
+use crate::{builtins::VmGlobals, frame::Frame, gc::Traceable, vm::VirtualMachine};
 
 use crate::symbol::Symbol;
 
@@ -37,6 +37,14 @@ impl EnumValue {
     }
 }
 
+impl Traceable for EnumValueImpl {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        if let Some(id) = self.payload.as_ref().and_then(|p| p.gc_identity()) {
+            visit(id);
+        }
+    }
+}
+
 impl EnumValueImpl {
     fn builtin_equals(&self, other: &Self, cur_frame: &mut Frame, vm: &mut VirtualMachine) -> bool {
         self.enumm == other.enumm
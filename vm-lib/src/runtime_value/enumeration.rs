@@ -179,13 +179,18 @@ impl Enum {
         match self.get_case_by_idx(cidx) {
             Some(case) => {
                 if case.payload_type.is_some() == payload.is_some() {
-                    Some(EnumValue {
-                        imp: Rc::new(EnumValueImpl {
-                            enumm: self.clone(),
-                            case: cidx,
-                            payload,
-                        }),
-                    })
+                    // only a payload that can itself be part of a cycle needs tracking
+                    let needs_tracking =
+                        payload.as_ref().is_some_and(|p| p.gc_identity().is_some());
+                    let imp = Rc::new(EnumValueImpl {
+                        enumm: self.clone(),
+                        case: cidx,
+                        payload,
+                    });
+                    if needs_tracking {
+                        crate::gc::track(&imp);
+                    }
+                    Some(EnumValue { imp })
                 } else {
                     None
                 }
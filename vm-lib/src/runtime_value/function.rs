@@ -5,7 +5,9 @@ use aria_compiler::line_table::LineTable;
 use aria_parser::ast::SourcePointer;
 use haxby_opcodes::{
     Opcode,
-    function_attribs::{FUNC_ACCEPTS_VARARG, FUNC_IS_METHOD, METHOD_ATTRIBUTE_TYPE},
+    function_attribs::{
+        FUNC_ACCEPTS_VARARG, FUNC_IS_GENERATOR, FUNC_IS_METHOD, METHOD_ATTRIBUTE_TYPE,
+    },
 };
 use rustc_data_structures::fx::FxHashSet;
 
@@ -13,6 +15,7 @@ use crate::{
     arity::Arity,
     builtins::VmGlobals,
     frame::Frame,
+    gc::Traceable,
     runtime_module::RuntimeModule,
     symbol::Symbol,
     vm::{ExecutionResult, RunloopExit, VirtualMachine},
@@ -45,6 +48,12 @@ impl BuiltinFunction {
     }
 }
 
+pub(crate) enum CallSetup {
+    // the function is ready to run in this frame
+    Frame(Box<Frame>),
+    Done(CallResult),
+}
+
 pub struct BytecodeFunction {
     pub name: String,
     pub body: Rc<[Opcode]>,
@@ -52,6 +61,7 @@ pub struct BytecodeFunction {
     pub arity: Arity,
     pub frame_size: u8,
     pub line_table: Rc<LineTable>,
+    pub local_names: Rc<[String]>,
     pub loc: SourcePointer,
     pub attrib_byte: u8,
     pub module: RuntimeModule,
@@ -59,6 +69,32 @@ pub struct BytecodeFunction {
     uplevels: std::cell::RefCell<HashMap<u8, RuntimeValue>>,
 }
 
+impl Traceable for FunctionImpl {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        match self {
+            Self::BytecodeFunction(bc) => {
+                bc.boxx.trace(visit);
+                bc.uplevels
+                    .borrow()
+                    .values()
+                    .filter_map(|v| v.gc_identity())
+                    .for_each(visit);
+            }
+            Self::BuiltinFunction(bf) => bf.boxx.trace(visit),
+        }
+    }
+
+    fn clear(&self, sink: &mut Vec<RuntimeValue>) {
+        match self {
+            Self::BytecodeFunction(bc) => {
+                bc.boxx.clear(sink);
+                sink.extend(bc.uplevels.borrow_mut().drain().map(|(_, v)| v));
+            }
+            Self::BuiltinFunction(bf) => bf.boxx.clear(sink),
+        }
+    }
+}
+
 impl BytecodeFunction {
     pub(crate) fn store_uplevel(&self, idx: u8, val: RuntimeValue) {
         self.uplevels.borrow_mut().insert(idx, val);
@@ -123,6 +159,20 @@ impl FunctionImpl {
         }
     }
 
+    pub(crate) fn local_names(&self) -> &[String] {
+        match self {
+            Self::BytecodeFunction(bc) => &bc.local_names,
+            Self::BuiltinFunction(_) => &[],
+        }
+    }
+
+    pub(crate) fn module(&self) -> Option<&RuntimeModule> {
+        match self {
+            Self::BytecodeFunction(bc) => Some(&bc.module),
+            Self::BuiltinFunction(_) => None,
+        }
+    }
+
     fn get_attribute_store(&self) -> &ObjectBox {
         match self {
             Self::BytecodeFunction(bc) => &bc.boxx,
@@ -160,6 +210,19 @@ impl Function {
         self.imp.loc()
     }
 
+    // the source name of each local slot; compiler temporaries are included
+    pub fn local_names(&self) -> &[String] {
+        self.imp.local_names()
+    }
+
+    pub fn module(&self) -> Option<&RuntimeModule> {
+        self.imp.module()
+    }
+
+    pub(crate) fn as_bytecode(&self) -> Option<&BytecodeFunction> {
+        self.imp.as_bytecode_function()
+    }
+
     pub(super) fn get_attribute_store(&self) -> &ObjectBox {
         self.imp.get_attribute_store()
     }
@@ -184,6 +247,10 @@ impl FunctionAttribute {
         self.val & FUNC_ACCEPTS_VARARG != 0
     }
 
+    pub fn is_generator(&self) -> bool {
+        self.val & FUNC_IS_GENERATOR != 0
+    }
+
     pub fn is_method(&self) -> bool {
         self.val & FUNC_IS_METHOD == FUNC_IS_METHOD
     }
@@ -207,7 +274,7 @@ impl FunctionImpl {
 
     pub fn builtin_from<T>(val: T) -> Self
     where
-        T: 'static + BuiltinFunctionImpl + Default,
+        T: 'static + BuiltinFunctionImpl,
     {
         Self::BuiltinFunction(BuiltinFunction::new(Rc::new(val)))
     }
@@ -228,6 +295,7 @@ impl FunctionImpl {
             },
             frame_size: co.frame_size,
             line_table: lt,
+            local_names: co.local_names.clone(),
             loc: co.loc.clone(),
             attrib_byte: co.attribute,
             module: m.clone(),
@@ -277,7 +345,7 @@ impl Function {
 
     pub fn builtin_from<T>(val: T) -> Self
     where
-        T: 'static + BuiltinFunctionImpl + Default,
+        T: 'static + BuiltinFunctionImpl,
     {
         Self {
             imp: Rc::new(FunctionImpl::builtin_from(val)),
@@ -285,9 +353,9 @@ impl Function {
     }
 
     pub fn from_code_object(co: &CodeObject, m: &RuntimeModule) -> Self {
-        Self {
-            imp: Rc::new(FunctionImpl::from_code_object(co, m)),
-        }
+        let imp = Rc::new(FunctionImpl::from_code_object(co, m));
+        crate::gc::track(&imp);
+        Self { imp }
     }
 
     pub fn read(&self, builtins: &VmGlobals, name: Symbol) -> Option<RuntimeValue> {
@@ -298,8 +366,8 @@ impl Function {
         self.imp.list_attributes(builtins)
     }
 
-    // DO NOT CALL unless you are Function or BoundFunction
-    pub(super) fn eval_in_frame(
+    // DO NOT CALL unless you are Function, BoundFunction or a generator
+    pub(crate) fn eval_in_frame(
         &self,
         argc: u8,
         target_frame: &mut Frame,
@@ -310,18 +378,31 @@ impl Function {
                 target_frame.set_argc(argc);
                 vm.eval_bytecode_in_frame(&bcf.module, &bcf.body, &bcf.sidecar, target_frame)
             }
-            FunctionImpl::BuiltinFunction(bnf) => bnf.body.eval(target_frame, vm),
+            FunctionImpl::BuiltinFunction(bnf) => {
+                let Some(profiler) = vm.profiler.as_mut() else {
+                    return bnf.body.eval(target_frame, vm);
+                };
+
+                profiler.enter_builtin(self);
+                let result = bnf.body.eval(target_frame, vm);
+                if let Some(profiler) = vm.profiler.as_mut() {
+                    profiler.leave_frame();
+                }
+                result
+            }
         }
     }
 
-    pub fn eval(
+    // checks the arguments of a call and moves them from cur_frame into a new
+    // frame for the function; calling a generator is done at this point
+    pub(crate) fn setup_call(
         &self,
         argc: u8,
         cur_frame: &mut Frame,
         vm: &mut VirtualMachine,
         other_args: &PartialFunctionApplication,
         discard_result: bool,
-    ) -> ExecutionResult<CallResult> {
+    ) -> ExecutionResult<CallSetup> {
         let other_argc = other_args.suffix_args.len() as u8;
         let effective_argc = argc + other_argc;
         let fixed_arity = self.arity().required + self.arity().optional;
@@ -383,7 +464,43 @@ impl Function {
             new_frame.stack.push(arg.clone());
         }
 
-        let eval_result = self.eval_in_frame(effective_argc, &mut new_frame, vm);
+        // calling a generator only binds its arguments, the body runs on next()
+        if self.attribute().is_generator() {
+            new_frame.set_argc(effective_argc);
+            let generator =
+                crate::builtins::generator::create_generator(self, new_frame, &mut vm.globals)?;
+            if !discard_result {
+                cur_frame.stack.push(generator.clone());
+            }
+            return Ok(CallSetup::Done(CallResult::Ok(generator)));
+        }
+
+        new_frame.set_argc(effective_argc);
+        Ok(CallSetup::Frame(new_frame))
+    }
+
+    // runs the function to completion; calls made by Aria code do not come
+    // through here, the run loop gives them a frame of their own instead
+    pub fn eval(
+        &self,
+        argc: u8,
+        cur_frame: &mut Frame,
+        vm: &mut VirtualMachine,
+        other_args: &PartialFunctionApplication,
+        discard_result: bool,
+    ) -> ExecutionResult<CallResult> {
+        let mut new_frame =
+            match self.setup_call(argc, cur_frame, vm, other_args, discard_result)? {
+                CallSetup::Frame(frame) => frame,
+                CallSetup::Done(result) => return Ok(result),
+            };
+
+        if let Err(reason) = vm.enter_call(cur_frame) {
+            vm.release_frame(new_frame);
+            return Err(reason.into());
+        }
+        let eval_result = self.eval_in_frame(new_frame.argc, &mut new_frame, vm);
+        vm.leave_call();
         let result = match eval_result {
             Ok(RunloopExit::Ok(_)) => match new_frame.stack.try_pop() {
                 Some(ret) => {
@@ -1,116 +1,231 @@
 // SPDX-License-Identifier: Apache-2.0
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive};
+
 use super::{builtin_value::BuiltinValue, float::FloatValue};
 
-pub type IntegerValue = BuiltinValue<i64>;
+// the largest left shift of a nonzero integer, about 8 MiB of digits
+pub const MAX_SHIFT_BITS: i64 = 1 << 26;
 
-impl IntegerValue {
-    pub fn to_fp(&self) -> FloatValue {
-        (*self.raw_value() as f64).into()
-    }
+// integers are stored as an i64 for as long as they fit, and are promoted to an
+// arbitrary precision representation only when an operation would overflow;
+// results are demoted again whenever they fit, so that equal values always
+// share the same representation
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IntegerRepr {
+    Small(i64),
+    Big(BigInt),
 }
 
-impl PartialEq<FloatValue> for IntegerValue {
-    fn eq(&self, other: &FloatValue) -> bool {
-        self.to_fp() == *other
+impl IntegerRepr {
+    fn from_big(n: BigInt) -> Self {
+        match n.to_i64() {
+            Some(n) => Self::Small(n),
+            None => Self::Big(n),
+        }
+    }
+
+    fn to_big(&self) -> BigInt {
+        match self {
+            Self::Small(n) => BigInt::from(*n),
+            Self::Big(n) => n.clone(),
+        }
     }
 }
 
-impl std::ops::Add<&IntegerValue> for &IntegerValue {
-    type Output = IntegerValue;
+impl std::fmt::Display for IntegerRepr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Small(n) => write!(f, "{n}"),
+            Self::Big(n) => write!(f, "{n}"),
+        }
+    }
+}
 
-    #[inline]
-    fn add(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value().wrapping_add(*rhs.raw_value()))
+impl PartialOrd for IntegerRepr {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl std::ops::Sub<&IntegerValue> for &IntegerValue {
-    type Output = IntegerValue;
+impl Ord for IntegerRepr {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use std::cmp::Ordering;
 
-    #[inline]
-    fn sub(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value().wrapping_sub(*rhs.raw_value()))
+        // a big value never fits in an i64, so its sign decides
+        match (self, other) {
+            (Self::Small(a), Self::Small(b)) => a.cmp(b),
+            (Self::Big(a), Self::Big(b)) => a.cmp(b),
+            (Self::Small(_), Self::Big(b)) => {
+                if b.is_negative() {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            }
+            (Self::Big(a), Self::Small(_)) => {
+                if a.is_negative() {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            }
+        }
     }
 }
 
-impl std::ops::Mul<&IntegerValue> for &IntegerValue {
-    type Output = IntegerValue;
+pub type IntegerValue = BuiltinValue<IntegerRepr>;
 
+impl From<i64> for IntegerValue {
     #[inline]
-    fn mul(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value().wrapping_mul(*rhs.raw_value()))
+    fn from(value: i64) -> Self {
+        From::from(IntegerRepr::Small(value))
     }
 }
 
-impl std::ops::Div<&IntegerValue> for &IntegerValue {
-    type Output = IntegerValue;
-
-    #[inline]
-    fn div(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value().wrapping_div(*rhs.raw_value()))
+impl From<BigInt> for IntegerValue {
+    fn from(value: BigInt) -> Self {
+        From::from(IntegerRepr::from_big(value))
     }
 }
 
-impl std::ops::Rem<&IntegerValue> for &IntegerValue {
-    type Output = IntegerValue;
+impl IntegerValue {
+    pub fn to_fp(&self) -> FloatValue {
+        match self.raw_value() {
+            IntegerRepr::Small(n) => (*n as f64).into(),
+            IntegerRepr::Big(n) => n.to_f64().unwrap_or(f64::NAN).into(),
+        }
+    }
 
-    #[inline]
-    fn rem(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value().wrapping_rem(*rhs.raw_value()))
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.raw_value() {
+            IntegerRepr::Small(n) => Some(*n),
+            IntegerRepr::Big(_) => None,
+        }
     }
-}
 
-impl std::ops::Neg for &IntegerValue {
-    type Output = IntegerValue;
+    // clamps values that do not fit to i64::MIN or i64::MAX, which is what
+    // callers using the value as an index, size or duration want
+    pub fn saturating_i64(&self) -> i64 {
+        match self.raw_value() {
+            IntegerRepr::Small(n) => *n,
+            IntegerRepr::Big(n) if n.is_negative() => i64::MIN,
+            IntegerRepr::Big(_) => i64::MAX,
+        }
+    }
 
-    #[inline]
-    fn neg(self) -> Self::Output {
-        From::from(-self.raw_value())
+    // the low 64 bits of the two's complement representation
+    pub fn wrapping_i64(&self) -> i64 {
+        match self.raw_value() {
+            IntegerRepr::Small(n) => *n,
+            IntegerRepr::Big(n) => {
+                let low_bits = n & BigInt::from(u64::MAX);
+                low_bits.to_u64().unwrap_or_default() as i64
+            }
+        }
     }
-}
 
-impl std::ops::BitAnd<&IntegerValue> for &IntegerValue {
-    type Output = IntegerValue;
+    pub fn to_bigint(&self) -> BigInt {
+        self.raw_value().to_big()
+    }
 
-    #[inline]
-    fn bitand(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value() & rhs.raw_value())
+    fn shift_amount(&self) -> i64 {
+        self.saturating_i64()
     }
-}
 
-impl std::ops::BitOr<&IntegerValue> for &IntegerValue {
-    type Output = IntegerValue;
+    // None if the result would need more than MAX_SHIFT_BITS extra bits; the
+    // allocation for something like 1 << 2**62 would abort the process
+    fn shift_left(&self, by: i64) -> Option<IntegerValue> {
+        if by < 0 {
+            return self.shift_right(by.checked_neg().unwrap_or(i64::MAX));
+        }
 
-    #[inline]
-    fn bitor(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value() | rhs.raw_value())
+        match self.raw_value() {
+            IntegerRepr::Small(0) => return Some(From::from(0)),
+            IntegerRepr::Small(n) if by < 64 => {
+                let shifted = n << by;
+                if shifted >> by == *n {
+                    return Some(From::from(shifted));
+                }
+            }
+            _ => {}
+        }
+
+        if by > MAX_SHIFT_BITS {
+            return None;
+        }
+        Some(From::from(self.to_bigint() << (by as usize)))
     }
-}
 
-impl std::ops::BitXor<&IntegerValue> for &IntegerValue {
-    type Output = IntegerValue;
+    fn shift_right(&self, by: i64) -> Option<IntegerValue> {
+        if by < 0 {
+            return self.shift_left(by.checked_neg().unwrap_or(i64::MAX));
+        }
 
-    #[inline]
-    fn bitxor(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value() ^ rhs.raw_value())
+        Some(match self.raw_value() {
+            IntegerRepr::Small(n) => From::from(n >> by.min(63)),
+            IntegerRepr::Big(n) => From::from(n >> (by as usize)),
+        })
     }
-}
 
-impl std::ops::Shl<&IntegerValue> for &IntegerValue {
-    type Output = IntegerValue;
+    pub fn checked_shl(&self, rhs: &IntegerValue) -> Option<IntegerValue> {
+        self.shift_left(rhs.shift_amount())
+    }
 
-    #[inline]
-    fn shl(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value() << rhs.raw_value())
+    pub fn checked_shr(&self, rhs: &IntegerValue) -> Option<IntegerValue> {
+        self.shift_right(rhs.shift_amount())
+    }
+}
+
+impl PartialEq<FloatValue> for IntegerValue {
+    fn eq(&self, other: &FloatValue) -> bool {
+        self.to_fp() == *other
     }
 }
 
-impl std::ops::Shr<&IntegerValue> for &IntegerValue {
+// tries the operation on i64 first, and only falls back to big integers if
+// either operand is already big or the i64 operation overflows
+macro_rules! integer_binop {
+    ($trait:ident, $fn:ident, $small:expr, $op:tt) => {
+        impl std::ops::$trait<&IntegerValue> for &IntegerValue {
+            type Output = IntegerValue;
+
+            #[inline]
+            fn $fn(self, rhs: &IntegerValue) -> Self::Output {
+                if let (IntegerRepr::Small(a), IntegerRepr::Small(b)) =
+                    (self.raw_value(), rhs.raw_value())
+                    && let Some(val) = $small(*a, *b)
+                {
+                    return From::from(val);
+                }
+
+                From::from(self.to_bigint() $op rhs.to_bigint())
+            }
+        }
+    };
+}
+
+integer_binop!(Add, add, i64::checked_add, +);
+integer_binop!(Sub, sub, i64::checked_sub, -);
+integer_binop!(Mul, mul, i64::checked_mul, *);
+integer_binop!(Div, div, i64::checked_div, /);
+integer_binop!(Rem, rem, i64::checked_rem, %);
+integer_binop!(BitAnd, bitand, |a: i64, b: i64| Some(a & b), &);
+integer_binop!(BitOr, bitor, |a: i64, b: i64| Some(a | b), |);
+integer_binop!(BitXor, bitxor, |a: i64, b: i64| Some(a ^ b), ^);
+
+impl std::ops::Neg for &IntegerValue {
     type Output = IntegerValue;
 
     #[inline]
-    fn shr(self, rhs: &IntegerValue) -> Self::Output {
-        From::from(self.raw_value() >> rhs.raw_value())
+    fn neg(self) -> Self::Output {
+        match self.raw_value() {
+            IntegerRepr::Small(n) => match n.checked_neg() {
+                Some(n) => From::from(n),
+                None => From::from(-BigInt::from(*n)),
+            },
+            IntegerRepr::Big(n) => From::from(-n),
+        }
     }
 }
 
@@ -138,6 +253,6 @@ impl Ord for IntegerValue {
 impl PartialEq<i64> for IntegerValue {
     #[inline]
     fn eq(&self, other: &i64) -> bool {
-        *self.raw_value() == *other
+        self.as_i64() == Some(*other)
     }
 }
@@ -94,6 +94,7 @@ impl RuntimeValueType {
             RuntimeValue::Integer(_) => builtins.get_builtin_type_by_id(BuiltinTypeId::Int),
             RuntimeValue::Float(_) => builtins.get_builtin_type_by_id(BuiltinTypeId::Float),
             RuntimeValue::List(_) => builtins.get_builtin_type_by_id(BuiltinTypeId::List),
+            RuntimeValue::Map(_) => builtins.get_builtin_type_by_id(BuiltinTypeId::Map),
             RuntimeValue::String(_) => builtins.get_builtin_type_by_id(BuiltinTypeId::String),
         }
     }
@@ -7,6 +7,7 @@ use crate::{
     builtins::VmGlobals,
     error::vm_error::{VmError, VmErrorReason},
     frame::Frame,
+    gc::Traceable,
     runtime_value::object::ObjectBox,
     symbol::Symbol,
     vm::{ExecutionResult, VirtualMachine},
@@ -83,6 +84,25 @@ impl ListImpl {
     }
 }
 
+impl Traceable for ListImpl {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        self.get()
+            .iter()
+            .filter_map(|v| v.gc_identity())
+            .for_each(&mut *visit);
+        self.boxx.trace(visit);
+    }
+
+    fn clear(&self, sink: &mut Vec<RuntimeValue>) {
+        sink.append(self.get_mut());
+        self.boxx.clear(sink);
+    }
+
+    fn heap_size(&self) -> usize {
+        self.get().len() * crate::gc::SLOT_SIZE + self.boxx.heap_size()
+    }
+}
+
 impl std::fmt::Debug for ListImpl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let li = self.get();
@@ -97,11 +117,17 @@ impl std::fmt::Debug for ListImpl {
     }
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct List {
     pub(super) imp: Rc<ListImpl>,
 }
 
+impl Default for List {
+    fn default() -> Self {
+        Self::new_with_capacity(0)
+    }
+}
+
 impl List {
     pub fn from(values: &[RuntimeValue]) -> Self {
         let ret = Self::default();
@@ -110,9 +136,9 @@ impl List {
     }
 
     pub fn new_with_capacity(cap: usize) -> Self {
-        Self {
-            imp: Rc::new(ListImpl::new_with_capacity(cap)),
-        }
+        let imp = Rc::new(ListImpl::new_with_capacity(cap));
+        crate::gc::track(&imp);
+        Self { imp }
     }
 
     pub fn len(&self) -> usize {
@@ -146,9 +172,9 @@ impl List {
         _: &mut VirtualMachine,
     ) -> Result<RuntimeValue, VmError> {
         if let Some(i) = idx.as_integer() {
-            match self.get_at(*i.raw_value() as usize) {
+            match self.get_at(i.saturating_i64() as usize) {
                 Some(val) => Ok(val),
-                _ => Err(VmErrorReason::IndexOutOfBounds(*i.raw_value() as usize).into()),
+                _ => Err(VmErrorReason::IndexOutOfBounds(i.saturating_i64() as usize).into()),
             }
         } else {
             Err(VmErrorReason::UnexpectedType.into())
@@ -163,7 +189,7 @@ impl List {
         _: &mut VirtualMachine,
     ) -> ExecutionResult {
         if let Some(i) = idx.as_integer() {
-            self.set_at(*i.raw_value() as usize, val.clone())?;
+            self.set_at(i.saturating_i64() as usize, val.clone())?;
             Ok(())
         } else {
             Err(VmErrorReason::UnexpectedType.into())
@@ -0,0 +1,344 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    cell::RefCell,
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use rustc_data_structures::fx::{FxHashMap, FxHashSet, FxHasher};
+
+use crate::{
+    builtins::VmGlobals,
+    error::vm_error::VmErrorReason,
+    frame::Frame,
+    gc::Traceable,
+    runtime_value::object::ObjectBox,
+    symbol::{INTERNED_ATTR_HASH, Symbol},
+    vm::{ExecutionResult, RunloopExit, VirtualMachine},
+};
+
+use super::{CallResult, RuntimeValue, integer::IntegerRepr};
+
+struct MapEntry {
+    hash: u64,
+    key: RuntimeValue,
+    value: RuntimeValue,
+}
+
+// entries are kept in insertion order; removing an entry leaves a hole behind,
+// which is compacted away once holes outnumber the live entries
+#[derive(Default)]
+struct MapEntries {
+    slots: Vec<Option<MapEntry>>,
+    index: FxHashMap<u64, Vec<usize>>,
+    len: usize,
+}
+
+impl MapEntries {
+    fn with_capacity(cap: usize) -> Self {
+        Self {
+            slots: Vec::with_capacity(cap),
+            index: FxHashMap::with_capacity_and_hasher(cap, Default::default()),
+            len: 0,
+        }
+    }
+
+    fn candidates(&self, hash: u64) -> Vec<usize> {
+        self.index.get(&hash).cloned().unwrap_or_default()
+    }
+
+    fn key_at(&self, slot: usize) -> Option<RuntimeValue> {
+        self.slots
+            .get(slot)
+            .and_then(|e| e.as_ref())
+            .map(|e| e.key.clone())
+    }
+
+    fn push(&mut self, hash: u64, key: RuntimeValue, value: RuntimeValue) {
+        self.index.entry(hash).or_default().push(self.slots.len());
+        self.slots.push(Some(MapEntry { hash, key, value }));
+        self.len += 1;
+    }
+
+    fn take(&mut self, slot: usize) -> Option<MapEntry> {
+        let entry = self.slots.get_mut(slot)?.take()?;
+        if let Some(bucket) = self.index.get_mut(&entry.hash) {
+            bucket.retain(|s| *s != slot);
+            if bucket.is_empty() {
+                self.index.remove(&entry.hash);
+            }
+        }
+        self.len -= 1;
+
+        if self.slots.len() > 8 && self.slots.len() > 2 * self.len {
+            self.compact();
+        }
+        Some(entry)
+    }
+
+    fn compact(&mut self) {
+        let slots = std::mem::take(&mut self.slots);
+        self.index.clear();
+        self.len = 0;
+        for entry in slots.into_iter().flatten() {
+            self.push(entry.hash, entry.key, entry.value);
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &MapEntry> {
+        self.slots.iter().flatten()
+    }
+}
+
+#[derive(Default)]
+pub(super) struct MapImpl {
+    entries: RefCell<MapEntries>,
+    pub(super) boxx: ObjectBox,
+}
+
+impl Traceable for MapImpl {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        self.entries
+            .borrow()
+            .iter()
+            .flat_map(|e| [e.key.gc_identity(), e.value.gc_identity()])
+            .flatten()
+            .for_each(&mut *visit);
+        self.boxx.trace(visit);
+    }
+
+    fn clear(&self, sink: &mut Vec<RuntimeValue>) {
+        let mut entries = self.entries.take();
+        for entry in entries.slots.drain(..).flatten() {
+            sink.push(entry.key);
+            sink.push(entry.value);
+        }
+        self.boxx.clear(sink);
+    }
+
+    fn heap_size(&self) -> usize {
+        self.entries
+            .borrow()
+            .iter()
+            .map(|e| std::mem::size_of_val(&e.hash) + 2 * crate::gc::SLOT_SIZE)
+            .sum::<usize>()
+            + self.boxx.heap_size()
+    }
+}
+
+fn fx_hash<T: Hash>(val: &T) -> u64 {
+    let mut hasher = FxHasher::default();
+    val.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Int, String and Bool keys are hashed natively, any other key is asked for
+// its hash() - which is the only case where Aria code runs on a lookup
+fn hash_key(
+    key: &RuntimeValue,
+    cur_frame: &mut Frame,
+    vm: &mut VirtualMachine,
+) -> ExecutionResult<RunloopExit<u64>> {
+    let hash = match key {
+        RuntimeValue::Integer(i) => fx_hash(i.raw_value()),
+        // floats must hash the same as the integers they compare equal to
+        RuntimeValue::Float(f) => {
+            let f = *f.raw_value();
+            if f.fract() == 0.0 && f >= i64::MIN as f64 && f < i64::MAX as f64 {
+                fx_hash(&IntegerRepr::Small(f as i64))
+            } else {
+                fx_hash(&f.to_bits())
+            }
+        }
+        RuntimeValue::String(s) => fx_hash(s.raw_value()),
+        RuntimeValue::Boolean(b) => fx_hash(b.raw_value()),
+        _ => {
+            let hash_fn = key
+                .read_attribute(INTERNED_ATTR_HASH, &vm.globals)
+                .map_err(|e| e.to_vm_error_reason("hash"))?;
+            match hash_fn.eval(0, cur_frame, vm, true)? {
+                CallResult::Ok(RuntimeValue::Integer(i)) => i.wrapping_i64() as u64,
+                CallResult::Ok(_) => return Err(VmErrorReason::UnexpectedType.into()),
+                CallResult::Exception(e) => return Ok(RunloopExit::Exception(e)),
+            }
+        }
+    };
+
+    Ok(RunloopExit::Ok(hash))
+}
+
+fn keys_equal(
+    lhs: &RuntimeValue,
+    rhs: &RuntimeValue,
+    cur_frame: &mut Frame,
+    vm: &mut VirtualMachine,
+) -> bool {
+    if lhs.is_builtin_type() && rhs.is_builtin_type() {
+        lhs.builtin_equals(rhs, cur_frame, vm)
+    } else {
+        RuntimeValue::equals(lhs, rhs, cur_frame, vm)
+    }
+}
+
+#[derive(Clone)]
+pub struct Map {
+    pub(super) imp: Rc<MapImpl>,
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Self::new_with_capacity(0)
+    }
+}
+
+macro_rules! try_hash {
+    ($key: expr, $frame: expr, $vm: expr) => {
+        match hash_key($key, $frame, $vm)? {
+            RunloopExit::Ok(h) => h,
+            RunloopExit::Exception(e) => return Ok(RunloopExit::Exception(e)),
+        }
+    };
+}
+
+impl Map {
+    pub fn new_with_capacity(cap: usize) -> Self {
+        let imp = Rc::new(MapImpl {
+            entries: RefCell::new(MapEntries::with_capacity(cap)),
+            boxx: ObjectBox::default(),
+        });
+        crate::gc::track(&imp);
+        Self { imp }
+    }
+
+    pub fn len(&self) -> usize {
+        self.imp.entries.borrow().len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // comparing keys may run Aria code, which could in turn change this map,
+    // so the entries are never borrowed across a comparison
+    fn find_slot(
+        &self,
+        hash: u64,
+        key: &RuntimeValue,
+        cur_frame: &mut Frame,
+        vm: &mut VirtualMachine,
+    ) -> Option<usize> {
+        let candidates = self.imp.entries.borrow().candidates(hash);
+        for slot in candidates {
+            let candidate = self.imp.entries.borrow().key_at(slot);
+            if let Some(candidate) = candidate
+                && keys_equal(&candidate, key, cur_frame, vm)
+            {
+                return Some(slot);
+            }
+        }
+        None
+    }
+
+    pub fn get(
+        &self,
+        key: &RuntimeValue,
+        cur_frame: &mut Frame,
+        vm: &mut VirtualMachine,
+    ) -> ExecutionResult<RunloopExit<Option<RuntimeValue>>> {
+        let hash = try_hash!(key, cur_frame, vm);
+        let val = self.find_slot(hash, key, cur_frame, vm).and_then(|slot| {
+            self.imp
+                .entries
+                .borrow()
+                .slots
+                .get(slot)?
+                .as_ref()
+                .map(|e| e.value.clone())
+        });
+        Ok(RunloopExit::Ok(val))
+    }
+
+    // returns true if the key was not in the map before
+    pub fn insert(
+        &self,
+        key: RuntimeValue,
+        value: RuntimeValue,
+        cur_frame: &mut Frame,
+        vm: &mut VirtualMachine,
+    ) -> ExecutionResult<RunloopExit<bool>> {
+        let hash = try_hash!(&key, cur_frame, vm);
+        let slot = self.find_slot(hash, &key, cur_frame, vm);
+        let mut entries = self.imp.entries.borrow_mut();
+        if let Some(slot) = slot
+            && let Some(Some(entry)) = entries.slots.get_mut(slot)
+        {
+            entry.value = value;
+            return Ok(RunloopExit::Ok(false));
+        }
+
+        entries.push(hash, key, value);
+        Ok(RunloopExit::Ok(true))
+    }
+
+    // returns true if the key was in the map
+    pub fn remove(
+        &self,
+        key: &RuntimeValue,
+        cur_frame: &mut Frame,
+        vm: &mut VirtualMachine,
+    ) -> ExecutionResult<RunloopExit<bool>> {
+        let hash = try_hash!(key, cur_frame, vm);
+        let removed = self
+            .find_slot(hash, key, cur_frame, vm)
+            .and_then(|slot| self.imp.entries.borrow_mut().take(slot));
+        Ok(RunloopExit::Ok(removed.is_some()))
+    }
+
+    pub fn keys(&self) -> Vec<RuntimeValue> {
+        self.imp
+            .entries
+            .borrow()
+            .iter()
+            .map(|e| e.key.clone())
+            .collect()
+    }
+
+    pub fn values(&self) -> Vec<RuntimeValue> {
+        self.imp
+            .entries
+            .borrow()
+            .iter()
+            .map(|e| e.value.clone())
+            .collect()
+    }
+
+    pub fn read(&self, builtins: &VmGlobals, name: Symbol) -> Option<RuntimeValue> {
+        self.imp.boxx.read(builtins, name)
+    }
+
+    pub fn list_attributes(&self, builtins: &VmGlobals) -> FxHashSet<Symbol> {
+        self.imp.boxx.list_attributes(builtins)
+    }
+}
+
+impl std::fmt::Debug for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let entries = self.imp.entries.borrow();
+        write!(
+            f,
+            "{{{}}}",
+            entries
+                .iter()
+                .map(|e| format!("{:?}: {:?}", e.key, e.value))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.imp, &other.imp)
+    }
+}
+impl Eq for Map {}
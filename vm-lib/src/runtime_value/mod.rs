@@ -14,6 +14,7 @@ use haxby_opcodes::BuiltinTypeId;
 use integer::IntegerValue;
 use kind::RuntimeValueType;
 use list::List;
+use map::Map;
 use mixin::Mixin;
 use object::Object;
 use opaque::OpaqueValue;
@@ -38,6 +39,7 @@ use crate::{
 pub mod boolean;
 pub mod bound_function;
 pub mod builtin_value;
+pub mod convert;
 pub mod enum_case;
 pub mod enumeration;
 pub mod float;
@@ -46,6 +48,7 @@ pub mod integer;
 pub mod isa;
 pub mod kind;
 pub mod list;
+pub mod map;
 pub mod mixin;
 pub mod object;
 pub mod opaque;
@@ -66,6 +69,7 @@ pub enum RuntimeValue {
     Function(Function),
     BoundFunction(BoundFunction),
     List(List),
+    Map(Map),
     Mixin(Mixin),
     Type(RuntimeValueType),
     Module(RuntimeModule),
@@ -95,6 +99,7 @@ impl RuntimeValue {
             (Self::Function(l0), Self::Function(r0)) => l0 == r0,
             (Self::BoundFunction(l0), Self::BoundFunction(r0)) => l0 == r0,
             (Self::List(l0), Self::List(r0)) => l0 == r0,
+            (Self::Map(l0), Self::Map(r0)) => l0 == r0,
             (Self::Type(l0), Self::Type(r0)) => l0 == r0,
             (Self::TypeCheck(l0), Self::TypeCheck(r0)) => l0 == r0,
             _ => false,
@@ -465,6 +470,7 @@ impl std::fmt::Debug for RuntimeValue {
             Self::Function(fnc) => write!(f, "{fnc:?}"),
             Self::BoundFunction(_) => write!(f, "<bound-function>"),
             Self::List(lt) => write!(f, "{lt:?}"),
+            Self::Map(mp) => write!(f, "{mp:?}"),
             Self::Type(t) => write!(f, "type<{t:?}>"),
             Self::TypeCheck(t) => write!(f, "type-check({t:?})"),
         }
@@ -486,6 +492,7 @@ impl TryFrom<&ConstantValue> for RuntimeValue {
     fn try_from(value: &ConstantValue) -> Result<Self, Self::Error> {
         match value {
             ConstantValue::Integer(n) => Ok(RuntimeValue::Integer(From::from(*n))),
+            ConstantValue::BigInteger(n) => Ok(RuntimeValue::Integer(From::from(n.clone()))),
             ConstantValue::String(s) => Ok(RuntimeValue::String(s.to_owned().into())),
             ConstantValue::CompiledCodeObject(s) => {
                 Ok(RuntimeValue::CodeObject(TryFrom::try_from(s)?))
@@ -566,6 +573,7 @@ impl RuntimeValue {
             | Self::Function(_)
             | Self::BoundFunction(_)
             | Self::List(_)
+            | Self::Map(_)
             | Self::Mixin(_)
             | Self::Type(_)
             | Self::Module(_)
@@ -574,6 +582,28 @@ impl RuntimeValue {
         }
     }
 
+    // values that can take part in a reference cycle are identified by their allocation
+    pub(crate) fn gc_identity(&self) -> Option<usize> {
+        match self {
+            Self::Object(o) => Some(crate::gc::identity(&o.imp)),
+            Self::List(l) => Some(crate::gc::identity(&l.imp)),
+            Self::Map(m) => Some(crate::gc::identity(&m.imp)),
+            Self::EnumValue(e) => Some(crate::gc::identity(&e.imp)),
+            Self::Function(f) => Some(crate::gc::identity(&f.imp)),
+            Self::BoundFunction(bf) => Some(crate::gc::identity(&bf.imp)),
+            Self::Integer(_)
+            | Self::String(_)
+            | Self::Float(_)
+            | Self::Boolean(_)
+            | Self::CodeObject(_)
+            | Self::Mixin(_)
+            | Self::Type(_)
+            | Self::Module(_)
+            | Self::Opaque(_)
+            | Self::TypeCheck(_) => None,
+        }
+    }
+
     pub fn get_builtin_type_id(&self) -> Option<BuiltinTypeId> {
         match self {
             Self::Integer(x) => Some(x.builtin_type_id()),
@@ -586,6 +616,7 @@ impl RuntimeValue {
             | Self::Function(_)
             | Self::BoundFunction(_)
             | Self::List(_)
+            | Self::Map(_)
             | Self::Mixin(_)
             | Self::Type(_)
             | Self::Module(_)
@@ -640,6 +671,7 @@ impl RuntimeValue {
             RuntimeValue::Function(f) => Some(f.get_attribute_store()),
             RuntimeValue::BoundFunction(_) => None,
             RuntimeValue::List(l) => Some(&l.imp.as_ref().boxx),
+            RuntimeValue::Map(m) => Some(&m.imp.as_ref().boxx),
             RuntimeValue::Mixin(m) => Some(&m.imp.as_ref().entries),
             RuntimeValue::Type(t) => t.get_attribute_store(),
             RuntimeValue::Module(_) => None,
@@ -710,6 +742,11 @@ impl RuntimeValue {
             let bt = builtins.get_builtin_type_by_id(BuiltinTypeId::List);
             attrs.extend(bt.list_attributes(builtins));
             push_resolved(attrs);
+        } else if let Some(m) = self.as_map() {
+            let mut attrs = m.list_attributes(builtins);
+            let bt = builtins.get_builtin_type_by_id(BuiltinTypeId::Map);
+            attrs.extend(bt.list_attributes(builtins));
+            push_resolved(attrs);
         } else if let Some(f) = self.as_function() {
             push_resolved(f.list_attributes(builtins));
         } else if let Some(m) = self.as_module() {
@@ -783,6 +820,14 @@ impl RuntimeValue {
                         .and_then(|val| val_or_bound_func!(val, self).ok())
                 }
             },
+            RuntimeValue::Map(m) => match m.imp.as_ref().boxx.read_slot(slot_id, sid) {
+                Some(val) => Some(val),
+                None => {
+                    let bt = builtins.get_builtin_type_by_id(BuiltinTypeId::Map);
+                    Self::read_slot_from_type(&bt, slot_id, sid)
+                        .and_then(|val| val_or_bound_func!(val, self).ok())
+                }
+            },
             RuntimeValue::Type(t) => {
                 let val = Self::read_slot_from_type(t, slot_id, sid)?;
                 if let Some(rf) = val.as_function() {
@@ -919,6 +964,16 @@ impl RuntimeValue {
                         .map(|v| (v, val.1, val.2))
                 }
             },
+            RuntimeValue::Map(m) => match m.imp.as_ref().boxx.resolve_to_slot(builtins, name) {
+                Some(val) => Some(val),
+                None => {
+                    let bt = builtins.get_builtin_type_by_id(BuiltinTypeId::Map);
+                    let val = Self::resolve_to_slot_from_type(&bt, builtins, name)?;
+                    val_or_bound_func!(val.0, self)
+                        .ok()
+                        .map(|v| (v, val.1, val.2))
+                }
+            },
             RuntimeValue::Type(t) => {
                 let val = Self::resolve_to_slot_from_type(t, builtins, name)?;
                 if let Some(rf) = val.0.as_function() {
@@ -1003,6 +1058,19 @@ impl RuntimeValue {
                     }
                 }
             }
+        } else if let Some(m) = self.as_map() {
+            match m.read(builtins, attrib_sym) {
+                Some(val) => Ok(val),
+                _ => {
+                    let bt = builtins.get_builtin_type_by_id(BuiltinTypeId::Map);
+                    match bt.read_attribute(builtins, attrib_sym) {
+                        Ok(val) => {
+                            val_or_bound_func!(val, self)
+                        }
+                        _ => Err(AttributeError::NoSuchAttribute),
+                    }
+                }
+            }
         } else if let Some(t) = self.as_type() {
             let val = t.read_attribute(builtins, attrib_sym)?;
             if let Some(rf) = val.as_function() {
@@ -6,7 +6,7 @@ use std::{
 
 use rustc_data_structures::fx::FxHashSet;
 
-use crate::{error::vm_error::VmErrorReason, shape::ShapeId};
+use crate::{error::vm_error::VmErrorReason, gc::Traceable, shape::ShapeId};
 use crate::{shape::SlotId, symbol::Symbol};
 
 use super::{RuntimeValue, structure::Struct};
@@ -102,6 +102,22 @@ impl ObjectBox {
         ret
     }
 
+    pub(crate) fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        self.get()
+            .iter()
+            .filter_map(|v| v.gc_identity())
+            .for_each(visit);
+    }
+
+    pub(crate) fn clear(&self, sink: &mut Vec<RuntimeValue>) {
+        sink.append(self.get_mut());
+        self.shape.set(crate::shape::Shapes::EMPTY_SHAPE_INDEX);
+    }
+
+    pub(crate) fn heap_size(&self) -> usize {
+        self.get().len() * crate::gc::SLOT_SIZE
+    }
+
     pub(crate) fn contains(&self, builtins: &crate::builtins::VmGlobals, name: Symbol) -> bool {
         let slot_count = self.get().len();
         if let Some(slot_id) = builtins.shapes.resolve_slot(self.shape.get(), name) {
@@ -155,11 +171,25 @@ impl ObjectImpl {
     }
 }
 
+impl Traceable for ObjectImpl {
+    fn trace(&self, visit: &mut dyn FnMut(usize)) {
+        self.boxx.trace(visit);
+    }
+
+    fn clear(&self, sink: &mut Vec<RuntimeValue>) {
+        self.boxx.clear(sink);
+    }
+
+    fn heap_size(&self) -> usize {
+        self.boxx.heap_size()
+    }
+}
+
 impl Object {
     pub fn new(kind: &Struct) -> Self {
-        Self {
-            imp: Rc::new(ObjectImpl::new(kind)),
-        }
+        let imp = Rc::new(ObjectImpl::new(kind));
+        crate::gc::track(&imp);
+        Self { imp }
     }
 
     pub(crate) fn read_slot(&self, slot_id: SlotId, sid: ShapeId) -> Option<RuntimeValue> {
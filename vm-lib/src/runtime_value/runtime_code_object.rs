@@ -19,6 +19,7 @@ pub struct CodeObject {
     pub frame_size: u8,
     pub loc: SourcePointer,
     pub line_table: Rc<LineTable>,
+    pub local_names: Rc<[String]>,
 }
 
 impl PartialEq for CodeObject {
@@ -61,6 +62,7 @@ impl TryFrom<&CompiledCodeObject> for CodeObject {
             frame_size: value.frame_size,
             loc: value.loc.clone(),
             line_table: Rc::from(value.line_table.clone()),
+            local_names: value.local_names.clone().into(),
         })
     }
 }
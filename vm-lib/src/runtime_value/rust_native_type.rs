@@ -19,6 +19,7 @@ pub enum RustNativeValueKind {
     Integer,
     Float,
     List,
+    Map,
     String,
     Type,
 }
@@ -130,6 +131,7 @@ impl std::fmt::Debug for RustNativeType {
             RustNativeValueKind::Integer => write!(f, "Int"),
             RustNativeValueKind::Float => write!(f, "Float"),
             RustNativeValueKind::List => write!(f, "List"),
+            RustNativeValueKind::Map => write!(f, "Map"),
             RustNativeValueKind::String => write!(f, "String"),
             RustNativeValueKind::Type => write!(f, "Type"),
         }
@@ -73,9 +73,9 @@ impl StringValue {
         _: &mut VirtualMachine,
     ) -> Result<RuntimeValue, VmErrorReason> {
         if let Some(i) = idx.as_integer() {
-            match self.get_at(*i.raw_value() as usize) {
+            match self.get_at(i.saturating_i64() as usize) {
                 Some(val) => Ok(val),
-                _ => Err(VmErrorReason::IndexOutOfBounds(*i.raw_value() as usize)),
+                _ => Err(VmErrorReason::IndexOutOfBounds(i.saturating_i64() as usize)),
             }
         } else {
             Err(VmErrorReason::UnexpectedType)
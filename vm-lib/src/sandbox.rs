@@ -0,0 +1,224 @@
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+use crate::error::vm_error::VmErrorReason;
+
+// What a program may do outside the VM. The default grants everything, as
+// Aria always has; a sandbox starts from Permissions::none() and grants only
+// what the program needs, e.g.
+//
+//   aria --allow-read=data --allow-net=example.com script.aria
+//
+// Builtins and the dylibs that ship with Aria check these before touching
+// the file system, the network, subprocesses or the environment; a denied
+// operation throws RuntimeError::PermissionDenied.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permissions {
+    // files and directories that may be read, including everything below them
+    pub read: Grant<PathBuf>,
+    // files and directories that may be created, written or removed
+    pub write: Grant<PathBuf>,
+    // hosts that may be connected to, as "host", "host:port" or "[ipv6]:port"
+    pub net: Grant<String>,
+    // dylibs that may be loaded by name, besides the ones that ship with Aria
+    pub dylibs: Grant<String>,
+    // system() and anything else that starts a process
+    pub run: bool,
+    // getenv() and setenv()
+    pub env: bool,
+    // exit(), which ends the whole process and not just the program
+    pub exit: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Grant<T> {
+    All,
+    Only(Vec<T>),
+}
+
+impl<T> Grant<T> {
+    pub fn none() -> Self {
+        Self::Only(vec![])
+    }
+
+    // grants items on top of what is already granted; an empty list grants everything
+    pub fn add(&mut self, items: Vec<T>) {
+        match self {
+            _ if items.is_empty() => *self = Self::All,
+            Self::All => {}
+            Self::Only(granted) => granted.extend(items),
+        }
+    }
+}
+
+// the dylibs that ship with Aria; they either check Permissions themselves
+// or only look at the platform they run on
+pub const BUNDLED_DYLIBS: &[&str] = &[
+    "aria_file",
+    "aria_http",
+    "aria_path",
+    "aria_platform",
+    "aria_regex",
+    "aria_timezone",
+    "aria_unicode",
+];
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            read: Grant::All,
+            write: Grant::All,
+            net: Grant::All,
+            dylibs: Grant::All,
+            run: true,
+            env: true,
+            exit: true,
+        }
+    }
+}
+
+// an absolute path with symlinks resolved, so that neither ".." nor a link
+// can lead out of a granted directory; the path itself need not exist yet
+fn resolve(path: &Path) -> Option<PathBuf> {
+    let path = std::path::absolute(path).ok()?;
+    let mut missing = vec![];
+    let mut existing = path.as_path();
+    loop {
+        if let Ok(mut resolved) = existing.canonicalize() {
+            resolved.extend(missing.iter().rev());
+            return Some(resolved);
+        }
+        // a ".." below a missing directory cannot be resolved, and is refused
+        missing.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+}
+
+fn path_granted(grant: &Grant<PathBuf>, path: &Path) -> bool {
+    match grant {
+        Grant::All => true,
+        Grant::Only(dirs) => resolve(path).is_some_and(|path| {
+            dirs.iter()
+                .filter_map(|dir| resolve(dir))
+                .any(|dir| path.starts_with(dir))
+        }),
+    }
+}
+
+// a net grant is "host" or "host:port", where an IPv6 host is written in
+// brackets when it has a port ("[::1]:8080") and may be bare when it has not
+fn split_host_port(grant: &str) -> (&str, Option<&str>) {
+    if let Some(rest) = grant.strip_prefix('[')
+        && let Some((host, after)) = rest.split_once(']')
+    {
+        return (host, after.strip_prefix(':'));
+    }
+    match grant.split_once(':') {
+        Some((host, port)) if !port.contains(':') => (host, Some(port)),
+        _ => (grant, None),
+    }
+}
+
+// hosts compare without case and IP addresses by value, so a grant for "::1"
+// covers the "[::1]" of a url as well as "0:0:0:0:0:0:0:1"
+fn same_host(a: &str, b: &str) -> bool {
+    fn unbracket(host: &str) -> &str {
+        host.strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host)
+    }
+    let (a, b) = (unbracket(a), unbracket(b));
+    match (a.parse::<IpAddr>(), b.parse::<IpAddr>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
+
+fn denied(what: String) -> Result<(), VmErrorReason> {
+    Err(VmErrorReason::PermissionDenied(what))
+}
+
+impl Permissions {
+    pub fn none() -> Self {
+        Self {
+            read: Grant::none(),
+            write: Grant::none(),
+            net: Grant::none(),
+            dylibs: Grant::none(),
+            run: false,
+            env: false,
+            exit: false,
+        }
+    }
+
+    pub fn check_read(&self, path: &Path) -> Result<(), VmErrorReason> {
+        if path_granted(&self.read, path) {
+            Ok(())
+        } else {
+            denied(format!("read access to {}", path.display()))
+        }
+    }
+
+    pub fn check_write(&self, path: &Path) -> Result<(), VmErrorReason> {
+        if path_granted(&self.write, path) {
+            Ok(())
+        } else {
+            denied(format!("write access to {}", path.display()))
+        }
+    }
+
+    pub fn check_net(&self, host: &str, port: Option<u16>) -> Result<(), VmErrorReason> {
+        let granted = match &self.net {
+            Grant::All => true,
+            Grant::Only(hosts) => hosts.iter().any(|grant| {
+                let (h, p) = split_host_port(grant);
+                same_host(h, host)
+                    && p.is_none_or(|p| Some(p) == port.map(|port| port.to_string()).as_deref())
+            }),
+        };
+        if granted {
+            Ok(())
+        } else {
+            denied(format!("network access to {host}"))
+        }
+    }
+
+    pub fn check_dylib(&self, name: &str) -> Result<(), VmErrorReason> {
+        let granted = match &self.dylibs {
+            Grant::All => true,
+            Grant::Only(names) => BUNDLED_DYLIBS.contains(&name) || names.iter().any(|n| n == name),
+        };
+        if granted {
+            Ok(())
+        } else {
+            denied(format!("loading dylib {name}"))
+        }
+    }
+
+    pub fn check_run(&self) -> Result<(), VmErrorReason> {
+        if self.run {
+            Ok(())
+        } else {
+            denied("running subprocesses".to_owned())
+        }
+    }
+
+    pub fn check_env(&self) -> Result<(), VmErrorReason> {
+        if self.env {
+            Ok(())
+        } else {
+            denied("access to environment variables".to_owned())
+        }
+    }
+
+    pub fn check_exit(&self) -> Result<(), VmErrorReason> {
+        if self.exit {
+            Ok(())
+        } else {
+            denied("exiting the process".to_owned())
+        }
+    }
+}
@@ -25,6 +25,10 @@ where
         self.values.push(val);
     }
 
+    pub fn as_slice(&self) -> &[T] {
+        &self.values
+    }
+
     pub fn peek(&mut self) -> Option<&T> {
         self.values.last()
     }
@@ -55,6 +55,9 @@ pub const INTERNED_ATTR_ACTUAL: Symbol = Symbol(38);
 pub const INTERNED_CASE_VARARGS: Symbol = Symbol(39);
 pub const INTERNED_CASE_BOUNDED: Symbol = Symbol(40);
 
+pub const INTERNED_ATTR_HASH: Symbol = Symbol(41);
+pub const INTERNED_ATTR_CAUSE: Symbol = Symbol(42);
+
 pub struct Interner {
     map: FxHashMap<String, Symbol>,
     strings: Vec<String>,
@@ -119,6 +122,9 @@ impl Default for Interner {
         assert!(this.intern("Varargs").unwrap() == INTERNED_CASE_VARARGS);
         assert!(this.intern("Bounded").unwrap() == INTERNED_CASE_BOUNDED);
 
+        assert!(this.intern("hash").unwrap() == INTERNED_ATTR_HASH);
+        assert!(this.intern("cause").unwrap() == INTERNED_ATTR_CAUSE);
+
         this
     }
 }
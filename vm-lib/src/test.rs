@@ -1,6 +1,14 @@
 // SPDX-License-Identifier: Apache-2.0
-use aria_compiler::compile_from_source;
+use aria_compiler::{
+    CompilationOptions,
+    bc_reader::BytecodeReader,
+    compile_from_source,
+    constant_value::ConstantValue,
+    module::CompiledModule,
+    module_cache::{COMPILER_BUILD, CacheError},
+};
 use aria_parser::ast::SourceBuffer;
+use haxby_opcodes::Opcode;
 
 use crate::{
     HaxbyEvalResult,
@@ -165,16 +173,141 @@ func main() {
         }
         crate::vm::RunloopExit::Exception(e) => {
             assert_eq!(
-                1,
-                *e.value
-                    .as_integer()
-                    .expect("integer value thrown")
-                    .raw_value()
+                Some(1),
+                e.value.as_integer().expect("integer value thrown").as_i64()
             )
         }
     }
 }
 
+#[test]
+fn test_uncaught_exception_backtrace_names_functions() {
+    let input = r##"
+struct Outer {
+    struct Inner {
+        type func fail() {
+            throw 1;
+        }
+    }
+}
+
+func main() {
+    Outer.Inner.fail();
+}
+"##;
+
+    let result = exec_code(input).expect("ok result expected");
+    match &result.exit {
+        crate::vm::RunloopExit::Ok(_) => {
+            panic!("expected exception to be thrown");
+        }
+        crate::vm::RunloopExit::Exception(e) => {
+            let entries: Vec<_> = e.backtrace.entries_iter().collect();
+            assert_eq!(2, entries.len());
+            assert_eq!(Some("Outer.Inner.fail"), entries[0].function.as_deref());
+            assert_eq!((5, 13), (entries[0].line(), entries[0].column()));
+            assert_eq!("throw 1;", entries[0].source_line().trim());
+            assert_eq!(Some("main"), entries[1].function.as_deref());
+            assert_eq!(11, entries[1].line());
+        }
+    }
+}
+
+#[test]
+fn test_tail_calls_replace_frames_in_the_backtrace() {
+    let input = r##"
+func fail(n) {
+    if n == 0 {
+        throw 1;
+    }
+    return fail(n - 1);
+}
+
+func main() {
+    fail(200000);
+}
+"##;
+
+    let result = exec_code(input).expect("ok result expected");
+    match &result.exit {
+        crate::vm::RunloopExit::Ok(_) => {
+            panic!("expected exception to be thrown");
+        }
+        crate::vm::RunloopExit::Exception(e) => {
+            // the thrower, the 16 most recent calls it replaced, and main
+            let entries: Vec<_> = e.backtrace.entries_iter().collect();
+            assert_eq!(18, entries.len());
+            assert!(
+                entries[..17]
+                    .iter()
+                    .all(|entry| entry.function.as_deref() == Some("fail"))
+            );
+            assert_eq!(4, entries[0].line());
+            assert_eq!(6, entries[1].line());
+            assert!(entries[..16].iter().all(|entry| entry.tail_calls == 0));
+            assert_eq!(200000 - 16, entries[16].tail_calls);
+            assert_eq!(Some("main"), entries[17].function.as_deref());
+            assert_eq!(0, entries[17].tail_calls);
+        }
+    }
+}
+
+#[test]
+fn test_uncaught_exception_keeps_its_causes() {
+    let input = r##"
+struct Oops {
+    type func new(msg) = alloc(This) { .msg };
+}
+
+func main() {
+    try {
+        try {
+            throw 1;
+        } catch e: Int {
+            throw Oops.new("middle") from e;
+        }
+    } catch e: Oops {
+        throw Oops.new("top") from e;
+    }
+}
+"##;
+
+    let result = exec_code(input).expect("ok result expected");
+    match &result.exit {
+        crate::vm::RunloopExit::Ok(_) => {
+            panic!("expected exception to be thrown");
+        }
+        crate::vm::RunloopExit::Exception(e) => {
+            let causes = e.causes(&result.vm);
+            assert_eq!(2, causes.len());
+            assert!(causes[0].as_object().is_some());
+            assert_eq!(
+                Some(1),
+                causes[1].as_integer().expect("integer cause").as_i64()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_yield_outside_function_fails_to_compile() {
+    let input = r##"
+for x in [1,2,3] {
+    yield x;
+}
+"##;
+
+    let sb = SourceBuffer::stdin(input);
+    let errors = match compile_from_source(&sb, &Default::default()) {
+        Ok(_) => panic!("module should not compile"),
+        Err(errors) => errors,
+    };
+    assert!(matches!(
+        errors[0].reason,
+        aria_compiler::do_compile::CompilationErrorReason::FlowControlNotAllowed
+    ));
+}
+
 #[test]
 fn test_cmdline_arguments() {
     let input = r##"
@@ -265,3 +398,935 @@ func main(x,y,z,...) {
             .is_err_and(|err| err.reason == VmErrorReason::InvalidMainSignature)
     );
 }
+
+#[test]
+fn test_module_cache_roundtrip() {
+    let input = r##"
+struct Pair {
+    type func new(a, b) = alloc(This) { .a = a, .b = b };
+    func sum() = this.a + this.b;
+}
+
+func main() {
+    val p = Pair.new(3, 4.5);
+    assert p.sum() == 7.5;
+    assert "hello".len() == 5;
+}
+"##;
+
+    let sb = SourceBuffer::stdin(input);
+    let opts = Default::default();
+    let module = compile_from_source(&sb, &opts).expect("module did not compile");
+    let bytes = module.to_cache_bytes(&sb, &opts);
+    let cached = CompiledModule::from_cache_bytes(&bytes, &sb, &opts).expect("cache rejected");
+
+    assert_eq!(module.constants.len(), cached.constants.len());
+    for (original, loaded) in module.constants.values().zip(cached.constants.values()) {
+        match (original, loaded) {
+            (ConstantValue::CompiledCodeObject(a), ConstantValue::CompiledCodeObject(b)) => {
+                assert_eq!(a.name, b.name);
+                assert_eq!(a.body, b.body);
+                assert_eq!(a.loc, b.loc);
+                assert_eq!(a.line_table.entries(), b.line_table.entries());
+            }
+            _ => assert!(original == loaded),
+        }
+    }
+
+    assert!(haxby_eval(cached, Default::default()).is_ok());
+}
+
+#[test]
+fn test_optimizer_folds_constants_and_fuses_method_calls() {
+    let input = r##"
+struct Counter {
+    type func new() { return alloc(This) { .n = 0 }; }
+    func add(k) { this.n += k; }
+}
+
+func main() {
+    val c = Counter.new();
+    c.add(60 * 60 * 24);
+    c.add(-(2 - 5));
+    assert c.n == 86403;
+    assert "a" + "b" == "ab";
+    return c.n;
+    assert false;
+}
+"##;
+
+    let sb = SourceBuffer::stdin(input);
+    let module = compile_from_source(&sb, &Default::default()).expect("module did not compile");
+    let main = module
+        .constants
+        .values()
+        .filter_map(|c| c.as_compiled_code_object())
+        .find(|cco| cco.name == "main")
+        .expect("missing main")
+        .clone();
+
+    let mut reader = BytecodeReader::try_from(main.body.as_slice()).expect("invalid bytecode");
+    let mut ops = vec![];
+    while let Ok(op) = reader.read_opcode() {
+        ops.push(op);
+    }
+    assert!(
+        !ops.iter()
+            .any(|op| matches!(op, Opcode::Mul | Opcode::Sub | Opcode::Neg))
+    );
+    assert_eq!(
+        ops.iter()
+            .filter(|op| matches!(op, Opcode::CallLocalMethod(..)))
+            .count(),
+        2
+    );
+    assert_eq!(
+        ops.iter()
+            .filter(|op| matches!(op, Opcode::Assert(_)))
+            .count(),
+        2
+    );
+    assert!(haxby_eval(module, Default::default()).is_ok());
+
+    let unoptimized = CompilationOptions {
+        optimize: false,
+        dump_builder: false,
+    };
+    let module = compile_from_source(&sb, &unoptimized).expect("module did not compile");
+    assert!(haxby_eval(module, Default::default()).is_ok());
+}
+
+#[test]
+fn test_module_cache_rejects_stale_or_corrupt_data() {
+    let sb = SourceBuffer::stdin("func main() { assert 1 + 1 == 2; }");
+    let opts = Default::default();
+    let module = compile_from_source(&sb, &opts).expect("module did not compile");
+    let bytes = module.to_cache_bytes(&sb, &opts);
+
+    let changed = SourceBuffer::stdin("func main() { assert 1 + 2 == 3; }");
+    assert!(
+        CompiledModule::from_cache_bytes(&bytes, &changed, &opts)
+            .is_err_and(|err| err == CacheError::SourceMismatch)
+    );
+
+    let unoptimized = CompilationOptions {
+        optimize: false,
+        dump_builder: false,
+    };
+    assert!(
+        CompiledModule::from_cache_bytes(&bytes, &sb, &unoptimized)
+            .is_err_and(|err| err == CacheError::OptionsMismatch)
+    );
+
+    // a cache written by a build of the compiler with different sources
+    let mut other_build = bytes.clone();
+    let build_id_end = 10 + COMPILER_BUILD.len();
+    other_build[build_id_end - 1] ^= 0x01;
+    assert!(
+        CompiledModule::from_cache_bytes(&other_build, &sb, &opts)
+            .is_err_and(|err| matches!(err, CacheError::CompilerMismatch(_)))
+    );
+
+    let mut corrupt = bytes.clone();
+    *corrupt.last_mut().unwrap() ^= 0xff;
+    assert!(
+        CompiledModule::from_cache_bytes(&corrupt, &sb, &opts)
+            .is_err_and(|err| err == CacheError::ChecksumMismatch)
+    );
+
+    assert!(CompiledModule::from_cache_bytes(&bytes[..bytes.len() / 2], &sb, &opts).is_err());
+    assert!(
+        CompiledModule::from_cache_bytes(b"not a cache", &sb, &opts)
+            .is_err_and(|err| err == CacheError::BadMagic)
+    );
+}
+
+#[test]
+fn test_module_cache_used_for_imports() {
+    let cache_dir =
+        std::env::temp_dir().join(format!("aria-module-cache-test-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&cache_dir);
+
+    let input = r##"
+import aria.structures.map;
+
+func main() {
+    val m = aria.structures.map.Map.new();
+    m.set(1, "one");
+    assert m.get(1)! == "one";
+}
+"##;
+
+    let vm_opts = VmOptions {
+        bytecode_cache_dir: Some(cache_dir.clone()),
+        ..Default::default()
+    };
+    assert!(exec_code_with_vm_options(input, vm_opts.clone()).is_ok());
+
+    let entries = std::fs::read_dir(&cache_dir)
+        .expect("cache directory was not created")
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "ariac"))
+        .count();
+    assert!(entries > 0);
+
+    // second run is served from the cache
+    assert!(exec_code_with_vm_options(input, vm_opts).is_ok());
+
+    let _ = std::fs::remove_dir_all(&cache_dir);
+}
+
+#[test]
+fn test_gc_collects_unreachable_cycles() {
+    let input = r##"
+struct Pair {
+    type func new() = alloc(This);
+}
+
+func make_cycles() {
+    val i = 0;
+    while i < 10 {
+        val a = Pair.new();
+        val b = Pair.new();
+        a.other = b;
+        b.other = a;
+        i += 1;
+    }
+}
+
+func main() {
+    make_cycles();
+}
+"##;
+
+    let vm_opts = VmOptions {
+        automatic_gc: false,
+        ..Default::default()
+    };
+    assert!(exec_code_with_vm_options(input, vm_opts).is_ok());
+
+    // the heap is per thread, and the VM leaves its cycles behind on return
+    let before = crate::gc::stats();
+    assert!(crate::gc::collect() >= 20);
+    let after = crate::gc::stats();
+    assert_eq!(after.collections, before.collections + 1);
+    assert!(after.tracked < before.tracked);
+}
+
+#[test]
+fn test_debugger_breakpoints_and_stepping() {
+    use crate::debugger::{DebugHandler, PausedVm, ResumeMode, StopReason};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[derive(Debug, PartialEq)]
+    struct Stop {
+        reason: StopReason,
+        frames: Vec<(String, usize)>,
+        locals: Vec<(String, String)>,
+    }
+
+    struct Recorder {
+        stops: Rc<RefCell<Vec<Stop>>>,
+        evals: Rc<RefCell<Vec<Result<String, String>>>>,
+        plan: Vec<ResumeMode>,
+    }
+
+    impl DebugHandler for Recorder {
+        fn has_breakpoint(&self, file: &str, line: usize) -> bool {
+            file == "<stdin>" && line == 3
+        }
+
+        fn stopped(&mut self, reason: StopReason, vm: &mut PausedVm<'_>) -> ResumeMode {
+            let frames = vm
+                .stack_frames()
+                .into_iter()
+                .map(|f| (f.name, f.location.map_or(0, |l| l.line)))
+                .collect();
+            let locals = vm
+                .locals(0)
+                .into_iter()
+                .map(|(name, val)| (name, vm.describe(&val)))
+                .collect();
+            self.stops.borrow_mut().push(Stop {
+                reason,
+                frames,
+                locals,
+            });
+            if reason == StopReason::Breakpoint {
+                for (depth, expr) in [(0, "a * b"), (1, "x + 1"), (0, "nope")] {
+                    let result = vm.evaluate(depth, expr);
+                    let result = match result {
+                        Ok(val) => Ok(vm.describe(&val)),
+                        Err(err) => Err(err),
+                    };
+                    self.evals.borrow_mut().push(result);
+                }
+            }
+            self.plan.remove(0)
+        }
+    }
+
+    let input = r##"
+func add(a, b) {
+    val sum = a + b;
+    return sum;
+}
+
+func main() {
+    val x = 3;
+    val y = add(x, 4);
+    assert y == 7;
+}
+"##;
+
+    let sb = SourceBuffer::stdin(input);
+    let module = compile_from_source(&sb, &Default::default()).expect("module did not compile");
+    let mut vm = crate::vm::VirtualMachine::default();
+    let rm = match vm.load_module("eval", module) {
+        Ok(crate::vm::RunloopExit::Ok(m)) => m.module,
+        _ => panic!("module did not load"),
+    };
+
+    let stops = Rc::new(RefCell::new(vec![]));
+    let evals = Rc::new(RefCell::new(vec![]));
+    vm.attach_debugger(
+        Box::new(Recorder {
+            stops: stops.clone(),
+            evals: evals.clone(),
+            plan: vec![
+                ResumeMode::StepOver,
+                ResumeMode::StepOut,
+                ResumeMode::Continue,
+            ],
+        }),
+        false,
+    );
+    assert!(matches!(
+        vm.execute_module(&rm),
+        Ok(crate::vm::RunloopExit::Ok(_))
+    ));
+
+    let frame = |name: &str, line| (name.to_owned(), line);
+    let local = |name: &str, val: &str| (name.to_owned(), val.to_owned());
+    let stops = stops.borrow();
+    assert_eq!(stops.len(), 3);
+    assert_eq!(stops[0].reason, StopReason::Breakpoint);
+    assert_eq!(stops[0].frames, vec![frame("add", 3), frame("main", 9)]);
+    assert_eq!(stops[0].locals[..2], [local("a", "3"), local("b", "4")]);
+    assert_eq!(stops[1].reason, StopReason::Step);
+    assert_eq!(stops[1].frames, vec![frame("add", 4), frame("main", 9)]);
+    assert_eq!(stops[1].locals[2], local("sum", "7"));
+    assert_eq!(stops[2].reason, StopReason::Step);
+    assert_eq!(stops[2].frames, vec![frame("main", 10)]);
+    assert_eq!(stops[2].locals, vec![local("x", "3"), local("y", "7")]);
+
+    let evals = evals.borrow();
+    assert_eq!(evals[0], Ok("12".to_owned()));
+    assert_eq!(evals[1], Ok("4".to_owned()));
+    assert!(evals[2].is_err());
+}
+
+#[test]
+fn test_embedding_calls_aria_functions_with_rust_values() {
+    use crate::{embed::EmbedError, vm::VirtualMachine};
+
+    let mut vm = VirtualMachine::default();
+    let module = vm
+        .load_source(
+            "embedded",
+            r##"
+func add(a, b) { return a + b; }
+func scale(values, factor) {
+    val ret = [];
+    for v in values { ret.append(v * factor); }
+    return ret;
+}
+func fail(msg) { throw msg; }
+"##,
+        )
+        .unwrap();
+
+    let sum: i64 = vm
+        .call_function(&module, "add", &[40.into(), 2.into()])
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(sum, 42);
+
+    let greeting: String = vm
+        .call_function(&module, "add", &["hello ".into(), "world".into()])
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(greeting, "hello world");
+
+    let scaled: Vec<f64> = vm
+        .call_function(&module, "scale", &[vec![1.0, 2.5].into(), 2.into()])
+        .unwrap()
+        .try_into()
+        .unwrap();
+    assert_eq!(scaled, [2.0, 5.0]);
+
+    let not_a_bool = vm.call_function(&module, "add", &[1.into(), 1.into()]);
+    assert!(matches!(
+        not_a_bool.map(bool::try_from),
+        Ok(Err(crate::runtime_value::convert::ConversionError {
+            expected: "Bool",
+            found: "Int"
+        }))
+    ));
+
+    match vm.call_function(&module, "fail", &["oops".into()]) {
+        Err(EmbedError::Exception { message, .. }) => assert_eq!(message, "oops"),
+        _ => panic!("expected an exception"),
+    }
+    assert!(matches!(
+        vm.call_function(&module, "missing", &[]),
+        Err(EmbedError::NoSuchFunction(_))
+    ));
+    assert!(matches!(
+        vm.call_function(&module, "add", &[1.into()]),
+        Err(EmbedError::Vm(_))
+    ));
+    assert!(matches!(
+        vm.load_source("broken", "func ("),
+        Err(EmbedError::Compile(_))
+    ));
+}
+
+#[test]
+fn test_embedding_registers_rust_closures() {
+    use crate::{arity::Arity, embed::EmbedError, vm::VirtualMachine};
+
+    let calls = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    let mut vm = VirtualMachine::default();
+
+    let seen = calls.clone();
+    vm.register_function("record", Arity::required(2), move |vm, args| {
+        let label = String::try_from(args[0].clone())?;
+        let count = i64::try_from(args[1].clone())?;
+        seen.borrow_mut().push((label, count));
+        Ok(vm.globals.create_unit_object()?)
+    });
+    vm.register_function("reject", Arity::required(1), |vm, args| {
+        // hands an exception back to the Aria caller
+        let message = args[0].prettyprint(&mut crate::frame::Frame::default(), vm);
+        Err(EmbedError::Exception {
+            message,
+            value: Box::new(args[0].clone()),
+        })
+    });
+
+    let module = vm
+        .load_source(
+            "embedded",
+            r##"
+func run() {
+    record("first", 1);
+    record("second", 1 + 1);
+    val caught = "not thrown";
+    try {
+        reject("no");
+    } catch e {
+        caught = e;
+    }
+    return caught;
+}
+func misuse() { record(1, 2); }
+"##,
+        )
+        .unwrap();
+
+    let caught = vm.call_function(&module, "run", &[]).unwrap();
+    assert_eq!(String::try_from(caught).unwrap(), "no");
+    assert_eq!(
+        *calls.borrow(),
+        [("first".to_owned(), 1), ("second".to_owned(), 2)]
+    );
+
+    // errors from the closure reach Aria as a RuntimeError it could catch
+    match vm.call_function(&module, "misuse", &[]) {
+        Err(EmbedError::Exception { message, .. }) => {
+            assert_eq!(message, "operation failed: expected String, found Int")
+        }
+        _ => panic!("expected an exception"),
+    }
+}
+
+#[test]
+fn test_endless_loop_compiles() {
+    // the jumps of `while true {}` form a cycle, which jump threading in the
+    // compiler used to follow forever
+    let input = r##"
+func spin() {
+    while true {}
+}
+
+func main() {
+    val x = 1;
+    assert x == 1;
+}
+"##;
+
+    assert!(exec_code(input).is_ok());
+}
+
+fn limited_vm_options(limits: crate::limits::ExecutionLimits) -> VmOptions {
+    VmOptions {
+        limits,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_instruction_budget_cannot_be_caught() {
+    let input = r##"
+func main() {
+    try {
+        while true {}
+    } catch e {
+        println("caught");
+    }
+}
+"##;
+
+    let opts = limited_vm_options(crate::limits::ExecutionLimits {
+        instruction_budget: Some(10_000),
+        ..Default::default()
+    });
+    assert!(
+        exec_code_with_vm_options(input, opts).is_err_and(|err| err.reason
+            == VmErrorReason::InstructionBudgetExhausted(10_000)
+            && err.loc.is_some())
+    );
+}
+
+#[test]
+fn test_deadline_stops_execution() {
+    let input = r##"
+func main() {
+    while true {}
+}
+"##;
+
+    let opts = limited_vm_options(crate::limits::ExecutionLimits {
+        deadline: Some(std::time::Instant::now() + std::time::Duration::from_millis(50)),
+        ..Default::default()
+    });
+    assert!(
+        exec_code_with_vm_options(input, opts)
+            .is_err_and(|err| err.reason == VmErrorReason::DeadlineExceeded)
+    );
+}
+
+#[test]
+fn test_interrupt_handle_stops_execution() {
+    use crate::{embed::EmbedError, vm::VirtualMachine};
+
+    let mut vm = VirtualMachine::default();
+    let module = vm
+        .load_source("spin", "func spin() { while true {} }")
+        .unwrap();
+
+    let handle = vm.interrupt_handle();
+    let interrupter = std::thread::spawn({
+        let handle = handle.clone();
+        move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            handle.interrupt();
+        }
+    });
+    match vm.call_function(&module, "spin", &[]) {
+        Err(EmbedError::Vm(err)) => assert_eq!(err.reason, VmErrorReason::Interrupted),
+        _ => panic!("expected the VM to be interrupted"),
+    }
+    interrupter.join().unwrap();
+
+    handle.reset();
+    let module = vm.load_source("done", "func done() { return 1; }").unwrap();
+    assert!(vm.call_function(&module, "done", &[]).is_ok());
+}
+
+#[test]
+fn test_call_depth_limit_can_be_caught() {
+    let input = r##"
+func recurse(n) {
+    return 1 + recurse(n + 1);
+}
+
+func main() {
+    val depth = 0;
+    try {
+        recurse(0);
+    } catch e {
+        depth = e.unwrap_CallDepthExceeded();
+    }
+    assert depth == 16;
+}
+"##;
+
+    let opts = limited_vm_options(crate::limits::ExecutionLimits {
+        max_call_depth: Some(16),
+        ..Default::default()
+    });
+    assert!(
+        exec_code_with_vm_options(input, opts)
+            .is_ok_and(|r| matches!(r.exit, crate::vm::RunloopExit::Ok(_)))
+    );
+}
+
+#[test]
+fn test_deep_recursion_does_not_use_the_native_stack() {
+    let input = r##"
+func count_down(n) {
+    if n == 0 {
+        return 0;
+    }
+    return 1 + count_down(n - 1);
+}
+
+func main() {
+    assert count_down(50000) == 50000;
+}
+"##;
+
+    // a thread with a small stack, which recursing in Rust would overflow
+    let result = std::thread::Builder::new()
+        .stack_size(1024 * 1024)
+        .spawn(|| exec_code(input).is_ok_and(|r| matches!(r.exit, crate::vm::RunloopExit::Ok(_))))
+        .unwrap()
+        .join()
+        .unwrap();
+    assert!(result);
+}
+
+#[test]
+fn test_default_call_depth_limit_can_be_caught() {
+    let input = r##"
+struct Node {
+    func descend(n) {
+        return 1 + this.descend(n + 1);
+    }
+}
+
+func main() {
+    val depth = 0;
+    try {
+        alloc(Node).descend(0);
+    } catch e {
+        depth = e.unwrap_CallDepthExceeded();
+    }
+    assert depth == 100000;
+    assert alloc(Node) isa Node;
+}
+"##;
+
+    assert!(exec_code(input).is_ok_and(|r| matches!(r.exit, crate::vm::RunloopExit::Ok(_))));
+}
+
+#[test]
+fn test_heap_limit_stops_runaway_allocation() {
+    let input = r##"
+func main() {
+    val hoard = [];
+    while true {
+        hoard.append("a string that takes up some room");
+    }
+}
+"##;
+
+    let opts = limited_vm_options(crate::limits::ExecutionLimits {
+        max_heap_bytes: Some(1 << 20),
+        ..Default::default()
+    });
+    assert!(
+        exec_code_with_vm_options(input, opts)
+            .is_err_and(|err| err.reason == VmErrorReason::HeapLimitExceeded(1 << 20))
+    );
+}
+
+#[test]
+fn test_heap_limit_counts_strings_and_big_integers_in_locals() {
+    let doubling_string = r##"
+func main() {
+    val s = "a string that takes up some room";
+    while true {
+        s = s + s;
+    }
+}
+"##;
+    let squaring_int = r##"
+func main() {
+    val x = 3;
+    while true {
+        x = x * x;
+    }
+}
+"##;
+
+    for input in [doubling_string, squaring_int] {
+        let opts = limited_vm_options(crate::limits::ExecutionLimits {
+            max_heap_bytes: Some(1 << 20),
+            ..Default::default()
+        });
+        assert!(
+            exec_code_with_vm_options(input, opts)
+                .is_err_and(|err| err.reason == VmErrorReason::HeapLimitExceeded(1 << 20))
+        );
+    }
+}
+
+#[test]
+fn test_finally_that_leaves_early_drops_the_thrown_value() {
+    let input = r##"
+func main() {
+    val s = "a string that takes up some room";
+    val n = 0;
+    while n < 100000 {
+        n = n + 1;
+        try {
+            throw s + s;
+        } finally {
+            continue;
+        }
+    }
+    while true {
+        try {
+            throw s + s;
+        } finally {
+            break;
+        }
+    }
+    assert n == 100000;
+}
+"##;
+
+    let opts = limited_vm_options(crate::limits::ExecutionLimits {
+        max_heap_bytes: Some(1 << 20),
+        ..Default::default()
+    });
+    assert!(exec_code_with_vm_options(input, opts).is_ok());
+}
+
+#[test]
+fn test_heap_limit_only_counts_what_the_vm_allocated() {
+    let hoarder = r##"
+val hoard = [];
+func main() {
+    val i = 0;
+    while i < 100000 {
+        hoard.append("a string " + "of its own");
+        i += 1;
+    }
+}
+"##;
+    let frugal = r##"
+func main() {
+    val i = 0;
+    while i < 100000 {
+        val pair = [i, "a string " + "of its own"];
+        i += 1;
+    }
+}
+"##;
+
+    // the first VM keeps several megabytes alive on this thread
+    let hoarding = exec_code(hoarder).expect("hoarder ran");
+    let opts = limited_vm_options(crate::limits::ExecutionLimits {
+        max_heap_bytes: Some(1 << 20),
+        ..Default::default()
+    });
+    assert!(exec_code_with_vm_options(frugal, opts).is_ok());
+    drop(hoarding);
+}
+
+#[test]
+fn test_sandbox_denies_builtins_with_a_catchable_error() {
+    let input = r##"
+func main() {
+    val denied = 0;
+    try {
+        system("true");
+    } catch e {
+        assert e.unwrap_PermissionDenied() == "running subprocesses";
+        denied += 1;
+    }
+    try {
+        getenv("HOME");
+    } catch e {
+        assert e.is_PermissionDenied();
+        denied += 1;
+    }
+    assert denied == 2;
+}
+"##;
+
+    let opts = VmOptions {
+        permissions: crate::sandbox::Permissions::none(),
+        ..Default::default()
+    };
+    assert!(
+        exec_code_with_vm_options(input, opts)
+            .is_ok_and(|r| matches!(r.exit, crate::vm::RunloopExit::Ok(_)))
+    );
+}
+
+#[test]
+fn test_sandbox_grants_paths_below_granted_directories() {
+    use crate::sandbox::{Grant, Permissions};
+
+    let dir = std::env::temp_dir().join(format!("aria-sandbox-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("inner")).unwrap();
+
+    let mut permissions = Permissions::none();
+    permissions.read.add(vec![dir.join("inner")]);
+    permissions.net = Grant::Only(vec![
+        "example.com".to_owned(),
+        "localhost:8080".to_owned(),
+        "[::1]:8080".to_owned(),
+        "fe80::2".to_owned(),
+        "[2001:db8::1]".to_owned(),
+    ]);
+
+    assert!(permissions.check_read(&dir.join("inner")).is_ok());
+    assert!(
+        permissions
+            .check_read(&dir.join("inner/new/file.txt"))
+            .is_ok()
+    );
+    assert!(permissions.check_read(&dir.join("inner/../other")).is_err());
+    assert!(
+        permissions
+            .check_read(&dir.join("inner/new/../../x"))
+            .is_err()
+    );
+    assert!(
+        permissions
+            .check_write(&dir.join("inner/file.txt"))
+            .is_err()
+    );
+
+    assert!(permissions.check_net("example.com", Some(443)).is_ok());
+    assert!(permissions.check_net("localhost", Some(8080)).is_ok());
+    assert!(permissions.check_net("localhost", Some(80)).is_err());
+    assert!(permissions.check_net("[::1]", Some(8080)).is_ok());
+    assert!(permissions.check_net("::1", Some(8080)).is_ok());
+    assert!(permissions.check_net("0:0:0:0:0:0:0:1", Some(8080)).is_ok());
+    assert!(permissions.check_net("[::1]", Some(80)).is_err());
+    assert!(permissions.check_net("[fe80::2]", Some(443)).is_ok());
+    assert!(permissions.check_net("fe80::2", None).is_ok());
+    assert!(permissions.check_net("[2001:db8::1]", Some(80)).is_ok());
+    assert!(permissions.check_net("[2001:db8::2]", Some(80)).is_err());
+    assert!(permissions.check_net("fe80::", Some(2)).is_err());
+    assert!(permissions.check_dylib("aria_file").is_ok());
+    assert!(permissions.check_dylib("libevil").is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_profiler_samples_aria_functions_and_lines() {
+    use crate::{profiler::ProfilerOptions, vm::VirtualMachine};
+
+    let mut vm = VirtualMachine::default();
+    let module = vm
+        .load_source(
+            "busy",
+            r##"
+func busy() {
+    val i = 0;
+    while i < 200000 {
+        i = i + 1;
+    }
+    return i;
+}
+"##,
+        )
+        .unwrap();
+
+    vm.start_profiling(ProfilerOptions {
+        interval: std::time::Duration::from_micros(100),
+        count_opcodes: true,
+    });
+    assert!(vm.call_function(&module, "busy", &[]).is_ok());
+    let profile = vm.stop_profiling().unwrap();
+
+    assert!(profile.total_samples() > 0);
+    assert!(profile.stacks.iter().any(|(stack, _)| {
+        stack
+            .last()
+            .is_some_and(|f| f.function == "busy" && f.location.is_some())
+    }));
+    assert!(profile.folded().contains("busy ("));
+    assert!(profile.opcodes.iter().any(|(name, _)| name == "ADD"));
+    assert!(vm.stop_profiling().is_none());
+}
+
+#[test]
+fn test_coverage_counts_lines_and_branches() {
+    use crate::{coverage::CoverageOptions, vm::VirtualMachine};
+
+    let mut vm = VirtualMachine::default();
+    vm.start_coverage(CoverageOptions { branches: true });
+    let module = vm
+        .load_source(
+            "covered",
+            r##"func sign(n) {
+    if n < 0 {
+        return -1;
+    }
+    return 1;
+}
+func unused() {
+    return 0;
+}
+"##,
+        )
+        .unwrap();
+    assert!(vm.call_function(&module, "sign", &[5i64.into()]).is_ok());
+    assert!(vm.call_function(&module, "sign", &[7i64.into()]).is_ok());
+    let coverage = vm.stop_coverage().unwrap();
+
+    let file = &coverage.files["covered"];
+    assert_eq!(file.lines.get(&2), Some(&2));
+    assert_eq!(file.lines.get(&3), Some(&0));
+    assert_eq!(file.lines.get(&5), Some(&2));
+    assert_eq!(file.lines.get(&8), Some(&0));
+    assert_eq!(file.branches.values().collect::<Vec<_>>(), [&[0, 2]]);
+
+    let lcov = coverage.lcov();
+    assert!(lcov.contains("SF:covered\n"));
+    assert!(lcov.contains("DA:3,0\n"));
+    assert!(lcov.contains("BRDA:2,0,0,0\nBRDA:2,0,1,2\n"));
+    assert!(lcov.ends_with("end_of_record\n"));
+
+    let mut merged = coverage.clone();
+    merged.merge(&coverage);
+    assert_eq!(merged.files["covered"].lines.get(&2), Some(&4));
+}
+
+#[test]
+fn test_match_over_enum_cases_without_else_fails() {
+    let input = r##"
+enum Color { case Red, case Green, case Blue }
+
+func main() {
+    val c = Color::Blue;
+    val seen = 0;
+    # rules that are not all enum cases still fall through quietly
+    match c {
+        == 1 => { seen = 1; },
+    }
+    assert seen == 0;
+    match c {
+        case Red => { seen = 2; },
+        case Green => { seen = 3; },
+    }
+}
+"##;
+
+    assert!(
+        exec_code(input)
+            .is_err_and(|err| err.reason == VmErrorReason::NonExhaustiveMatch("c".to_owned()))
+    );
+}
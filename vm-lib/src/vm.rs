@@ -6,11 +6,12 @@ use std::{
     rc::Rc,
 };
 
-use aria_compiler::{compile_from_source, module::CompiledModule};
+use aria_compiler::{compile_from_source_cached, module::CompiledModule, widget::Lockfile};
 use aria_parser::ast::SourceBuffer;
 use haxby_opcodes::{
-    BuiltinTypeId, OPCODE_BIND_CASE, OPCODE_ENUM_CHECK_IS_CASE, OPCODE_NEW_ENUM_VAL,
-    OPCODE_READ_ATTRIBUTE, OPCODE_WRITE_ATTRIBUTE, Opcode, enum_case_attribs::CASE_HAS_PAYLOAD,
+    BuiltinTypeId, OPCODE_BIND_CASE, OPCODE_CALL_LOCAL_METHOD, OPCODE_ENUM_CHECK_IS_CASE,
+    OPCODE_NEW_ENUM_VAL, OPCODE_READ_ATTRIBUTE, OPCODE_WRITE_ATTRIBUTE, Opcode,
+    enum_case_attribs::CASE_HAS_PAYLOAD,
 };
 use std::sync::OnceLock;
 
@@ -18,6 +19,7 @@ use crate::{
     builtins::VmGlobals,
     console::{Console, StdConsole},
     error::{
+        backtrace::{BacktraceEntry, CallSite},
         dylib_load::{LoadResult, LoadStatus},
         exception::VmException,
         vm_error::{SymbolKind, VmError, VmErrorReason},
@@ -31,15 +33,17 @@ use crate::{
     runtime_value::{
         RuntimeValue,
         enumeration::{Enum, EnumCase},
-        function::Function,
+        function::{CallSetup, Function, PartialFunctionApplication},
         isa::IsaCheckable,
         kind::RuntimeValueType,
         list::List,
+        map::Map,
         mixin::Mixin,
         object::Object,
         structure::Struct,
     },
     stack::Stack,
+    symbol::INTERNED_ATTR_CAUSE,
 };
 
 pub type ConsoleHandle = Rc<RefCell<dyn Console>>;
@@ -52,6 +56,10 @@ pub struct VmOptions {
     pub dump_stack: bool,
     pub vm_args: Vec<String>,
     pub console: ConsoleHandle,
+    pub bytecode_cache_dir: Option<PathBuf>,
+    pub automatic_gc: bool,
+    pub limits: crate::limits::ExecutionLimits,
+    pub permissions: crate::sandbox::Permissions,
 }
 
 impl Default for VmOptions {
@@ -63,6 +71,10 @@ impl Default for VmOptions {
             dump_stack: Default::default(),
             vm_args: Default::default(),
             console: Rc::new(RefCell::new(StdConsole {})),
+            bytecode_cache_dir: aria_compiler::module_cache::default_cache_dir(),
+            automatic_gc: true,
+            limits: Default::default(),
+            permissions: Default::default(),
         }
     }
 }
@@ -71,10 +83,30 @@ pub struct VirtualMachine {
     pub modules: HashMap<String, RuntimeModule>,
     pub options: VmOptions,
     pub globals: VmGlobals,
-    pub import_stack: Stack<String>,
-    pub imported_modules: HashMap<String, ModuleLoadInfo>,
+    pub import_stack: Stack<PathBuf>,
+    // keyed by the canonical path of the module source, since the same import
+    // path can name different files in different widgets
+    pub imported_modules: HashMap<PathBuf, ModuleLoadInfo>,
+    // modules made importable by name without a source file (e.g. the repl)
+    injected_modules: HashMap<String, ModuleLoadInfo>,
+    // (widget root of the importing module, import path) to canonical path
+    resolved_imports: HashMap<(Option<PathBuf>, String), PathBuf>,
     pub loaded_dylibs: HashMap<String, libloading::Library>,
-    frame_pool: Vec<Frame>,
+    // boxed, so that a frame keeps its address while it runs on the call
+    // stack of the run loop, and goes back and forth without being copied
+    #[allow(clippy::vec_box)]
+    frame_pool: Vec<Box<Frame>>,
+    pub(crate) debugger: Option<crate::debugger::DebugSession>,
+    pub(crate) profiler: Option<crate::profiler::ProfileSession>,
+    pub(crate) coverage: Option<crate::coverage::CoverageSession>,
+    // for each dependency widget imported so far, the widget whose lockfile
+    // it came from; its own imports of other widgets resolve through that
+    // same lockfile
+    widget_lock_roots: HashMap<PathBuf, PathBuf>,
+    widget_lockfiles: HashMap<PathBuf, Option<Rc<Lockfile>>>,
+    pub(crate) limit_state: crate::limits::LimitState,
+    // where each function that is running was called from, outermost first
+    pub(crate) call_stack: Vec<CallSite>,
 }
 
 impl VirtualMachine {
@@ -82,14 +114,46 @@ impl VirtualMachine {
         &self.options.console
     }
 
-    pub(crate) fn acquire_frame(&mut self, f: &Function) -> Frame {
+    pub(crate) fn acquire_frame(&mut self, f: &Function) -> Box<Frame> {
+        // function calls are where the cycle collector gets a chance to run
+        if self.options.automatic_gc && crate::gc::should_collect() {
+            crate::gc::collect();
+        }
+
         let mut frame = self.frame_pool.pop().unwrap_or_default();
         frame.reset_for_function(f);
         frame
     }
 
-    pub(crate) fn release_frame(&mut self, frame: Frame) {
-        self.frame_pool.push(frame.reset_for_pool());
+    pub(crate) fn release_frame(&mut self, mut frame: Box<Frame>) {
+        frame.reset_for_pool();
+        self.frame_pool.push(frame);
+    }
+
+    // bookkeeping for a call made from frame, once its arguments are in
+    // place; fails when the call would go deeper than the limit allows
+    pub(crate) fn enter_call(&mut self, frame: &Frame) -> Result<(), VmErrorReason> {
+        if let Some(max_depth) = self.options.limits.max_call_depth
+            && self.limit_state.call_depth >= max_depth
+        {
+            return Err(VmErrorReason::CallDepthExceeded(max_depth));
+        }
+
+        if let Some(debugger) = self.debugger.as_mut() {
+            debugger.calling_from(frame);
+        }
+        self.limit_state.call_depth += 1;
+        self.call_stack.push(CallSite {
+            func: frame.func.clone(),
+            pc: frame.pc,
+            tail_callers: frame.tail_callers.clone(),
+        });
+        Ok(())
+    }
+
+    pub(crate) fn leave_call(&mut self) {
+        self.call_stack.pop();
+        self.limit_state.call_depth -= 1;
     }
 
     fn load_version_into_globals(mut self) -> Self {
@@ -116,8 +180,17 @@ impl VirtualMachine {
             globals: Default::default(),
             import_stack: Default::default(),
             imported_modules: Default::default(),
+            injected_modules: Default::default(),
+            resolved_imports: Default::default(),
             loaded_dylibs: Default::default(),
             frame_pool: Default::default(),
+            debugger: None,
+            profiler: None,
+            coverage: None,
+            widget_lock_roots: Default::default(),
+            widget_lockfiles: Default::default(),
+            limit_state: Default::default(),
+            call_stack: Default::default(),
         }
         .load_version_into_globals()
     }
@@ -184,10 +257,45 @@ impl RunloopExit {
 
 enum OpcodeRunExit {
     Continue,
+    // an Aria function is ready to run on top of the current frame
+    Call(Callee),
+    // an Aria function is ready to run instead of the current frame
+    TailCall(Callee),
     Return,
+    Yield,
     Exception(VmException),
 }
 
+// a call from Aria code to an Aria function; the run loop keeps these on a
+// stack of its own instead of recursing, so that deep recursion in Aria code
+// does not use up the native stack
+struct Callee {
+    frame: Box<Frame>,
+    body: Rc<[Opcode]>,
+    sidecar: Rc<SidecarSlice>,
+    module: RuntimeModule,
+    // whether the debugger and the profiler were told about the frame
+    debugging: bool,
+    profiling: bool,
+}
+
+// the Aria function a value calls into, and the receiver bound to it
+fn bytecode_callee(x: &RuntimeValue) -> Option<(&Function, PartialFunctionApplication)> {
+    if let Some(f) = x.as_function() {
+        f.as_bytecode()
+            .map(|_| (f, PartialFunctionApplication::default()))
+    } else if let Some(bf) = x.as_bound_function() {
+        bf.func().as_bytecode().map(|_| {
+            (
+                bf.func(),
+                PartialFunctionApplication::default().with_suffix_arg(bf.this().clone()),
+            )
+        })
+    } else {
+        None
+    }
+}
+
 macro_rules! binop_eval {
     ( ($op_expr: expr), $next: expr, $frame: expr, $op_idx: expr) => {
         match $op_expr {
@@ -362,7 +470,60 @@ impl VirtualMachine {
         }
     }
 
+    // the file for an import of a dependency listed in the lockfile that
+    // governs widget_root, or None if the import does not name a dependency
+    fn resolve_widget_dependency(
+        &mut self,
+        ipath: &str,
+        widget_root: &Path,
+    ) -> Option<Result<PathBuf, VmErrorReason>> {
+        let (name, _) = ipath.split_once('.')?;
+        let widget_root = widget_root
+            .canonicalize()
+            .unwrap_or_else(|_| widget_root.to_path_buf());
+        let lock_root = self
+            .widget_lock_roots
+            .get(&widget_root)
+            .cloned()
+            .unwrap_or(widget_root);
+
+        let lockfile = match self.widget_lockfiles.get(&lock_root) {
+            Some(lockfile) => lockfile.clone()?,
+            None => match Lockfile::load(&lock_root) {
+                Ok(lockfile) => {
+                    let lockfile = lockfile.map(Rc::new);
+                    self.widget_lockfiles
+                        .insert(lock_root.clone(), lockfile.clone());
+                    lockfile?
+                }
+                Err(e) => {
+                    return Some(Err(VmErrorReason::ImportNotAvailable(
+                        ipath.to_owned(),
+                        e.to_string(),
+                    )));
+                }
+            },
+        };
+
+        let dep_root = lockfile.widget_root(&lock_root, name)?;
+        if let Ok(dep_root) = dep_root.canonicalize() {
+            self.widget_lock_roots.insert(dep_root, lock_root.clone());
+        }
+        Some(
+            lockfile
+                .resolve_import(&lock_root, ipath)
+                .filter(|path| path.is_file())
+                .ok_or_else(|| {
+                    VmErrorReason::ImportNotAvailable(
+                        ipath.to_owned(),
+                        format!("import not found in widget {name}"),
+                    )
+                }),
+        )
+    }
+
     fn resolve_import_path_to_path(
+        &mut self,
         ipath: &str,
         widget_root_path: Option<&PathBuf>,
     ) -> Result<PathBuf, VmErrorReason> {
@@ -383,19 +544,44 @@ impl VirtualMachine {
             };
         }
 
+        // dependencies of the widget come before the library paths
+        if let Some(widget_root_path) = widget_root_path
+            && let Some(path) = self.resolve_widget_dependency(ipath, widget_root_path)
+        {
+            return path;
+        }
+
+        Self::resolve_library_import(ipath).ok_or_else(|| {
+            VmErrorReason::ImportNotAvailable(
+                format!("{}.aria", ipath.replace(".", "/")),
+                "no such path".to_owned(),
+            )
+        })
+    }
+
+    fn resolve_library_import(ipath: &str) -> Option<PathBuf> {
         let ipath = format!("{}.aria", ipath.replace(".", "/"));
+        VirtualMachine::get_aria_library_paths()
+            .iter()
+            .find_map(|aria_lib_dir| Self::try_get_import_path_from_name(aria_lib_dir, &ipath))
+    }
 
-        let aria_lib_dirs = VirtualMachine::get_aria_library_paths();
-        for aria_lib_dir in aria_lib_dirs {
-            if let Some(path) = Self::try_get_import_path_from_name(aria_lib_dir, &ipath) {
-                return Ok(path);
-            }
+    // the canonical path of the file an import refers to, which is what
+    // identifies an imported module
+    fn resolve_import(
+        &mut self,
+        ipath: &str,
+        widget_root_path: Option<&PathBuf>,
+    ) -> Result<PathBuf, VmErrorReason> {
+        let key = (widget_root_path.cloned(), ipath.to_owned());
+        if let Some(path) = self.resolved_imports.get(&key) {
+            return Ok(path.clone());
         }
 
-        Err(VmErrorReason::ImportNotAvailable(
-            ipath.to_owned(),
-            "no such path".to_owned(),
-        ))
+        let path = self.resolve_import_path_to_path(ipath, widget_root_path)?;
+        let path = path.canonicalize().unwrap_or(path);
+        self.resolved_imports.insert(key, path.clone());
+        Ok(path)
     }
 
     fn create_import_model_from_path(
@@ -460,6 +646,9 @@ impl VirtualMachine {
     ) -> ExecutionResult<RunloopExit<ModuleLoadInfo>> {
         if !name.is_empty() {
             self.modules.insert(name.to_owned(), r_mod.clone());
+            if r_mod.name().is_none() {
+                r_mod.set_name(name);
+            }
         }
 
         let entry_co = r_mod.load_entry_code_object();
@@ -489,14 +678,21 @@ impl VirtualMachine {
         self.modules.get(name).cloned()
     }
 
+    // a library module by import path, if it has been imported already
     pub(crate) fn find_imported_module(&self, name: &str) -> Option<RuntimeModule> {
+        if let Some(mli) = self.injected_modules.get(name) {
+            return Some(mli.module.clone());
+        }
+
+        let path = Self::resolve_library_import(name)?;
+        let path = path.canonicalize().unwrap_or(path);
         self.imported_modules
-            .get(name)
+            .get(&path)
             .map(|mli| mli.module.clone())
     }
 
     pub fn inject_imported_module(&mut self, name: &str, module: RuntimeModule) {
-        self.imported_modules
+        self.injected_modules
             .insert(name.to_owned(), ModuleLoadInfo { module });
     }
 
@@ -564,9 +760,148 @@ impl VirtualMachine {
         sidecar: &SidecarSlice,
         target_frame: &mut Frame,
     ) -> ExecutionResult<RunloopExit> {
+        let _charging = self.limit_state.charge_allocations(&self.options.limits);
         self.runloop(bc, sidecar, module, target_frame)
     }
 
+    // reads attribute n of val_obj onto the stack, caching where it was
+    // found in the sidecar of the instruction that asked for it
+    fn read_attribute_symbol(
+        &mut self,
+        val_obj: RuntimeValue,
+        n: crate::symbol::Symbol,
+        next: Opcode,
+        next_sidecar: &SidecarCell,
+        op_idx: &mut usize,
+        frame: &mut Frame,
+    ) -> ExecutionResult<OpcodeRunExit, VmError> {
+        let current_sidecar = next_sidecar
+            .get()
+            .and_then(|sc| sc.as_read_attribute().copied());
+        let mut current_misses = current_sidecar
+            .as_ref()
+            .map(|sc| sc.misses)
+            .unwrap_or_default();
+
+        if let Some(sc) = current_sidecar
+            && current_misses < ReadAttributeSidecar::MAXIMUM_ALLOWED_MISSES
+        {
+            if let Some(v) = val_obj.read_slot(&self.globals, sc.slot_id, sc.shape_id) {
+                frame.stack.push(v);
+                return Ok(OpcodeRunExit::Continue);
+            } else {
+                current_misses = current_misses
+                    .saturating_add(1)
+                    .clamp(0, ReadAttributeSidecar::MAXIMUM_ALLOWED_MISSES);
+            }
+        }
+
+        if current_misses < ReadAttributeSidecar::MAXIMUM_ALLOWED_MISSES
+            && let Some((v, sid, slot)) = val_obj.resolve_to_slot(&self.globals, n)
+        {
+            next_sidecar.set(Some(OpcodeSidecar::ReadAttribute(ReadAttributeSidecar {
+                misses: current_misses,
+                shape_id: sid,
+                slot_id: slot,
+            })));
+            frame.stack.push(v);
+            return Ok(OpcodeRunExit::Continue);
+        }
+
+        // if you're here, either you had no sidecar, or you did but your sidecar failed and you didn't get a valid
+        // alternative slot to try (or you would have returned in the earlier if) - record where you're at (if you had a
+        // sidecar to begin with), and then do a full slow path attribute read
+        if let Some(sc) = current_sidecar {
+            next_sidecar.set(Some(OpcodeSidecar::ReadAttribute(ReadAttributeSidecar {
+                misses: current_misses,
+                shape_id: sc.shape_id,
+                slot_id: sc.slot_id,
+            })));
+        }
+
+        match val_obj.read_attribute(n, &self.globals) {
+            Ok(val) => {
+                frame.stack.push(val);
+                Ok(OpcodeRunExit::Continue)
+            }
+            Err(err) => build_vm_error!(
+                match err {
+                    crate::runtime_value::AttributeError::NoSuchAttribute => {
+                        VmErrorReason::NoSuchSymbol(n.0, SymbolKind::Identifier)
+                    }
+                    crate::runtime_value::AttributeError::InvalidFunctionBinding => {
+                        VmErrorReason::InvalidBinding
+                    }
+                    crate::runtime_value::AttributeError::ValueHasNoAttributes => {
+                        VmErrorReason::UnexpectedType
+                    }
+                },
+                next,
+                frame,
+                op_idx
+            ),
+        }
+    }
+
+    // calls x with argc arguments from the stack; Aria functions get a frame
+    // of their own, which the run loop takes over
+    fn call_value(
+        &mut self,
+        x: RuntimeValue,
+        argc: u8,
+        next: Opcode,
+        op_idx: &mut usize,
+        frame: &mut Frame,
+    ) -> ExecutionResult<OpcodeRunExit, VmError> {
+        if let Some((func, other_args)) = bytecode_callee(&x) {
+            let callee_frame = match func.setup_call(argc, frame, self, &other_args, false) {
+                Ok(CallSetup::Frame(callee_frame)) => callee_frame,
+                Ok(CallSetup::Done(_)) => return Ok(OpcodeRunExit::Continue),
+                Err(err) => {
+                    if err.loc.is_some() {
+                        return Err(err);
+                    } else {
+                        return build_vm_error!(err.reason, next, frame, op_idx);
+                    }
+                }
+            };
+            // a tail call does not go any deeper, unless the run loop
+            // finds out that the frame cannot be replaced
+            let tail_call = matches!(next, Opcode::TailCall(_));
+            if !tail_call && let Err(reason) = self.enter_call(frame) {
+                self.release_frame(callee_frame);
+                return build_vm_error!(reason, next, frame, op_idx);
+            }
+            let Some(bcf) = func.as_bytecode() else {
+                unreachable!("bytecode_callee only returns bytecode functions");
+            };
+            let callee = Callee {
+                frame: callee_frame,
+                body: bcf.body.clone(),
+                sidecar: bcf.sidecar.clone(),
+                module: bcf.module.clone(),
+                debugging: false,
+                profiling: false,
+            };
+            return Ok(if tail_call {
+                OpcodeRunExit::TailCall(callee)
+            } else {
+                OpcodeRunExit::Call(callee)
+            });
+        }
+        match x.eval(argc, frame, self, false) {
+            Ok(crate::runtime_value::CallResult::Ok(_)) => Ok(OpcodeRunExit::Continue),
+            Ok(crate::runtime_value::CallResult::Exception(e)) => Ok(OpcodeRunExit::Exception(e)),
+            Err(err) => {
+                if err.loc.is_some() {
+                    Err(err)
+                } else {
+                    build_vm_error!(err.reason, next, frame, op_idx)
+                }
+            }
+        }
+    }
+
     fn run_opcode(
         &mut self,
         next: Opcode,
@@ -690,7 +1025,7 @@ impl VirtualMachine {
                 let x = pop_or_err!(next, frame, op_idx);
                 let y = pop_or_err!(next, frame, op_idx);
                 if let (RuntimeValue::Integer(a), RuntimeValue::Integer(b)) = (&x, &y) {
-                    if *a.raw_value() == 0 {
+                    if *a == 0 {
                         return build_vm_error!(VmErrorReason::DivisionByZero, next, frame, op_idx);
                     }
                     frame.stack.push(RuntimeValue::Integer(b / a));
@@ -700,7 +1035,7 @@ impl VirtualMachine {
                     }
                     frame.stack.push(RuntimeValue::Float(b / a))
                 } else if let (RuntimeValue::Integer(a), RuntimeValue::Float(b)) = (&x, &y) {
-                    if *a.raw_value() == 0 {
+                    if *a == 0 {
                         return build_vm_error!(VmErrorReason::DivisionByZero, next, frame, op_idx);
                     }
                     frame.stack.push(RuntimeValue::Float(b / &a.to_fp()));
@@ -722,7 +1057,7 @@ impl VirtualMachine {
                 let x = pop_or_err!(next, frame, op_idx);
                 let y = pop_or_err!(next, frame, op_idx);
                 if let (RuntimeValue::Integer(a), RuntimeValue::Integer(b)) = (&x, &y) {
-                    if *a.raw_value() == 0 {
+                    if *a == 0 {
                         return build_vm_error!(VmErrorReason::DivisionByZero, next, frame, op_idx);
                     }
                     frame.stack.push(RuntimeValue::Integer(b % a));
@@ -732,7 +1067,7 @@ impl VirtualMachine {
                     }
                     frame.stack.push(RuntimeValue::Float(b % a))
                 } else if let (RuntimeValue::Integer(a), RuntimeValue::Float(b)) = (&x, &y) {
-                    if *a.raw_value() == 0 {
+                    if *a == 0 {
                         return build_vm_error!(VmErrorReason::DivisionByZero, next, frame, op_idx);
                     }
                     frame.stack.push(RuntimeValue::Float(b % &a.to_fp()))
@@ -764,7 +1099,17 @@ impl VirtualMachine {
                 let by = pop_or_err!(next, frame, op_idx);
                 let n = pop_or_err!(next, frame, op_idx);
                 if let (RuntimeValue::Integer(n), RuntimeValue::Integer(by)) = (&n, &by) {
-                    frame.stack.push(RuntimeValue::Integer(n << by));
+                    match n.checked_shl(by) {
+                        Some(val) => frame.stack.push(RuntimeValue::Integer(val)),
+                        None => {
+                            return build_vm_error!(
+                                VmErrorReason::OperationFailed("shift amount too large".to_owned()),
+                                next,
+                                frame,
+                                op_idx
+                            );
+                        }
+                    }
                 } else {
                     binop_eval!(
                         (RuntimeValue::leftshift(&n, &by, frame, self)),
@@ -778,7 +1123,17 @@ impl VirtualMachine {
                 let by = pop_or_err!(next, frame, op_idx);
                 let n = pop_or_err!(next, frame, op_idx);
                 if let (RuntimeValue::Integer(n), RuntimeValue::Integer(by)) = (&n, &by) {
-                    frame.stack.push(RuntimeValue::Integer(n >> by));
+                    match n.checked_shr(by) {
+                        Some(val) => frame.stack.push(RuntimeValue::Integer(val)),
+                        None => {
+                            return build_vm_error!(
+                                VmErrorReason::OperationFailed("shift amount too large".to_owned()),
+                                next,
+                                frame,
+                                op_idx
+                            );
+                        }
+                    }
                 } else {
                     binop_eval!(
                         (RuntimeValue::rightshift(&n, &by, frame, self)),
@@ -1055,77 +1410,15 @@ impl VirtualMachine {
                 );
             }
             Opcode::ReadAttributeSymbol(n) => {
-                let n = crate::symbol::Symbol(n);
-
                 let val_obj = pop_or_err!(next, frame, op_idx);
-
-                let current_sidecar = next_sidecar
-                    .get()
-                    .and_then(|sc| sc.as_read_attribute().copied());
-                let mut current_misses = current_sidecar
-                    .as_ref()
-                    .map(|sc| sc.misses)
-                    .unwrap_or_default();
-
-                if let Some(sc) = current_sidecar
-                    && current_misses < ReadAttributeSidecar::MAXIMUM_ALLOWED_MISSES
-                {
-                    if let Some(v) = val_obj.read_slot(&self.globals, sc.slot_id, sc.shape_id) {
-                        frame.stack.push(v);
-                        return Ok(OpcodeRunExit::Continue);
-                    } else {
-                        current_misses = current_misses
-                            .saturating_add(1)
-                            .clamp(0, ReadAttributeSidecar::MAXIMUM_ALLOWED_MISSES);
-                    }
-                }
-
-                if current_misses < ReadAttributeSidecar::MAXIMUM_ALLOWED_MISSES
-                    && let Some((v, sid, slot)) = val_obj.resolve_to_slot(&self.globals, n)
-                {
-                    next_sidecar.set(Some(OpcodeSidecar::ReadAttribute(ReadAttributeSidecar {
-                        misses: current_misses,
-                        shape_id: sid,
-                        slot_id: slot,
-                    })));
-                    frame.stack.push(v);
-                    return Ok(OpcodeRunExit::Continue);
-                }
-
-                // if you're here, either you had no sidecar, or you did but your sidecar failed and you didn't get a valid
-                // alternative slot to try (or you would have returned in the earlier if) - record where you're at (if you had a
-                // sidecar to begin with), and then do a full slow path attribute read
-                if let Some(sc) = current_sidecar {
-                    next_sidecar.set(Some(OpcodeSidecar::ReadAttribute(ReadAttributeSidecar {
-                        misses: current_misses,
-                        shape_id: sc.shape_id,
-                        slot_id: sc.slot_id,
-                    })));
-                }
-
-                match val_obj.read_attribute(n, &self.globals) {
-                    Ok(val) => {
-                        frame.stack.push(val);
-                    }
-                    Err(err) => {
-                        return build_vm_error!(
-                            match err {
-                                crate::runtime_value::AttributeError::NoSuchAttribute => {
-                                    VmErrorReason::NoSuchSymbol(n.0, SymbolKind::Identifier)
-                                }
-                                crate::runtime_value::AttributeError::InvalidFunctionBinding => {
-                                    VmErrorReason::InvalidBinding
-                                }
-                                crate::runtime_value::AttributeError::ValueHasNoAttributes => {
-                                    VmErrorReason::UnexpectedType
-                                }
-                            },
-                            next,
-                            frame,
-                            op_idx
-                        );
-                    }
-                }
+                return self.read_attribute_symbol(
+                    val_obj,
+                    crate::symbol::Symbol(n),
+                    next,
+                    next_sidecar,
+                    op_idx,
+                    frame,
+                );
             }
             Opcode::WriteAttributeSymbol(n) => {
                 let val = pop_or_err!(next, frame, op_idx);
@@ -1258,25 +1551,40 @@ impl VirtualMachine {
                     *op_idx = dest as usize;
                 }
             }
-            Opcode::Call(argc) => {
+            Opcode::Call(argc) | Opcode::TailCall(argc) => {
                 let x = pop_or_err!(next, frame, op_idx);
-                match x.eval(argc, frame, self, false) {
-                    Ok(crate::runtime_value::CallResult::Ok(_)) => {}
-                    Ok(crate::runtime_value::CallResult::Exception(e)) => {
-                        return Ok(OpcodeRunExit::Exception(e));
-                    }
-                    Err(err) => {
-                        if err.loc.is_some() {
-                            return Err(err);
-                        } else {
-                            return build_vm_error!(err.reason, next, frame, op_idx);
-                        }
-                    }
+                return self.call_value(x, argc, next, op_idx, frame);
+            }
+            Opcode::CallLocalMethod(..) => {
+                return build_vm_error!(
+                    VmErrorReason::UnknownOpcode(OPCODE_CALL_LOCAL_METHOD),
+                    next,
+                    frame,
+                    op_idx
+                );
+            }
+            Opcode::CallLocalMethodSymbol(x, n, argc) => {
+                let local = frame.locals[x as usize].val.clone();
+                match self.read_attribute_symbol(
+                    local,
+                    crate::symbol::Symbol(n),
+                    next,
+                    next_sidecar,
+                    op_idx,
+                    frame,
+                )? {
+                    OpcodeRunExit::Continue => {}
+                    other => return Ok(other),
                 }
+                let method = pop_or_err!(next, frame, op_idx);
+                return self.call_value(method, argc, next, op_idx, frame);
             }
             Opcode::Return => {
                 return Ok(OpcodeRunExit::Return);
             }
+            Opcode::Yield => {
+                return Ok(OpcodeRunExit::Yield);
+            }
             Opcode::ReturnUnit => {
                 return match self.globals.create_unit_object() {
                     Ok(unit) => {
@@ -1314,6 +1622,14 @@ impl VirtualMachine {
                     }
                 };
             }
+            Opcode::ThrowFrom => {
+                let cause = pop_or_err!(next, frame, op_idx);
+                let ev = pop_or_err!(next, frame, op_idx);
+                // values that cannot have attributes are thrown without their cause
+                let _ = ev.write_attribute(INTERNED_ATTR_CAUSE, cause, &mut self.globals);
+                frame.stack.push(ev);
+                return self.run_opcode(Opcode::Throw, next_sidecar, op_idx, this_module, frame);
+            }
             Opcode::BuildList(n) => {
                 let values = (0..n).map(|_| frame.stack.try_pop()).collect::<Vec<_>>();
                 let list = List::default();
@@ -1328,6 +1644,30 @@ impl VirtualMachine {
                 let list = RuntimeValue::List(list);
                 frame.stack.push(list);
             }
+            Opcode::BuildMap(n) => {
+                let mut values = Vec::<_>::with_capacity(2 * n as usize);
+                for _ in 0..2 * n {
+                    values.push(pop_or_err!(next, frame, op_idx));
+                }
+                values.reverse();
+                let map = Map::new_with_capacity(n as usize);
+                for kv in values.chunks_exact(2) {
+                    match map.insert(kv[0].clone(), kv[1].clone(), frame, self) {
+                        Ok(RunloopExit::Ok(_)) => {}
+                        Ok(RunloopExit::Exception(e)) => {
+                            return Ok(OpcodeRunExit::Exception(e));
+                        }
+                        Err(e) => {
+                            return if e.loc.is_none() {
+                                build_vm_error!(e.reason, next, frame, op_idx)
+                            } else {
+                                Err(e)
+                            };
+                        }
+                    }
+                }
+                frame.stack.push(RuntimeValue::Map(map));
+            }
             Opcode::BuildFunction => {
                 let val = pop_or_err!(next, frame, op_idx);
                 if let Some(co) = val.as_code_object() {
@@ -1553,10 +1893,13 @@ impl VirtualMachine {
             Opcode::EnumCheckIsCaseSymbol(n) => {
                 let case_sym = crate::symbol::Symbol(n);
                 let ev = pop_or_err!(next, frame, op_idx);
+                // a value that is not an enum is not any case, so that case
+                // patterns can be mixed with others, and nested in them
                 let ev = if let Some(ev) = ev.as_enum_value() {
                     ev
                 } else {
-                    return build_vm_error!(VmErrorReason::UnexpectedType, next, frame, op_idx);
+                    frame.stack.push(RuntimeValue::Boolean(false.into()));
+                    return Ok(OpcodeRunExit::Continue);
                 };
 
                 let current_sidecar = next_sidecar
@@ -1737,6 +2080,42 @@ impl VirtualMachine {
                     return build_vm_error!(VmErrorReason::UnexpectedType, next, frame, op_idx);
                 }
             }
+            Opcode::MatchFailed(n) => {
+                let Some(what) = this_module
+                    .load_indexed_const(n)
+                    .and_then(|ct| ct.as_string().map(|s| s.raw_value().to_owned()))
+                else {
+                    return build_vm_error!(VmErrorReason::UnexpectedType, next, frame, op_idx);
+                };
+                return build_vm_error!(
+                    VmErrorReason::NonExhaustiveMatch(what),
+                    next,
+                    frame,
+                    op_idx
+                );
+            }
+            Opcode::FormatValue(n) => {
+                let Some(spec) = this_module
+                    .load_indexed_const(n)
+                    .and_then(|ct| ct.as_string().map(|s| s.raw_value().to_owned()))
+                else {
+                    return build_vm_error!(VmErrorReason::UnexpectedType, next, frame, op_idx);
+                };
+                let x = pop_or_err!(next, frame, op_idx);
+                match crate::format::format_value(&x, &spec, frame, self) {
+                    Ok(RunloopExit::Ok(text)) => {
+                        frame.stack.push(RuntimeValue::String(text.into()))
+                    }
+                    Ok(RunloopExit::Exception(e)) => return Ok(OpcodeRunExit::Exception(e)),
+                    Err(err) => {
+                        if err.loc.is_some() {
+                            return Err(err);
+                        } else {
+                            return build_vm_error!(err.reason, next, frame, op_idx);
+                        }
+                    }
+                }
+            }
             Opcode::Assert(n) => {
                 let assert_msg = if let Some(ct) = this_module.load_indexed_const(n) {
                     if let Some(sv) = ct.as_string() {
@@ -1789,6 +2168,10 @@ impl VirtualMachine {
                     return build_vm_error!(VmErrorReason::UnexpectedType, next, frame, op_idx);
                 };
 
+                if let Err(reason) = self.options.permissions.check_dylib(lib_name) {
+                    return build_vm_error!(reason, next, frame, op_idx);
+                }
+
                 // this means that one cannot use the same dylib for multiple modules!
                 #[allow(clippy::map_entry)]
                 if !self.loaded_dylibs.contains_key(lib_name) {
@@ -1875,97 +2258,113 @@ impl VirtualMachine {
                     return build_vm_error!(VmErrorReason::UnexpectedType, next, frame, op_idx);
                 };
 
-                if let Some(mli) = self.imported_modules.get(ipath) {
-                    if let Err(e) = Self::create_import_model_from_path(
-                        this_module,
-                        &mut self.globals,
-                        ipath,
-                        RuntimeValue::Module(mli.module.clone()),
-                    ) {
-                        return build_vm_error!(e, next, frame, op_idx);
-                    }
-
-                    frame.stack.push(RuntimeValue::Module(mli.module.clone()));
+                let loaded = if let Some(mli) = self.injected_modules.get(ipath) {
+                    Ok(mli.module.clone())
                 } else {
-                    let import_path = match Self::resolve_import_path_to_path(
+                    match self.resolve_import(
                         ipath,
                         this_module.get_compiled_module().widget_root_path.as_ref(),
                     ) {
-                        Ok(ipath) => ipath,
+                        Ok(import_path) => match self.imported_modules.get(&import_path) {
+                            Some(mli) => Ok(mli.module.clone()),
+                            None => Err(import_path),
+                        },
                         Err(err) => {
                             return build_vm_error!(err, next, frame, op_idx);
                         }
-                    };
+                    }
+                };
 
-                    let sb = match SourceBuffer::from_path(&import_path) {
-                        Ok(sb) => sb,
-                        Err(_) => {
-                            return build_vm_error!(
-                                VmErrorReason::ImportNotAvailable(
-                                    ipath.to_owned(),
-                                    "no such file".to_owned()
-                                ),
-                                next,
-                                frame,
-                                op_idx
-                            );
+                match loaded {
+                    Ok(module) => {
+                        if let Err(e) = Self::create_import_model_from_path(
+                            this_module,
+                            &mut self.globals,
+                            ipath,
+                            RuntimeValue::Module(module.clone()),
+                        ) {
+                            return build_vm_error!(e, next, frame, op_idx);
                         }
-                    };
 
-                    if self.import_stack.contains(ipath) {
-                        return build_vm_error!(
-                            VmErrorReason::CircularImport(ipath.to_owned()),
-                            next,
-                            frame,
-                            op_idx
-                        );
-                    } else {
-                        self.import_stack.push(ipath.clone());
+                        frame.stack.push(RuntimeValue::Module(module));
                     }
+                    Err(import_path) => {
+                        let sb = match SourceBuffer::from_path(&import_path) {
+                            Ok(sb) => sb,
+                            Err(_) => {
+                                return build_vm_error!(
+                                    VmErrorReason::ImportNotAvailable(
+                                        ipath.to_owned(),
+                                        "no such file".to_owned()
+                                    ),
+                                    next,
+                                    frame,
+                                    op_idx
+                                );
+                            }
+                        };
 
-                    let c_module = match compile_from_source(&sb, &Default::default()) {
-                        Ok(cm) => cm,
-                        Err(ces) => {
-                            let err_msg = ces
-                                .iter()
-                                .map(|x| format!("error: {x}"))
-                                .collect::<Vec<_>>()
-                                .join("\n");
-                            assert!(*ipath == self.import_stack.pop());
+                        if self.import_stack.contains(&import_path) {
                             return build_vm_error!(
-                                VmErrorReason::ImportNotAvailable(
-                                    ipath.to_owned(),
-                                    format!("module failed to compile: {err_msg}")
-                                ),
+                                VmErrorReason::CircularImport(ipath.to_owned()),
                                 next,
                                 frame,
                                 op_idx
                             );
+                        } else {
+                            self.import_stack.push(import_path.clone());
                         }
-                    };
-                    let mli = match self.load_module(&sb.name, c_module)? {
-                        RunloopExit::Ok(mli) => mli,
-                        RunloopExit::Exception(e) => {
-                            assert!(*ipath == self.import_stack.pop());
-                            return Ok(OpcodeRunExit::Exception(e));
-                        }
-                    };
 
-                    if let Err(e) = Self::create_import_model_from_path(
-                        this_module,
-                        &mut self.globals,
-                        ipath,
-                        RuntimeValue::Module(mli.module.clone()),
-                    ) {
-                        return build_vm_error!(e, next, frame, op_idx);
-                    }
+                        let c_module = match compile_from_source_cached(
+                            &sb,
+                            &Default::default(),
+                            self.options.bytecode_cache_dir.as_deref(),
+                        ) {
+                            Ok(cm) => cm,
+                            Err(ces) => {
+                                let err_msg = ces
+                                    .iter()
+                                    .map(|x| format!("error: {x}"))
+                                    .collect::<Vec<_>>()
+                                    .join("\n");
+                                assert!(import_path == self.import_stack.pop());
+                                return build_vm_error!(
+                                    VmErrorReason::ImportNotAvailable(
+                                        ipath.to_owned(),
+                                        format!("module failed to compile: {err_msg}")
+                                    ),
+                                    next,
+                                    frame,
+                                    op_idx
+                                );
+                            }
+                        };
+                        let r_mod = RuntimeModule::new(self, c_module)?;
+                        r_mod.set_name(ipath);
+                        let mli = match self.load_into_module(&sb.name, r_mod)? {
+                            RunloopExit::Ok(mli) => mli,
+                            RunloopExit::Exception(e) => {
+                                assert!(import_path == self.import_stack.pop());
+                                return Ok(OpcodeRunExit::Exception(e));
+                            }
+                        };
+
+                        if let Err(e) = Self::create_import_model_from_path(
+                            this_module,
+                            &mut self.globals,
+                            ipath,
+                            RuntimeValue::Module(mli.module.clone()),
+                        ) {
+                            return build_vm_error!(e, next, frame, op_idx);
+                        }
 
-                    assert!(*ipath == self.import_stack.pop());
+                        assert!(import_path == self.import_stack.pop());
 
-                    frame.stack.push(RuntimeValue::Module(mli.module.clone()));
+                        frame.stack.push(RuntimeValue::Module(mli.module.clone()));
 
-                    self.imported_modules.insert(ipath.to_owned(), mli);
-                };
+                        self.imported_modules.insert(import_path, mli);
+                    }
+                }
             }
         }
 
@@ -1979,8 +2378,109 @@ impl VirtualMachine {
         module: &RuntimeModule,
         frame: &mut Frame,
     ) -> ExecutionResult<RunloopExit, VmError> {
-        let mut op_counter = 0;
+        if self.debugger.is_none() && self.profiler.is_none() {
+            return self.run_frame(bc, sidecar, module, frame);
+        }
+
+        let debugging = self.debugger.is_some();
+        let profiling = self.profiler.is_some();
+        if let Some(debugger) = self.debugger.as_mut() {
+            debugger.enter_frame(frame);
+        }
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.enter_frame(frame);
+        }
+        let result = self.run_frame(bc, sidecar, module, frame);
+        if profiling && let Some(profiler) = self.profiler.as_mut() {
+            profiler.leave_frame();
+        }
+        if debugging && let Some(debugger) = self.debugger.as_mut() {
+            debugger.leave_frame();
+        }
+        result
+    }
+
+    fn run_frame(
+        &mut self,
+        bc: &[Opcode],
+        sidecar: &SidecarSlice,
+        module: &RuntimeModule,
+        frame: &mut Frame,
+    ) -> ExecutionResult<RunloopExit, VmError> {
+        let mut callees = vec![];
+        let result = self.run_calls(bc, sidecar, module, frame, &mut callees);
+
+        // an error stops the calls that were running in this loop, each of
+        // them adds the place it was called from to the backtrace
+        if let Err(mut err) = result {
+            while let Some(callee) = callees.pop() {
+                self.leave_callee(callee);
+                let caller = top_frame(&mut callees, frame);
+                if let Some(lt) = caller.get_line_entry_at_pos(caller.pc as u16) {
+                    err.backtrace.push_frame(lt, caller);
+                }
+            }
+            return Err(err);
+        }
+        result
+    }
+
+    fn enter_callee(&mut self, callee: &mut Callee) {
+        if let Some(debugger) = self.debugger.as_mut() {
+            debugger.enter_frame(&callee.frame);
+            callee.debugging = true;
+        }
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.enter_frame(&callee.frame);
+            callee.profiling = true;
+        }
+    }
+
+    fn leave_callee(&mut self, callee: Callee) {
+        self.leave_call();
+        self.drop_callee(callee);
+    }
+
+    fn drop_callee(&mut self, callee: Callee) {
+        if callee.profiling
+            && let Some(profiler) = self.profiler.as_mut()
+        {
+            profiler.leave_frame();
+        }
+        if callee.debugging
+            && let Some(debugger) = self.debugger.as_mut()
+        {
+            debugger.leave_frame();
+        }
+        self.release_frame(callee.frame);
+    }
+
+    fn run_calls(
+        &mut self,
+        root_bc: &[Opcode],
+        root_sidecar: &SidecarSlice,
+        root_module: &RuntimeModule,
+        root: &mut Frame,
+        callees: &mut Vec<Callee>,
+    ) -> ExecutionResult<RunloopExit, VmError> {
+        let mut op_counter = root.resume_at.take().unwrap_or(0);
         loop {
+            let at_root = callees.is_empty();
+            let (bc, sidecar, module, frame): (
+                &[Opcode],
+                &SidecarSlice,
+                &RuntimeModule,
+                &mut Frame,
+            ) = match callees.last_mut() {
+                Some(callee) => (
+                    &callee.body,
+                    &callee.sidecar,
+                    &callee.module,
+                    &mut callee.frame,
+                ),
+                None => (root_bc, root_sidecar, root_module, &mut *root),
+            };
+
             #[cfg(debug_assertions)]
             if self.options.tracing && self.options.dump_stack {
                 frame.stack.dump();
@@ -2029,18 +2529,87 @@ impl VirtualMachine {
                 }
             }
 
+            if self.debugger.is_some() {
+                self.debug_hook(op_counter, frame);
+            }
+
+            if let Some(profiler) = self.profiler.as_mut() {
+                profiler.instruction(op_counter, next);
+            }
+
+            if let Some(coverage) = self.coverage.as_mut() {
+                coverage.instruction(op_counter, next, frame);
+            }
+
+            self.limit_state.instructions += 1;
+            if self.limit_state.check_due()
+                && let Err(reason) = self.check_limits()
+            {
+                return build_vm_error!(reason, next, frame, &op_counter);
+            }
+
             // we save the original counter (the current instruction) for two reasons:
             // - if an exception occurs, we need to figure out where we came from to build the backtrace
             // - run_opcode does not advance the counter unless it's jumping, so we need to know if it changed
             //   and if not advance it ourselves
             let current_op_counter = op_counter;
+            frame.pc = op_counter;
 
             // some errors can be converted into exceptions, so reserve the right to postpone exception handling
             let mut need_handle_exception: Option<VmException> = None;
 
-            match self.run_opcode(next, next_sidecar, &mut op_counter, module, frame) {
+            let result = match self.run_opcode(next, next_sidecar, &mut op_counter, module, frame) {
+                // the frame this loop started with belongs to whoever started
+                // it, so a tail call from there is an ordinary call
+                Ok(OpcodeRunExit::TailCall(callee)) if at_root => match self.enter_call(frame) {
+                    Ok(()) => Ok(OpcodeRunExit::Call(callee)),
+                    Err(reason) => {
+                        self.release_frame(callee.frame);
+                        build_vm_error!(reason, next, frame, &current_op_counter)
+                    }
+                },
+                result => result,
+            };
+
+            match result {
                 Ok(OpcodeRunExit::Continue) => {}
+                Ok(OpcodeRunExit::Call(mut callee)) => {
+                    self.enter_callee(&mut callee);
+                    callees.push(callee);
+                    op_counter = 0;
+                    continue;
+                }
+                Ok(OpcodeRunExit::TailCall(mut callee)) => {
+                    let Some(mut replaced) = callees.pop() else {
+                        unreachable!("tail calls from the root frame are ordinary calls");
+                    };
+                    let mut tail_callers = replaced.frame.tail_callers.take().unwrap_or_default();
+                    Rc::make_mut(&mut tail_callers)
+                        .push(replaced.frame.func.clone(), replaced.frame.pc);
+                    callee.frame.tail_callers = Some(tail_callers);
+                    self.drop_callee(replaced);
+                    self.enter_callee(&mut callee);
+                    callees.push(callee);
+                    op_counter = 0;
+                    continue;
+                }
                 Ok(OpcodeRunExit::Return) => {
+                    let Some(mut callee) = callees.pop() else {
+                        return Ok(RunloopExit::Ok(()));
+                    };
+                    let Some(ret) = callee.frame.stack.try_pop() else {
+                        panic!("functions must return a value");
+                    };
+                    self.leave_callee(callee);
+                    let caller = top_frame(callees, root);
+                    caller.stack.push(ret);
+                    op_counter = caller.pc + 1;
+                    continue;
+                }
+                Ok(OpcodeRunExit::Yield) => {
+                    // only generators yield, and they always run in a loop of
+                    // their own, so this is the root frame
+                    frame.resume_at = Some(op_counter + 1);
                     return Ok(RunloopExit::Ok(()));
                 }
                 Ok(OpcodeRunExit::Exception(except)) => {
@@ -2055,7 +2624,7 @@ impl VirtualMachine {
                             frame.get_line_entry_at_pos(current_op_counter as u16)
                         {
                             let mut new_err = err.clone();
-                            new_err.backtrace.push(lt);
+                            new_err.backtrace.push_frame(lt, frame);
                             new_err
                         } else {
                             err
@@ -2069,25 +2638,51 @@ impl VirtualMachine {
                 op_counter += 1;
             }
 
-            if let Some(except) = need_handle_exception {
-                except.fill_in_backtrace(&mut self.globals);
-                match frame.drop_to_first_try(self) {
-                    Some(o) => {
+            // an exception that is not caught in a frame leaves it, and is
+            // thrown again at the call in its caller; the backtrace attribute
+            // is only written where it lands, since unwinding can go through a
+            // great many frames
+            if let Some(mut except) = need_handle_exception {
+                let mut throw_at = current_op_counter;
+                let depth = callees.len();
+                loop {
+                    let frame = top_frame(callees, root);
+                    if let Some(o) = frame.drop_to_first_try(self) {
+                        except.fill_in_backtrace(&mut self.globals);
                         op_counter = o as usize;
                         frame.stack.push(except.value);
+                        break;
                     }
-                    None => {
-                        let new_except = if let Some(lt) =
-                            frame.get_line_entry_at_pos(current_op_counter as u16)
-                        {
-                            except.thrown_at(lt)
+                    if callees.is_empty() {
+                        except.fill_in_backtrace(&mut self.globals);
+                    }
+                    let thrower = callees.len() == depth;
+                    let frame = top_frame(callees, root);
+                    if let Some(lt) = frame.get_line_entry_at_pos(throw_at as u16) {
+                        let entry = BacktraceEntry::new(lt, frame.func.as_ref());
+                        // a recursive caller is at the same place as the frame
+                        // that threw, but it is a call of its own
+                        if thrower {
+                            except = except.thrown_at(entry);
                         } else {
-                            except
-                        };
-                        return Ok(RunloopExit::Exception(new_except));
+                            except.backtrace.push(entry);
+                        }
+                        except.backtrace.push_tail_callers(frame);
                     }
+                    let Some(callee) = callees.pop() else {
+                        return Ok(RunloopExit::Exception(except));
+                    };
+                    self.leave_callee(callee);
+                    throw_at = top_frame(callees, root).pc;
                 }
             }
         }
     }
 }
+
+fn top_frame<'a>(callees: &'a mut [Callee], root: &'a mut Frame) -> &'a mut Frame {
+    match callees.last_mut() {
+        Some(callee) => &mut callee.frame,
+        None => root,
+    }
+}
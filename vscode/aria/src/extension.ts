@@ -1,4 +1,12 @@
-import { workspace, EventEmitter, ExtensionContext, Uri } from "vscode";
+import {
+  debug,
+  workspace,
+  DebugAdapterDescriptor,
+  DebugAdapterExecutable,
+  EventEmitter,
+  ExtensionContext,
+  Uri,
+} from "vscode";
 
 import {
   Disposable,
@@ -39,6 +47,16 @@ export async function activate(context: ExtensionContext) {
 
 	client = new LanguageClient("aria-language-server", "aria language server", serverOptions, clientOptions);
 	client.start();
+
+	context.subscriptions.push(
+		debug.registerDebugAdapterDescriptorFactory("aria", {
+			createDebugAdapterDescriptor(): DebugAdapterDescriptor {
+				const configuredAria = workspace.getConfiguration('aria').get<string>('debugger.path')?.trim();
+				const defaultAria = Uri.joinPath(context.extensionUri, '..', '..', 'target', 'debug', 'aria');
+				return new DebugAdapterExecutable(configuredAria || defaultAria.fsPath, ["--dap"]);
+			},
+		}),
+	);
 }
 
 export function deactivate(): Thenable<void> | undefined {